@@ -0,0 +1,108 @@
+use crate::*;
+
+/// Permissions that the owner can delegate. The owner has all of them.
+#[derive(
+    BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, PartialEq, Debug,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum Role {
+    /// Changes the contract-wide configuration and grants other roles except `Admin`.
+    Admin,
+    /// Adds, removes and configures assets, indexes and pairs.
+    AssetManager,
+    /// Adds and removes oracles and relayers.
+    OracleManager,
+    /// Pauses the contract and resumes tripped assets.
+    Guardian,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Grants the role to the account. `Admin` can only be granted by the owner, other roles
+    /// also by admins.
+    #[payable]
+    pub fn grant_role(&mut self, account_id: AccountId, role: Role) {
+        assert_one_yocto();
+        self.assert_can_manage_role(role);
+        let mut roles = self.roles.get(&account_id).unwrap_or_default();
+        assert!(
+            !roles.contains(&role),
+            "{}",
+            ContractError::RoleAlreadyGranted
+        );
+        roles.push(role);
+        self.roles.insert(&account_id, &roles);
+        Event::GrantRole {
+            account_id: &account_id,
+            role,
+        }
+        .emit();
+    }
+
+    #[payable]
+    pub fn revoke_role(&mut self, account_id: AccountId, role: Role) {
+        assert_one_yocto();
+        self.assert_can_manage_role(role);
+        let mut roles = self.roles.get(&account_id).unwrap_or_default();
+        assert!(roles.contains(&role), "{}", ContractError::MissingRole);
+        roles.retain(|r| *r != role);
+        if roles.is_empty() {
+            self.roles.remove(&account_id);
+        } else {
+            self.roles.insert(&account_id, &roles);
+        }
+        Event::RevokeRole {
+            account_id: &account_id,
+            role,
+        }
+        .emit();
+    }
+
+    pub fn get_roles(&self, account_id: AccountId) -> Vec<Role> {
+        self.roles.get(&account_id).unwrap_or_default()
+    }
+
+    pub fn get_role_members(&self, role: Role) -> Vec<AccountId> {
+        self.roles
+            .iter()
+            .filter(|(_, roles)| roles.contains(&role))
+            .map(|(account_id, _)| account_id)
+            .collect()
+    }
+}
+
+impl Contract {
+    /// In the multi-owner mode, the contract itself acts as the owner when it executes confirmed
+    /// proposals. Roles of other accounts and of the DAO are suspended then, except `Guardian`.
+    pub fn has_role(&self, account_id: &AccountId, role: Role) -> bool {
+        if account_id == &self.internal_acting_owner_id() {
+            return true;
+        }
+        // Guardians can still pause the contract right away.
+        if self.multisig.is_some() && role != Role::Guardian {
+            return false;
+        }
+        self.dao_id.as_ref() == Some(account_id)
+            || self
+                .roles
+                .get(account_id)
+                .unwrap_or_default()
+                .contains(&role)
+    }
+
+    pub fn assert_role(&self, role: Role) {
+        assert!(
+            self.has_role(&env::predecessor_account_id(), role),
+            "{}",
+            ContractError::RequiresRole(role)
+        );
+    }
+
+    fn assert_can_manage_role(&self, role: Role) {
+        if role == Role::Admin {
+            self.assert_owner();
+        } else {
+            self.assert_role(Role::Admin);
+        }
+    }
+}
@@ -1,12 +1,152 @@
 use crate::*;
 
 pub type AssetId = String;
+pub type GroupId = String;
+pub type BundleId = String;
 
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
 #[serde(crate = "near_sdk::serde")]
 pub struct Asset {
     pub reports: Vec<Report>,
     pub emas: Vec<AssetEma>,
+    pub twaps: Vec<AssetTwap>,
+    /// The group this asset belongs to, e.g. `stablecoins`.
+    pub group: Option<GroupId>,
+    /// Paused assets don't accept reports and don't return prices.
+    pub paused: bool,
+    /// Overrides the contract-wide recency duration for this asset.
+    pub recency_duration_sec: Option<DurationSec>,
+    /// The last aggregated price computed from reports.
+    pub last_good: Option<PricePoint>,
+    /// For how long the aggregated price stays valid after it's computed. Defaults to the recency
+    /// duration of the asset.
+    pub price_ttl_sec: Option<DurationSec>,
+    /// Overrides the contract-wide number of recent reports required for a price.
+    pub min_num_recent_reports: Option<u32>,
+    /// Reports deviating from the last aggregated price by more than this are ignored.
+    pub max_deviation_bps: Option<u32>,
+    pub circuit_breaker: Option<CircuitBreaker>,
+    /// The circuit breaker tripped, the asset doesn't return prices until it's resumed.
+    pub tripped: bool,
+    /// Overrides the contract-wide weighting of oracles.
+    pub oracle_weighting: Option<OracleWeighting>,
+    /// Reports outside of the band are rejected.
+    pub price_band: Option<PriceBand>,
+    /// The AMM pool the DEX price of the asset is fetched from.
+    pub dex_source: Option<DexSource>,
+    /// The last DEX price of the asset.
+    pub dex_price: Option<PricePoint>,
+    /// The aggregation round that collects reports.
+    pub open_round: Option<Round>,
+    /// The ID of the last finalized round, `0` if there is none.
+    pub latest_round_id: RoundId,
+    /// Overrides the contract-wide minimum interval between reports of an oracle.
+    pub min_report_interval_sec: Option<DurationSec>,
+    /// Overrides the contract-wide expiry policy.
+    pub expiry_policy: Option<ExpiryPolicy>,
+    /// When set, oracles report prices of the asset with `commit_prices` and `reveal_prices`.
+    pub commit_reveal: Option<CommitRevealConfig>,
+    /// Commitments of oracles that aren't revealed yet.
+    pub commitments: Vec<Commitment>,
+    /// Makes the asset a pegged asset.
+    pub peg: Option<Peg>,
+    /// When the aggregated price of the pegged asset started to deviate from the peg.
+    pub depegged_since: Option<Timestamp>,
+    /// Additional quote assets the asset is priced in, e.g. `near` next to the default USD.
+    pub quote_asset_ids: Vec<AssetId>,
+    /// Restricts prices of the asset to these accounts and active subscribers. `None` makes
+    /// prices public.
+    pub consumers: Option<Vec<AccountId>>,
+    /// Makes the asset return the median of the last `multi_block_median_len` finalized block
+    /// medians instead of the current median.
+    pub multi_block_median_len: Option<u32>,
+    /// The median of the latest report of every block, up to the current one.
+    pub block_medians: Vec<BlockMedian>,
+    pub volatilities: Vec<AssetVolatility>,
+    /// The global sequence number of the latest aggregated price.
+    pub sequence: u64,
+    /// The decimals reported prices must have. Prices with other decimals are rescaled when it's
+    /// exact and rejected otherwise.
+    pub price_decimals: Option<u8>,
+    pub deprecation: Option<AssetDeprecation>,
+    /// Recent intervals between two reports of an oracle, oldest first.
+    pub report_intervals: Vec<DurationSec>,
+    /// The price oracle contract `oracle_call` gets the price from while the asset doesn't have a
+    /// fresh one.
+    pub fallback_oracle_id: Option<AccountId>,
+    /// When the reports of the asset were last aggregated.
+    pub aggregated_at: Timestamp,
+    /// The asset got reports since the last aggregation, which is deferred.
+    pub pending_aggregation: bool,
+}
+
+/// Trips when the aggregated price moves more than `max_deviation_bps` away from the last
+/// aggregated price that is at most `window_sec` old.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CircuitBreaker {
+    pub max_deviation_bps: u32,
+    pub window_sec: DurationSec,
+}
+
+/// The planned end of the price feed of an asset. Prices are served until the sunset, reports
+/// after it are rejected.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetDeprecation {
+    #[serde(with = "u64_dec_format")]
+    pub sunset_timestamp: Timestamp,
+    /// The asset consumers should move to.
+    pub replacement_asset_id: Option<AssetId>,
+}
+
+/// The range of sane prices of an asset, e.g. `0.5` to `2.0` for a stablecoin.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceBand {
+    pub min: Price,
+    pub max: Price,
+}
+
+impl PriceBand {
+    pub fn assert_valid(&self) {
+        self.min.assert_valid();
+        self.max.assert_valid();
+        assert!(self.min <= self.max, "{}", ContractError::InvalidPriceBand);
+    }
+
+    pub fn contains(&self, price: &Price) -> bool {
+        self.min <= *price && *price <= self.max
+    }
+}
+
+impl CircuitBreaker {
+    pub fn assert_valid(&self) {
+        assert!(
+            self.max_deviation_bps > 0,
+            "{}",
+            ContractError::DeviationNotPositive
+        );
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PricePoint {
+    pub price: Price,
+    #[serde(with = "u64_dec_format")]
+    pub timestamp: Timestamp,
+    #[serde(default, with = "u64_dec_format")]
+    pub block_height: BlockHeight,
+}
+
+/// The aggregated price after the last report within a block.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BlockMedian {
+    #[serde(with = "u64_dec_format")]
+    pub block_height: BlockHeight,
+    pub price: Price,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
@@ -16,25 +156,322 @@ pub struct Report {
     #[serde(with = "u64_dec_format")]
     pub timestamp: Timestamp,
     pub price: Price,
+    #[serde(default)]
+    pub source: PriceSource,
+    /// The block the report was made in.
+    #[serde(default, with = "u64_dec_format")]
+    pub block_height: BlockHeight,
 }
 
-#[derive(Serialize, Deserialize)]
+/// The mechanism that produced a price.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub enum PriceSource {
+    /// Reported by the oracle itself.
+    Report,
+    /// Signed by the oracle and submitted by a relayer.
+    Delegated,
+    /// Revealed after a commitment of the oracle.
+    CommitReveal,
+    /// Signed by the attesters of the price feed.
+    PriceFeed,
+    /// Fulfilled an on-demand price request.
+    Request,
+    /// Read from the AMM pool of the asset.
+    Dex,
+}
+
+// Deriving `Default` for enums requires a newer compiler than the contract is built with.
+#[allow(clippy::derivable_impls)]
+impl Default for PriceSource {
+    fn default() -> Self {
+        PriceSource::Report
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub struct AssetPrice {
     pub asset_id: AssetId,
     pub price: Price,
+    /// The quote asset of the price, which must be configured for the asset. The default quote
+    /// asset if `None`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quote_asset_id: Option<AssetId>,
+    /// Must be greater than the nonce of the previous report of the oracle for the asset, so the
+    /// report can't be replayed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<U64>,
 }
 
-#[derive(Serialize, Deserialize)]
+/// The Borsh form of `AssetPrice` taken by `report_prices_borsh`.
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+pub struct AssetPriceBorsh {
+    pub asset_id: AssetId,
+    pub price: Price,
+    pub quote_asset_id: Option<AssetId>,
+    pub nonce: Option<u64>,
+}
+
+impl From<AssetPriceBorsh> for AssetPrice {
+    fn from(v: AssetPriceBorsh) -> Self {
+        AssetPrice {
+            asset_id: v.asset_id,
+            price: v.price,
+            quote_asset_id: v.quote_asset_id,
+            nonce: v.nonce.map(U64),
+        }
+    }
+}
+
+/// Returns the ID of the asset of an EMA, TWAP or inverse, e.g. `wrap.near` for
+/// `wrap.near#3600`.
+pub fn base_asset_id(asset_id: &str) -> &str {
+    let asset_id = inverse_asset_id(asset_id).map_or(asset_id, |(asset_id, _)| asset_id);
+    asset_id
+        .split_once('#')
+        .map_or(asset_id, |(base_asset_id, _)| base_asset_id)
+}
+
+/// Returns the ID under which prices of the asset in the quote asset are aggregated, e.g.
+/// `wrap.near@usdt.near`. Such IDs never collide with asset IDs, since `@` isn't valid in them.
+pub fn quoted_asset_id(asset_id: &str, quote_asset_id: &str) -> AssetId {
+    format!("{}@{}", asset_id, quote_asset_id)
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub struct AssetOptionalPrice {
     pub asset_id: AssetId,
     pub price: Option<Price>,
+    /// The decimals of the asset from its metadata, e.g. `24` for `wrap.near`.
+    #[serde(default)]
+    pub decimals: Option<u8>,
+    /// The effective recency duration of the asset, which may differ from the contract-wide one.
+    #[serde(default)]
+    pub recency_duration_sec: Option<DurationSec>,
+    /// The price is the last aggregated price of the asset, because the current one is stale.
+    #[serde(default)]
+    pub expired: bool,
+    /// The global sequence number of the latest aggregated price of the asset.
+    #[serde(default)]
+    pub sequence: Option<U64>,
+    #[serde(default)]
+    pub last_update_timestamp: Option<U64>,
+    /// Set when the price feed of the asset is going to stop.
+    #[serde(default)]
+    pub deprecation: Option<AssetDeprecation>,
+    /// The price comes from the fallback oracle of the asset.
+    #[serde(default)]
+    pub fallback: bool,
+}
+
+/// What `get_price_data` returns when the price of an asset is stale.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub enum ExpiryPolicy {
+    ReturnNone,
+    /// Returns the last aggregated price with the `expired` flag.
+    ReturnLastGoodWithFlag,
+    /// Fails the whole call.
+    Panic,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetMetadata {
+    /// The decimals of the asset token, so the price of `10**decimals` units is `1` unit of the
+    /// quote asset.
+    pub decimals: u8,
+    pub symbol: String,
+    /// The asset the price is quoted in, e.g. `usd`.
+    pub quote_asset_id: AssetId,
+    pub description: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub enum PriceStatus {
+    Fresh,
+    /// The asset exists, but doesn't have a price based on recent enough data.
+    Stale,
+    Paused,
+    /// The circuit breaker of the asset tripped.
+    Tripped,
+    Unknown,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetPriceStatus {
+    pub asset_id: AssetId,
+    pub price: Option<Price>,
+    pub status: PriceStatus,
+}
+
+/// The price of an asset together with the time of its latest report, so a missing price can be
+/// told apart from a stale one.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetPriceV2 {
+    pub asset_id: AssetId,
+    pub price: Option<Price>,
+    /// The time of the latest report of the asset, `None` if it was never reported. EMAs and
+    /// TWAPs use the reports of their asset, indexes and pairs don't have reports.
+    pub last_report_timestamp: Option<U64>,
+    /// The asset exists, but doesn't have a price based on recent enough data.
+    pub stale: bool,
+    pub status: PriceStatus,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub enum AggregationMode {
+    Median,
+    /// Median weighted by the age of reports, see `WeightDecay`, and the weights of oracles, see
+    /// `OracleWeighting`.
+    WeightedMedian,
+}
+
+/// A recent report used to compute the aggregated price of an asset.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ContributingReport {
+    pub oracle_id: AccountId,
+    #[serde(with = "u64_dec_format")]
+    pub timestamp: Timestamp,
+    pub price: Price,
+    /// The weight of the report after the weight decay.
+    pub weight: u32,
+    /// The weight of the oracle, see `OracleWeighting`.
+    pub oracle_weight: U128,
+    pub source: PriceSource,
+}
+
+/// The aggregated price of an asset together with the reports it was computed from.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceDetails {
+    pub asset_id: AssetId,
+    #[serde(with = "u64_dec_format")]
+    pub timestamp: Timestamp,
+    pub recency_duration_sec: DurationSec,
+    pub price: Option<Price>,
+    pub mode: AggregationMode,
+    pub reports: Vec<ContributingReport>,
+    /// The distinct mechanisms the price is based on, including the DEX price if the DEX policy
+    /// of the asset uses it.
+    pub sources: Vec<PriceSource>,
+}
+
+/// Whether a report is part of the aggregated price, and why not.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub enum ReportInclusion {
+    Included,
+    /// The report is older than the recency duration of the asset.
+    Stale,
+    /// The report deviates too far from the last aggregated price.
+    Outlier,
+    /// The weight of the report or of its oracle is zero.
+    ZeroWeight,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AuditedReport {
+    pub oracle_id: AccountId,
+    #[serde(with = "u64_dec_format")]
+    pub timestamp: Timestamp,
+    pub price: Price,
+    pub inclusion: ReportInclusion,
+}
+
+/// All current reports of an asset, including the ones left out of the aggregated price.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetReports {
+    pub asset_id: AssetId,
+    #[serde(with = "u64_dec_format")]
+    pub timestamp: Timestamp,
+    pub price: Option<Price>,
+    pub mode: AggregationMode,
+    pub reports: Vec<AuditedReport>,
+}
+
+/// The spread of the reports an aggregated price is based on.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceConfidence {
+    pub min: Price,
+    pub max: Price,
+    /// The population standard deviation of the reported prices.
+    pub std_dev: Price,
+    pub num_reports: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetPriceConfidence {
+    pub asset_id: AssetId,
+    pub price: Option<Price>,
+    /// Only available for prices aggregated directly from reports.
+    pub confidence: Option<PriceConfidence>,
 }
 
+/// Returns the spread of the given prices.
+pub fn price_confidence(prices: &[Price]) -> Option<PriceConfidence> {
+    let min = *prices.iter().min()?;
+    let max = *prices.iter().max()?;
+    let decimals = prices.iter().map(|price| price.decimals).max()?;
+    let values: Vec<f64> = prices
+        .iter()
+        .map(|price| price.multiplier as f64 * 10f64.powi(decimals as i32 - price.decimals as i32))
+        .collect();
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let variance = values
+        .iter()
+        .map(|value| (value - mean).powi(2))
+        .sum::<f64>()
+        / values.len() as f64;
+    Some(PriceConfidence {
+        min,
+        max,
+        std_dev: f64_to_price(variance.sqrt(), decimals),
+        num_reports: prices.len() as u32,
+    })
+}
+
+/// The last aggregated price of an asset with its explicit expiry.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CheckedPrice {
+    pub asset_id: AssetId,
+    pub price: Price,
+    /// The time the price was aggregated.
+    #[serde(with = "u64_dec_format")]
+    pub timestamp: Timestamp,
+    pub price_ttl_sec: DurationSec,
+    /// The price must not be used at or after this time.
+    #[serde(with = "u64_dec_format")]
+    pub expires_at: Timestamp,
+}
+
+/// Stored versions of an asset. Legacy versions are upgraded when read and stored in the current
+/// version on the next write, or in batches with `migrate_legacy_assets`. Once
+/// `get_unmigrated_count` is `0`, the legacy variants can be removed.
+/// To change the layout of `Asset`, move its current layout to `legacy.rs`, add a variant for it
+/// before `Current` and convert it into the new layout.
 #[derive(BorshSerialize, BorshDeserialize)]
+#[allow(clippy::large_enum_variant)]
 pub enum VAsset {
     V0(AssetV0),
+    V1(AssetV1),
     Current(Asset),
 }
 
@@ -42,6 +479,7 @@ impl From<VAsset> for Asset {
     fn from(v: VAsset) -> Self {
         match v {
             VAsset::V0(c) => c.into(),
+            VAsset::V1(c) => c.into(),
             VAsset::Current(c) => c,
         }
     }
@@ -53,15 +491,69 @@ impl From<Asset> for VAsset {
     }
 }
 
+impl VAsset {
+    pub fn is_current(&self) -> bool {
+        matches!(self, VAsset::Current(_))
+    }
+}
+
 impl Asset {
     pub fn new() -> Self {
         Self {
             reports: Vec::new(),
             emas: Vec::new(),
+            twaps: Vec::new(),
+            group: None,
+            paused: false,
+            recency_duration_sec: None,
+            last_good: None,
+            price_ttl_sec: None,
+            min_num_recent_reports: None,
+            max_deviation_bps: None,
+            circuit_breaker: None,
+            tripped: false,
+            oracle_weighting: None,
+            price_band: None,
+            dex_source: None,
+            dex_price: None,
+            open_round: None,
+            latest_round_id: 0,
+            min_report_interval_sec: None,
+            expiry_policy: None,
+            commit_reveal: None,
+            commitments: vec![],
+            peg: None,
+            depegged_since: None,
+            quote_asset_ids: vec![],
+            consumers: None,
+            multi_block_median_len: None,
+            block_medians: vec![],
+            volatilities: vec![],
+            sequence: 0,
+            price_decimals: None,
+            deprecation: None,
+            report_intervals: Vec::new(),
+            fallback_oracle_id: None,
+            aggregated_at: 0,
+            pending_aggregation: false,
         }
     }
 
-    pub fn add_report(&mut self, report: Report) {
+    /// Adds the report, replacing the previous report of the oracle. Reports older than
+    /// `timestamp_cut` are evicted, and so is the oldest report once the asset has
+    /// `MAX_REPORTS_PER_ASSET` of them.
+    pub fn add_report(&mut self, report: Report, timestamp_cut: Timestamp) {
+        self.reports
+            .retain(|rp| rp.oracle_id != report.oracle_id && rp.timestamp >= timestamp_cut);
+        if self.reports.len() >= MAX_REPORTS_PER_ASSET {
+            let (index, _) = self
+                .reports
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, rp)| rp.timestamp)
+                .unwrap();
+            self.reports.swap_remove(index);
+        }
         self.reports.push(report);
     }
 
@@ -79,7 +571,7 @@ impl Asset {
         let mut recent_reports: Vec<_> = self
             .reports
             .iter()
-            .filter(|rp| rp.timestamp >= timestamp_cut)
+            .filter(|rp| rp.timestamp >= timestamp_cut && !self.is_outlier(rp, timestamp_cut))
             .collect();
         if recent_reports.len() < min_num_recent_reports {
             return None;
@@ -88,14 +580,269 @@ impl Asset {
         recent_reports.select_nth_unstable_by(index, |a, b| a.price.cmp(&b.price));
         recent_reports.get(index).map(|tp| tp.price)
     }
+
+    /// Records the aggregated price as the median of the block, replacing an earlier one of the
+    /// same block. The block median becomes final once a later block starts.
+    pub fn record_block_median(&mut self, block_height: BlockHeight, price: Price) {
+        let len = match self.multi_block_median_len {
+            Some(len) => len as usize,
+            None => return,
+        };
+        if let Some(block_median) = self.block_medians.last_mut() {
+            if block_median.block_height == block_height {
+                block_median.price = price;
+                return;
+            }
+        }
+        self.block_medians.push(BlockMedian {
+            block_height,
+            price,
+        });
+        // The finalized block medians and the one of the current block.
+        if self.block_medians.len() > len + 1 {
+            self.block_medians.remove(0);
+        }
+    }
+
+    /// Returns the median of the last `multi_block_median_len` block medians finalized before the
+    /// given block.
+    pub fn multi_block_median(&self, block_height: BlockHeight) -> Option<Price> {
+        let len = self.multi_block_median_len? as usize;
+        let mut prices: Vec<_> = self
+            .block_medians
+            .iter()
+            .filter(|block_median| block_median.block_height < block_height)
+            .map(|block_median| block_median.price)
+            .collect();
+        if prices.len() > len {
+            prices.drain(..prices.len() - len);
+        }
+        if prices.is_empty() {
+            return None;
+        }
+        let index = prices.len() / 2;
+        prices.select_nth_unstable(index);
+        Some(prices[index])
+    }
+
+    /// Paused assets and assets with a tripped circuit breaker don't return prices.
+    pub fn is_available(&self) -> bool {
+        !self.paused && !self.tripped
+    }
+
+    pub fn trips_circuit_breaker(&self, price: Price, timestamp: Timestamp) -> bool {
+        match (self.circuit_breaker, self.last_good) {
+            (Some(circuit_breaker), Some(last_good))
+                if last_good.timestamp + to_nano(circuit_breaker.window_sec) >= timestamp =>
+            {
+                price.deviates_from(&last_good.price, circuit_breaker.max_deviation_bps)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the report deviates too much from the last aggregated price. Reports are only
+    /// checked while the last aggregated price is recent, so the filter can't lock the price
+    /// forever.
+    pub fn is_outlier(&self, report: &Report, timestamp_cut: Timestamp) -> bool {
+        match (self.max_deviation_bps, self.last_good) {
+            (Some(max_deviation_bps), Some(last_good)) if last_good.timestamp >= timestamp_cut => {
+                report
+                    .price
+                    .deviates_from(&last_good.price, max_deviation_bps)
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the timestamp at which fewer than `min_num_recent_reports` reports remain recent,
+    /// i.e. the time when the oldest report required for the median falls out of recency.
+    pub fn staleness_deadline(
+        &self,
+        timestamp_cut: Timestamp,
+        min_num_recent_reports: usize,
+        recency_duration: Duration,
+    ) -> Option<Timestamp> {
+        let mut recent_timestamps: Vec<_> = self
+            .reports
+            .iter()
+            .map(|rp| rp.timestamp)
+            .filter(|&timestamp| timestamp >= timestamp_cut)
+            .collect();
+        if recent_timestamps.len() < min_num_recent_reports {
+            return None;
+        }
+        recent_timestamps.sort_unstable_by(|a, b| b.cmp(a));
+        recent_timestamps
+            .get(min_num_recent_reports.saturating_sub(1))
+            .map(|timestamp| timestamp + recency_duration)
+    }
 }
 
 impl Contract {
     pub fn internal_get_asset(&self, asset_id: &AssetId) -> Option<Asset> {
-        self.assets.get(asset_id).map(|v| v.into())
+        self.assets
+            .get(asset_id)
+            .or_else(|| {
+                if self.legacy_assets.is_empty() {
+                    None
+                } else {
+                    self.legacy_assets.get(asset_id)
+                }
+            })
+            .map(|v| v.into())
     }
 
+    /// Stores the asset in the current version. An asset left in the map of `ContractV0` moves
+    /// out of it.
     pub fn internal_set_asset(&mut self, asset_id: &AssetId, asset: Asset) {
-        self.assets.insert(asset_id, &asset.into());
+        if self.assets.insert(asset_id, &asset.into()).is_none() {
+            if !self.legacy_assets.is_empty() {
+                self.legacy_assets.remove(asset_id);
+            }
+            self.asset_ids.push(asset_id);
+        }
+    }
+
+    pub fn internal_remove_asset(&mut self, asset_id: &AssetId) -> Option<Asset> {
+        let asset = match self.assets.remove(asset_id) {
+            Some(asset) => {
+                let index = self
+                    .asset_ids
+                    .iter()
+                    .position(|id| &id == asset_id)
+                    .unwrap();
+                self.asset_ids.swap_remove(index as u64);
+                asset
+            }
+            None => self.legacy_assets.remove(asset_id)?,
+        };
+        Some(asset.into())
+    }
+
+    /// Removes the prices of the asset in one of its additional quote assets.
+    pub fn internal_remove_quoted_asset(&mut self, asset_id: &str, quote_asset_id: &str) {
+        let quoted_asset_id = quoted_asset_id(asset_id, quote_asset_id);
+        if let Some(quoted_asset) = self.assets.remove(&quoted_asset_id) {
+            self.internal_remove_rounds(&quoted_asset_id, &quoted_asset.into());
+        }
+        self.price_history.remove(&quoted_asset_id);
+    }
+
+    /// Returns the decimals of the asset, EMAs and TWAPs share them with their asset.
+    pub fn internal_asset_decimals(&self, asset_id: &str) -> Option<u8> {
+        self.asset_metadata
+            .get(&base_asset_id(asset_id).to_string())
+            .map(|metadata| metadata.decimals)
+    }
+
+    /// Returns the sequence number and the timestamp of the latest aggregated price of the asset.
+    /// EMAs and TWAPs share them with their asset.
+    pub fn internal_asset_sequence(&self, asset_id: &str) -> (Option<U64>, Option<U64>) {
+        self.internal_get_asset(&base_asset_id(asset_id).to_string())
+            .and_then(|asset| Some((asset.sequence, asset.last_good?.timestamp)))
+            .map_or((None, None), |(sequence, timestamp)| {
+                (Some(sequence.into()), Some(timestamp.into()))
+            })
+    }
+
+    /// Returns the deprecation of the asset, EMAs and TWAPs share it with their asset.
+    pub fn internal_asset_deprecation(&self, asset_id: &str) -> Option<AssetDeprecation> {
+        self.internal_get_asset(&base_asset_id(asset_id).to_string())
+            .and_then(|asset| asset.deprecation)
+    }
+
+    /// Returns the assets whose consumers decide who can read the price of the ID. Inverses, EMAs,
+    /// TWAPs and volatilities share them with their asset, quoted prices with the asset and the
+    /// quote asset, pairs and indexes with their components.
+    pub fn internal_access_asset_ids(&self, asset_id: &str) -> Vec<AssetId> {
+        let asset_id = inverse_asset_id(asset_id).map_or(asset_id, |(asset_id, _)| asset_id);
+        let component_ids = if let Some(components) = self.indexes.get(&asset_id.to_string()) {
+            components
+                .into_iter()
+                .map(|component| component.asset_id)
+                .collect()
+        } else if let Some(pair) = self.pairs.get(&asset_id.to_string()) {
+            vec![pair.base_asset_id, pair.quote_asset_id]
+        } else {
+            vec![asset_id.to_string()]
+        };
+        let mut access_asset_ids = vec![];
+        for component_id in &component_ids {
+            let (asset_id, quote_asset_id) = component_id.split_once('@').map_or(
+                (component_id.as_str(), None),
+                |(asset_id, quote_asset_id)| (asset_id, Some(quote_asset_id)),
+            );
+            access_asset_ids.push(base_asset_id(asset_id).to_string());
+            access_asset_ids
+                .extend(quote_asset_id.map(|quote_asset_id| quote_asset_id.to_string()));
+        }
+        access_asset_ids
+    }
+
+    /// Whether prices of the asset are restricted to consumers.
+    pub fn internal_has_consumers(&self, asset_id: &str) -> bool {
+        self.internal_access_asset_ids(asset_id)
+            .iter()
+            .any(|asset_id| self.internal_asset_consumers(asset_id).is_some())
+    }
+
+    /// Whether the account is a consumer or has an active subscription of every asset the price is
+    /// based on that has consumers.
+    pub fn internal_has_price_access(&self, asset_id: &str, account_id: &AccountId) -> bool {
+        self.internal_access_asset_ids(asset_id)
+            .iter()
+            .all(|asset_id| match self.internal_asset_consumers(asset_id) {
+                Some(consumers) => {
+                    consumers.contains(account_id)
+                        || self.internal_has_active_subscription(asset_id, account_id)
+                }
+                None => true,
+            })
+    }
+
+    fn internal_asset_consumers(&self, asset_id: &AssetId) -> Option<Vec<AccountId>> {
+        self.internal_get_asset(asset_id)
+            .and_then(|asset| asset.consumers)
+    }
+
+    /// Subscriptions only give access if pushes are paid, so access can't be bought for nothing.
+    fn internal_has_active_subscription(&self, asset_id: &AssetId, account_id: &AccountId) -> bool {
+        self.push_fee > 0
+            && self
+                .asset_subscriptions
+                .get(asset_id)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|subscription_id| self.subscriptions.get(&subscription_id))
+                .any(|subscription| {
+                    &subscription.receiver_id == account_id && subscription.balance >= self.push_fee
+                })
+    }
+
+    /// Requires the predecessor to have access to prices of the asset.
+    pub fn assert_asset_price_access(&self, asset_id: &AssetId) {
+        self.assert_price_access(std::slice::from_ref(asset_id), None);
+    }
+
+    /// Requires the account to have access to prices of all the assets. The predecessor is the
+    /// account by default, it's only read for assets with consumers, so views of public assets
+    /// keep working.
+    pub fn assert_price_access(&self, asset_ids: &[AssetId], account_id: Option<&AccountId>) {
+        let mut predecessor_id = None;
+        for asset_id in asset_ids {
+            if !self.internal_has_consumers(asset_id) {
+                continue;
+            }
+            let account_id = match account_id {
+                Some(account_id) => account_id,
+                None => predecessor_id.get_or_insert_with(env::predecessor_account_id),
+            };
+            assert!(
+                self.internal_has_price_access(asset_id, account_id),
+                "{}",
+                ContractError::NoPriceAccess(asset_id)
+            );
+        }
     }
 }
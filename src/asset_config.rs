@@ -0,0 +1,358 @@
+use crate::*;
+
+/// The configuration of an EMA of an asset without its averaged price.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EmaConfig {
+    pub period_sec: DurationSec,
+    #[serde(default)]
+    pub warm_up_sec: DurationSec,
+    #[serde(default)]
+    pub gap_policy: Option<EmaGapPolicy>,
+}
+
+/// The configuration of an asset without its price state, as exported by `export_config` and
+/// imported by `import_config`. Pause flags and the stored DEX price are state, so aren't part of
+/// it. The replacement asset of the deprecation doesn't have to exist yet.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetConfig {
+    pub asset_id: AssetId,
+    #[serde(default)]
+    pub metadata: Option<AssetMetadata>,
+    #[serde(default)]
+    pub emas: Vec<EmaConfig>,
+    #[serde(default)]
+    pub twap_periods: Vec<DurationSec>,
+    #[serde(default)]
+    pub volatility_periods: Vec<DurationSec>,
+    #[serde(default)]
+    pub recency_duration_sec: Option<DurationSec>,
+    #[serde(default)]
+    pub price_ttl_sec: Option<DurationSec>,
+    #[serde(default)]
+    pub min_num_recent_reports: Option<u32>,
+    #[serde(default)]
+    pub max_deviation_bps: Option<u32>,
+    #[serde(default)]
+    pub min_report_interval_sec: Option<DurationSec>,
+    #[serde(default)]
+    pub expiry_policy: Option<ExpiryPolicy>,
+    #[serde(default)]
+    pub oracle_weighting: Option<OracleWeighting>,
+    #[serde(default)]
+    pub circuit_breaker: Option<CircuitBreaker>,
+    #[serde(default)]
+    pub price_band: Option<PriceBand>,
+    #[serde(default)]
+    pub peg: Option<Peg>,
+    #[serde(default)]
+    pub commit_reveal: Option<CommitRevealConfig>,
+    #[serde(default)]
+    pub multi_block_median_len: Option<u32>,
+    #[serde(default)]
+    pub price_decimals: Option<u8>,
+    #[serde(default)]
+    pub quote_asset_ids: Vec<AssetId>,
+    #[serde(default)]
+    pub group: Option<GroupId>,
+    #[serde(default)]
+    pub consumers: Option<Vec<AccountId>>,
+    #[serde(default)]
+    pub dex_source: Option<DexSource>,
+    #[serde(default)]
+    pub fallback_oracle_id: Option<AccountId>,
+    #[serde(default)]
+    pub deprecation: Option<AssetDeprecation>,
+}
+
+impl AssetConfig {
+    pub fn assert_valid(&self) {
+        assert_ne!(
+            self.price_ttl_sec,
+            Some(0),
+            "{}",
+            ContractError::TtlNotPositive
+        );
+        assert_ne!(
+            self.min_num_recent_reports,
+            Some(0),
+            "{}",
+            ContractError::QuorumNotPositive
+        );
+        assert_ne!(
+            self.max_deviation_bps,
+            Some(0),
+            "{}",
+            ContractError::DeviationNotPositive
+        );
+        assert_ne!(
+            self.multi_block_median_len,
+            Some(0),
+            "{}",
+            ContractError::LengthNotPositive
+        );
+        assert!(
+            self.price_decimals.unwrap_or(0) <= MAX_VALID_DECIMALS,
+            "{}",
+            ContractError::InvalidDecimals
+        );
+        assert!(
+            self.twap_periods
+                .iter()
+                .chain(self.volatility_periods.iter())
+                .all(|period_sec| *period_sec > 0),
+            "{}",
+            ContractError::PeriodNotPositive
+        );
+        assert!(
+            self.emas
+                .iter()
+                .all(|ema| ema.gap_policy.map(|gap_policy| gap_policy.max_gap_sec()) != Some(0)),
+            "{}",
+            ContractError::MaxGapNotPositive
+        );
+        for (i, quote_asset_id) in self.quote_asset_ids.iter().enumerate() {
+            assert!(
+                is_valid_asset_id(quote_asset_id),
+                "{}",
+                ContractError::InvalidAssetId
+            );
+            assert!(
+                !self.quote_asset_ids[..i].contains(quote_asset_id),
+                "{}",
+                ContractError::QuoteAssetAlreadyExists
+            );
+        }
+        assert_ne!(
+            self.fallback_oracle_id.as_ref(),
+            Some(&env::current_account_id()),
+            "{}",
+            ContractError::InvalidFallbackOracle
+        );
+        if let Some(dex_source) = self.dex_source.as_ref() {
+            dex_source.assert_valid();
+        }
+        if let Some(circuit_breaker) = self.circuit_breaker.as_ref() {
+            circuit_breaker.assert_valid();
+        }
+        if let Some(price_band) = self.price_band.as_ref() {
+            price_band.assert_valid();
+        }
+        if let Some(peg) = self.peg.as_ref() {
+            peg.assert_valid();
+        }
+        if let Some(commit_reveal) = self.commit_reveal.as_ref() {
+            commit_reveal.assert_valid();
+        }
+    }
+
+    /// Applies the configuration to the asset, except for its quote assets and group that are
+    /// kept by the contract. EMAs, TWAPs and volatilities that are kept keep their state.
+    pub fn apply(&self, asset: &mut Asset) {
+        asset.emas.retain(|ema| {
+            self.emas
+                .iter()
+                .any(|ema_config| ema_config.period_sec == ema.period_sec)
+        });
+        for ema_config in &self.emas {
+            let index = match asset
+                .emas
+                .iter()
+                .position(|ema| ema.period_sec == ema_config.period_sec)
+            {
+                Some(index) => index,
+                None => {
+                    asset.emas.push(AssetEma::new(ema_config.period_sec));
+                    asset.emas.len() - 1
+                }
+            };
+            asset.emas[index].warm_up_sec = ema_config.warm_up_sec;
+            asset.emas[index].gap_policy = ema_config.gap_policy;
+        }
+        asset
+            .twaps
+            .retain(|twap| self.twap_periods.contains(&twap.period_sec));
+        for &period_sec in &self.twap_periods {
+            if asset.twaps.iter().all(|twap| twap.period_sec != period_sec) {
+                asset.twaps.push(AssetTwap::new(period_sec));
+            }
+        }
+        asset
+            .volatilities
+            .retain(|volatility| self.volatility_periods.contains(&volatility.period_sec));
+        for &period_sec in &self.volatility_periods {
+            if asset
+                .volatilities
+                .iter()
+                .all(|volatility| volatility.period_sec != period_sec)
+            {
+                asset.volatilities.push(AssetVolatility::new(period_sec));
+            }
+        }
+        asset.recency_duration_sec = self.recency_duration_sec;
+        asset.price_ttl_sec = self.price_ttl_sec;
+        asset.min_num_recent_reports = self.min_num_recent_reports;
+        asset.max_deviation_bps = self.max_deviation_bps;
+        asset.min_report_interval_sec = self.min_report_interval_sec;
+        asset.expiry_policy = self.expiry_policy;
+        asset.oracle_weighting = self.oracle_weighting;
+        asset.circuit_breaker = self.circuit_breaker;
+        asset.price_band = self.price_band;
+        if asset.peg.map(|peg| peg.price) != self.peg.map(|peg| peg.price) {
+            asset.depegged_since = None;
+        }
+        asset.peg = self.peg;
+        if self.commit_reveal.is_none() {
+            asset.commitments.clear();
+        }
+        asset.commit_reveal = self.commit_reveal;
+        if asset.multi_block_median_len != self.multi_block_median_len {
+            asset.block_medians.clear();
+        }
+        asset.multi_block_median_len = self.multi_block_median_len;
+        asset.price_decimals = self.price_decimals;
+        asset.consumers = self.consumers.clone();
+        asset.dex_source = self.dex_source.clone();
+        asset.dex_price = None;
+        asset.fallback_oracle_id = self.fallback_oracle_id.clone();
+        asset.deprecation = self.deprecation.clone();
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns configurations of assets in the order of `get_assets`, by default of up to
+    /// `max_assets_per_request` of them.
+    pub fn export_config(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<AssetConfig> {
+        self.internal_asset_ids(
+            from_index.unwrap_or(0),
+            limit.unwrap_or(self.max_assets_per_request),
+        )
+        .into_iter()
+        .map(|asset_id| {
+            let asset = self.internal_get_asset(&asset_id).unwrap();
+            AssetConfig {
+                metadata: self.asset_metadata.get(&asset_id),
+                emas: asset
+                    .emas
+                    .iter()
+                    .map(|ema| EmaConfig {
+                        period_sec: ema.period_sec,
+                        warm_up_sec: ema.warm_up_sec,
+                        gap_policy: ema.gap_policy,
+                    })
+                    .collect(),
+                twap_periods: asset.twaps.iter().map(|twap| twap.period_sec).collect(),
+                volatility_periods: asset
+                    .volatilities
+                    .iter()
+                    .map(|volatility| volatility.period_sec)
+                    .collect(),
+                recency_duration_sec: asset.recency_duration_sec,
+                price_ttl_sec: asset.price_ttl_sec,
+                min_num_recent_reports: asset.min_num_recent_reports,
+                max_deviation_bps: asset.max_deviation_bps,
+                min_report_interval_sec: asset.min_report_interval_sec,
+                expiry_policy: asset.expiry_policy,
+                oracle_weighting: asset.oracle_weighting,
+                circuit_breaker: asset.circuit_breaker,
+                price_band: asset.price_band,
+                peg: asset.peg,
+                commit_reveal: asset.commit_reveal,
+                multi_block_median_len: asset.multi_block_median_len,
+                price_decimals: asset.price_decimals,
+                quote_asset_ids: asset.quote_asset_ids,
+                group: asset.group,
+                consumers: asset.consumers,
+                dex_source: asset.dex_source,
+                fallback_oracle_id: asset.fallback_oracle_id,
+                deprecation: asset.deprecation,
+                asset_id,
+            }
+        })
+        .collect()
+    }
+
+    /// Adds the missing assets of the exported configurations and configures them. Existing
+    /// assets are reconfigured with `overwrite`, otherwise they're skipped. Returns the number
+    /// of configured assets.
+    #[payable]
+    pub fn import_config(&mut self, config: Vec<AssetConfig>, overwrite: bool) -> u64 {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        self.assert_no_timelock();
+        let account_id = env::predecessor_account_id();
+        let mut asset_ids = vec![];
+        for asset_config in config {
+            asset_config.assert_valid();
+            if let Some(dex_source) = asset_config.dex_source.as_ref() {
+                assert!(
+                    self.dex_contracts.contains(&dex_source.contract_id),
+                    "{}",
+                    ContractError::DexContractNotWhitelisted
+                );
+            }
+            let asset_id = asset_config.asset_id.clone();
+            let mut asset = match self.internal_get_asset(&asset_id) {
+                Some(_) if !overwrite => continue,
+                Some(asset) => asset,
+                None => {
+                    if let Some(symbol) = asset_id.strip_prefix(SYMBOL_ASSET_ID_PREFIX) {
+                        assert!(is_valid_symbol(symbol), "{}", ContractError::InvalidSymbol);
+                        self.internal_add_asset(&asset_id);
+                        self.symbols.insert(&symbol.to_string(), &asset_id);
+                    } else {
+                        assert!(
+                            is_valid_asset_id(&asset_id),
+                            "{}",
+                            ContractError::InvalidAssetId
+                        );
+                        self.internal_add_asset(&asset_id);
+                    }
+                    self.internal_get_asset(&asset_id).unwrap()
+                }
+            };
+            let initial_storage_usage = env::storage_usage();
+            match asset_config.metadata.as_ref() {
+                Some(metadata) => self.asset_metadata.insert(&asset_id, metadata),
+                None => self.asset_metadata.remove(&asset_id),
+            };
+            for quote_asset_id in &asset.quote_asset_ids {
+                if !asset_config.quote_asset_ids.contains(quote_asset_id) {
+                    self.internal_remove_quoted_asset(&asset_id, quote_asset_id);
+                }
+            }
+            for quote_asset_id in &asset_config.quote_asset_ids {
+                if !asset.quote_asset_ids.contains(quote_asset_id) {
+                    // Quoted prices aren't listed among assets, so they're inserted directly.
+                    self.assets.insert(
+                        &quoted_asset_id(&asset_id, quote_asset_id),
+                        &Asset::new().into(),
+                    );
+                }
+            }
+            asset.quote_asset_ids = asset_config.quote_asset_ids.clone();
+            if asset.group != asset_config.group {
+                if let Some(old_group) = asset.group.as_ref() {
+                    self.internal_remove_from_group(old_group, &asset_id);
+                }
+                if let Some(group) = asset_config.group.as_ref() {
+                    let mut asset_ids = self.asset_groups.get(group).unwrap_or_default();
+                    asset_ids.push(asset_id.clone());
+                    self.asset_groups.insert(group, &asset_ids);
+                }
+                asset.group = asset_config.group.clone();
+            }
+            asset_config.apply(&mut asset);
+            self.internal_set_asset(&asset_id, asset);
+            self.internal_update_storage(&account_id, initial_storage_usage);
+            asset_ids.push(asset_id);
+        }
+        Event::ImportConfig {
+            asset_ids: &asset_ids,
+        }
+        .emit();
+        asset_ids.len() as u64
+    }
+}
@@ -0,0 +1,54 @@
+use crate::*;
+use near_sdk::serde_json;
+use near_sdk::CryptoHash;
+use std::convert::TryInto;
+
+/// The commitment of the latest price data delivered by `oracle_call`, and the block it was
+/// delivered in.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceDataCommitment {
+    #[serde(with = "u64_dec_format")]
+    pub block_height: BlockHeight,
+    pub commitment: Base58CryptoHash,
+}
+
+impl PriceData {
+    /// Returns the SHA-256 hash of the JSON of the price data without the commitment, which is
+    /// how receivers get it. Verifiers recompute it the same way.
+    pub fn commitment_hash(&self) -> CryptoHash {
+        let price_data = PriceData {
+            commitment: None,
+            ..self.clone()
+        };
+        env::sha256(&serde_json::to_vec(&price_data).unwrap())
+            .try_into()
+            .unwrap()
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn get_last_price_data_commitment(&self) -> Option<PriceDataCommitment> {
+        self.last_price_data_commitment.clone()
+    }
+}
+
+impl Contract {
+    /// Sets the commitment of the price data before it's delivered and records it as the latest
+    /// one. The event keeps the commitments of earlier blocks.
+    pub fn internal_commit_price_data(&mut self, price_data: &mut PriceData) {
+        let commitment: Base58CryptoHash = price_data.commitment_hash().into();
+        let block_height = env::block_height();
+        Event::CommitPriceData {
+            block_height,
+            commitment,
+        }
+        .emit();
+        price_data.commitment = Some(commitment);
+        self.last_price_data_commitment = Some(PriceDataCommitment {
+            block_height,
+            commitment,
+        });
+    }
+}
@@ -0,0 +1,85 @@
+use crate::*;
+
+/// The number of recent inter-report intervals kept per asset.
+pub const MAX_REPORT_INTERVALS: usize = 16;
+/// The number of intervals required before the recency adapts to the cadence of the asset.
+pub const MIN_REPORT_INTERVALS: usize = 3;
+
+/// Derives the recency duration of assets from their median interval between two reports of an
+/// oracle, scaled by `multiplier_bps` and bounded by the minimum and maximum. Assets with their
+/// own recency duration keep it.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AdaptiveRecency {
+    pub min_recency_sec: DurationSec,
+    pub max_recency_sec: DurationSec,
+    pub multiplier_bps: u32,
+}
+
+impl AdaptiveRecency {
+    pub fn assert_valid(&self) {
+        assert!(
+            self.min_recency_sec > 0
+                && self.min_recency_sec <= self.max_recency_sec
+                && self.multiplier_bps > 0,
+            "{}",
+            ContractError::InvalidAdaptiveRecency
+        );
+    }
+
+    pub fn recency_sec(&self, median_report_interval_sec: DurationSec) -> DurationSec {
+        let recency_sec =
+            u64::from(median_report_interval_sec) * u64::from(self.multiplier_bps) / 10000;
+        recency_sec.clamp(
+            u64::from(self.min_recency_sec),
+            u64::from(self.max_recency_sec),
+        ) as DurationSec
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetCadence {
+    /// The median of the recent intervals between two reports of an oracle.
+    pub median_report_interval_sec: Option<DurationSec>,
+    pub effective_recency_sec: DurationSec,
+}
+
+impl Asset {
+    /// Records the interval since the previous report of the oracle, if it has one.
+    pub fn record_report_interval(&mut self, oracle_id: &AccountId, timestamp: Timestamp) {
+        if let Some(report) = self
+            .reports
+            .iter()
+            .find(|report| &report.oracle_id == oracle_id)
+        {
+            self.report_intervals
+                .push(to_sec(timestamp.saturating_sub(report.timestamp)) as DurationSec);
+            let excess = self
+                .report_intervals
+                .len()
+                .saturating_sub(MAX_REPORT_INTERVALS);
+            self.report_intervals.drain(..excess);
+        }
+    }
+
+    pub fn median_report_interval_sec(&self) -> Option<DurationSec> {
+        if self.report_intervals.len() < MIN_REPORT_INTERVALS {
+            return None;
+        }
+        let mut intervals = self.report_intervals.clone();
+        intervals.sort_unstable();
+        Some(intervals[intervals.len() / 2])
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn get_asset_cadence(&self, asset_id: AssetId) -> Option<AssetCadence> {
+        let asset = self.internal_get_asset(&asset_id)?;
+        Some(AssetCadence {
+            median_report_interval_sec: asset.median_report_interval_sec(),
+            effective_recency_sec: self.asset_recency_duration_sec(&asset),
+        })
+    }
+}
@@ -0,0 +1,113 @@
+use crate::*;
+use near_sdk::json_types::I128;
+
+/// The decimals of answers of the aggregator views, the same as of Chainlink USD feeds.
+pub const AGGREGATOR_DECIMALS: u8 = 8;
+
+/// The result of `latest_round_data` shaped like `latestRoundData` of Chainlink's
+/// `AggregatorV3Interface`. The answer is the price of one whole token of the asset with
+/// `AGGREGATOR_DECIMALS` decimals. Timestamps are in seconds.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AggregatorRoundData {
+    pub round_id: U64,
+    pub answer: I128,
+    pub started_at: U64,
+    pub updated_at: U64,
+    pub answered_in_round: U64,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the latest answer of the asset, which requires its metadata. With aggregation
+    /// rounds it's the answer of the latest round that has one, otherwise it's the current
+    /// price with the round ID `0`. There is no answer while the contract or the asset is
+    /// paused, after its circuit breaker tripped, or past its sunset.
+    pub fn latest_round_data(&self, asset_id: AssetId) -> Option<AggregatorRoundData> {
+        self.assert_asset_price_access(&asset_id);
+        let metadata = self.internal_get_aggregator_metadata(&asset_id);
+        let asset = self.internal_get_asset(&asset_id)?;
+        let timestamp = now();
+        let past_sunset = matches!(
+            &asset.deprecation,
+            Some(deprecation) if timestamp >= deprecation.sunset_timestamp
+        );
+        if self.paused || !asset.is_available() || past_sunset {
+            return None;
+        }
+        let (round_id, price, started_at, updated_at, answered_in_round) =
+            if asset.latest_round_id > 0 {
+                let oldest_round_id = asset
+                    .latest_round_id
+                    .saturating_sub(u64::from(self.round_history_len));
+                let (round, price) = (oldest_round_id + 1..=asset.latest_round_id)
+                    .rev()
+                    .filter_map(|round_id| self.rounds.get(&(asset_id.clone(), round_id)))
+                    .find_map(|round| round.answer.map(|price| (round, price)))?;
+                (
+                    asset.latest_round_id,
+                    price,
+                    round.started_at,
+                    round.ended_at,
+                    round.round_id,
+                )
+            } else {
+                let price = self.internal_get_price(&asset_id, timestamp, None)?;
+                let updated_at = asset
+                    .reports
+                    .iter()
+                    .map(|report| report.timestamp)
+                    .max()
+                    .unwrap_or(timestamp);
+                (0, price, updated_at, updated_at, 0)
+            };
+        Some(AggregatorRoundData {
+            round_id: round_id.into(),
+            answer: I128(aggregator_answer(&price, metadata.decimals)? as i128),
+            started_at: to_sec(started_at).into(),
+            updated_at: to_sec(updated_at).into(),
+            answered_in_round: answered_in_round.into(),
+        })
+    }
+
+    /// Returns the decimals of answers of the asset.
+    pub fn decimals(&self, asset_id: AssetId) -> u8 {
+        self.internal_get_aggregator_metadata(&asset_id);
+        AGGREGATOR_DECIMALS
+    }
+
+    /// Returns the description of the asset, e.g. `NEAR / USD`.
+    pub fn description(&self, asset_id: AssetId) -> String {
+        let metadata = self.internal_get_aggregator_metadata(&asset_id);
+        format!(
+            "{} / {}",
+            metadata.symbol,
+            metadata.quote_asset_id.to_uppercase()
+        )
+    }
+}
+
+impl Contract {
+    fn internal_get_aggregator_metadata(&self, asset_id: &AssetId) -> AssetMetadata {
+        self.asset_metadata
+            .get(asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAssetMetadata(asset_id)))
+    }
+}
+
+/// Returns the price of `10**token_decimals` units with `AGGREGATOR_DECIMALS` decimals, or `None`
+/// if it doesn't fit into `i128`.
+pub fn aggregator_answer(price: &Price, token_decimals: u8) -> Option<u128> {
+    let exponent =
+        i32::from(token_decimals) + i32::from(AGGREGATOR_DECIMALS) - i32::from(price.decimals);
+    let answer = if exponent >= 0 {
+        price
+            .multiplier
+            .checked_mul(10u128.checked_pow(exponent as u32)?)?
+    } else {
+        10u128
+            .checked_pow(-exponent as u32)
+            .map_or(0, |divisor| price.multiplier / divisor)
+    };
+    Some(answer).filter(|answer| *answer <= i128::MAX as u128)
+}
@@ -0,0 +1,157 @@
+use crate::*;
+use near_sdk::json_types::Base58CryptoHash;
+use near_sdk::CryptoHash;
+use std::convert::TryInto;
+
+/// Two-phase reporting of an asset. Time is split into epochs of a commit phase followed by a
+/// reveal phase. Oracles commit to hashes of their prices during the commit phase and reveal the
+/// prices during the reveal phase of the same epoch. Only revealed prices are accepted for the
+/// asset.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CommitRevealConfig {
+    pub commit_duration_sec: DurationSec,
+    pub reveal_duration_sec: DurationSec,
+}
+
+#[derive(PartialEq)]
+pub enum CommitRevealPhase {
+    Commit,
+    Reveal,
+}
+
+impl CommitRevealConfig {
+    pub fn assert_valid(&self) {
+        assert!(
+            self.commit_duration_sec > 0 && self.reveal_duration_sec > 0,
+            "Phase durations must be positive"
+        );
+    }
+
+    /// Returns the epoch and the phase at the given time.
+    pub fn phase(&self, timestamp: Timestamp) -> (u64, CommitRevealPhase) {
+        let epoch_duration = to_nano(self.commit_duration_sec + self.reveal_duration_sec);
+        let phase = if timestamp % epoch_duration < to_nano(self.commit_duration_sec) {
+            CommitRevealPhase::Commit
+        } else {
+            CommitRevealPhase::Reveal
+        };
+        (timestamp / epoch_duration, phase)
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Commitment {
+    pub oracle_id: AccountId,
+    pub hash: CryptoHash,
+    pub epoch: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceCommitment {
+    pub asset_id: AssetId,
+    /// The SHA-256 hash of the borsh-serialized `(asset_id, price, salt)`.
+    pub hash: Base58CryptoHash,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceReveal {
+    pub asset_id: AssetId,
+    pub price: Price,
+    pub salt: String,
+}
+
+/// Returns the hash an oracle commits to before revealing the price.
+pub fn commitment_hash(asset_id: &AssetId, price: &Price, salt: &str) -> CryptoHash {
+    env::sha256(&(asset_id, price, salt).try_to_vec().unwrap())
+        .try_into()
+        .unwrap()
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Commits to prices of assets with the commit-reveal reporting. A new commitment replaces
+    /// the previous one of the oracle.
+    pub fn commit_prices(&mut self, commitments: Vec<PriceCommitment>) {
+        self.assert_not_paused();
+        let oracle_id = env::predecessor_account_id();
+        let oracle = self.internal_get_oracle(&oracle_id).expect("Not an oracle");
+        assert!(self.has_min_stake(&oracle), "Insufficient stake");
+        let timestamp = now();
+        for PriceCommitment { asset_id, hash } in commitments {
+            let mut asset = self
+                .internal_get_asset(&asset_id)
+                .expect("Missing an asset");
+            let (epoch, phase) = asset
+                .commit_reveal
+                .expect("The asset doesn't use commit-reveal")
+                .phase(timestamp);
+            assert!(
+                phase == CommitRevealPhase::Commit,
+                "Not in the commit phase"
+            );
+            // Commitments of past epochs can't be revealed anymore.
+            asset.commitments.retain(|commitment| {
+                commitment.oracle_id != oracle_id && commitment.epoch == epoch
+            });
+            asset.commitments.push(Commitment {
+                oracle_id: oracle_id.clone(),
+                hash: hash.into(),
+                epoch,
+            });
+            self.internal_set_asset(&asset_id, asset);
+        }
+    }
+
+    /// Reveals prices committed in the commit phase of the current epoch and reports them.
+    pub fn reveal_prices(&mut self, reveals: Vec<PriceReveal>) {
+        self.assert_not_paused();
+        assert!(!reveals.is_empty());
+        let oracle_id = env::predecessor_account_id();
+        let oracle = self.internal_get_oracle(&oracle_id).expect("Not an oracle");
+        assert!(self.has_min_stake(&oracle), "Insufficient stake");
+        let timestamp = now();
+        let mut prices = vec![];
+        for PriceReveal {
+            asset_id,
+            price,
+            salt,
+        } in reveals
+        {
+            let mut asset = self
+                .internal_get_asset(&asset_id)
+                .expect("Missing an asset");
+            let (epoch, phase) = asset
+                .commit_reveal
+                .expect("The asset doesn't use commit-reveal")
+                .phase(timestamp);
+            assert!(
+                phase == CommitRevealPhase::Reveal,
+                "Not in the reveal phase"
+            );
+            let index = asset
+                .commitments
+                .iter()
+                .position(|commitment| {
+                    commitment.oracle_id == oracle_id && commitment.epoch == epoch
+                })
+                .expect("Missing a commitment");
+            assert_eq!(
+                asset.commitments.swap_remove(index).hash,
+                commitment_hash(&asset_id, &price, &salt),
+                "The price doesn't match the commitment"
+            );
+            self.internal_set_asset(&asset_id, asset);
+            prices.push(AssetPrice {
+                asset_id,
+                price,
+                quote_asset_id: None,
+                nonce: None,
+            });
+        }
+        self.internal_report_prices(&oracle_id, oracle, prices, None, PriceSource::CommitReveal);
+    }
+}
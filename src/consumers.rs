@@ -0,0 +1,94 @@
+use crate::*;
+use near_sdk::PromiseResult;
+
+/// The gas of the callback of every receiver call.
+pub const GAS_FOR_ON_ORACLE_CALL_COMPLETE: Gas = Gas(Gas::ONE_TERA.0 * 10);
+
+/// Price requests of a consumer, the account paying for them.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ConsumerStats {
+    pub num_calls: u64,
+    /// Calls where the receiver failed.
+    pub num_failed_calls: u64,
+    /// NEAR fees of the calls, fees paid in tokens aren't included.
+    #[serde(with = "u128_dec_format")]
+    pub fees_paid: Balance,
+    #[serde(with = "u128_dec_format")]
+    pub fees_refunded: Balance,
+}
+
+#[ext_contract(ext_oracle_call_callback)]
+pub trait ExtOracleCallCallback {
+    fn on_oracle_call_complete(&mut self, sender_id: AccountId, receiver_id: AccountId, fee: U128);
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn get_consumer_stats(&self, account_id: AccountId) -> Option<ConsumerStats> {
+        self.consumer_stats.get(&account_id)
+    }
+
+    /// Records whether the receiver succeeded and forwards its result. The NEAR fee of a failed
+    /// call is refunded if the owner enabled refunds.
+    #[private]
+    pub fn on_oracle_call_complete(
+        &mut self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        fee: U128,
+    ) {
+        let result = match env::promise_result(0) {
+            PromiseResult::Successful(value) => Some(value),
+            _ => None,
+        };
+        if let Some(value) =
+            self.internal_on_oracle_call_complete(sender_id, receiver_id, fee.0, result)
+        {
+            env::value_return(&value);
+        }
+    }
+}
+
+impl Contract {
+    pub fn internal_record_consumer_call(&mut self, sender_id: &AccountId, fee: Balance) {
+        let mut stats = self.consumer_stats.get(sender_id).unwrap_or_default();
+        stats.num_calls += 1;
+        stats.fees_paid += fee;
+        self.consumer_stats.insert(sender_id, &stats);
+    }
+
+    /// Returns the result of the receiver, `None` if it failed.
+    pub fn internal_on_oracle_call_complete(
+        &mut self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        fee: Balance,
+        result: Option<Vec<u8>>,
+    ) -> Option<Vec<u8>> {
+        let success = result.is_some();
+        let mut refund = 0;
+        if !success {
+            let mut stats = self.consumer_stats.get(&sender_id).unwrap_or_default();
+            stats.num_failed_calls += 1;
+            if self.refund_failed_calls {
+                // The fees may have been withdrawn in the meantime.
+                refund = std::cmp::min(fee, self.collected_fees);
+                stats.fees_refunded += refund;
+                self.collected_fees -= refund;
+            }
+            self.consumer_stats.insert(&sender_id, &stats);
+        }
+        Event::OracleCallComplete {
+            sender_id: &sender_id,
+            receiver_id: &receiver_id,
+            success,
+            refund: U128(refund),
+        }
+        .emit();
+        if refund > 0 {
+            Promise::new(sender_id).transfer(refund);
+        }
+        result
+    }
+}
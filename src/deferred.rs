@@ -0,0 +1,35 @@
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Aggregates and publishes prices of the assets that got reports since their last
+    /// aggregation, when the aggregation is deferred. Can be called by anyone. Returns the IDs
+    /// of the aggregated assets.
+    pub fn finalize_prices(&mut self, asset_ids: Vec<AssetId>) -> Vec<AssetId> {
+        self.assert_not_paused();
+        assert!(
+            self.aggregation_window_sec.is_some(),
+            "The aggregation isn't deferred"
+        );
+        let timestamp = now();
+        let mut finalized_asset_ids = vec![];
+        let mut price_changes = vec![];
+        for asset_id in asset_ids {
+            let mut asset = self
+                .internal_get_asset(&asset_id)
+                .unwrap_or_else(|| panic!("{}", ContractError::MissingAssetId(&asset_id)));
+            if !asset.pending_aggregation {
+                continue;
+            }
+            self.internal_publish_price(&asset_id, &mut asset, timestamp, &mut price_changes);
+            self.internal_set_asset(&asset_id, asset);
+            finalized_asset_ids.push(asset_id);
+        }
+        Event::FinalizePrices {
+            asset_ids: &finalized_asset_ids,
+        }
+        .emit();
+        self.internal_emit_price_changes(price_changes);
+        finalized_asset_ids
+    }
+}
@@ -0,0 +1,122 @@
+use crate::*;
+use ed25519_dalek::Verifier;
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::CurveType;
+
+/// Prices signed by an oracle and submitted to the contract by a relayer.
+///
+/// The signature is an ed25519 signature of the Borsh-serialized tuple
+/// `(contract_id, oracle_id, timestamp, prices)` made with the oracle's registered public key.
+/// The signed timestamp has to be recent and greater than the timestamp of the previous relayed
+/// report of this oracle, which prevents replaying signed prices.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DelegatedReport {
+    pub oracle_id: AccountId,
+    #[serde(with = "u64_dec_format")]
+    pub timestamp: Timestamp,
+    pub prices: Vec<AssetPrice>,
+    pub signature: Base64VecU8,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Sets the ED25519 public key used to verify prices relayed on behalf of the calling oracle.
+    /// Passing `None` disables delegated reporting for the oracle.
+    pub fn set_oracle_public_key(&mut self, public_key: Option<PublicKey>) {
+        let oracle_id = env::predecessor_account_id();
+        let mut oracle = self.internal_get_oracle(&oracle_id).expect("Not an oracle");
+        if let Some(public_key) = public_key.as_ref() {
+            assert!(
+                matches!(public_key.curve_type(), CurveType::ED25519),
+                "Only ED25519 keys are supported"
+            );
+        }
+        oracle.public_key = public_key;
+        self.internal_set_oracle(&oracle_id, oracle);
+    }
+
+    /// Records prices signed by multiple oracles in one call. Can only be called by a relayer.
+    /// Entries with invalid signatures are skipped with a warning, unless `fail_on_invalid` is
+    /// set, in which case the whole call panics.
+    pub fn report_prices_delegated(
+        &mut self,
+        reports: Vec<DelegatedReport>,
+        fail_on_invalid: Option<bool>,
+    ) {
+        self.assert_not_paused();
+        assert!(
+            self.relayers.contains(&env::predecessor_account_id()),
+            "Not a relayer"
+        );
+        let fail_on_invalid = fail_on_invalid.unwrap_or(false);
+        for report in reports {
+            match self.internal_verify_delegated_report(&report) {
+                Ok(mut oracle) => {
+                    oracle.last_signed_timestamp = report.timestamp;
+                    self.internal_report_prices(
+                        &report.oracle_id,
+                        oracle,
+                        report.prices,
+                        None,
+                        PriceSource::Delegated,
+                    );
+                }
+                Err(err) => {
+                    if fail_on_invalid {
+                        panic!("Invalid report of {}: {}", report.oracle_id, err);
+                    }
+                    log!("Warning! Skipping report of {}: {}", report.oracle_id, err);
+                }
+            }
+        }
+    }
+
+    pub fn get_relayers(&self) -> Vec<AccountId> {
+        self.relayers.to_vec()
+    }
+}
+
+impl Contract {
+    fn internal_verify_delegated_report(
+        &self,
+        report: &DelegatedReport,
+    ) -> Result<Oracle, &'static str> {
+        if report.prices.is_empty() {
+            return Err("No prices");
+        }
+        let oracle = self
+            .internal_get_oracle(&report.oracle_id)
+            .ok_or("Not an oracle")?;
+        if !self.has_min_stake(&oracle) {
+            return Err("Insufficient stake");
+        }
+        if report.timestamp <= oracle.last_signed_timestamp {
+            return Err("Replayed report");
+        }
+        let timestamp = now();
+        if report.timestamp > timestamp
+            || report.timestamp + to_nano(self.recency_duration_sec) < timestamp
+        {
+            return Err("Report timestamp is outside of the recency window");
+        }
+        let public_key = oracle.public_key.as_ref().ok_or("Missing public key")?;
+        // The first byte of the key is the curve type.
+        let public_key = ed25519_dalek::PublicKey::from_bytes(&public_key.as_bytes()[1..])
+            .map_err(|_| "Invalid public key")?;
+        let signature = ed25519_dalek::Signature::from_bytes(&report.signature.0)
+            .map_err(|_| "Invalid signature")?;
+        let message = (
+            env::current_account_id(),
+            &report.oracle_id,
+            report.timestamp,
+            &report.prices,
+        )
+            .try_to_vec()
+            .unwrap();
+        public_key
+            .verify(&message, &signature)
+            .map_err(|_| "Invalid signature")?;
+        Ok(oracle)
+    }
+}
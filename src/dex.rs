@@ -0,0 +1,166 @@
+use crate::*;
+use near_sdk::PromiseError;
+
+const GAS_FOR_GET_RETURN: Gas = Gas(Gas::ONE_TERA.0 * 10);
+const GAS_FOR_ON_DEX_PRICE: Gas = Gas(Gas::ONE_TERA.0 * 10);
+
+/// How the DEX price of an asset is combined with the median of reports.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy)]
+#[serde(crate = "near_sdk::serde")]
+pub enum DexPolicy {
+    /// The DEX price is only recorded, the median of reports is returned.
+    MedianOnly,
+    /// The DEX price is returned instead of the median of reports.
+    DexOnly,
+    /// The median of reports is returned only if the DEX price is within `max_deviation_bps` of
+    /// it.
+    RequireAgreement { max_deviation_bps: u32 },
+}
+
+/// A pool of a whitelisted AMM contract with the Ref Finance `get_return` view. The DEX price of
+/// the asset is `Price { multiplier: amount_out, decimals }`, where `amount_out` is the return of
+/// swapping `amount_in` of `token_in` into `token_out`, e.g. for 1 NEAR (`10**24`) swapped into
+/// USDC with 6 decimals, `decimals` are `30`.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DexSource {
+    pub contract_id: AccountId,
+    pub pool_id: u64,
+    pub token_in: AccountId,
+    pub amount_in: U128,
+    pub token_out: AccountId,
+    pub decimals: u8,
+    pub policy: DexPolicy,
+}
+
+impl DexSource {
+    pub fn assert_valid(&self) {
+        assert!(self.amount_in.0 > 0, "{}", ContractError::InvalidAmount);
+        Price {
+            multiplier: 0,
+            decimals: self.decimals,
+        }
+        .assert_valid();
+    }
+}
+
+#[ext_contract(ext_dex)]
+pub trait ExtDex {
+    fn get_return(
+        &self,
+        pool_id: u64,
+        token_in: AccountId,
+        amount_in: U128,
+        token_out: AccountId,
+    ) -> U128;
+}
+
+#[ext_contract(ext_dex_callback)]
+pub trait ExtDexCallback {
+    fn on_dex_price(&mut self, asset_id: AssetId);
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Fetches the DEX price of the asset from its pool. Can be called by anyone.
+    pub fn refresh_dex_price(&mut self, asset_id: AssetId) -> Promise {
+        self.assert_not_paused();
+        let asset = self
+            .internal_get_asset(&asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        let dex_source = asset
+            .dex_source
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingDexSource));
+        assert!(
+            self.dex_contracts.contains(&dex_source.contract_id),
+            "{}",
+            ContractError::DexContractNotWhitelisted
+        );
+        ext_dex::get_return(
+            dex_source.pool_id,
+            dex_source.token_in,
+            dex_source.amount_in,
+            dex_source.token_out,
+            dex_source.contract_id,
+            NO_DEPOSIT,
+            GAS_FOR_GET_RETURN,
+        )
+        .then(ext_dex_callback::on_dex_price(
+            asset_id,
+            env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_ON_DEX_PRICE,
+        ))
+    }
+
+    /// Records the DEX price of the asset, unless the DEX source changed since the request.
+    #[private]
+    pub fn on_dex_price(
+        &mut self,
+        asset_id: AssetId,
+        #[callback_result] amount_out: Result<U128, PromiseError>,
+    ) {
+        let amount_out = match amount_out {
+            Ok(amount_out) => amount_out,
+            Err(_) => {
+                log!("Warning! Failed to fetch the DEX price of {}", asset_id);
+                return;
+            }
+        };
+        if let Some(mut asset) = self.internal_get_asset(&asset_id) {
+            if let Some(dex_source) = asset.dex_source.as_ref() {
+                let price = Price {
+                    multiplier: amount_out.0,
+                    decimals: dex_source.decimals,
+                };
+                asset.dex_price = Some(PricePoint {
+                    price,
+                    timestamp: now(),
+                    block_height: env::block_height(),
+                });
+                self.internal_set_asset(&asset_id, asset);
+                Event::UpdateDexPrice {
+                    asset_id: &asset_id,
+                    price,
+                }
+                .emit();
+            }
+        }
+    }
+
+    pub fn get_dex_contracts(&self) -> Vec<AccountId> {
+        self.dex_contracts.to_vec()
+    }
+}
+
+impl Contract {
+    /// Combines the price aggregated from reports with the recent DEX price according to the DEX
+    /// policy of the asset.
+    pub fn internal_apply_dex_policy(
+        &self,
+        asset: &Asset,
+        price: Option<Price>,
+        timestamp_cut: Timestamp,
+    ) -> Option<Price> {
+        let dex_source = match asset.dex_source.as_ref() {
+            Some(dex_source) => dex_source,
+            None => return price,
+        };
+        let dex_price = asset
+            .dex_price
+            .filter(|point| point.timestamp >= timestamp_cut)
+            .map(|point| point.price);
+        match dex_source.policy {
+            DexPolicy::MedianOnly => price,
+            DexPolicy::DexOnly => dex_price,
+            DexPolicy::RequireAgreement { max_deviation_bps } => {
+                let (price, dex_price) = (price?, dex_price?);
+                if dex_price.deviates_from(&price, max_deviation_bps) {
+                    None
+                } else {
+                    Some(price)
+                }
+            }
+        }
+    }
+}
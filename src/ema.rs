@@ -2,6 +2,9 @@ use crate::*;
 
 const MAX_F64_FOR_PRECISE_MULTIPLIER: f64 = 1e30;
 
+/// The decimals of volatilities, e.g. `5%` is `50_000_000_000` with 12 decimals.
+pub const VOLATILITY_DECIMALS: u8 = 12;
+
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub struct AssetEma {
@@ -9,6 +12,43 @@ pub struct AssetEma {
     #[serde(with = "u64_dec_format")]
     pub timestamp: Timestamp,
     pub price: Option<Price>,
+    /// When the EMA got its first price after it was added or reset.
+    #[serde(with = "u64_dec_format")]
+    pub started_at: Timestamp,
+    /// For how long after the start the EMA isn't returned.
+    pub warm_up_sec: DurationSec,
+    /// How a long gap between aggregated prices is handled. Without it, the whole gap counts.
+    #[serde(default)]
+    pub gap_policy: Option<EmaGapPolicy>,
+}
+
+/// How an EMA handles a gap of more than `max_gap_sec` since its last update, e.g. after a
+/// downtime of the oracles.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub enum EmaGapPolicy {
+    /// The gap counts as `max_gap_sec`.
+    Cap { max_gap_sec: DurationSec },
+    /// The EMA starts over from the new price, including the warm-up.
+    Reset { max_gap_sec: DurationSec },
+}
+
+impl EmaGapPolicy {
+    pub fn max_gap_sec(&self) -> DurationSec {
+        match self {
+            EmaGapPolicy::Cap { max_gap_sec } | EmaGapPolicy::Reset { max_gap_sec } => *max_gap_sec,
+        }
+    }
+}
+
+/// The state of an EMA along with whether its warm-up is over.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EmaState {
+    #[serde(flatten)]
+    pub ema: AssetEma,
+    pub warmed_up: bool,
 }
 
 impl AssetEma {
@@ -17,39 +57,125 @@ impl AssetEma {
             period_sec,
             timestamp: 0,
             price: None,
+            started_at: 0,
+            warm_up_sec: 0,
+            gap_policy: None,
         }
     }
 
+    /// Discards the averaged price, the next aggregated price starts the EMA over.
+    pub fn reset(&mut self) {
+        self.timestamp = 0;
+        self.price = None;
+        self.started_at = 0;
+    }
+
+    pub fn is_warmed_up(&self, timestamp: Timestamp) -> bool {
+        self.price.is_some() && self.started_at + to_nano(self.warm_up_sec) <= timestamp
+    }
+
     pub fn recompute(&mut self, median_price: Price, timestamp: Timestamp) {
+        let gap_policy = self.gap_policy.filter(|gap_policy| {
+            self.price.is_some() && timestamp - self.timestamp > to_nano(gap_policy.max_gap_sec())
+        });
+        if let Some(EmaGapPolicy::Reset { .. }) = gap_policy {
+            self.reset();
+        }
         if let Some(current) = self.price.as_mut() {
-            let time_diff = timestamp - self.timestamp;
+            let time_diff = match gap_policy {
+                Some(EmaGapPolicy::Cap { max_gap_sec }) => to_nano(max_gap_sec),
+                _ => timestamp - self.timestamp,
+            };
             // Based on https://stackoverflow.com/questions/1023860/exponential-moving-average-sampled-at-varying-times
             let alpha =
                 1.0f64 - (-2.0f64 * time_diff as f64 / to_nano(self.period_sec) as f64).exp();
             let mut current_f64 = current.multiplier as f64;
             current_f64 *= 10f64.powi(median_price.decimals as i32 - current.decimals as i32);
             current_f64 += alpha * (median_price.multiplier as f64 - current_f64);
-            if current_f64 <= MAX_F64_FOR_PRECISE_MULTIPLIER {
-                *current = Price {
-                    multiplier: (current_f64 * 1e4).round() as u128,
-                    decimals: median_price.decimals + 4,
-                }
-            } else {
-                *current = Price {
-                    multiplier: current_f64.round() as u128,
-                    decimals: median_price.decimals,
-                }
-            }
+            *current = f64_to_price(current_f64, median_price.decimals);
         } else {
             self.price = Some(median_price);
+            self.started_at = timestamp;
         }
         self.timestamp = timestamp;
     }
 }
 
+/// The realized volatility of an asset, tracked as the EWMA of squared log returns of its
+/// aggregated prices. The volatility is the standard deviation of returns over `period_sec` and
+/// is returned as a price with `VOLATILITY_DECIMALS`.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetVolatility {
+    pub period_sec: DurationSec,
+    #[serde(with = "u64_dec_format")]
+    pub timestamp: Timestamp,
+    pub last_price: Option<Price>,
+    /// The averaged squared log return per second, set after the first return.
+    pub variance_rate: Option<f64>,
+}
+
+impl AssetVolatility {
+    pub fn new(period_sec: DurationSec) -> AssetVolatility {
+        Self {
+            period_sec,
+            timestamp: 0,
+            last_price: None,
+            variance_rate: None,
+        }
+    }
+
+    pub fn recompute(&mut self, price: Price, timestamp: Timestamp) {
+        if price.multiplier == 0 {
+            return;
+        }
+        if let Some(last_price) = self.last_price {
+            let time_diff = timestamp - self.timestamp;
+            // Prices of the same block are merged into the next return.
+            if time_diff == 0 {
+                return;
+            }
+            let log_return = (price.multiplier as f64).ln() - (last_price.multiplier as f64).ln()
+                + (last_price.decimals as f64 - price.decimals as f64) * 10f64.ln();
+            let sample = log_return * log_return / (time_diff as f64 / 1e9);
+            let alpha =
+                1.0f64 - (-2.0f64 * time_diff as f64 / to_nano(self.period_sec) as f64).exp();
+            let variance_rate = self
+                .variance_rate
+                .map_or(sample, |current| current + alpha * (sample - current));
+            self.variance_rate = Some(variance_rate);
+        }
+        self.last_price = Some(price);
+        self.timestamp = timestamp;
+    }
+
+    pub fn volatility(&self) -> Option<Price> {
+        let volatility = (self.variance_rate? * self.period_sec as f64).sqrt();
+        Some(Price {
+            multiplier: (volatility * 10f64.powi(VOLATILITY_DECIMALS as i32)).round() as u128,
+            decimals: VOLATILITY_DECIMALS,
+        })
+    }
+}
+
+/// Converts an averaged multiplier back to a price, keeping 4 extra decimals when it fits.
+pub fn f64_to_price(multiplier: f64, decimals: u8) -> Price {
+    if multiplier <= MAX_F64_FOR_PRECISE_MULTIPLIER {
+        Price {
+            multiplier: (multiplier * 1e4).round() as u128,
+            decimals: decimals + 4,
+        }
+    } else {
+        Price {
+            multiplier: multiplier.round() as u128,
+            decimals,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{to_nano, AssetEma, Price};
+    use crate::{to_nano, AssetEma, AssetVolatility, EmaGapPolicy, Price, VOLATILITY_DECIMALS};
     use approx::assert_relative_eq;
     use near_sdk::Timestamp;
 
@@ -69,9 +195,8 @@ mod tests {
     #[test]
     pub fn test_ema_init() {
         let mut ema = AssetEma {
-            period_sec: 60000,
             timestamp: ts(0),
-            price: None,
+            ..AssetEma::new(60000)
         };
         let timestamp = ts(10);
         let price = mp(100000);
@@ -80,6 +205,30 @@ mod tests {
         assert_eq!(ema.price, Some(price));
     }
 
+    #[test]
+    pub fn test_ema_gap_policy() {
+        let mut capped = AssetEma {
+            gap_policy: Some(EmaGapPolicy::Cap { max_gap_sec: 60 }),
+            ..AssetEma::new(600)
+        };
+        let mut reset = AssetEma {
+            gap_policy: Some(EmaGapPolicy::Reset { max_gap_sec: 60 }),
+            ..AssetEma::new(600)
+        };
+        let mut uncapped = AssetEma::new(600);
+        for ema in [&mut capped, &mut reset, &mut uncapped] {
+            ema.recompute(mp(100000), ts(0));
+            ema.recompute(mp(200000), ts(3600));
+        }
+        let mut expected = AssetEma::new(600);
+        expected.recompute(mp(100000), ts(0));
+        expected.recompute(mp(200000), ts(60));
+        assert_eq!(capped.price, expected.price);
+        assert_eq!(reset.price, Some(mp(200000)));
+        assert_eq!(reset.started_at, ts(3600));
+        assert!(uncapped.price.unwrap().multiplier > capped.price.unwrap().multiplier);
+    }
+
     #[test]
     pub fn test_ema_period() {
         let price_multipliers = vec![
@@ -95,9 +244,8 @@ mod tests {
         let step = 60;
         let period_sec = step * 10;
         let mut ema = AssetEma {
-            period_sec,
             timestamp: ts(0),
-            price: None,
+            ..AssetEma::new(period_sec)
         };
         for (i, (multiplier, expected_ema)) in
             price_multipliers.into_iter().zip(expected_emas).enumerate()
@@ -114,4 +262,24 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    pub fn test_volatility() {
+        let step = 60;
+        let mut volatility = AssetVolatility::new(3600);
+        volatility.recompute(mp(100000), ts(0));
+        assert!(volatility.volatility().is_none());
+        for i in 1..=200 {
+            let multiplier = if i % 2 == 0 { 100000 } else { 110000 };
+            volatility.recompute(mp(multiplier), ts(step * i));
+        }
+        let price = volatility.volatility().unwrap();
+        assert_eq!(price.decimals, VOLATILITY_DECIMALS);
+        let expected = (1.1f64.ln().powi(2) / step as f64 * 3600.0).sqrt();
+        assert_relative_eq!(
+            price.multiplier as f64 / 10f64.powi(VOLATILITY_DECIMALS as i32),
+            expected,
+            epsilon = 1e-6
+        );
+    }
 }
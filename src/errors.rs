@@ -0,0 +1,466 @@
+use crate::*;
+use std::fmt;
+
+/// The errors the contract panics with. The panic message starts with the stable code of the
+/// error, e.g. `E001_NOT_AN_ORACLE: Not an oracle`, so integrators can match on the code.
+/// New errors get new codes, existing codes never change.
+#[derive(Clone, Copy)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub enum ContractError<'a> {
+    NotAnOracle,
+    OracleAlreadyExists,
+    MissingAsset,
+    MissingAssetId(&'a str),
+    UnknownAsset,
+    AssetAlreadyExists,
+    InvalidAssetId,
+    MaxAssetsReached(u64),
+    MaxAssetsBelowNumAssets,
+    Paused,
+    OracleSuspended,
+    InsufficientStake,
+    RequiresPrices,
+    NoPriceAccess(&'a str),
+    MissingPrice(&'a str),
+    StalePrice(&'a str),
+    NotOwner,
+    NotProposedOwner,
+    InvalidConfirmation,
+    RequiresDeposit(Balance),
+    NotEnoughGas,
+    ReceiverGasTooLow(u64),
+    RequiresReceivers,
+    RequiresActions,
+    NothingToWithdraw,
+    NotEnoughCollectedFees,
+    PeriodNotPositive,
+    QuorumNotPositive,
+    DeviationNotPositive,
+    TtlNotPositive,
+    LengthNotPositive,
+    WeightNotPositive,
+    InvalidDecimals,
+    RequiresRoundHistory,
+    InvalidPriceBand,
+    AssetNotTripped,
+    RelayerAlreadyExists,
+    NotARelayer,
+    DexContractAlreadyExists,
+    DexContractNotWhitelisted,
+    MissingGroup,
+    MissingQuoteAsset,
+    QuoteAssetAlreadyExists,
+    MissingIndex,
+    InvalidIndexId,
+    IndexAlreadyExists,
+    IndexRequiresComponents,
+    MissingPair,
+    InvalidPairId,
+    PairAlreadyExists,
+    MissingBundle,
+    InvalidBundleId,
+    BundleRequiresAssets,
+    BundleTooLarge(u64),
+    RequiresAssetIdsOrBundle,
+    EmaAlreadyExists,
+    MissingEma,
+    UnknownEmaPeriod(&'a str),
+    InvalidEmaPeriod,
+    TwapAlreadyExists,
+    MissingTwap,
+    UnknownTwapPeriod(&'a str),
+    InvalidTwapPeriod,
+    VolatilityAlreadyExists,
+    MissingVolatility,
+    UnknownVolatilityPeriod(&'a str),
+    InvalidVolatilityPeriod,
+    MirrorAlreadyExists,
+    NotAMirror,
+    SunsetNotInFuture,
+    MaxGapNotPositive,
+    InvalidSymbol,
+    SymbolAlreadyRegistered,
+    TooManyAssetsRequested(u64),
+    InvalidMaxAssetsPerRequest(u64),
+    InvalidAdaptiveRecency,
+    InvalidFallbackOracle,
+    InvalidRate,
+    RateFeedAlreadyExists,
+    MissingRateFeed,
+    InvalidThreshold,
+    MissingProposal,
+    ProposalExpired,
+    AlreadyConfirmed,
+    ProposalNotExpired,
+    MultisigDisabled,
+    NotAMultisigAdmin,
+    MissingAssetMetadata(&'a str),
+    AssetUsedByIndex(&'a str),
+    PushFeeRequired,
+    TooManySubscriptions(usize),
+    InvalidCallback,
+    NotEnoughPrepaidGas(u64),
+    AssetUsedByPair(&'a str),
+    RoleAlreadyGranted,
+    MissingRole,
+    RequiresRole(Role),
+    ProposalTimelocked,
+    RequiresTimelockedProposal,
+    DeployTimeBeforeTimelock,
+    NoStagedCode,
+    StagedCodeNotDeployable,
+    StagedCodeMismatch,
+    NotEnoughGasForMigration,
+    NotInitialized,
+    InvalidAmount,
+    StakeUnbonding,
+    NotRegistered(&'a str),
+    NotEnoughStorageBalance,
+    ForceUnregisterNotSupported,
+    StorageInUse,
+    NotEnoughStorageDeposit(&'a str),
+    MissingSubscription,
+    NotSubscriber,
+    MissingPriceRequest,
+    PriceRequestFulfilled,
+    OracleFulfilledRequest,
+    NotConsumer,
+    TokenNotAccepted,
+    InvalidMessage,
+    RequiresTokenFee(Balance),
+    MissingDexSource,
+}
+
+impl ContractError<'_> {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ContractError::NotAnOracle => "E001_NOT_AN_ORACLE",
+            ContractError::OracleAlreadyExists => "E002_ORACLE_ALREADY_EXISTS",
+            ContractError::MissingAsset => "E003_MISSING_ASSET",
+            ContractError::MissingAssetId(_) => "E004_MISSING_ASSET_ID",
+            ContractError::UnknownAsset => "E005_UNKNOWN_ASSET",
+            ContractError::AssetAlreadyExists => "E006_ASSET_ALREADY_EXISTS",
+            ContractError::InvalidAssetId => "E007_INVALID_ASSET_ID",
+            ContractError::MaxAssetsReached(_) => "E008_MAX_ASSETS_REACHED",
+            ContractError::MaxAssetsBelowNumAssets => "E009_MAX_ASSETS_BELOW_NUM_ASSETS",
+            ContractError::Paused => "E010_PAUSED",
+            ContractError::OracleSuspended => "E011_ORACLE_SUSPENDED",
+            ContractError::InsufficientStake => "E012_INSUFFICIENT_STAKE",
+            ContractError::RequiresPrices => "E013_REQUIRES_PRICES",
+            ContractError::NoPriceAccess(_) => "E014_NO_PRICE_ACCESS",
+            ContractError::MissingPrice(_) => "E015_MISSING_PRICE",
+            ContractError::StalePrice(_) => "E016_STALE_PRICE",
+            ContractError::NotOwner => "E017_NOT_OWNER",
+            ContractError::NotProposedOwner => "E018_NOT_PROPOSED_OWNER",
+            ContractError::InvalidConfirmation => "E019_INVALID_CONFIRMATION",
+            ContractError::RequiresDeposit(_) => "E020_REQUIRES_DEPOSIT",
+            ContractError::NotEnoughGas => "E021_NOT_ENOUGH_GAS",
+            ContractError::ReceiverGasTooLow(_) => "E022_RECEIVER_GAS_TOO_LOW",
+            ContractError::RequiresReceivers => "E023_REQUIRES_RECEIVERS",
+            ContractError::RequiresActions => "E024_REQUIRES_ACTIONS",
+            ContractError::NothingToWithdraw => "E025_NOTHING_TO_WITHDRAW",
+            ContractError::NotEnoughCollectedFees => "E026_NOT_ENOUGH_COLLECTED_FEES",
+            ContractError::PeriodNotPositive => "E027_PERIOD_NOT_POSITIVE",
+            ContractError::QuorumNotPositive => "E028_QUORUM_NOT_POSITIVE",
+            ContractError::DeviationNotPositive => "E029_DEVIATION_NOT_POSITIVE",
+            ContractError::TtlNotPositive => "E030_TTL_NOT_POSITIVE",
+            ContractError::LengthNotPositive => "E031_LENGTH_NOT_POSITIVE",
+            ContractError::WeightNotPositive => "E032_WEIGHT_NOT_POSITIVE",
+            ContractError::InvalidDecimals => "E033_INVALID_DECIMALS",
+            ContractError::RequiresRoundHistory => "E034_REQUIRES_ROUND_HISTORY",
+            ContractError::InvalidPriceBand => "E035_INVALID_PRICE_BAND",
+            ContractError::AssetNotTripped => "E036_ASSET_NOT_TRIPPED",
+            ContractError::RelayerAlreadyExists => "E037_RELAYER_ALREADY_EXISTS",
+            ContractError::NotARelayer => "E038_NOT_A_RELAYER",
+            ContractError::DexContractAlreadyExists => "E039_DEX_CONTRACT_ALREADY_EXISTS",
+            ContractError::DexContractNotWhitelisted => "E040_DEX_CONTRACT_NOT_WHITELISTED",
+            ContractError::MissingGroup => "E041_MISSING_GROUP",
+            ContractError::MissingQuoteAsset => "E042_MISSING_QUOTE_ASSET",
+            ContractError::QuoteAssetAlreadyExists => "E043_QUOTE_ASSET_ALREADY_EXISTS",
+            ContractError::MissingIndex => "E044_MISSING_INDEX",
+            ContractError::InvalidIndexId => "E045_INVALID_INDEX_ID",
+            ContractError::IndexAlreadyExists => "E046_INDEX_ALREADY_EXISTS",
+            ContractError::IndexRequiresComponents => "E047_INDEX_REQUIRES_COMPONENTS",
+            ContractError::MissingPair => "E048_MISSING_PAIR",
+            ContractError::InvalidPairId => "E049_INVALID_PAIR_ID",
+            ContractError::PairAlreadyExists => "E050_PAIR_ALREADY_EXISTS",
+            ContractError::MissingBundle => "E051_MISSING_BUNDLE",
+            ContractError::InvalidBundleId => "E052_INVALID_BUNDLE_ID",
+            ContractError::BundleRequiresAssets => "E053_BUNDLE_REQUIRES_ASSETS",
+            ContractError::BundleTooLarge(_) => "E054_BUNDLE_TOO_LARGE",
+            ContractError::RequiresAssetIdsOrBundle => "E055_REQUIRES_ASSET_IDS_OR_BUNDLE",
+            ContractError::EmaAlreadyExists => "E056_EMA_ALREADY_EXISTS",
+            ContractError::MissingEma => "E057_MISSING_EMA",
+            ContractError::UnknownEmaPeriod(_) => "E058_UNKNOWN_EMA_PERIOD",
+            ContractError::InvalidEmaPeriod => "E059_INVALID_EMA_PERIOD",
+            ContractError::TwapAlreadyExists => "E060_TWAP_ALREADY_EXISTS",
+            ContractError::MissingTwap => "E061_MISSING_TWAP",
+            ContractError::UnknownTwapPeriod(_) => "E062_UNKNOWN_TWAP_PERIOD",
+            ContractError::InvalidTwapPeriod => "E063_INVALID_TWAP_PERIOD",
+            ContractError::VolatilityAlreadyExists => "E064_VOLATILITY_ALREADY_EXISTS",
+            ContractError::MissingVolatility => "E065_MISSING_VOLATILITY",
+            ContractError::UnknownVolatilityPeriod(_) => "E066_UNKNOWN_VOLATILITY_PERIOD",
+            ContractError::InvalidVolatilityPeriod => "E067_INVALID_VOLATILITY_PERIOD",
+            ContractError::MirrorAlreadyExists => "E068_MIRROR_ALREADY_EXISTS",
+            ContractError::NotAMirror => "E069_NOT_A_MIRROR",
+            ContractError::SunsetNotInFuture => "E070_SUNSET_NOT_IN_FUTURE",
+            ContractError::MaxGapNotPositive => "E071_MAX_GAP_NOT_POSITIVE",
+            ContractError::InvalidSymbol => "E072_INVALID_SYMBOL",
+            ContractError::SymbolAlreadyRegistered => "E073_SYMBOL_ALREADY_REGISTERED",
+            ContractError::TooManyAssetsRequested(_) => "E074_TOO_MANY_ASSETS_REQUESTED",
+            ContractError::InvalidMaxAssetsPerRequest(_) => "E075_INVALID_MAX_ASSETS_PER_REQUEST",
+            ContractError::InvalidAdaptiveRecency => "E076_INVALID_ADAPTIVE_RECENCY",
+            ContractError::InvalidFallbackOracle => "E077_INVALID_FALLBACK_ORACLE",
+            ContractError::InvalidRate => "E078_INVALID_RATE",
+            ContractError::RateFeedAlreadyExists => "E079_RATE_FEED_ALREADY_EXISTS",
+            ContractError::MissingRateFeed => "E080_MISSING_RATE_FEED",
+            ContractError::InvalidThreshold => "E081_INVALID_THRESHOLD",
+            ContractError::MissingProposal => "E082_MISSING_PROPOSAL",
+            ContractError::ProposalExpired => "E083_PROPOSAL_EXPIRED",
+            ContractError::AlreadyConfirmed => "E084_ALREADY_CONFIRMED",
+            ContractError::ProposalNotExpired => "E085_PROPOSAL_NOT_EXPIRED",
+            ContractError::MultisigDisabled => "E086_MULTISIG_DISABLED",
+            ContractError::NotAMultisigAdmin => "E087_NOT_A_MULTISIG_ADMIN",
+            ContractError::MissingAssetMetadata(_) => "E088_MISSING_ASSET_METADATA",
+            ContractError::AssetUsedByIndex(_) => "E089_ASSET_USED_BY_INDEX",
+            ContractError::PushFeeRequired => "E090_PUSH_FEE_REQUIRED",
+            ContractError::TooManySubscriptions(_) => "E091_TOO_MANY_SUBSCRIPTIONS",
+            ContractError::InvalidCallback => "E092_INVALID_CALLBACK",
+            ContractError::NotEnoughPrepaidGas(_) => "E093_NOT_ENOUGH_PREPAID_GAS",
+            ContractError::AssetUsedByPair(_) => "E094_ASSET_USED_BY_PAIR",
+            ContractError::RoleAlreadyGranted => "E095_ROLE_ALREADY_GRANTED",
+            ContractError::MissingRole => "E096_MISSING_ROLE",
+            ContractError::RequiresRole(_) => "E097_REQUIRES_ROLE",
+            ContractError::ProposalTimelocked => "E098_PROPOSAL_TIMELOCKED",
+            ContractError::RequiresTimelockedProposal => "E099_REQUIRES_TIMELOCKED_PROPOSAL",
+            ContractError::DeployTimeBeforeTimelock => "E100_DEPLOY_TIME_BEFORE_TIMELOCK",
+            ContractError::NoStagedCode => "E101_NO_STAGED_CODE",
+            ContractError::StagedCodeNotDeployable => "E102_STAGED_CODE_NOT_DEPLOYABLE",
+            ContractError::StagedCodeMismatch => "E103_STAGED_CODE_MISMATCH",
+            ContractError::NotEnoughGasForMigration => "E104_NOT_ENOUGH_GAS_FOR_MIGRATION",
+            ContractError::NotInitialized => "E105_NOT_INITIALIZED",
+            ContractError::InvalidAmount => "E106_INVALID_AMOUNT",
+            ContractError::StakeUnbonding => "E107_STAKE_UNBONDING",
+            ContractError::NotRegistered(_) => "E108_NOT_REGISTERED",
+            ContractError::NotEnoughStorageBalance => "E109_NOT_ENOUGH_STORAGE_BALANCE",
+            ContractError::ForceUnregisterNotSupported => "E110_FORCE_UNREGISTER_NOT_SUPPORTED",
+            ContractError::StorageInUse => "E111_STORAGE_IN_USE",
+            ContractError::NotEnoughStorageDeposit(_) => "E112_NOT_ENOUGH_STORAGE_DEPOSIT",
+            ContractError::MissingSubscription => "E113_MISSING_SUBSCRIPTION",
+            ContractError::NotSubscriber => "E114_NOT_SUBSCRIBER",
+            ContractError::MissingPriceRequest => "E115_MISSING_PRICE_REQUEST",
+            ContractError::PriceRequestFulfilled => "E116_PRICE_REQUEST_FULFILLED",
+            ContractError::OracleFulfilledRequest => "E117_ORACLE_FULFILLED_REQUEST",
+            ContractError::NotConsumer => "E118_NOT_CONSUMER",
+            ContractError::TokenNotAccepted => "E119_TOKEN_NOT_ACCEPTED",
+            ContractError::InvalidMessage => "E120_INVALID_MESSAGE",
+            ContractError::RequiresTokenFee(_) => "E121_REQUIRES_TOKEN_FEE",
+            ContractError::MissingDexSource => "E122_MISSING_DEX_SOURCE",
+        }
+    }
+}
+
+impl fmt::Display for ContractError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: ", self.code())?;
+        match self {
+            ContractError::NotAnOracle => write!(f, "Not an oracle"),
+            ContractError::OracleAlreadyExists => write!(f, "The oracle already exists"),
+            ContractError::MissingAsset => write!(f, "Missing an asset"),
+            ContractError::MissingAssetId(value) => write!(f, "Missing an asset {}", value),
+            ContractError::UnknownAsset => write!(f, "Unknown asset"),
+            ContractError::AssetAlreadyExists => write!(f, "Asset with this ID already exists"),
+            ContractError::InvalidAssetId => write!(f, "Invalid asset ID"),
+            ContractError::MaxAssetsReached(value) => {
+                write!(f, "Reached the maximum number of assets: {}", value)
+            }
+            ContractError::MaxAssetsBelowNumAssets => {
+                write!(f, "The limit is below the current number of assets")
+            }
+            ContractError::Paused => write!(f, "The contract is paused"),
+            ContractError::OracleSuspended => write!(f, "The oracle is suspended"),
+            ContractError::InsufficientStake => write!(f, "Insufficient stake"),
+            ContractError::RequiresPrices => write!(f, "Requires prices"),
+            ContractError::NoPriceAccess(value) => write!(f, "No access to prices of {}", value),
+            ContractError::MissingPrice(value) => write!(f, "Missing a price for {}", value),
+            ContractError::StalePrice(value) => write!(f, "The price of {} is stale", value),
+            ContractError::NotOwner => write!(f, "Can only be called by the owner"),
+            ContractError::NotProposedOwner => {
+                write!(f, "Can only be called by the proposed owner")
+            }
+            ContractError::InvalidConfirmation => write!(f, "Invalid confirmation"),
+            ContractError::RequiresDeposit(value) => write!(
+                f,
+                "Requires attached deposit of at least {} yoctoNEAR",
+                value
+            ),
+            ContractError::NotEnoughGas => write!(f, "Not enough gas for the receivers"),
+            ContractError::ReceiverGasTooLow(value) => {
+                write!(f, "The receivers would get less than {} gas", value)
+            }
+            ContractError::RequiresReceivers => write!(f, "Requires receivers"),
+            ContractError::RequiresActions => write!(f, "Requires actions"),
+            ContractError::NothingToWithdraw => write!(f, "Nothing to withdraw"),
+            ContractError::NotEnoughCollectedFees => write!(f, "Not enough collected fees"),
+            ContractError::PeriodNotPositive => write!(f, "Period must be positive"),
+            ContractError::QuorumNotPositive => write!(f, "Quorum must be positive"),
+            ContractError::DeviationNotPositive => write!(f, "Deviation must be positive"),
+            ContractError::TtlNotPositive => write!(f, "TTL must be positive"),
+            ContractError::LengthNotPositive => write!(f, "The length must be positive"),
+            ContractError::WeightNotPositive => write!(f, "Weight must be positive"),
+            ContractError::InvalidDecimals => write!(f, "Invalid decimals"),
+            ContractError::RequiresRoundHistory => write!(f, "Requires keeping at least one round"),
+            ContractError::InvalidPriceBand => write!(f, "The minimum price exceeds the maximum"),
+            ContractError::AssetNotTripped => write!(f, "The asset is not tripped"),
+            ContractError::RelayerAlreadyExists => write!(f, "The relayer already exists"),
+            ContractError::NotARelayer => write!(f, "Not a relayer"),
+            ContractError::DexContractAlreadyExists => write!(f, "The DEX contract already exists"),
+            ContractError::DexContractNotWhitelisted => {
+                write!(f, "The DEX contract is not whitelisted")
+            }
+            ContractError::MissingGroup => write!(f, "Missing a group"),
+            ContractError::MissingQuoteAsset => write!(f, "Missing a quote asset"),
+            ContractError::QuoteAssetAlreadyExists => write!(f, "The quote asset already exists"),
+            ContractError::MissingIndex => write!(f, "Missing an index"),
+            ContractError::InvalidIndexId => write!(f, "Invalid index ID"),
+            ContractError::IndexAlreadyExists => write!(f, "Index already exists"),
+            ContractError::IndexRequiresComponents => write!(f, "Index requires components"),
+            ContractError::MissingPair => write!(f, "Missing a pair"),
+            ContractError::InvalidPairId => write!(f, "Invalid pair ID"),
+            ContractError::PairAlreadyExists => write!(f, "Pair already exists"),
+            ContractError::MissingBundle => write!(f, "Missing a bundle"),
+            ContractError::InvalidBundleId => write!(f, "Invalid bundle ID"),
+            ContractError::BundleRequiresAssets => write!(f, "Bundle requires assets"),
+            ContractError::BundleTooLarge(value) => {
+                write!(f, "Bundle can contain at most {} assets", value)
+            }
+            ContractError::RequiresAssetIdsOrBundle => {
+                write!(f, "Requires either asset IDs or a bundle")
+            }
+            ContractError::EmaAlreadyExists => write!(f, "EMA for this period already exists"),
+            ContractError::MissingEma => write!(f, "EMA for this period doesn't exists"),
+            ContractError::UnknownEmaPeriod(value) => write!(f, "Unknown EMA period: {}", value),
+            ContractError::InvalidEmaPeriod => write!(f, "Failed to parse EMA period"),
+            ContractError::TwapAlreadyExists => write!(f, "TWAP for this period already exists"),
+            ContractError::MissingTwap => write!(f, "TWAP for this period doesn't exist"),
+            ContractError::UnknownTwapPeriod(value) => write!(f, "Unknown TWAP period: {}", value),
+            ContractError::InvalidTwapPeriod => write!(f, "Failed to parse TWAP period"),
+            ContractError::VolatilityAlreadyExists => {
+                write!(f, "Volatility for this period already exists")
+            }
+            ContractError::MissingVolatility => {
+                write!(f, "Volatility for this period doesn't exist")
+            }
+            ContractError::UnknownVolatilityPeriod(value) => {
+                write!(f, "Unknown volatility period: {}", value)
+            }
+            ContractError::InvalidVolatilityPeriod => {
+                write!(f, "Failed to parse volatility period")
+            }
+            ContractError::MirrorAlreadyExists => write!(f, "The mirror already exists"),
+            ContractError::NotAMirror => write!(f, "Not a mirror"),
+            ContractError::SunsetNotInFuture => write!(f, "The sunset must be in the future"),
+            ContractError::MaxGapNotPositive => write!(f, "The max gap must be positive"),
+            ContractError::InvalidSymbol => write!(f, "Invalid symbol"),
+            ContractError::SymbolAlreadyRegistered => {
+                write!(f, "The symbol is already registered")
+            }
+            ContractError::TooManyAssetsRequested(value) => {
+                write!(f, "Can request prices of at most {} assets", value)
+            }
+            ContractError::InvalidMaxAssetsPerRequest(value) => {
+                write!(f, "The maximum must be between 1 and {}", value)
+            }
+            ContractError::InvalidAdaptiveRecency => write!(
+                f,
+                "The minimum recency must be positive and at most the maximum"
+            ),
+            ContractError::InvalidFallbackOracle => {
+                write!(f, "The contract can't be its own fallback oracle")
+            }
+            ContractError::InvalidRate => {
+                write!(f, "The rate is out of the range of the feed kind")
+            }
+            ContractError::RateFeedAlreadyExists => write!(f, "The rate feed already exists"),
+            ContractError::MissingRateFeed => write!(f, "Missing a rate feed"),
+            ContractError::InvalidThreshold => write!(f, "Invalid threshold"),
+            ContractError::MissingProposal => write!(f, "Missing a proposal"),
+            ContractError::ProposalExpired => write!(f, "The proposal expired"),
+            ContractError::AlreadyConfirmed => write!(f, "Already confirmed"),
+            ContractError::ProposalNotExpired => write!(f, "The proposal didn't expire"),
+            ContractError::MultisigDisabled => write!(f, "The multi-owner mode is off"),
+            ContractError::NotAMultisigAdmin => write!(f, "Not a multisig admin"),
+            ContractError::MissingAssetMetadata(value) => {
+                write!(f, "Missing metadata of the asset {}", value)
+            }
+            ContractError::AssetUsedByIndex(value) => {
+                write!(f, "The asset is a component of the index {}", value)
+            }
+            ContractError::PushFeeRequired => write!(f, "Subscriptions require a push fee"),
+            ContractError::TooManySubscriptions(value) => {
+                write!(f, "The asset has the maximum of {} subscriptions", value)
+            }
+            ContractError::InvalidCallback => write!(f, "Invalid callback"),
+            ContractError::NotEnoughPrepaidGas(value) => {
+                write!(f, "Requires at least {} TGas of prepaid gas", value)
+            }
+            ContractError::AssetUsedByPair(value) => {
+                write!(f, "The asset is used by the pair {}", value)
+            }
+            ContractError::RoleAlreadyGranted => write!(f, "The account already has the role"),
+            ContractError::MissingRole => write!(f, "The account doesn't have the role"),
+            ContractError::RequiresRole(value) => write!(
+                f,
+                "Can only be called by the owner or an account with the {:?} role",
+                value
+            ),
+            ContractError::ProposalTimelocked => write!(f, "The proposal is still timelocked"),
+            ContractError::RequiresTimelockedProposal => {
+                write!(f, "The change requires a timelocked proposal")
+            }
+            ContractError::DeployTimeBeforeTimelock => {
+                write!(f, "The deploy time is earlier than the timelock allows")
+            }
+            ContractError::NoStagedCode => write!(f, "No staged code"),
+            ContractError::StagedCodeNotDeployable => {
+                write!(f, "The staged code can't be deployed yet")
+            }
+            ContractError::StagedCodeMismatch => {
+                write!(f, "The code doesn't match the staged code")
+            }
+            ContractError::NotEnoughGasForMigration => {
+                write!(f, "Not enough gas to complete state migration")
+            }
+            ContractError::NotInitialized => write!(f, "The contract is not initialized"),
+            ContractError::InvalidAmount => write!(f, "Invalid amount"),
+            ContractError::StakeUnbonding => write!(f, "The stake is still unbonding"),
+            ContractError::NotRegistered(value) => {
+                write!(f, "The account {} is not registered", value)
+            }
+            ContractError::NotEnoughStorageBalance => {
+                write!(f, "Not enough available storage balance")
+            }
+            ContractError::ForceUnregisterNotSupported => {
+                write!(f, "Force unregistration is not supported")
+            }
+            ContractError::StorageInUse => write!(f, "The account still uses storage"),
+            ContractError::NotEnoughStorageDeposit(value) => {
+                write!(f, "Not enough storage deposit of {}", value)
+            }
+            ContractError::MissingSubscription => write!(f, "Missing a subscription"),
+            ContractError::NotSubscriber => write!(f, "Can only be called by the subscriber"),
+            ContractError::MissingPriceRequest => write!(f, "Missing a price request"),
+            ContractError::PriceRequestFulfilled => write!(f, "The request is already fulfilled"),
+            ContractError::OracleFulfilledRequest => {
+                write!(f, "The oracle already fulfilled the request")
+            }
+            ContractError::NotConsumer => write!(f, "Can only be called by the consumer"),
+            ContractError::TokenNotAccepted => write!(f, "The token is not accepted"),
+            ContractError::InvalidMessage => write!(f, "Failed to parse the message"),
+            ContractError::RequiresTokenFee(value) => {
+                write!(f, "Requires at least {} of the token", value)
+            }
+            ContractError::MissingDexSource => write!(f, "The asset has no DEX source"),
+        }
+    }
+}
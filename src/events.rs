@@ -0,0 +1,239 @@
+use crate::*;
+use near_sdk::serde_json::json;
+
+const EVENT_STANDARD: &str = "price_oracle";
+const EVENT_STANDARD_VERSION: &str = "1.0.0";
+
+/// A change of the aggregated price of an asset within a report call.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceChange {
+    pub asset_id: AssetId,
+    pub old_price: Option<Price>,
+    pub new_price: Price,
+    /// The number of recent reports the new price is aggregated from.
+    pub num_reports: u32,
+}
+
+/// Events logged in the NEP-297 format, e.g.
+/// `EVENT_JSON:{"standard":"price_oracle","version":"1.0.0","event":"add_asset","data":[{"asset_id":"wrap.near"}]}`
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+pub enum Event<'a> {
+    ReportPrices {
+        oracle_id: &'a AccountId,
+        prices: &'a [AssetPrice],
+        source: PriceSource,
+    },
+    /// The aggregated price of the asset was recomputed after a report.
+    UpdatePrice {
+        asset_id: &'a AssetId,
+        price: Price,
+    },
+    /// The aggregated prices changed by a report call, logged with `batch_price_events`.
+    PriceChanges {
+        block_height: BlockHeight,
+        changes: &'a [PriceChange],
+    },
+    /// The reported price is outside of the price band of the asset.
+    RejectPrice {
+        oracle_id: &'a AccountId,
+        asset_id: &'a AssetId,
+        price: Price,
+    },
+    /// The DEX price of the asset was fetched from its pool.
+    UpdateDexPrice {
+        asset_id: &'a AssetId,
+        price: Price,
+    },
+    FinalizeRound {
+        asset_id: &'a AssetId,
+        round_id: RoundId,
+        answer: Option<Price>,
+    },
+    /// The oracle reported the asset again before the minimum report interval passed.
+    IgnoreReport {
+        oracle_id: &'a AccountId,
+        asset_id: &'a AssetId,
+    },
+    RegisterSymbol {
+        symbol: &'a str,
+        asset_id: &'a AssetId,
+    },
+    /// The price feed of the asset stops at the sunset.
+    DeprecateAsset {
+        asset_id: &'a AssetId,
+        sunset_timestamp: U64,
+        replacement_asset_id: Option<&'a AssetId>,
+    },
+    /// The aggregated price of the pegged asset started to deviate from the peg.
+    Depeg {
+        asset_id: &'a AssetId,
+        price: Price,
+    },
+    /// The aggregated price of the pegged asset returned to the peg.
+    Repeg {
+        asset_id: &'a AssetId,
+        price: Price,
+    },
+    /// Too few oracles were live, so the backup oracles were added to the oracle set.
+    Failover {
+        num_live_oracles: u32,
+        backup_oracle_ids: &'a [AccountId],
+    },
+    /// The aggregated price moved too far and the asset stopped returning prices.
+    TripCircuitBreaker {
+        asset_id: &'a AssetId,
+        price: Price,
+    },
+    ResumeAsset {
+        asset_id: &'a AssetId,
+    },
+    AddOracle {
+        account_id: &'a AccountId,
+    },
+    RemoveOracle {
+        account_id: &'a AccountId,
+    },
+    /// The oracle scored below the min score for too many rounds in a row.
+    SuspendOracle {
+        account_id: &'a AccountId,
+        score_bps: u32,
+    },
+    ReinstateOracle {
+        account_id: &'a AccountId,
+    },
+    AddAsset {
+        asset_id: &'a AssetId,
+    },
+    RemoveAsset {
+        asset_id: &'a AssetId,
+    },
+    AddRateFeed {
+        feed_id: &'a str,
+        kind: RateKind,
+    },
+    RemoveRateFeed {
+        feed_id: &'a str,
+    },
+    ReportRates {
+        oracle_id: &'a AccountId,
+        rates: &'a [AssetRate],
+    },
+    PruneReports {
+        asset_id: &'a AssetId,
+        num_reports: u64,
+    },
+    GrantRole {
+        account_id: &'a AccountId,
+        role: Role,
+    },
+    RevokeRole {
+        account_id: &'a AccountId,
+        role: Role,
+    },
+    ProposeOwner {
+        owner_id: &'a AccountId,
+        proposed_owner_id: &'a AccountId,
+    },
+    TransferOwnership {
+        old_owner_id: &'a AccountId,
+        new_owner_id: &'a AccountId,
+    },
+    RenounceOwnership {
+        owner_id: &'a AccountId,
+    },
+    ProposeConfigChange {
+        proposal_id: u64,
+        proposal: &'a Proposal,
+    },
+    ExecuteProposal {
+        proposal_id: u64,
+    },
+    CancelProposal {
+        proposal_id: u64,
+    },
+    ProposeOwnerActions {
+        proposal_id: u64,
+        proposer_id: &'a AccountId,
+    },
+    ConfirmOwnerProposal {
+        proposal_id: u64,
+        account_id: &'a AccountId,
+    },
+    /// The actions of the proposal that got enough confirmations were applied.
+    ExecuteOwnerProposal {
+        proposal_id: u64,
+    },
+    /// The receiver of a price request finished, the refund is the NEAR fee returned on failure.
+    OracleCallComplete {
+        sender_id: &'a AccountId,
+        receiver_id: &'a AccountId,
+        success: bool,
+        refund: U128,
+    },
+    /// A batch of assets stored by older versions of the contract was rewritten.
+    MigrateLegacyAssets {
+        num_migrated: u64,
+        next_index: u64,
+    },
+    /// Price data with the commitment was delivered to receivers.
+    CommitPriceData {
+        block_height: BlockHeight,
+        commitment: Base58CryptoHash,
+    },
+    /// Prices of the assets were aggregated after a deferred aggregation.
+    FinalizePrices {
+        asset_ids: &'a [AssetId],
+    },
+    /// The assets were configured from exported configurations.
+    ImportConfig {
+        asset_ids: &'a [AssetId],
+    },
+    /// The delivery of price data was recorded as the receipt.
+    IssuePriceReceipt {
+        #[serde(with = "u64_dec_format")]
+        receipt_id: u64,
+        sender_id: &'a AccountId,
+        receiver_ids: &'a [AccountId],
+    },
+    /// Prices were relayed to the mirror with the sequence number.
+    RelayPrices {
+        mirror_id: &'a AccountId,
+        sequence: u64,
+    },
+    RequestPrice {
+        request_id: u64,
+        consumer_id: &'a AccountId,
+        asset_id: &'a AssetId,
+    },
+    FulfillPriceRequest {
+        request_id: u64,
+        answer: Price,
+    },
+    Subscribe {
+        subscription_id: u64,
+        receiver_id: &'a AccountId,
+        asset_id: &'a AssetId,
+    },
+    Unsubscribe {
+        subscription_id: u64,
+    },
+    /// The owner changed the configuration, contains the new configuration.
+    UpdateConfig(Box<Config>),
+}
+
+impl Event<'_> {
+    pub fn emit(&self) {
+        let event = json!(self);
+        let event_json = json!({
+            "standard": EVENT_STANDARD,
+            "version": EVENT_STANDARD_VERSION,
+            "event": event["event"],
+            "data": [event["data"]],
+        });
+        log!("EVENT_JSON:{}", event_json);
+    }
+}
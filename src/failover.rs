@@ -0,0 +1,85 @@
+use crate::*;
+
+/// A dead-man switch of the oracle set. If fewer than `min_live_oracles` oracles reported within
+/// `failover_window_sec`, anyone can activate the failover, which adds the backup oracles to the
+/// oracle set. The failover fires once and has to be configured again afterwards.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FailoverConfig {
+    pub backup_oracle_ids: Vec<AccountId>,
+    pub min_live_oracles: u32,
+    pub failover_window_sec: DurationSec,
+}
+
+impl FailoverConfig {
+    pub fn assert_valid(&self) {
+        assert!(
+            !self.backup_oracle_ids.is_empty(),
+            "Requires at least one backup oracle"
+        );
+        assert!(
+            self.min_live_oracles > 0 && self.failover_window_sec > 0,
+            "The minimum number of live oracles and the window must be positive"
+        );
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the number of oracles that reported within the failover window.
+    pub fn get_num_live_oracles(&self) -> u32 {
+        let failover = self
+            .failover
+            .as_ref()
+            .expect("The failover isn't configured");
+        self.internal_num_live_oracles(now(), failover.failover_window_sec)
+    }
+
+    /// Hands control to the backup oracles once too few oracles are live. The window counts from
+    /// the time the failover was configured at the earliest. Can be called by anyone.
+    pub fn activate_failover(&mut self) {
+        let failover = self.failover.take().expect("The failover isn't configured");
+        let timestamp = now();
+        assert!(
+            timestamp >= self.failover_configured_at + to_nano(failover.failover_window_sec),
+            "The failover window hasn't passed yet"
+        );
+        let num_live_oracles =
+            self.internal_num_live_oracles(timestamp, failover.failover_window_sec);
+        assert!(
+            num_live_oracles < failover.min_live_oracles,
+            "Enough oracles are live"
+        );
+        for account_id in &failover.backup_oracle_ids {
+            if self.internal_get_oracle(account_id).is_none() {
+                self.internal_set_oracle(account_id, Oracle::new());
+                Event::AddOracle { account_id }.emit();
+            }
+        }
+        Event::Failover {
+            num_live_oracles,
+            backup_oracle_ids: &failover.backup_oracle_ids,
+        }
+        .emit();
+    }
+}
+
+impl Contract {
+    pub fn internal_num_live_oracles(&self, timestamp: Timestamp, window_sec: DurationSec) -> u32 {
+        let timestamp_cut = timestamp.saturating_sub(to_nano(window_sec));
+        self.oracles
+            .values()
+            .map(Oracle::from)
+            .filter(|oracle| oracle.last_report >= timestamp_cut)
+            .count() as u32
+    }
+
+    pub fn internal_set_failover(&mut self, failover: Option<FailoverConfig>) {
+        if let Some(failover) = failover.as_ref() {
+            failover.assert_valid();
+        }
+        self.failover = failover;
+        self.failover_configured_at = now();
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
+    }
+}
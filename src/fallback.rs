@@ -0,0 +1,184 @@
+use crate::*;
+use near_sdk::serde_json;
+use near_sdk::PromiseResult;
+
+/// The gas of every query of a fallback oracle.
+const GAS_FOR_FALLBACK_QUERY: Gas = Gas(Gas::ONE_TERA.0 * 10);
+
+/// Another price oracle contract that `oracle_call` queries for prices of the asset while it
+/// doesn't have a fresh price, e.g. because it's paused or oracles stopped reporting.
+#[ext_contract(ext_fallback_oracle)]
+pub trait ExtFallbackOracle {
+    fn get_price_data(
+        &self,
+        asset_ids: Option<Vec<AssetId>>,
+        max_staleness_sec: Option<DurationSec>,
+    ) -> PriceData;
+}
+
+#[ext_contract(ext_fallback_callback)]
+pub trait ExtFallbackCallback {
+    fn on_fallback_price_data(&mut self, delivery: FallbackDelivery);
+}
+
+/// A price request waiting for prices of fallback oracles.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FallbackDelivery {
+    pub sender_id: AccountId,
+    pub receiver_id: AccountId,
+    pub price_data: PriceData,
+    /// The assets queried from every fallback oracle, in the order of the queries.
+    pub fallback_asset_ids: Vec<Vec<AssetId>>,
+    pub msg: String,
+    pub require_all: bool,
+    pub gas_budget: GasBudget,
+    pub fee: U128,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Fills in the missing prices from the fallback oracles and sends the price data to the
+    /// receiver. If prices are still missing while all are required, the request fails like a
+    /// failed receiver call.
+    #[private]
+    pub fn on_fallback_price_data(&mut self, delivery: FallbackDelivery) {
+        let FallbackDelivery {
+            sender_id,
+            receiver_id,
+            mut price_data,
+            fallback_asset_ids,
+            msg,
+            require_all,
+            gas_budget,
+            fee,
+        } = delivery;
+        let results = (0..env::promise_results_count())
+            .map(|index| match env::promise_result(index) {
+                PromiseResult::Successful(value) => serde_json::from_slice(&value).ok(),
+                _ => None,
+            })
+            .collect();
+        apply_fallback_prices(&mut price_data, &fallback_asset_ids, results);
+        if require_all {
+            if let Some(missing) = price_data.prices.iter().find(|price| price.price.is_none()) {
+                log!("Warning! Missing a price of {}", missing.asset_id);
+                self.internal_record_consumer_call(&sender_id, fee.0);
+                self.internal_on_oracle_call_complete(sender_id, receiver_id, fee.0, None);
+                return;
+            }
+        }
+        let receiver_gas = gas_budget.receiver_gas(1);
+        self.internal_deliver_price_data(
+            sender_id,
+            receiver_id,
+            price_data,
+            msg,
+            receiver_gas,
+            fee.0,
+        )
+        .as_return();
+    }
+}
+
+impl Contract {
+    /// Returns the assets of the price data without a fresh price grouped by the fallback oracle
+    /// of their asset.
+    pub fn internal_fallback_requests(
+        &self,
+        price_data: &PriceData,
+    ) -> Vec<(AccountId, Vec<AssetId>)> {
+        let mut requests: Vec<(AccountId, Vec<AssetId>)> = vec![];
+        for asset_price in &price_data.prices {
+            if asset_price.price.is_some() && !asset_price.expired {
+                continue;
+            }
+            let fallback_oracle_id = match self
+                .internal_get_asset(&base_asset_id(&asset_price.asset_id).to_string())
+                .and_then(|asset| asset.fallback_oracle_id)
+            {
+                Some(fallback_oracle_id) => fallback_oracle_id,
+                None => continue,
+            };
+            match requests
+                .iter_mut()
+                .find(|(account_id, _)| account_id == &fallback_oracle_id)
+            {
+                Some((_, asset_ids)) => asset_ids.push(asset_price.asset_id.clone()),
+                None => requests.push((fallback_oracle_id, vec![asset_price.asset_id.clone()])),
+            }
+        }
+        requests
+    }
+
+    /// Queries the fallback oracles and hands the price data to `on_fallback_price_data` with the
+    /// remaining gas.
+    pub fn internal_query_fallback_oracles(
+        &mut self,
+        mut delivery: FallbackDelivery,
+        fallback_requests: Vec<(AccountId, Vec<AssetId>)>,
+        max_staleness_sec: Option<DurationSec>,
+    ) -> Promise {
+        let num_queries = fallback_requests.len() as u64;
+        let callback_gas = (env::prepaid_gas() - env::used_gas())
+            .0
+            .checked_sub((GAS_FOR_FALLBACK_QUERY.0 + GAS_FOR_PROMISE.0) * num_queries)
+            .and_then(|gas| gas.checked_sub(2 * GAS_FOR_PROMISE.0))
+            .unwrap_or_else(|| panic!("{}", ContractError::NotEnoughGas));
+        let query = fallback_requests
+            .into_iter()
+            .map(|(fallback_oracle_id, asset_ids)| {
+                delivery.fallback_asset_ids.push(asset_ids.clone());
+                ext_fallback_oracle::get_price_data(
+                    Some(asset_ids),
+                    max_staleness_sec,
+                    fallback_oracle_id,
+                    NO_DEPOSIT,
+                    GAS_FOR_FALLBACK_QUERY,
+                )
+            })
+            .reduce(|promise, next| promise.and(next))
+            .unwrap();
+        query.then(ext_fallback_callback::on_fallback_price_data(
+            delivery,
+            env::current_account_id(),
+            NO_DEPOSIT,
+            Gas(callback_gas),
+        ))
+    }
+}
+
+/// Replaces missing and expired prices with fresh prices of the fallback oracles, which are
+/// flagged as `fallback`. `results` are the price data of the fallback oracles in the order of
+/// `fallback_asset_ids`, `None` if the query failed.
+pub fn apply_fallback_prices(
+    price_data: &mut PriceData,
+    fallback_asset_ids: &[Vec<AssetId>],
+    results: Vec<Option<PriceData>>,
+) {
+    for (asset_ids, fallback_price_data) in fallback_asset_ids.iter().zip(results) {
+        let fallback_price_data = match fallback_price_data {
+            Some(fallback_price_data) => fallback_price_data,
+            None => continue,
+        };
+        for fallback_price in fallback_price_data.prices {
+            if fallback_price.price.is_none()
+                || fallback_price.expired
+                || !asset_ids.contains(&fallback_price.asset_id)
+            {
+                continue;
+            }
+            if let Some(asset_price) = price_data
+                .prices
+                .iter_mut()
+                .find(|asset_price| asset_price.asset_id == fallback_price.asset_id)
+            {
+                asset_price.price = fallback_price.price;
+                asset_price.expired = false;
+                asset_price.sequence = None;
+                asset_price.last_update_timestamp = fallback_price.last_update_timestamp;
+                asset_price.fallback = true;
+            }
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use crate::*;
+use near_sdk::{is_promise_success, serde_json, PromiseOrValue};
+
+const GAS_FOR_FT_TRANSFER: Gas = Gas(Gas::ONE_TERA.0 * 10);
+const GAS_FOR_AFTER_FT_TRANSFER: Gas = Gas(Gas::ONE_TERA.0 * 10);
+const ONE_YOCTO: Balance = 1;
+
+/// The fee of a price request paid in a fungible token.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenFee {
+    #[serde(with = "u128_dec_format")]
+    pub flat_fee: Balance,
+    #[serde(with = "u128_dec_format")]
+    pub per_asset_fee: Balance,
+}
+
+/// The `msg` of `ft_transfer_call` to request prices, it has the arguments of `oracle_call`.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OracleCallArgs {
+    pub receiver_id: AccountId,
+    pub asset_ids: Option<Vec<AssetId>>,
+    pub msg: String,
+    #[serde(default)]
+    pub require_all: bool,
+    #[serde(default)]
+    pub gas_budget: GasBudget,
+    #[serde(default)]
+    pub bundle_id: Option<BundleId>,
+    #[serde(default)]
+    pub max_staleness_sec: Option<DurationSec>,
+}
+
+#[ext_contract(ext_fungible_token)]
+pub trait ExtFungibleToken {
+    fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>);
+}
+
+#[ext_contract(ext_self)]
+pub trait ExtSelf {
+    fn after_withdraw_token_fees(&mut self, token_id: AccountId, amount: U128);
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Requests prices like `oracle_call`, but pays the fee in a whitelisted token. The whole
+    /// transferred amount is collected.
+    pub fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        self.assert_not_paused();
+        let token_id = env::predecessor_account_id();
+        let token_fee = self
+            .token_fees
+            .get(&token_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::TokenNotAccepted));
+        let args: OracleCallArgs = serde_json::from_str(&msg)
+            .unwrap_or_else(|_| panic!("{}", ContractError::InvalidMessage));
+        let asset_ids = self.internal_bundle_asset_ids(args.asset_ids, args.bundle_id);
+        let fee = token_fee.flat_fee
+            + token_fee.per_asset_fee * Balance::from(self.num_requested_assets(&asset_ids));
+        assert!(amount.0 >= fee, "{}", ContractError::RequiresTokenFee(fee));
+        let collected_fees = self.collected_token_fees.get(&token_id).unwrap_or(0);
+        self.collected_token_fees
+            .insert(&token_id, &(collected_fees + amount.0));
+        self.internal_oracle_call(
+            sender_id,
+            args.receiver_id,
+            asset_ids,
+            args.msg,
+            args.require_all,
+            args.gas_budget,
+            0,
+            args.max_staleness_sec,
+        );
+        PromiseOrValue::Value(U128(0))
+    }
+
+    pub fn get_token_fees(&self) -> Vec<(AccountId, TokenFee)> {
+        self.token_fees.to_vec()
+    }
+
+    pub fn get_collected_token_fees(&self) -> Vec<(AccountId, U128)> {
+        self.collected_token_fees
+            .iter()
+            .map(|(token_id, amount)| (token_id, amount.into()))
+            .collect()
+    }
+
+    /// Transfers collected fees of the token to the given account, by default to the owner.
+    /// Withdraws all collected fees if the amount is not given.
+    #[payable]
+    pub fn withdraw_token_fees(
+        &mut self,
+        token_id: AccountId,
+        amount: Option<U128>,
+        receiver_id: Option<AccountId>,
+    ) -> Promise {
+        assert_one_yocto();
+        self.assert_owner();
+        let collected_fees = self.collected_token_fees.get(&token_id).unwrap_or(0);
+        let amount = amount.map_or(collected_fees, |amount| amount.into());
+        assert!(amount > 0, "{}", ContractError::NothingToWithdraw);
+        assert!(
+            amount <= collected_fees,
+            "{}",
+            ContractError::NotEnoughCollectedFees
+        );
+        self.collected_token_fees
+            .insert(&token_id, &(collected_fees - amount));
+        ext_fungible_token::ft_transfer(
+            receiver_id.unwrap_or_else(|| self.owner_id.clone()),
+            amount.into(),
+            None,
+            token_id.clone(),
+            ONE_YOCTO,
+            GAS_FOR_FT_TRANSFER,
+        )
+        .then(ext_self::after_withdraw_token_fees(
+            token_id,
+            amount.into(),
+            env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_AFTER_FT_TRANSFER,
+        ))
+    }
+
+    /// Returns the fees back to the collected fees if the transfer failed.
+    #[private]
+    pub fn after_withdraw_token_fees(&mut self, token_id: AccountId, amount: U128) {
+        if !is_promise_success() {
+            let collected_fees = self.collected_token_fees.get(&token_id).unwrap_or(0);
+            self.collected_token_fees
+                .insert(&token_id, &(collected_fees + amount.0));
+        }
+    }
+}
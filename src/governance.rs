@@ -0,0 +1,265 @@
+use crate::*;
+
+/// A configuration change that takes effect only after the timelock.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ConfigChange {
+    SetRecencyDurationSec {
+        recency_duration_sec: DurationSec,
+    },
+    ReplaceOracleSet {
+        account_ids: Vec<AccountId>,
+    },
+    SetAssetCircuitBreaker {
+        asset_id: AssetId,
+        circuit_breaker: Option<CircuitBreaker>,
+    },
+    SetTimelockSec {
+        timelock_sec: DurationSec,
+    },
+    SetFailover {
+        failover: Option<FailoverConfig>,
+    },
+    AddOracle {
+        account_id: AccountId,
+    },
+    RemoveOracle {
+        account_id: AccountId,
+    },
+    SetAssetRecencyDurationSec {
+        asset_id: AssetId,
+        recency_duration_sec: Option<DurationSec>,
+    },
+    SetGroupRecencyDurationSec {
+        group: GroupId,
+        recency_duration_sec: Option<DurationSec>,
+    },
+    SetAdaptiveRecency {
+        adaptive_recency: Option<AdaptiveRecency>,
+    },
+    SetMinStake {
+        min_stake: U128,
+    },
+    SetOracleWeight {
+        account_id: AccountId,
+        weight: u32,
+    },
+    SetOracleWeighting {
+        oracle_weighting: OracleWeighting,
+    },
+    SetAssetOracleWeighting {
+        asset_id: AssetId,
+        oracle_weighting: Option<OracleWeighting>,
+    },
+    SetMinNumRecentReports {
+        min_num_recent_reports: Option<u32>,
+    },
+    SetAssetMinNumRecentReports {
+        asset_id: AssetId,
+        min_num_recent_reports: Option<u32>,
+    },
+    SetWeightDecay {
+        weight_decay: Option<WeightDecay>,
+    },
+    SetAssetMaxDeviationBps {
+        asset_id: AssetId,
+        max_deviation_bps: Option<u32>,
+    },
+    SetAssetPriceBand {
+        asset_id: AssetId,
+        price_band: Option<PriceBand>,
+    },
+    ReinstateOracle {
+        account_id: AccountId,
+    },
+}
+
+impl ConfigChange {
+    /// Rejects invalid parameters when the change is proposed rather than after the timelock.
+    fn assert_valid(&self) {
+        match self {
+            ConfigChange::SetAssetCircuitBreaker {
+                circuit_breaker: Some(circuit_breaker),
+                ..
+            } => circuit_breaker.assert_valid(),
+            ConfigChange::SetFailover {
+                failover: Some(failover),
+            } => failover.assert_valid(),
+            ConfigChange::SetAdaptiveRecency {
+                adaptive_recency: Some(adaptive_recency),
+            } => adaptive_recency.assert_valid(),
+            ConfigChange::SetWeightDecay {
+                weight_decay: Some(weight_decay),
+            } => weight_decay.assert_valid(),
+            ConfigChange::SetAssetPriceBand {
+                price_band: Some(price_band),
+                ..
+            } => price_band.assert_valid(),
+            ConfigChange::SetMinNumRecentReports {
+                min_num_recent_reports,
+            }
+            | ConfigChange::SetAssetMinNumRecentReports {
+                min_num_recent_reports,
+                ..
+            } => assert_ne!(
+                *min_num_recent_reports,
+                Some(0),
+                "{}",
+                ContractError::QuorumNotPositive
+            ),
+            ConfigChange::SetAssetMaxDeviationBps {
+                max_deviation_bps, ..
+            } => assert_ne!(
+                *max_deviation_bps,
+                Some(0),
+                "{}",
+                ContractError::DeviationNotPositive
+            ),
+            _ => {}
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Proposal {
+    pub change: ConfigChange,
+    /// The change can be executed at or after this time.
+    #[serde(with = "u64_dec_format")]
+    pub executable_at: Timestamp,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Schedules the change, anyone can execute it once the timelock passes.
+    /// Returns the ID of the proposal.
+    #[payable]
+    pub fn propose_config_change(&mut self, change: ConfigChange) -> u64 {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        change.assert_valid();
+        let proposal_id = self.next_proposal_id;
+        self.next_proposal_id += 1;
+        let proposal = Proposal {
+            change,
+            executable_at: now() + to_nano(self.timelock_sec),
+        };
+        Event::ProposeConfigChange {
+            proposal_id,
+            proposal: &proposal,
+        }
+        .emit();
+        self.proposals.insert(&proposal_id, &proposal);
+        proposal_id
+    }
+
+    /// Applies the change of the proposal after its timelock. Can be called by anyone.
+    pub fn execute_proposal(&mut self, proposal_id: u64) {
+        let proposal = self
+            .proposals
+            .remove(&proposal_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingProposal));
+        assert!(
+            now() >= proposal.executable_at,
+            "{}",
+            ContractError::ProposalTimelocked
+        );
+        Event::ExecuteProposal { proposal_id }.emit();
+        match proposal.change {
+            ConfigChange::SetRecencyDurationSec {
+                recency_duration_sec,
+            } => self.internal_set_recency_duration_sec(recency_duration_sec),
+            ConfigChange::ReplaceOracleSet { account_ids } => {
+                self.internal_replace_oracle_set(account_ids)
+            }
+            ConfigChange::SetAssetCircuitBreaker {
+                asset_id,
+                circuit_breaker,
+            } => self.internal_set_asset_circuit_breaker(&asset_id, circuit_breaker),
+            ConfigChange::SetTimelockSec { timelock_sec } => {
+                self.internal_set_timelock_sec(timelock_sec)
+            }
+            ConfigChange::SetFailover { failover } => self.internal_set_failover(failover),
+            ConfigChange::AddOracle { account_id } => self.internal_add_oracle(&account_id),
+            ConfigChange::RemoveOracle { account_id } => assert!(
+                self.internal_remove_oracle(&account_id),
+                "{}",
+                ContractError::NotAnOracle
+            ),
+            ConfigChange::SetAssetRecencyDurationSec {
+                asset_id,
+                recency_duration_sec,
+            } => self.internal_set_asset_recency_duration_sec(&asset_id, recency_duration_sec),
+            ConfigChange::SetGroupRecencyDurationSec {
+                group,
+                recency_duration_sec,
+            } => self.internal_set_group_recency_duration_sec(&group, recency_duration_sec),
+            ConfigChange::SetAdaptiveRecency { adaptive_recency } => {
+                self.internal_set_adaptive_recency(adaptive_recency)
+            }
+            ConfigChange::SetMinStake { min_stake } => self.internal_set_min_stake(min_stake.0),
+            ConfigChange::SetOracleWeight { account_id, weight } => {
+                self.internal_set_oracle_weight(&account_id, weight)
+            }
+            ConfigChange::SetOracleWeighting { oracle_weighting } => {
+                self.internal_set_oracle_weighting(oracle_weighting)
+            }
+            ConfigChange::SetAssetOracleWeighting {
+                asset_id,
+                oracle_weighting,
+            } => self.internal_set_asset_oracle_weighting(&asset_id, oracle_weighting),
+            ConfigChange::SetMinNumRecentReports {
+                min_num_recent_reports,
+            } => self.internal_set_min_num_recent_reports(min_num_recent_reports),
+            ConfigChange::SetAssetMinNumRecentReports {
+                asset_id,
+                min_num_recent_reports,
+            } => self.internal_set_asset_min_num_recent_reports(&asset_id, min_num_recent_reports),
+            ConfigChange::SetWeightDecay { weight_decay } => {
+                self.internal_set_weight_decay(weight_decay)
+            }
+            ConfigChange::SetAssetMaxDeviationBps {
+                asset_id,
+                max_deviation_bps,
+            } => self.internal_set_asset_max_deviation_bps(&asset_id, max_deviation_bps),
+            ConfigChange::SetAssetPriceBand {
+                asset_id,
+                price_band,
+            } => self.internal_set_asset_price_band(&asset_id, price_band),
+            ConfigChange::ReinstateOracle { account_id } => {
+                self.internal_reinstate_oracle(&account_id)
+            }
+        }
+    }
+
+    #[payable]
+    pub fn cancel_proposal(&mut self, proposal_id: u64) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        self.proposals
+            .remove(&proposal_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingProposal));
+        Event::CancelProposal { proposal_id }.emit();
+    }
+
+    pub fn get_proposals(&self) -> Vec<(u64, Proposal)> {
+        self.proposals.to_vec()
+    }
+}
+
+impl Contract {
+    /// Critical parameters can only be changed directly while there is no timelock.
+    pub fn assert_no_timelock(&self) {
+        assert_eq!(
+            self.timelock_sec,
+            0,
+            "{}",
+            ContractError::RequiresTimelockedProposal
+        );
+    }
+
+    pub fn internal_set_timelock_sec(&mut self, timelock_sec: DurationSec) {
+        self.timelock_sec = timelock_sec;
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
+    }
+}
@@ -0,0 +1,68 @@
+use crate::*;
+
+const DEFAULT_PRICE_HISTORY_LIMIT: u64 = 100;
+
+#[near_bindgen]
+impl Contract {
+    /// Returns price snapshots of the asset taken within the given time range, oldest first.
+    pub fn get_price_history(
+        &self,
+        asset_id: AssetId,
+        from_timestamp: U64,
+        to_timestamp: U64,
+        limit: Option<u64>,
+    ) -> Vec<PricePoint> {
+        self.assert_asset_price_access(&asset_id);
+        self.price_history
+            .get(&asset_id)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|point| {
+                point.timestamp >= from_timestamp.0 && point.timestamp <= to_timestamp.0
+            })
+            .take(limit.unwrap_or(DEFAULT_PRICE_HISTORY_LIMIT) as usize)
+            .collect()
+    }
+
+    /// Returns the latest price snapshot of the asset taken at or before the given time.
+    pub fn get_price_at(&self, asset_id: AssetId, timestamp: U64) -> Option<PricePoint> {
+        self.assert_asset_price_access(&asset_id);
+        self.price_history
+            .get(&asset_id)?
+            .into_iter()
+            .rev()
+            .find(|point| point.timestamp <= timestamp.0)
+    }
+
+    /// Returns the latest price snapshot of the asset taken at or before the given block.
+    pub fn get_price_at_height(&self, asset_id: AssetId, block_height: U64) -> Option<PricePoint> {
+        self.assert_asset_price_access(&asset_id);
+        self.price_history
+            .get(&asset_id)?
+            .into_iter()
+            .rev()
+            .find(|point| point.block_height <= block_height.0)
+    }
+}
+
+impl Contract {
+    /// Takes a snapshot of the aggregated price if the last one is older than the snapshot
+    /// interval. Only the last `price_history_len` snapshots are kept.
+    pub fn internal_record_price(&mut self, asset_id: &AssetId, point: PricePoint) {
+        if self.price_history_len == 0 {
+            return;
+        }
+        let mut history = self.price_history.get(asset_id).unwrap_or_default();
+        if let Some(last) = history.last() {
+            if last.timestamp + to_nano(self.price_history_interval_sec) > point.timestamp {
+                return;
+            }
+        }
+        history.push(point);
+        let excess = history
+            .len()
+            .saturating_sub(self.price_history_len as usize);
+        history.drain(..excess);
+        self.price_history.insert(asset_id, &history);
+    }
+}
@@ -0,0 +1,78 @@
+use crate::*;
+
+/// Weights are in basis points.
+const INDEX_WEIGHT_DECIMALS: u8 = 4;
+
+/// A constituent of a composite index. The index price is the sum of prices of one whole token
+/// of the constituents multiplied by their weights, where a weight of `10000` stands for `1.0`.
+/// Whole tokens are given by the decimals of the asset metadata.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct IndexComponent {
+    pub asset_id: AssetId,
+    pub weight: u32,
+}
+
+/// Computes the weighted sum of the given prices of the smallest units of tokens with the given
+/// decimals, after converting them to prices of whole tokens with common decimals. Returns `None`
+/// if the sum doesn't fit into the price multiplier.
+pub fn compute_index_price(components: &[(Price, u8, u32)]) -> Option<Price> {
+    // The price of a whole token has `price.decimals - token_decimals` decimals, scaling the
+    // multiplier up when that's negative.
+    let whole_token_prices = components
+        .iter()
+        .map(|(price, token_decimals, weight)| {
+            if price.decimals >= *token_decimals {
+                Some((price.multiplier, price.decimals - token_decimals, *weight))
+            } else {
+                let scale = 10u128.checked_pow(u32::from(token_decimals - price.decimals))?;
+                Some((price.multiplier.checked_mul(scale)?, 0, *weight))
+            }
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let max_decimals = whole_token_prices
+        .iter()
+        .map(|(_, decimals, _)| *decimals)
+        .max()?;
+    let mut multiplier: Balance = 0;
+    for (price_multiplier, decimals, weight) in whole_token_prices {
+        let scale = 10u128.checked_pow(u32::from(max_decimals - decimals))?;
+        let term = price_multiplier
+            .checked_mul(weight as u128)?
+            .checked_mul(scale)?;
+        multiplier = multiplier.checked_add(term)?;
+    }
+    Some(Price {
+        multiplier,
+        decimals: max_decimals.checked_add(INDEX_WEIGHT_DECIMALS)?,
+    })
+}
+
+/// Returns the asset a component is derived from, e.g. `wrap.near` of `wrap.near#ema3600`.
+pub fn index_component_base_asset_id(asset_id: &str) -> &str {
+    asset_id
+        .split_once('#')
+        .map_or(asset_id, |(base_asset_id, _)| base_asset_id)
+}
+
+impl Contract {
+    /// Returns the price of the index if all its constituents have fresh prices and metadata.
+    pub fn internal_get_index_price(
+        &self,
+        components: &[IndexComponent],
+        timestamp: Timestamp,
+        max_age_sec: Option<DurationSec>,
+    ) -> Option<Price> {
+        let prices = components
+            .iter()
+            .map(|component| {
+                let metadata = self
+                    .asset_metadata
+                    .get(&index_component_base_asset_id(&component.asset_id).to_string())?;
+                self.internal_get_price(&component.asset_id, timestamp, max_age_sec)
+                    .map(|price| (price, metadata.decimals, component.weight))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        compute_index_price(&prices)
+    }
+}
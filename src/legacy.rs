@@ -1,15 +1,274 @@
 use crate::*;
 
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct ReportV0 {
+    pub oracle_id: AccountId,
+    pub timestamp: Timestamp,
+    pub price: Price,
+}
+
+impl From<ReportV0> for Report {
+    fn from(v: ReportV0) -> Self {
+        Report {
+            oracle_id: v.oracle_id,
+            timestamp: v.timestamp,
+            price: v.price,
+            source: PriceSource::Report,
+            block_height: 0,
+        }
+    }
+}
+
 #[derive(BorshSerialize, BorshDeserialize)]
 pub struct AssetV0 {
-    pub reports: Vec<Report>,
+    pub reports: Vec<ReportV0>,
 }
 
 impl From<AssetV0> for Asset {
     fn from(v: AssetV0) -> Self {
-        Asset {
+        AssetV1 {
             reports: v.reports,
             emas: vec![],
         }
+        .into()
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct AssetEmaV0 {
+    pub period_sec: DurationSec,
+    pub timestamp: Timestamp,
+    pub price: Option<Price>,
+}
+
+impl From<AssetEmaV0> for AssetEma {
+    fn from(v: AssetEmaV0) -> Self {
+        AssetEma {
+            period_sec: v.period_sec,
+            timestamp: v.timestamp,
+            price: v.price,
+            started_at: v.timestamp,
+            warm_up_sec: 0,
+            gap_policy: None,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct AssetV1 {
+    pub reports: Vec<ReportV0>,
+    pub emas: Vec<AssetEmaV0>,
+}
+
+impl From<AssetV1> for Asset {
+    fn from(v: AssetV1) -> Self {
+        Asset {
+            reports: v.reports.into_iter().map(|report| report.into()).collect(),
+            emas: v.emas.into_iter().map(|ema| ema.into()).collect(),
+            twaps: vec![],
+            group: None,
+            paused: false,
+            recency_duration_sec: None,
+            last_good: None,
+            price_ttl_sec: None,
+            min_num_recent_reports: None,
+            max_deviation_bps: None,
+            circuit_breaker: None,
+            tripped: false,
+            oracle_weighting: None,
+            price_band: None,
+            dex_source: None,
+            dex_price: None,
+            open_round: None,
+            latest_round_id: 0,
+            min_report_interval_sec: None,
+            expiry_policy: None,
+            commit_reveal: None,
+            commitments: vec![],
+            peg: None,
+            depegged_since: None,
+            quote_asset_ids: vec![],
+            consumers: None,
+            multi_block_median_len: None,
+            block_medians: vec![],
+            volatilities: vec![],
+            sequence: 0,
+            price_decimals: None,
+            deprecation: None,
+            report_intervals: vec![],
+            fallback_oracle_id: None,
+            aggregated_at: 0,
+            pending_aggregation: false,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct ContractV0 {
+    pub oracles: UnorderedMap<AccountId, VOracle>,
+    pub assets: UnorderedMap<AssetId, VAsset>,
+    pub recency_duration_sec: DurationSec,
+    pub owner_id: AccountId,
+    pub near_claim_amount: Balance,
+}
+
+impl From<ContractV0> for Contract {
+    fn from(c: ContractV0) -> Self {
+        // Assets stay in the unordered map and move into the lookup map and the list of asset IDs
+        // one at a time, so the migration fits into one call regardless of the number of assets.
+        Contract {
+            oracles: c.oracles,
+            assets: LookupMap::new(StorageKey::AssetsById),
+            asset_ids: Vector::new(StorageKey::AssetIds),
+            legacy_assets: c.assets,
+            recency_duration_sec: c.recency_duration_sec,
+            owner_id: c.owner_id,
+            near_claim_amount: c.near_claim_amount,
+            relayers: UnorderedSet::new(StorageKey::Relayers),
+            asset_groups: UnorderedMap::new(StorageKey::AssetGroups),
+            indexes: UnorderedMap::new(StorageKey::Indexes),
+            weight_decay: None,
+            max_assets: DEFAULT_MAX_ASSETS,
+            oracle_set_stabilization_sec: 0,
+            stabilization_until: 0,
+            min_stake: 0,
+            unbonding_period_sec: 0,
+            total_stake: 0,
+            min_num_recent_reports: None,
+            price_history: UnorderedMap::new(StorageKey::PriceHistory),
+            price_history_len: 0,
+            price_history_interval_sec: 0,
+            roles: UnorderedMap::new(StorageKey::Roles),
+            paused: false,
+            asset_metadata: UnorderedMap::new(StorageKey::AssetMetadata),
+            pairs: UnorderedMap::new(StorageKey::Pairs),
+            rewards_pool: 0,
+            total_unclaimed_rewards: 0,
+            reward_per_report: 0,
+            max_rewards_per_epoch: None,
+            reward_epoch_sec: 0,
+            flat_fee: 0,
+            per_asset_fee: 0,
+            collected_fees: 0,
+            token_fees: UnorderedMap::new(StorageKey::TokenFees),
+            collected_token_fees: UnorderedMap::new(StorageKey::CollectedTokenFees),
+            price_feed: None,
+            price_feed_publish_times: UnorderedMap::new(StorageKey::PriceFeedPublishTimes),
+            proposed_owner_id: None,
+            timelock_sec: 0,
+            proposals: UnorderedMap::new(StorageKey::Proposals),
+            next_proposal_id: 0,
+            staged_code: LazyOption::new(StorageKey::StagedCode, None),
+            staged_code_info: None,
+            storage_deposit_required: false,
+            storage_accounts: UnorderedMap::new(StorageKey::StorageAccounts),
+            total_storage_deposits: 0,
+            oracle_weighting: OracleWeighting::Equal,
+            dex_contracts: UnorderedSet::new(StorageKey::DexContracts),
+            subscriptions: UnorderedMap::new(StorageKey::Subscriptions),
+            next_subscription_id: 0,
+            asset_subscriptions: UnorderedMap::new(StorageKey::AssetSubscriptions),
+            push_fee: 0,
+            total_subscription_balances: 0,
+            rounds: LookupMap::new(StorageKey::Rounds),
+            round_duration_sec: 0,
+            round_history_len: 0,
+            min_report_interval_sec: 0,
+            expiry_policy: ExpiryPolicy::ReturnNone,
+            failover: None,
+            failover_configured_at: 0,
+            consumer_stats: LookupMap::new(StorageKey::ConsumerStats),
+            refund_failed_calls: false,
+            bundles: UnorderedMap::new(StorageKey::Bundles),
+            dao_id: None,
+            mirrors: UnorderedMap::new(StorageKey::Mirrors),
+            oracle_scoring: None,
+            oracle_performance: LookupMap::new(StorageKey::OraclePerformance),
+            next_price_request_id: 0,
+            price_requests: LookupMap::new(StorageKey::PriceRequests),
+            open_price_request_ids: UnorderedSet::new(StorageKey::OpenPriceRequestIds),
+            total_price_request_fees: 0,
+            price_sequence: 0,
+            last_price_update: 0,
+            legacy_migration_index: 0,
+            multisig: None,
+            next_owner_proposal_id: 0,
+            owner_proposals: UnorderedMap::new(StorageKey::OwnerProposals),
+            last_price_data_commitment: None,
+            batch_price_events: false,
+            symbols: UnorderedMap::new(StorageKey::Symbols),
+            max_assets_per_request: MAX_UNFILTERED_ASSETS,
+            adaptive_recency: None,
+            aggregation_window_sec: None,
+            price_receipts_enabled: false,
+            next_price_receipt_id: 0,
+            price_receipts: LookupMap::new(StorageKey::PriceReceipts),
+            rate_feeds: UnorderedMap::new(StorageKey::RateFeeds),
+            removed_oracle_stakes: LookupMap::new(StorageKey::RemovedOracleStakes),
+            asset_storage_payers: LookupMap::new(StorageKey::AssetStoragePayers),
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the number of assets left in the map of `ContractV0` or stored in a legacy version
+    /// of `VAsset`. Once it's `0`, the next version of the contract can drop `legacy_assets`,
+    /// the `V0` and `V1` variants of `VAsset` and their layouts in this module.
+    pub fn get_unmigrated_count(&self) -> u64 {
+        self.legacy_assets.len() + self.get_num_legacy_assets()
+    }
+}
+
+impl Contract {
+    /// Moves up to `limit` assets out of the map of `ContractV0`, then checks the rest of the
+    /// limit of assets from the migration index and rewrites the legacy ones. The index wraps
+    /// around after the last asset, so that assets moved by removals are checked on the next
+    /// pass. Returns the number of migrated assets.
+    pub fn internal_migrate_legacy_assets(&mut self, limit: u64) -> u64 {
+        let num_moved = self.internal_move_legacy_assets(limit);
+        let num_assets = self.asset_ids.len();
+        let mut index = self.legacy_migration_index;
+        if index >= num_assets {
+            index = 0;
+        }
+        let end = std::cmp::min(num_assets, index.saturating_add(limit - num_moved));
+        let asset_ids: Vec<AssetId> = (index..end)
+            .map(|i| self.asset_ids.get(i).unwrap())
+            .collect();
+        let mut num_migrated = num_moved;
+        for asset_id in asset_ids {
+            let v_asset = self.assets.get(&asset_id).unwrap();
+            if !v_asset.is_current() {
+                self.internal_set_asset(&asset_id, v_asset.into());
+                num_migrated += 1;
+            }
+        }
+        self.legacy_migration_index = end;
+        Event::MigrateLegacyAssets {
+            num_migrated,
+            next_index: end,
+        }
+        .emit();
+        num_migrated
+    }
+
+    /// Moves up to `limit` assets left in the map of `ContractV0` into the lookup map in the
+    /// current version. Returns the number of moved assets.
+    fn internal_move_legacy_assets(&mut self, limit: u64) -> u64 {
+        let mut num_moved = 0;
+        while num_moved < limit && !self.legacy_assets.is_empty() {
+            // Removing the last key of the unordered map doesn't move other keys.
+            let asset_id = self
+                .legacy_assets
+                .keys_as_vector()
+                .get(self.legacy_assets.len() - 1)
+                .unwrap();
+            let asset: Asset = self.legacy_assets.remove(&asset_id).unwrap().into();
+            self.assets.insert(&asset_id, &asset.into());
+            self.asset_ids.push(&asset_id);
+            num_moved += 1;
+        }
+        num_moved
     }
 }
@@ -1,40 +1,159 @@
+mod acl;
 mod asset;
+mod asset_config;
+mod attestation;
+mod cadence;
+mod chainlink;
+mod commit_reveal;
+mod consumers;
+mod deferred;
+mod delegated;
+mod dex;
 mod ema;
+mod errors;
+mod events;
+mod failover;
+mod fallback;
+mod ft;
+mod governance;
+mod history;
+mod index;
 mod legacy;
 mod oracle;
 mod owner;
+mod pairs;
+mod peg;
+mod pessimistic;
+mod price_feed;
+mod rates;
+mod receipts;
+mod relay;
+mod requests;
+mod rewards;
+mod rounds;
+mod scoring;
+mod stake;
+mod storage;
+mod subscriptions;
+#[cfg(feature = "testing")]
+mod testing;
+#[cfg(test)]
+mod tests;
+mod twap;
 mod upgrade;
 mod utils;
+mod weight;
 
+pub use crate::acl::*;
 pub use crate::asset::*;
+pub use crate::asset_config::*;
+pub use crate::attestation::*;
+pub use crate::cadence::*;
+pub use crate::chainlink::*;
+pub use crate::commit_reveal::*;
+pub use crate::consumers::*;
+pub use crate::delegated::*;
+pub use crate::dex::*;
 pub use crate::ema::*;
+pub use crate::errors::*;
+pub use crate::events::*;
+pub use crate::failover::*;
+pub use crate::fallback::*;
+pub use crate::ft::*;
+pub use crate::governance::*;
+pub use crate::index::*;
 use crate::legacy::*;
 pub use crate::oracle::*;
+pub use crate::owner::*;
+pub use crate::pairs::*;
+pub use crate::peg::*;
+pub use crate::pessimistic::*;
+pub use crate::price_feed::*;
+pub use crate::rates::*;
+pub use crate::receipts::*;
+pub use crate::relay::*;
+pub use crate::requests::*;
+pub use crate::rounds::*;
+pub use crate::scoring::*;
+pub use crate::stake::*;
+pub use crate::storage::*;
+pub use crate::subscriptions::*;
+pub use crate::twap::*;
+pub use crate::upgrade::*;
 pub use crate::utils::*;
+pub use crate::weight::*;
 
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::UnorderedMap;
-use near_sdk::json_types::U128;
+use near_sdk::collections::{LazyOption, LookupMap, UnorderedMap, UnorderedSet, Vector};
+use near_sdk::json_types::{Base58CryptoHash, U128, U64};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{
-    assert_one_yocto, env, ext_contract, log, near_bindgen, AccountId, Balance, BorshStorageKey,
-    Duration, Gas, PanicOnDefault, Promise, Timestamp, ONE_NEAR,
+    assert_one_yocto, env, ext_contract, log, near_bindgen, AccountId, Balance, BlockHeight,
+    BorshStorageKey, Duration, Gas, PanicOnDefault, Promise, PublicKey, Timestamp, ONE_NEAR,
 };
 
 const NO_DEPOSIT: Balance = 0;
 
 const GAS_FOR_PROMISE: Gas = Gas(Gas::ONE_TERA.0 * 10);
 
+/// The minimum gas a price request can reserve for the contract per promise.
+const MIN_RESERVED_GAS: Gas = Gas(Gas::ONE_TERA.0 * 5);
+
 const NEAR_CLAIM_DURATION: Duration = 24 * 60 * 60 * 10u64.pow(9);
 // This is a safety margin in NEAR for to cover potential extra storage.
 const SAFETY_MARGIN_NEAR_CLAIM: Balance = ONE_NEAR;
 
+/// The default limit on the number of assets, can be changed by the owner.
+const DEFAULT_MAX_ASSETS: u64 = 100;
+
+/// The maximum number of assets returned by price views without a filter, so they fit into the
+/// view gas limit. Prices of more assets can be read with `get_price_data_paged`.
+pub const MAX_UNFILTERED_ASSETS: u64 = 200;
+
+/// The maximum number of reports kept per asset, so the median stays cheap to compute.
+pub const MAX_REPORTS_PER_ASSET: usize = 256;
+
 pub type DurationSec = u32;
 
+/// The decimals of values returned by `value_in_usd`.
+pub const VALUE_DECIMALS: u8 = 18;
+
 #[derive(BorshSerialize, BorshStorageKey)]
 enum StorageKey {
     Oracles,
+    /// The prefix of the assets map of `ContractV0`, kept as `legacy_assets`.
     Assets,
+    Relayers,
+    AssetGroups,
+    Indexes,
+    PriceHistory,
+    Roles,
+    AssetMetadata,
+    Pairs,
+    TokenFees,
+    CollectedTokenFees,
+    PriceFeedPublishTimes,
+    Proposals,
+    StagedCode,
+    StorageAccounts,
+    AssetsById,
+    AssetIds,
+    DexContracts,
+    Subscriptions,
+    AssetSubscriptions,
+    Rounds,
+    ConsumerStats,
+    Bundles,
+    Mirrors,
+    OraclePerformance,
+    PriceRequests,
+    OpenPriceRequestIds,
+    OwnerProposals,
+    Symbols,
+    PriceReceipts,
+    RateFeeds,
+    RemovedOracleStakes,
+    AssetStoragePayers,
 }
 
 #[near_bindgen]
@@ -42,23 +161,442 @@ enum StorageKey {
 pub struct Contract {
     pub oracles: UnorderedMap<AccountId, VOracle>,
 
-    pub assets: UnorderedMap<AssetId, VAsset>,
+    pub assets: LookupMap<AssetId, VAsset>,
+
+    /// IDs of all assets in the order they were added, except that removing an asset moves the
+    /// last asset to its place.
+    pub asset_ids: Vector<AssetId>,
+
+    /// Assets left in the map of `ContractV0` by `migrate_state`. They're read from here until
+    /// they're written or moved by `migrate_legacy_assets`, so the state migration doesn't have to
+    /// copy all assets at once.
+    pub legacy_assets: UnorderedMap<AssetId, VAsset>,
 
     pub recency_duration_sec: DurationSec,
 
     pub owner_id: AccountId,
 
     pub near_claim_amount: Balance,
+
+    /// Accounts allowed to submit signed reports on behalf of oracles.
+    pub relayers: UnorderedSet<AccountId>,
+
+    /// Asset IDs of every asset group.
+    pub asset_groups: UnorderedMap<GroupId, Vec<AssetId>>,
+
+    /// Composite assets priced as a weighted basket of other assets.
+    pub indexes: UnorderedMap<AssetId, Vec<IndexComponent>>,
+
+    /// When set, reports lose their weight in the aggregated price as they age.
+    pub weight_decay: Option<WeightDecay>,
+
+    /// The maximum number of assets, bounds the storage and gas of the unfiltered price views.
+    pub max_assets: u64,
+
+    /// For how long prices stay frozen after the oracle set is replaced.
+    pub oracle_set_stabilization_sec: DurationSec,
+
+    /// Until this time prices are served from `Asset::last_good`.
+    pub stabilization_until: Timestamp,
+
+    /// The stake an oracle needs to report prices.
+    pub min_stake: Balance,
+
+    /// For how long unstaked NEAR stays slashable before it can be withdrawn.
+    pub unbonding_period_sec: DurationSec,
+
+    /// The total stake of all oracles including the unbonding stake. It isn't available for NEAR
+    /// claims.
+    pub total_stake: Balance,
+
+    /// Overrides the default quorum of recent reports from half of the oracles.
+    pub min_num_recent_reports: Option<u32>,
+
+    /// Snapshots of aggregated prices of every asset.
+    pub price_history: UnorderedMap<AssetId, Vec<PricePoint>>,
+
+    /// The number of snapshots kept per asset. `0` disables the price history.
+    pub price_history_len: u32,
+
+    /// The minimum interval between two snapshots of the same asset.
+    pub price_history_interval_sec: DurationSec,
+
+    /// Roles granted to accounts other than the owner.
+    pub roles: UnorderedMap<AccountId, Vec<Role>>,
+
+    /// The paused contract doesn't accept reports and doesn't return prices.
+    pub paused: bool,
+
+    /// Describes how prices of assets are quoted.
+    pub asset_metadata: UnorderedMap<AssetId, AssetMetadata>,
+
+    /// Synthetic assets priced from two other assets.
+    pub pairs: UnorderedMap<AssetId, DerivedPair>,
+
+    /// NEAR available for rewards that aren't accrued yet.
+    pub rewards_pool: Balance,
+
+    /// Rewards accrued by oracles, but not claimed yet.
+    pub total_unclaimed_rewards: Balance,
+
+    /// The reward for every accepted price. `0` disables rewards.
+    pub reward_per_report: Balance,
+
+    /// The maximum reward an oracle can accrue within a reward epoch.
+    pub max_rewards_per_epoch: Option<Balance>,
+
+    pub reward_epoch_sec: DurationSec,
+
+    /// The fee of every `oracle_call`.
+    pub flat_fee: Balance,
+
+    /// The fee for every asset requested by `oracle_call`.
+    pub per_asset_fee: Balance,
+
+    /// Fees that aren't withdrawn yet.
+    pub collected_fees: Balance,
+
+    /// Tokens accepted as a payment for price requests.
+    pub token_fees: UnorderedMap<AccountId, TokenFee>,
+
+    /// Fees paid in tokens that aren't withdrawn yet.
+    pub collected_token_fees: UnorderedMap<AccountId, Balance>,
+
+    /// The attestation network accepted by `update_price_feeds`.
+    pub price_feed: Option<PriceFeedConfig>,
+
+    /// The publish time of the last accepted attested price of each asset.
+    pub price_feed_publish_times: UnorderedMap<AssetId, Timestamp>,
+
+    /// The account that can accept the ownership.
+    pub proposed_owner_id: Option<AccountId>,
+
+    /// The delay of timelocked configuration changes.
+    pub timelock_sec: DurationSec,
+
+    /// Scheduled configuration changes.
+    pub proposals: UnorderedMap<u64, Proposal>,
+
+    pub next_proposal_id: u64,
+
+    /// The code of the pending upgrade, it's only read when deployed.
+    pub staged_code: LazyOption<Vec<u8>>,
+
+    pub staged_code_info: Option<StagedCode>,
+
+    /// Whether accounts pay for the storage of assets they register and prices they report.
+    pub storage_deposit_required: bool,
+
+    pub storage_accounts: UnorderedMap<AccountId, StorageAccount>,
+
+    pub total_storage_deposits: Balance,
+
+    /// Where the weights of oracles in the aggregated price come from, unless overridden by the
+    /// asset.
+    pub oracle_weighting: OracleWeighting,
+
+    /// AMM contracts the DEX prices of assets can be fetched from.
+    pub dex_contracts: UnorderedSet<AccountId>,
+
+    /// Consumer contracts receiving price pushes.
+    pub subscriptions: UnorderedMap<u64, Subscription>,
+
+    pub next_subscription_id: u64,
+
+    /// IDs of subscriptions of every asset.
+    pub asset_subscriptions: UnorderedMap<AssetId, Vec<u64>>,
+
+    /// The fee of every price push, paid from the balance of the subscription.
+    pub push_fee: Balance,
+
+    /// Balances of all subscriptions, they aren't available for NEAR claims.
+    pub total_subscription_balances: Balance,
+
+    /// Finalized aggregation rounds of every asset.
+    pub rounds: LookupMap<(AssetId, RoundId), Round>,
+
+    /// The duration of aggregation rounds. `0` disables rounds.
+    pub round_duration_sec: DurationSec,
+
+    /// The number of finalized rounds kept per asset.
+    pub round_history_len: u32,
+
+    /// The minimum interval between two reports of an oracle for the same asset, unless
+    /// overridden by the asset. `0` disables the limit.
+    pub min_report_interval_sec: DurationSec,
+
+    /// What `get_price_data` returns for stale prices, unless overridden by the asset.
+    pub expiry_policy: ExpiryPolicy,
+
+    /// The dead-man switch handing control to backup oracles.
+    pub failover: Option<FailoverConfig>,
+
+    /// When the failover was configured, its window doesn't start earlier.
+    pub failover_configured_at: Timestamp,
+
+    /// Price request stats per consumer.
+    pub consumer_stats: LookupMap<AccountId, ConsumerStats>,
+
+    /// Refund NEAR fees of price requests whose receiver failed.
+    pub refund_failed_calls: bool,
+
+    /// Named lists of asset IDs that price requests can use instead of the IDs.
+    pub bundles: UnorderedMap<BundleId, Vec<AssetId>>,
+
+    /// The DAO account that has every role, e.g. a Sputnik DAO executing proposals.
+    pub dao_id: Option<AccountId>,
+
+    /// Mirror oracles prices are relayed to, with the sequence numbers of their latest relays.
+    pub mirrors: UnorderedMap<AccountId, u64>,
+
+    /// Scores oracles on finalized rounds and suspends the low scoring ones.
+    pub oracle_scoring: Option<OracleScoring>,
+
+    pub oracle_performance: LookupMap<AccountId, OraclePerformance>,
+
+    pub next_price_request_id: u64,
+    /// On-demand price requests, fulfilled ones keep their answers.
+    pub price_requests: LookupMap<u64, PriceRequest>,
+    pub open_price_request_ids: UnorderedSet<u64>,
+    /// Fees of open price requests.
+    pub total_price_request_fees: Balance,
+
+    /// The number of aggregated price updates of all assets.
+    pub price_sequence: u64,
+    pub last_price_update: Timestamp,
+
+    /// The index in `asset_ids` the next `migrate_legacy_assets` call continues from.
+    pub legacy_migration_index: u64,
+
+    /// While set, actions of the owner require confirmations of the multisig admins.
+    pub multisig: Option<Multisig>,
+    pub next_owner_proposal_id: u64,
+    pub owner_proposals: UnorderedMap<u64, OwnerProposal>,
+
+    pub last_price_data_commitment: Option<PriceDataCommitment>,
+
+    /// Log the aggregated price changes of a report call as one `price_changes` event instead of
+    /// an `update_price` event per asset.
+    pub batch_price_events: bool,
+
+    /// Symbols of assets that aren't tokens, with the IDs of their assets.
+    pub symbols: UnorderedMap<String, AssetId>,
+
+    /// The maximum number of assets of a price request, at most `MAX_UNFILTERED_ASSETS`.
+    pub max_assets_per_request: u64,
+
+    /// Adapts recency durations of assets to their report cadence.
+    pub adaptive_recency: Option<AdaptiveRecency>,
+
+    /// When set, reports are only stored and prices are aggregated by `finalize_prices` or by
+    /// the first report after the window since the last aggregation of the asset.
+    pub aggregation_window_sec: Option<DurationSec>,
+
+    /// Record deliveries of price data as receipts.
+    pub price_receipts_enabled: bool,
+    pub next_price_receipt_id: u64,
+    pub price_receipts: LookupMap<u64, PriceReceipt>,
+
+    /// Feeds of signed values, e.g. funding rates, by their symbols.
+    pub rate_feeds: UnorderedMap<String, RateFeed>,
+
+    /// Stakes of removed oracles until they unbond, included in `total_stake`.
+    pub removed_oracle_stakes: LookupMap<AccountId, RemovedOracleStake>,
+
+    /// The accounts that paid for the storage of assets added while storage deposits were
+    /// required. They get the storage back once the asset is removed.
+    pub asset_storage_payers: LookupMap<AssetId, AccountId>,
 }
 
 #[derive(Serialize, Deserialize)]
 #[serde(crate = "near_sdk::serde")]
+pub struct Config {
+    pub owner_id: AccountId,
+    pub proposed_owner_id: Option<AccountId>,
+    pub recency_duration_sec: DurationSec,
+    pub near_claim_amount: U128,
+    pub weight_decay: Option<WeightDecay>,
+    pub oracle_weighting: OracleWeighting,
+    pub num_oracles: u64,
+    pub num_assets: u64,
+    pub max_assets: u64,
+    pub oracle_set_stabilization_sec: DurationSec,
+    #[serde(with = "u64_dec_format")]
+    pub stabilization_until: Timestamp,
+    pub min_stake: U128,
+    pub unbonding_period_sec: DurationSec,
+    pub total_stake: U128,
+    /// The number of recent reports required for a price, unless overridden by the asset.
+    pub min_num_recent_reports: u32,
+    pub min_report_interval_sec: DurationSec,
+    pub expiry_policy: ExpiryPolicy,
+    pub failover: Option<FailoverConfig>,
+    pub price_history_len: u32,
+    pub price_history_interval_sec: DurationSec,
+    pub round_duration_sec: DurationSec,
+    pub round_history_len: u32,
+    pub paused: bool,
+    pub timelock_sec: DurationSec,
+    pub storage_deposit_required: bool,
+    pub total_storage_deposits: U128,
+    pub rewards_pool: U128,
+    pub reward_per_report: U128,
+    pub max_rewards_per_epoch: Option<U128>,
+    pub reward_epoch_sec: DurationSec,
+    pub flat_fee: U128,
+    pub per_asset_fee: U128,
+    pub collected_fees: U128,
+    pub push_fee: U128,
+    pub total_subscription_balances: U128,
+    pub refund_failed_calls: bool,
+    pub dao_id: Option<AccountId>,
+    pub oracle_scoring: Option<OracleScoring>,
+    pub total_price_request_fees: U128,
+    pub multisig: Option<Multisig>,
+    pub batch_price_events: bool,
+    pub max_assets_per_request: u64,
+    pub price_receipts_enabled: bool,
+    pub adaptive_recency: Option<AdaptiveRecency>,
+    pub aggregation_window_sec: Option<DurationSec>,
+}
+
+/// Aggregate stats of the oracle for monitoring.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OracleHealth {
+    pub num_assets: u64,
+    /// Assets that currently have a price.
+    pub num_fresh_assets: u64,
+    pub num_paused_assets: u64,
+    pub num_oracles: u64,
+    /// Oracles that reported within the recency duration.
+    pub num_active_oracles: u64,
+    /// The oldest of the latest reports of assets that have a price.
+    pub oldest_fresh_report: Option<U64>,
+    pub paused: bool,
+    pub frozen: bool,
+    pub staged_code_hash: Option<near_sdk::json_types::Base58CryptoHash>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
 pub struct PriceData {
     #[serde(with = "u64_dec_format")]
     pub timestamp: Timestamp,
+    /// The block the price data was read in.
+    #[serde(default, with = "u64_dec_format")]
+    pub block_height: BlockHeight,
     pub recency_duration_sec: DurationSec,
 
     pub prices: Vec<AssetOptionalPrice>,
+
+    /// The oracle set was recently replaced and the prices are the last ones computed by the
+    /// previous set.
+    #[serde(default)]
+    pub frozen: bool,
+
+    /// The number of aggregated price updates of all assets, only grows. Receivers can compare it
+    /// to reject deliveries that are older than the ones they already got.
+    #[serde(default, with = "u64_dec_format")]
+    pub sequence: u64,
+    /// When any asset got its latest aggregated price.
+    #[serde(default, with = "u64_dec_format")]
+    pub last_update_timestamp: Timestamp,
+
+    /// The hash of the delivered price data, see `PriceData::commitment_hash`. Only set on
+    /// deliveries to receivers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commitment: Option<Base58CryptoHash>,
+    /// The ID of the receipt of the delivery, see `get_price_receipt`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub receipt_id: Option<U64>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceDataV2 {
+    #[serde(with = "u64_dec_format")]
+    pub timestamp: Timestamp,
+    pub recency_duration_sec: DurationSec,
+    pub prices: Vec<AssetPriceV2>,
+    pub frozen: bool,
+}
+
+/// The result of `get_price_data_detailed`, which partitions the requested assets by whether they
+/// have a fresh price.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceDataDetailed {
+    #[serde(with = "u64_dec_format")]
+    pub timestamp: Timestamp,
+    #[serde(with = "u64_dec_format")]
+    pub block_height: BlockHeight,
+    pub recency_duration_sec: DurationSec,
+    /// Assets with fresh prices.
+    pub prices: Vec<AssetOptionalPrice>,
+    /// Known assets without a fresh price, which may come with the last aggregated price and the
+    /// `expired` flag.
+    pub stale: Vec<AssetOptionalPrice>,
+    /// Requested IDs that aren't assets, indexes or pairs.
+    pub unknown_assets: Vec<AssetId>,
+    pub frozen: bool,
+    #[serde(with = "u64_dec_format")]
+    pub sequence: u64,
+    #[serde(with = "u64_dec_format")]
+    pub last_update_timestamp: Timestamp,
+}
+
+/// How a price request splits the remaining gas. By default `GAS_FOR_PROMISE` is reserved for
+/// the contract per promise and receivers get the rest.
+#[derive(Serialize, Deserialize, Default, Clone, Copy)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GasBudget {
+    /// The gas reserved per promise, at least `MIN_RESERVED_GAS`.
+    #[serde(default)]
+    pub reserved_gas: Option<Gas>,
+    /// The request fails unless every receiver gets at least this much gas.
+    #[serde(default)]
+    pub min_receiver_gas: Option<Gas>,
+}
+
+impl GasBudget {
+    /// Returns the gas of every receiver. One reservation is kept for the contract and one more
+    /// per created promise, i.e. two per receiver with its callback, besides the gas of the
+    /// callbacks. If the promises can't be created, the request fails and the attached deposit is
+    /// refunded.
+    pub fn receiver_gas(&self, num_receivers: u64) -> Gas {
+        let reserved_gas = std::cmp::max(
+            self.reserved_gas.unwrap_or(GAS_FOR_PROMISE).0,
+            MIN_RESERVED_GAS.0,
+        );
+        let remaining_gas = (env::prepaid_gas() - env::used_gas()).0;
+        let receiver_gas = remaining_gas
+            .checked_sub(reserved_gas + GAS_FOR_ON_ORACLE_CALL_COMPLETE.0 * num_receivers)
+            .and_then(|gas| (gas / num_receivers).checked_sub(2 * reserved_gas))
+            .unwrap_or_else(|| panic!("{}", ContractError::NotEnoughGas));
+        let min_receiver_gas = self.min_receiver_gas.unwrap_or(Gas(0));
+        assert!(
+            receiver_gas >= min_receiver_gas.0,
+            "{}",
+            ContractError::ReceiverGasTooLow(min_receiver_gas.0)
+        );
+        Gas(receiver_gas)
+    }
+}
+
+/// A receiver of `oracle_call_many` with its own message.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReceiverCall {
+    pub receiver_id: AccountId,
+    pub msg: String,
+}
+
+/// Fails if any of the assets in the price data doesn't have a price.
+fn assert_all_prices(price_data: &PriceData) {
+    if let Some(missing) = price_data.prices.iter().find(|price| price.price.is_none()) {
+        panic!("{}", ContractError::MissingPrice(&missing.asset_id));
+    }
 }
 
 #[ext_contract(ext_price_receiver)]
@@ -76,24 +614,164 @@ impl Contract {
     ) -> Self {
         Self {
             oracles: UnorderedMap::new(StorageKey::Oracles),
-            assets: UnorderedMap::new(StorageKey::Assets),
+            assets: LookupMap::new(StorageKey::AssetsById),
+            asset_ids: Vector::new(StorageKey::AssetIds),
+            legacy_assets: UnorderedMap::new(StorageKey::Assets),
             recency_duration_sec,
             owner_id,
             near_claim_amount: near_claim_amount.into(),
+            relayers: UnorderedSet::new(StorageKey::Relayers),
+            asset_groups: UnorderedMap::new(StorageKey::AssetGroups),
+            indexes: UnorderedMap::new(StorageKey::Indexes),
+            weight_decay: None,
+            max_assets: DEFAULT_MAX_ASSETS,
+            oracle_set_stabilization_sec: 0,
+            stabilization_until: 0,
+            min_stake: 0,
+            unbonding_period_sec: 0,
+            total_stake: 0,
+            min_num_recent_reports: None,
+            price_history: UnorderedMap::new(StorageKey::PriceHistory),
+            price_history_len: 0,
+            price_history_interval_sec: 0,
+            roles: UnorderedMap::new(StorageKey::Roles),
+            paused: false,
+            asset_metadata: UnorderedMap::new(StorageKey::AssetMetadata),
+            pairs: UnorderedMap::new(StorageKey::Pairs),
+            rewards_pool: 0,
+            total_unclaimed_rewards: 0,
+            reward_per_report: 0,
+            max_rewards_per_epoch: None,
+            reward_epoch_sec: 0,
+            flat_fee: 0,
+            per_asset_fee: 0,
+            collected_fees: 0,
+            token_fees: UnorderedMap::new(StorageKey::TokenFees),
+            collected_token_fees: UnorderedMap::new(StorageKey::CollectedTokenFees),
+            price_feed: None,
+            price_feed_publish_times: UnorderedMap::new(StorageKey::PriceFeedPublishTimes),
+            proposed_owner_id: None,
+            timelock_sec: 0,
+            proposals: UnorderedMap::new(StorageKey::Proposals),
+            next_proposal_id: 0,
+            staged_code: LazyOption::new(StorageKey::StagedCode, None),
+            staged_code_info: None,
+            storage_deposit_required: false,
+            storage_accounts: UnorderedMap::new(StorageKey::StorageAccounts),
+            total_storage_deposits: 0,
+            oracle_weighting: OracleWeighting::Equal,
+            dex_contracts: UnorderedSet::new(StorageKey::DexContracts),
+            subscriptions: UnorderedMap::new(StorageKey::Subscriptions),
+            next_subscription_id: 0,
+            asset_subscriptions: UnorderedMap::new(StorageKey::AssetSubscriptions),
+            push_fee: 0,
+            total_subscription_balances: 0,
+            rounds: LookupMap::new(StorageKey::Rounds),
+            round_duration_sec: 0,
+            round_history_len: 0,
+            min_report_interval_sec: 0,
+            expiry_policy: ExpiryPolicy::ReturnNone,
+            failover: None,
+            failover_configured_at: 0,
+            consumer_stats: LookupMap::new(StorageKey::ConsumerStats),
+            refund_failed_calls: false,
+            bundles: UnorderedMap::new(StorageKey::Bundles),
+            dao_id: None,
+            mirrors: UnorderedMap::new(StorageKey::Mirrors),
+            oracle_scoring: None,
+            oracle_performance: LookupMap::new(StorageKey::OraclePerformance),
+            next_price_request_id: 0,
+            price_requests: LookupMap::new(StorageKey::PriceRequests),
+            open_price_request_ids: UnorderedSet::new(StorageKey::OpenPriceRequestIds),
+            total_price_request_fees: 0,
+            price_sequence: 0,
+            last_price_update: 0,
+            legacy_migration_index: 0,
+            multisig: None,
+            next_owner_proposal_id: 0,
+            owner_proposals: UnorderedMap::new(StorageKey::OwnerProposals),
+            last_price_data_commitment: None,
+            batch_price_events: false,
+            symbols: UnorderedMap::new(StorageKey::Symbols),
+            max_assets_per_request: MAX_UNFILTERED_ASSETS,
+            adaptive_recency: None,
+            aggregation_window_sec: None,
+            price_receipts_enabled: false,
+            next_price_receipt_id: 0,
+            price_receipts: LookupMap::new(StorageKey::PriceReceipts),
+            rate_feeds: UnorderedMap::new(StorageKey::RateFeeds),
+            removed_oracle_stakes: LookupMap::new(StorageKey::RemovedOracleStakes),
+            asset_storage_payers: LookupMap::new(StorageKey::AssetStoragePayers),
         }
     }
 
     /// Remove price data from removed oracle.
     pub fn clean_oracle_data(&mut self, account_id: AccountId, asset_ids: Vec<AssetId>) {
-        assert!(self.internal_get_oracle(&account_id).is_none());
+        assert!(
+            self.internal_get_oracle(&account_id).is_none(),
+            "{}",
+            ContractError::OracleAlreadyExists
+        );
         for asset_id in asset_ids {
-            let mut asset = self.internal_get_asset(&asset_id).expect("Unknown asset");
+            let mut asset = self
+                .internal_get_asset(&asset_id)
+                .unwrap_or_else(|| panic!("{}", ContractError::UnknownAsset));
             if asset.remove_report(&account_id) {
                 self.internal_set_asset(&asset_id, asset);
             }
         }
     }
 
+    pub fn get_config(&self) -> Config {
+        Config {
+            owner_id: self.owner_id.clone(),
+            proposed_owner_id: self.proposed_owner_id.clone(),
+            recency_duration_sec: self.recency_duration_sec,
+            near_claim_amount: self.near_claim_amount.into(),
+            weight_decay: self.weight_decay,
+            oracle_weighting: self.oracle_weighting,
+            num_oracles: self.oracles.len(),
+            num_assets: self.internal_num_assets(),
+            max_assets: self.max_assets,
+            oracle_set_stabilization_sec: self.oracle_set_stabilization_sec,
+            stabilization_until: self.stabilization_until,
+            min_stake: self.min_stake.into(),
+            unbonding_period_sec: self.unbonding_period_sec,
+            total_stake: self.total_stake.into(),
+            min_num_recent_reports: self.min_num_recent_reports() as u32,
+            min_report_interval_sec: self.min_report_interval_sec,
+            expiry_policy: self.expiry_policy,
+            failover: self.failover.clone(),
+            price_history_len: self.price_history_len,
+            price_history_interval_sec: self.price_history_interval_sec,
+            round_duration_sec: self.round_duration_sec,
+            round_history_len: self.round_history_len,
+            paused: self.paused,
+            timelock_sec: self.timelock_sec,
+            storage_deposit_required: self.storage_deposit_required,
+            total_storage_deposits: self.total_storage_deposits.into(),
+            rewards_pool: self.rewards_pool.into(),
+            reward_per_report: self.reward_per_report.into(),
+            max_rewards_per_epoch: self.max_rewards_per_epoch.map(|amount| amount.into()),
+            reward_epoch_sec: self.reward_epoch_sec,
+            flat_fee: self.flat_fee.into(),
+            per_asset_fee: self.per_asset_fee.into(),
+            collected_fees: self.collected_fees.into(),
+            push_fee: self.push_fee.into(),
+            refund_failed_calls: self.refund_failed_calls,
+            dao_id: self.dao_id.clone(),
+            oracle_scoring: self.oracle_scoring,
+            total_price_request_fees: self.total_price_request_fees.into(),
+            multisig: self.multisig.clone(),
+            batch_price_events: self.batch_price_events,
+            max_assets_per_request: self.max_assets_per_request,
+            price_receipts_enabled: self.price_receipts_enabled,
+            adaptive_recency: self.adaptive_recency,
+            aggregation_window_sec: self.aggregation_window_sec,
+            total_subscription_balances: self.total_subscription_balances.into(),
+        }
+    }
+
     pub fn get_oracle(&self, account_id: AccountId) -> Option<Oracle> {
         self.internal_get_oracle(&account_id)
     }
@@ -106,54 +784,494 @@ impl Contract {
         unordered_map_pagination(&self.oracles, from_index, limit)
     }
 
+    /// Returns oracles sorted by the number of reported prices, starting from the most active one.
+    pub fn get_oracle_leaderboard(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<(AccountId, Oracle)> {
+        let mut oracles: Vec<(AccountId, Oracle)> =
+            unordered_map_pagination(&self.oracles, None, None);
+        oracles.sort_by_key(|(_, oracle)| std::cmp::Reverse(oracle.price_reports));
+        let from_index = from_index.unwrap_or(0) as usize;
+        let limit = limit.map(|limit| limit as usize).unwrap_or(oracles.len());
+        oracles.into_iter().skip(from_index).take(limit).collect()
+    }
+
+    pub fn get_oracle_status(&self, account_id: AccountId) -> Option<OracleStatus> {
+        let oracle = self.internal_get_oracle(&account_id)?;
+        Some(OracleStatus::new(account_id, oracle, now()))
+    }
+
+    /// Returns oracles that haven't reported within the given duration, including oracles that
+    /// have never reported.
+    pub fn get_stale_oracles(&self, max_age_sec: DurationSec) -> Vec<OracleStatus> {
+        let timestamp = now();
+        let timestamp_cut = timestamp.saturating_sub(to_nano(max_age_sec));
+        self.oracles
+            .iter()
+            .map(|(account_id, oracle)| (account_id, Oracle::from(oracle)))
+            .filter(|(_, oracle)| oracle.last_report < timestamp_cut)
+            .map(|(account_id, oracle)| OracleStatus::new(account_id, oracle, timestamp))
+            .collect()
+    }
+
+    /// Returns aggregate stats of assets and oracles.
+    pub fn get_oracle_health(&self) -> OracleHealth {
+        let timestamp = now();
+        let mut num_fresh_assets = 0;
+        let mut num_paused_assets = 0;
+        let mut oldest_fresh_report: Option<Timestamp> = None;
+        for asset_id in self.internal_asset_ids(0, self.internal_num_assets()) {
+            let asset = self.internal_get_asset(&asset_id).unwrap();
+            if asset.paused {
+                num_paused_assets += 1;
+            }
+            if self
+                .internal_get_price(&asset_id, timestamp, None)
+                .is_none()
+            {
+                continue;
+            }
+            num_fresh_assets += 1;
+            if let Some(last_report) = asset.reports.iter().map(|report| report.timestamp).max() {
+                oldest_fresh_report = Some(
+                    oldest_fresh_report
+                        .map_or(last_report, |oldest| std::cmp::min(oldest, last_report)),
+                );
+            }
+        }
+        OracleHealth {
+            num_assets: self.internal_num_assets(),
+            num_fresh_assets,
+            num_paused_assets,
+            num_oracles: self.oracles.len(),
+            num_active_oracles: u64::from(
+                self.internal_num_live_oracles(timestamp, self.recency_duration_sec),
+            ),
+            oldest_fresh_report: oldest_fresh_report.map(U64),
+            paused: self.paused,
+            frozen: self.is_stabilizing(timestamp),
+            staged_code_hash: self
+                .staged_code_info
+                .as_ref()
+                .map(|staged_code_info| staged_code_info.code_hash.into()),
+        }
+    }
+
+    /// Returns assets in the order they were added. Assets with consumers are skipped.
     pub fn get_assets(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<(AssetId, Asset)> {
-        unordered_map_pagination(&self.assets, from_index, limit)
+        let from_index = from_index.unwrap_or(0);
+        let limit = limit.unwrap_or_else(|| self.internal_num_assets());
+        self.internal_public_asset_ids(from_index, limit)
+            .into_iter()
+            .map(|asset_id| {
+                let asset = self.internal_get_asset(&asset_id).unwrap();
+                (asset_id, asset)
+            })
+            .collect()
     }
 
     pub fn get_asset(&self, asset_id: AssetId) -> Option<Asset> {
+        self.assert_asset_price_access(&asset_id);
         self.internal_get_asset(&asset_id)
     }
 
-    pub fn get_price_data(&self, asset_ids: Option<Vec<AssetId>>) -> PriceData {
-        let asset_ids = asset_ids.unwrap_or_else(|| self.assets.keys().collect());
-        let timestamp = env::block_timestamp();
-        let timestamp_cut = timestamp.saturating_sub(to_nano(self.recency_duration_sec));
-        let min_num_recent_reports = std::cmp::max(1, (self.oracles.len() + 1) / 2) as usize;
+    pub fn get_ema_state(&self, asset_id: AssetId, period_sec: DurationSec) -> Option<EmaState> {
+        self.assert_asset_price_access(&asset_id);
+        let ema = self
+            .internal_get_asset(&asset_id)?
+            .emas
+            .into_iter()
+            .find(|ema| ema.period_sec == period_sec)?;
+        Some(EmaState {
+            warmed_up: ema.is_warmed_up(now()),
+            ema,
+        })
+    }
 
-        PriceData {
+    /// Returns the realized volatility of the asset over the period with `VOLATILITY_DECIMALS`,
+    /// the same as the price of `asset_id#vol{period_sec}`.
+    pub fn get_volatility(&self, asset_id: AssetId, period_sec: DurationSec) -> Option<Price> {
+        self.assert_asset_price_access(&asset_id);
+        self.internal_get_price(&format!("{}#vol{}", asset_id, period_sec), now(), None)
+    }
+
+    pub fn get_pair(&self, pair_id: AssetId) -> Option<DerivedPair> {
+        self.pairs.get(&pair_id)
+    }
+
+    pub fn get_asset_metadata(&self, asset_id: AssetId) -> Option<AssetMetadata> {
+        self.asset_metadata.get(&asset_id)
+    }
+
+    pub fn get_asset_group(&self, group: GroupId) -> Vec<AssetId> {
+        self.asset_groups.get(&group).unwrap_or_default()
+    }
+
+    pub fn get_index(&self, index_id: AssetId) -> Option<Vec<IndexComponent>> {
+        self.indexes.get(&index_id)
+    }
+
+    pub fn get_bundle(&self, bundle_id: BundleId) -> Option<Vec<AssetId>> {
+        self.bundles.get(&bundle_id)
+    }
+
+    pub fn get_bundles(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<(BundleId, Vec<AssetId>)> {
+        unordered_map_pagination(&self.bundles, from_index, limit)
+    }
+
+    /// Returns prices of the given assets, by default of the first `MAX_UNFILTERED_ASSETS` assets.
+    /// With `quote_asset_id` prices are in that quote asset, which only assets configured with it
+    /// have. Assets with consumers can only be requested by them, and aren't returned by default.
+    /// A bundle can be given instead of asset IDs. With `max_staleness_sec`, prices are only
+    /// based on reports at most that old, and assets without such reports get `None`.
+    pub fn get_price_data(
+        &self,
+        asset_ids: Option<Vec<AssetId>>,
+        quote_asset_id: Option<AssetId>,
+        bundle_id: Option<BundleId>,
+        max_staleness_sec: Option<DurationSec>,
+    ) -> PriceData {
+        let asset_ids = self.internal_bundle_asset_ids(asset_ids, bundle_id);
+        let asset_ids = self.internal_requested_asset_ids(asset_ids, None);
+        if let Some(quote_asset_id) = quote_asset_id.as_ref() {
+            self.assert_asset_price_access(quote_asset_id);
+        }
+        self.internal_get_price_data(asset_ids, quote_asset_id, max_staleness_sec)
+    }
+
+    /// Returns prices like `get_price_data`, but tells unknown assets apart from stale ones.
+    pub fn get_price_data_detailed(
+        &self,
+        asset_ids: Option<Vec<AssetId>>,
+        quote_asset_id: Option<AssetId>,
+        bundle_id: Option<BundleId>,
+        max_staleness_sec: Option<DurationSec>,
+    ) -> PriceDataDetailed {
+        let price_data = self.get_price_data(
+            asset_ids,
+            quote_asset_id.clone(),
+            bundle_id,
+            max_staleness_sec,
+        );
+        let mut prices = vec![];
+        let mut stale = vec![];
+        let mut unknown_assets = vec![];
+        for asset_price in price_data.prices {
+            if asset_price.price.is_some() && !asset_price.expired {
+                prices.push(asset_price);
+                continue;
+            }
+            let price_asset_id = match quote_asset_id.as_ref() {
+                Some(quote_asset_id) => quoted_asset_id(&asset_price.asset_id, quote_asset_id),
+                None => asset_price.asset_id.clone(),
+            };
+            if self.internal_missing_price_status(&price_asset_id) == PriceStatus::Unknown {
+                unknown_assets.push(asset_price.asset_id);
+            } else {
+                stale.push(asset_price);
+            }
+        }
+        PriceDataDetailed {
+            timestamp: price_data.timestamp,
+            block_height: price_data.block_height,
+            recency_duration_sec: price_data.recency_duration_sec,
+            prices,
+            stale,
+            unknown_assets,
+            frozen: price_data.frozen,
+            sequence: price_data.sequence,
+            last_update_timestamp: price_data.last_update_timestamp,
+        }
+    }
+
+    /// Returns prices of assets in the order of `get_assets`, at most `MAX_UNFILTERED_ASSETS` at a
+    /// time. Assets with consumers are skipped.
+    pub fn get_price_data_paged(&self, from_index: Option<u64>, limit: Option<u64>) -> PriceData {
+        let limit = std::cmp::min(
+            limit.unwrap_or(MAX_UNFILTERED_ASSETS),
+            MAX_UNFILTERED_ASSETS,
+        );
+        self.internal_get_price_data(
+            self.internal_public_asset_ids(from_index.unwrap_or(0), limit),
+            None,
+            None,
+        )
+    }
+
+    /// Returns prices of the given assets, where each asset has its own maximum age of the data
+    /// the price can be based on. Prices that aren't fresh enough come with the failure status.
+    pub fn get_price_data_per_asset_age(
+        &self,
+        asset_ages: Vec<(AssetId, DurationSec)>,
+    ) -> Vec<AssetPriceStatus> {
+        let asset_ids: Vec<AssetId> = asset_ages
+            .iter()
+            .map(|(asset_id, _)| asset_id.clone())
+            .collect();
+        self.assert_price_access(&asset_ids, None);
+        let timestamp = now();
+        asset_ages
+            .into_iter()
+            .map(|(asset_id, max_age_sec)| {
+                let price = self.internal_get_price(&asset_id, timestamp, Some(max_age_sec));
+                AssetPriceStatus {
+                    status: if price.is_some() {
+                        PriceStatus::Fresh
+                    } else {
+                        self.internal_missing_price_status(&asset_id)
+                    },
+                    asset_id,
+                    price,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns prices like `get_price_data` along with the time of the latest report of each
+    /// asset and whether its price is stale.
+    pub fn get_price_data_v2(&self, asset_ids: Option<Vec<AssetId>>) -> PriceDataV2 {
+        let asset_ids = self.internal_accessible_asset_ids(asset_ids);
+        let timestamp = now();
+        PriceDataV2 {
             timestamp,
             recency_duration_sec: self.recency_duration_sec,
             prices: asset_ids
                 .into_iter()
                 .map(|asset_id| {
-                    // EMA for a specific asset, e.g. wrap.near#3600 is 1 hour EMA for wrap.near
-                    if let Some((base_asset_id, period_sec)) = asset_id.split_once('#') {
-                        let period_sec: DurationSec =
-                            period_sec.parse().expect("Failed to parse EMA period");
-                        let asset = self.internal_get_asset(&base_asset_id.to_string());
-                        AssetOptionalPrice {
-                            asset_id,
-                            price: asset.and_then(|asset| {
-                                asset
-                                    .emas
-                                    .into_iter()
-                                    .find(|ema| ema.period_sec == period_sec)
-                                    .filter(|ema| ema.timestamp >= timestamp_cut)
-                                    .and_then(|ema| ema.price)
-                            }),
-                        }
+                    let price = self.internal_get_price(&asset_id, timestamp, None);
+                    let status = if price.is_some() {
+                        PriceStatus::Fresh
                     } else {
-                        let asset = self.internal_get_asset(&asset_id);
-                        AssetOptionalPrice {
-                            asset_id,
-                            price: asset.and_then(|asset| {
-                                asset.median_price(timestamp_cut, min_num_recent_reports)
-                            }),
-                        }
+                        self.internal_missing_price_status(&asset_id)
+                    };
+                    let is_derived = self.indexes.get(&asset_id).is_some()
+                        || self.pairs.get(&asset_id).is_some();
+                    let base_asset_id = asset_id
+                        .split_once('#')
+                        .map_or(asset_id.as_str(), |(base_asset_id, _)| base_asset_id);
+                    let last_report_timestamp = if is_derived {
+                        None
+                    } else {
+                        self.internal_get_asset(&base_asset_id.to_string())
+                            .and_then(|asset| {
+                                asset.reports.iter().map(|report| report.timestamp).max()
+                            })
+                            .map(U64)
+                    };
+                    AssetPriceV2 {
+                        asset_id,
+                        price,
+                        last_report_timestamp,
+                        stale: status == PriceStatus::Stale,
+                        status,
                     }
                 })
                 .collect(),
+            frozen: self.is_stabilizing(timestamp),
+        }
+    }
+
+    /// Returns the aggregated price of the asset along with all recent reports it's based on.
+    pub fn get_price_detailed(&self, asset_id: AssetId) -> Option<PriceDetails> {
+        self.assert_asset_price_access(&asset_id);
+        let asset = self.internal_get_asset(&asset_id)?;
+        let timestamp = now();
+        let price = self.internal_get_price(&asset_id, timestamp, None);
+        let reports: Vec<ContributingReport> = self
+            .internal_weighted_reports(&asset, timestamp, None)
+            .into_iter()
+            .map(|(report, weight)| ContributingReport {
+                oracle_id: report.oracle_id.clone(),
+                timestamp: report.timestamp,
+                price: report.price,
+                weight,
+                oracle_weight: self
+                    .internal_oracle_weight(&asset, &report.oracle_id)
+                    .into(),
+                source: report.source,
+            })
+            .collect();
+        let mut sources = vec![];
+        let dex_policy = asset
+            .dex_source
+            .as_ref()
+            .map(|dex_source| dex_source.policy);
+        if !matches!(dex_policy, Some(DexPolicy::DexOnly)) {
+            for report in &reports {
+                if !sources.contains(&report.source) {
+                    sources.push(report.source);
+                }
+            }
+        }
+        if matches!(
+            dex_policy,
+            Some(DexPolicy::DexOnly) | Some(DexPolicy::RequireAgreement { .. })
+        ) {
+            sources.push(PriceSource::Dex);
+        }
+        Some(PriceDetails {
+            asset_id,
+            timestamp,
+            recency_duration_sec: self.asset_recency_duration_sec(&asset),
+            price,
+            mode: self.aggregation_mode(&asset),
+            reports,
+            sources,
+        })
+    }
+
+    /// Returns the value of `amount` units of the asset in USD with `VALUE_DECIMALS` decimals, e.g.
+    /// 5 NEAR (`5 * 10**24`) at `Price { multiplier: 1000, decimals: 26 }` are worth `50 * 10**18`.
+    pub fn value_in_usd(&self, asset_id: AssetId, amount: U128) -> Option<U128> {
+        self.assert_asset_price_access(&asset_id);
+        let price = self.internal_get_price(&asset_id, now(), None)?;
+        mul_div_pow10(
+            amount.0,
+            price.multiplier,
+            i32::from(VALUE_DECIMALS) - i32::from(price.decimals),
+            1,
+        )
+        .map(U128)
+    }
+
+    /// Converts `amount` units of one asset into units of another asset of the same value.
+    pub fn convert_amount(
+        &self,
+        amount: U128,
+        from_asset_id: AssetId,
+        to_asset_id: AssetId,
+    ) -> Option<U128> {
+        self.assert_price_access(&[from_asset_id.clone(), to_asset_id.clone()], None);
+        let timestamp = now();
+        let from_price = self.internal_get_price(&from_asset_id, timestamp, None)?;
+        let to_price = self.internal_get_price(&to_asset_id, timestamp, None)?;
+        mul_div_pow10(
+            amount.0,
+            from_price.multiplier,
+            i32::from(to_price.decimals) - i32::from(from_price.decimals),
+            to_price.multiplier,
+        )
+        .map(U128)
+    }
+
+    /// Returns all current reports of the asset and whether each of them is included in the
+    /// aggregated price.
+    pub fn get_asset_reports(&self, asset_id: AssetId) -> Option<AssetReports> {
+        self.assert_asset_price_access(&asset_id);
+        let asset = self.internal_get_asset(&asset_id)?;
+        let timestamp = now();
+        let timestamp_cut = self.asset_timestamp_cut(&asset, timestamp, None);
+        let mode = self.aggregation_mode(&asset);
+        Some(AssetReports {
+            price: self.internal_get_price(&asset_id, timestamp, None),
+            asset_id,
+            timestamp,
+            mode,
+            reports: asset
+                .reports
+                .iter()
+                .map(|report| AuditedReport {
+                    oracle_id: report.oracle_id.clone(),
+                    timestamp: report.timestamp,
+                    price: report.price,
+                    inclusion: if report.timestamp < timestamp_cut {
+                        ReportInclusion::Stale
+                    } else if asset.is_outlier(report, timestamp_cut) {
+                        ReportInclusion::Outlier
+                    } else if mode == AggregationMode::WeightedMedian
+                        && (self.internal_report_weight(report, timestamp) == 0
+                            || self.internal_oracle_weight(&asset, &report.oracle_id) == 0)
+                    {
+                        ReportInclusion::ZeroWeight
+                    } else {
+                        ReportInclusion::Included
+                    },
+                })
+                .collect(),
+        })
+    }
+
+    /// Returns prices along with the spread of the reports they are based on, so consumers can
+    /// account for the disagreement between oracles.
+    pub fn get_price_data_with_confidence(
+        &self,
+        asset_ids: Option<Vec<AssetId>>,
+    ) -> Vec<AssetPriceConfidence> {
+        let asset_ids = self.internal_accessible_asset_ids(asset_ids);
+        let timestamp = now();
+        asset_ids
+            .into_iter()
+            .map(|asset_id| {
+                let price = self.internal_get_price(&asset_id, timestamp, None);
+                let confidence = price
+                    .filter(|_| !self.is_stabilizing(timestamp))
+                    .and_then(|_| self.internal_get_asset(&asset_id))
+                    .and_then(|asset| {
+                        let prices: Vec<Price> = self
+                            .internal_weighted_reports(&asset, timestamp, None)
+                            .into_iter()
+                            .filter(|(_, weight)| *weight > 0)
+                            .map(|(report, _)| report.price)
+                            .collect();
+                        price_confidence(&prices)
+                    });
+                AssetPriceConfidence {
+                    asset_id,
+                    price,
+                    confidence,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the last aggregated price of the asset if it hasn't expired yet. Unlike the other
+    /// views, the validity is bound to the time of the aggregation rather than the age of the
+    /// underlying reports.
+    pub fn get_price_checked(&self, asset_id: AssetId) -> Option<CheckedPrice> {
+        self.assert_asset_price_access(&asset_id);
+        if self.paused {
+            return None;
         }
+        let asset = self
+            .internal_get_asset(&asset_id)
+            .filter(|asset| asset.is_available())?;
+        let last_good = asset.last_good?;
+        let price_ttl_sec = self.asset_price_ttl_sec(&asset);
+        let expires_at = last_good.timestamp + to_nano(price_ttl_sec);
+        if now() >= expires_at {
+            return None;
+        }
+        Some(CheckedPrice {
+            asset_id,
+            price: last_good.price,
+            timestamp: last_good.timestamp,
+            price_ttl_sec,
+            expires_at,
+        })
+    }
+
+    /// Returns the timestamp at which the current median price of the given asset becomes stale,
+    /// or `None` if the price is already unavailable.
+    pub fn get_staleness_deadline(&self, asset_id: AssetId) -> Option<U64> {
+        self.assert_asset_price_access(&asset_id);
+        let asset = self.internal_get_asset(&asset_id)?;
+        if self.paused || !asset.is_available() {
+            return None;
+        }
+        let recency_duration = to_nano(self.asset_recency_duration_sec(&asset));
+        asset
+            .staleness_deadline(
+                now().saturating_sub(recency_duration),
+                self.asset_min_num_recent_reports(&asset),
+                recency_duration,
+            )
+            .map(|deadline| deadline.into())
     }
 
     /// Returns price data for a given oracle ID and given list of asset IDs.
@@ -165,20 +1283,23 @@ impl Contract {
         asset_ids: Option<Vec<AssetId>>,
         recency_duration_sec: Option<DurationSec>,
     ) -> PriceData {
-        let asset_ids = asset_ids.unwrap_or_else(|| self.assets.keys().collect());
-        let timestamp = env::block_timestamp();
+        let asset_ids = self.internal_accessible_asset_ids(asset_ids);
+        let timestamp = now();
         let recency_duration_sec = recency_duration_sec.unwrap_or(self.recency_duration_sec);
         let timestamp_cut = timestamp.saturating_sub(to_nano(recency_duration_sec));
 
         let oracle_id: AccountId = account_id.into();
         PriceData {
             timestamp,
+            block_height: env::block_height(),
             recency_duration_sec,
             prices: asset_ids
                 .into_iter()
                 .map(|asset_id| {
                     let asset = self.internal_get_asset(&asset_id);
                     AssetOptionalPrice {
+                        decimals: self.internal_asset_decimals(&asset_id),
+                        recency_duration_sec: asset.as_ref().map(|_| recency_duration_sec),
                         asset_id,
                         price: asset.and_then(|asset| {
                             asset
@@ -188,91 +1309,902 @@ impl Contract {
                                 .filter(|report| report.timestamp >= timestamp_cut)
                                 .map(|report| report.price)
                         }),
+                        expired: false,
+                        sequence: None,
+                        last_update_timestamp: None,
+                        deprecation: None,
+                        fallback: false,
                     }
                 })
                 .collect(),
+            frozen: false,
+            sequence: self.price_sequence,
+            last_update_timestamp: self.last_price_update,
+            commitment: None,
+            receipt_id: None,
         }
     }
 
     pub fn report_prices(&mut self, prices: Vec<AssetPrice>, claim_near: Option<bool>) {
-        assert!(!prices.is_empty());
+        self.internal_report_own_prices(prices, claim_near, None);
+    }
+
+    /// Reports prices like `report_prices` from a Borsh payload. It skips JSON parsing, which
+    /// saves about 2% of the gas of a report. Most of the gas goes to storing the reports and
+    /// aggregating the prices, see README.md.
+    pub fn report_prices_borsh(&mut self, #[serializer(borsh)] payload: Vec<AssetPriceBorsh>) {
+        let prices = payload.into_iter().map(AssetPrice::from).collect();
+        self.internal_report_own_prices(prices, None, None);
+    }
+
+    /// Reports prices like `report_prices`, but prices within `min_change_bps` of the previous
+    /// report of the oracle only refresh the timestamp of that report. They don't update the
+    /// aggregated price, EMAs, TWAPs or the price history.
+    pub fn report_prices_if_changed(
+        &mut self,
+        prices: Vec<AssetPrice>,
+        min_change_bps: u32,
+        claim_near: Option<bool>,
+    ) {
+        self.internal_report_own_prices(prices, claim_near, Some(min_change_bps));
+    }
+
+    /// Sends price data of the given assets to the receiver. With `require_all`, the call fails
+    /// up front if any of the assets doesn't have a price. The gas budget decides how much of the
+    /// remaining gas the receiver gets. A bundle can be given instead of asset IDs.
+    /// `max_staleness_sec` limits the age of reports prices are based on like in
+    /// `get_price_data`.
+    #[payable]
+    #[allow(clippy::too_many_arguments)]
+    pub fn oracle_call(
+        &mut self,
+        receiver_id: AccountId,
+        asset_ids: Option<Vec<AssetId>>,
+        msg: String,
+        require_all: Option<bool>,
+        gas_budget: Option<GasBudget>,
+        bundle_id: Option<BundleId>,
+        max_staleness_sec: Option<DurationSec>,
+    ) -> Promise {
+        self.assert_not_paused();
+        let asset_ids = self.internal_bundle_asset_ids(asset_ids, bundle_id);
+        self.internal_collect_fee(self.num_requested_assets(&asset_ids));
+        self.internal_oracle_call(
+            env::predecessor_account_id(),
+            receiver_id,
+            asset_ids,
+            msg,
+            require_all.unwrap_or(false),
+            gas_budget.unwrap_or_default(),
+            env::attached_deposit(),
+            max_staleness_sec,
+        )
+    }
+
+    /// Sends the same price data to multiple receivers. The remaining gas is split equally between
+    /// the receivers, and the fee is charged for every receiver.
+    #[payable]
+    pub fn oracle_call_many(
+        &mut self,
+        receivers: Vec<ReceiverCall>,
+        asset_ids: Option<Vec<AssetId>>,
+        require_all: Option<bool>,
+        gas_budget: Option<GasBudget>,
+        bundle_id: Option<BundleId>,
+    ) -> Promise {
+        self.assert_not_paused();
+        assert!(
+            !receivers.is_empty(),
+            "{}",
+            ContractError::RequiresReceivers
+        );
+        let asset_ids = self.internal_bundle_asset_ids(asset_ids, bundle_id);
+        let num_receivers = receivers.len() as u64;
+        self.internal_collect_fee(self.num_requested_assets(&asset_ids) * num_receivers);
+
+        let sender_id = env::predecessor_account_id();
+        let asset_ids = match asset_ids {
+            Some(asset_ids) => {
+                self.assert_num_requested_assets(&asset_ids);
+                for ReceiverCall { receiver_id, .. } in &receivers {
+                    self.assert_price_access(&asset_ids, Some(receiver_id));
+                }
+                asset_ids
+            }
+            None => self.internal_public_asset_ids(0, self.max_assets_per_request),
+        };
+        let mut price_data = self.internal_get_price_data(asset_ids, None, None);
+        if require_all.unwrap_or(false) {
+            assert_all_prices(&price_data);
+        }
+        self.internal_assign_price_receipt_id(&mut price_data);
+        self.internal_commit_price_data(&mut price_data);
+        self.internal_record_price_receipt(
+            &sender_id,
+            receivers
+                .iter()
+                .map(|receiver| receiver.receiver_id.clone())
+                .collect(),
+            &price_data,
+        );
+        let gas_per_receiver = gas_budget.unwrap_or_default().receiver_gas(num_receivers);
+
+        // The fee of every receiver is refundable separately.
+        let fee = env::attached_deposit() / Balance::from(num_receivers);
+        receivers
+            .into_iter()
+            .map(|ReceiverCall { receiver_id, msg }| {
+                self.internal_record_consumer_call(&sender_id, fee);
+                ext_price_receiver::oracle_on_call(
+                    sender_id.clone(),
+                    price_data.clone(),
+                    msg,
+                    receiver_id.clone(),
+                    NO_DEPOSIT,
+                    gas_per_receiver,
+                )
+                .then(ext_oracle_call_callback::on_oracle_call_complete(
+                    sender_id.clone(),
+                    receiver_id,
+                    U128(fee),
+                    env::current_account_id(),
+                    NO_DEPOSIT,
+                    GAS_FOR_ON_ORACLE_CALL_COMPLETE,
+                ))
+            })
+            .reduce(|promise, next| promise.and(next))
+            .unwrap()
+    }
+}
+
+impl Contract {
+    /// Returns prices of the given assets in the quote asset, if it's given. With
+    /// `max_staleness_sec`, prices that aren't fresh enough are `None` regardless of the expiry
+    /// policy.
+    pub fn internal_get_price_data(
+        &self,
+        asset_ids: Vec<AssetId>,
+        quote_asset_id: Option<AssetId>,
+        max_staleness_sec: Option<DurationSec>,
+    ) -> PriceData {
+        let timestamp = now();
+        PriceData {
+            timestamp,
+            block_height: env::block_height(),
+            recency_duration_sec: self.recency_duration_sec,
+            prices: asset_ids
+                .into_iter()
+                .map(|asset_id| {
+                    let price_asset_id = match quote_asset_id.as_ref() {
+                        Some(quote_asset_id) => quoted_asset_id(&asset_id, quote_asset_id),
+                        None => asset_id.clone(),
+                    };
+                    let (price, expired) = match max_staleness_sec {
+                        Some(max_staleness_sec) => (
+                            self.internal_get_price(
+                                &price_asset_id,
+                                timestamp,
+                                Some(max_staleness_sec),
+                            ),
+                            false,
+                        ),
+                        None => self.internal_get_price_or_expired(&price_asset_id, timestamp),
+                    };
+                    let (sequence, last_update_timestamp) =
+                        self.internal_asset_sequence(&price_asset_id);
+                    AssetOptionalPrice {
+                        price,
+                        decimals: self.internal_asset_decimals(&asset_id),
+                        recency_duration_sec: self.internal_asset_recency_duration_sec(&asset_id),
+                        expired,
+                        sequence,
+                        last_update_timestamp,
+                        deprecation: self.internal_asset_deprecation(&asset_id),
+                        fallback: false,
+                        asset_id,
+                    }
+                })
+                .collect(),
+            frozen: self.is_stabilizing(timestamp),
+            sequence: self.price_sequence,
+            last_update_timestamp: self.last_price_update,
+            commitment: None,
+            receipt_id: None,
+        }
+    }
+
+    /// Sends price data of the given assets to the receiver with the remaining gas. The fee is the
+    /// NEAR fee of the request that can be refunded.
+    #[allow(clippy::too_many_arguments)]
+    pub fn internal_oracle_call(
+        &mut self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        asset_ids: Option<Vec<AssetId>>,
+        msg: String,
+        require_all: bool,
+        gas_budget: GasBudget,
+        fee: Balance,
+        max_staleness_sec: Option<DurationSec>,
+    ) -> Promise {
+        let asset_ids = self.internal_requested_asset_ids(asset_ids, Some(&receiver_id));
+        let price_data = self.internal_get_price_data(asset_ids, None, max_staleness_sec);
+        let fallback_requests = self.internal_fallback_requests(&price_data);
+        if !fallback_requests.is_empty() {
+            return self.internal_query_fallback_oracles(
+                FallbackDelivery {
+                    sender_id,
+                    receiver_id,
+                    price_data,
+                    fallback_asset_ids: vec![],
+                    msg,
+                    require_all,
+                    gas_budget,
+                    fee: U128(fee),
+                },
+                fallback_requests,
+                max_staleness_sec,
+            );
+        }
+        if require_all {
+            assert_all_prices(&price_data);
+        }
+        let receiver_gas = gas_budget.receiver_gas(1);
+        self.internal_deliver_price_data(sender_id, receiver_id, price_data, msg, receiver_gas, fee)
+    }
+
+    /// Sends the price data to the receiver with the callback that records the result.
+    pub fn internal_deliver_price_data(
+        &mut self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        mut price_data: PriceData,
+        msg: String,
+        receiver_gas: Gas,
+        fee: Balance,
+    ) -> Promise {
+        self.internal_assign_price_receipt_id(&mut price_data);
+        self.internal_commit_price_data(&mut price_data);
+        self.internal_record_price_receipt(&sender_id, vec![receiver_id.clone()], &price_data);
+
+        self.internal_record_consumer_call(&sender_id, fee);
+        ext_price_receiver::oracle_on_call(
+            sender_id.clone(),
+            price_data,
+            msg,
+            receiver_id.clone(),
+            NO_DEPOSIT,
+            receiver_gas,
+        )
+        .then(ext_oracle_call_callback::on_oracle_call_complete(
+            sender_id,
+            receiver_id,
+            U128(fee),
+            env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_ON_ORACLE_CALL_COMPLETE,
+        ))
+    }
+
+    /// Returns the number of assets, including the ones left in the map of `ContractV0`.
+    pub fn internal_num_assets(&self) -> u64 {
+        self.asset_ids.len() + self.legacy_assets.len()
+    }
+
+    /// Returns IDs of at most `limit` assets starting from the given index. Assets left in the
+    /// map of `ContractV0` follow the other ones.
+    pub fn internal_asset_ids(&self, from_index: u64, limit: u64) -> Vec<AssetId> {
+        let num_current_assets = self.asset_ids.len();
+        let legacy_asset_ids = self.legacy_assets.keys_as_vector();
+        (from_index..std::cmp::min(self.internal_num_assets(), from_index.saturating_add(limit)))
+            .map(|index| {
+                if index < num_current_assets {
+                    self.asset_ids.get(index).unwrap()
+                } else {
+                    legacy_asset_ids.get(index - num_current_assets).unwrap()
+                }
+            })
+            .collect()
+    }
+
+    /// Returns IDs of at most `limit` assets starting from the given index, skipping assets with
+    /// consumers.
+    pub fn internal_public_asset_ids(&self, from_index: u64, limit: u64) -> Vec<AssetId> {
+        self.internal_asset_ids(from_index, limit)
+            .into_iter()
+            .filter(|asset_id| !self.internal_has_consumers(asset_id))
+            .collect()
+    }
+
+    /// Returns the given asset IDs after checking that the predecessor can read their prices, or
+    /// the public ones of the first `MAX_UNFILTERED_ASSETS` assets if none are given.
+    pub fn internal_accessible_asset_ids(&self, asset_ids: Option<Vec<AssetId>>) -> Vec<AssetId> {
+        match asset_ids {
+            Some(asset_ids) => {
+                self.assert_price_access(&asset_ids, None);
+                asset_ids
+            }
+            None => self.internal_public_asset_ids(0, MAX_UNFILTERED_ASSETS),
+        }
+    }
+
+    /// Returns the asset IDs of the bundle, if it's given instead of asset IDs.
+    pub fn internal_bundle_asset_ids(
+        &self,
+        asset_ids: Option<Vec<AssetId>>,
+        bundle_id: Option<BundleId>,
+    ) -> Option<Vec<AssetId>> {
+        match bundle_id {
+            Some(bundle_id) => {
+                assert!(
+                    asset_ids.is_none(),
+                    "{}",
+                    ContractError::RequiresAssetIdsOrBundle
+                );
+                Some(
+                    self.bundles
+                        .get(&bundle_id)
+                        .unwrap_or_else(|| panic!("{}", ContractError::MissingBundle)),
+                )
+            }
+            None => asset_ids,
+        }
+    }
+
+    /// Returns the requested asset IDs after checking that the account can read their prices,
+    /// or the public assets if none are requested.
+    pub fn internal_requested_asset_ids(
+        &self,
+        asset_ids: Option<Vec<AssetId>>,
+        account_id: Option<&AccountId>,
+    ) -> Vec<AssetId> {
+        match asset_ids {
+            Some(asset_ids) => {
+                self.assert_num_requested_assets(&asset_ids);
+                self.assert_price_access(&asset_ids, account_id);
+                asset_ids
+            }
+            None => self.internal_public_asset_ids(0, self.max_assets_per_request),
+        }
+    }
+
+    pub fn assert_num_requested_assets(&self, asset_ids: &[AssetId]) {
+        assert!(
+            asset_ids.len() as u64 <= self.max_assets_per_request,
+            "{}",
+            ContractError::TooManyAssetsRequested(self.max_assets_per_request)
+        );
+    }
+
+    /// Returns the number of assets in a price request, where `None` requests all assets.
+    pub fn num_requested_assets(&self, asset_ids: &Option<Vec<AssetId>>) -> u64 {
+        asset_ids.as_ref().map_or(
+            std::cmp::min(self.internal_num_assets(), self.max_assets_per_request),
+            |asset_ids| asset_ids.len() as u64,
+        )
+    }
+
+    /// Returns the fee for a price request of the given number of assets.
+    pub fn internal_fee(&self, num_assets: u64) -> Balance {
+        self.flat_fee + self.per_asset_fee * Balance::from(num_assets)
+    }
+
+    /// Requires the attached deposit to cover the fee, but at least 1 yoctoNEAR. The whole deposit
+    /// is collected.
+    pub fn internal_collect_fee(&mut self, num_assets: u64) {
+        let fee = std::cmp::max(1, self.internal_fee(num_assets));
+        let deposit = env::attached_deposit();
+        assert!(deposit >= fee, "{}", ContractError::RequiresDeposit(fee));
+        self.collected_fees += deposit;
+    }
+
+    pub fn assert_not_paused(&self) {
+        assert!(!self.paused, "{}", ContractError::Paused);
+    }
+
+    /// Records prices reported by the caller and lets it claim NEAR.
+    fn internal_report_own_prices(
+        &mut self,
+        prices: Vec<AssetPrice>,
+        claim_near: Option<bool>,
+        min_change_bps: Option<u32>,
+    ) {
+        self.assert_not_paused();
+        assert!(!prices.is_empty(), "{}", ContractError::RequiresPrices);
         let oracle_id = env::predecessor_account_id();
-        let timestamp = env::block_timestamp();
+        let timestamp = now();
 
-        // Oracle stats
-        let mut oracle = self.internal_get_oracle(&oracle_id).expect("Not an oracle");
-        oracle.last_report = timestamp;
-        oracle.price_reports += prices.len() as u64;
+        let mut oracle = self
+            .internal_get_oracle(&oracle_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::NotAnOracle));
+        assert!(
+            self.has_min_stake(&oracle),
+            "{}",
+            ContractError::InsufficientStake
+        );
 
         if claim_near.unwrap_or(false) && oracle.last_near_claim + NEAR_CLAIM_DURATION <= timestamp
         {
-            let liquid_balance = env::account_balance() + env::account_locked_balance()
-                - env::storage_byte_cost() * u128::from(env::storage_usage());
+            let liquid_balance = self.internal_liquid_balance();
             if liquid_balance > self.near_claim_amount + SAFETY_MARGIN_NEAR_CLAIM {
                 oracle.last_near_claim = timestamp;
                 Promise::new(oracle_id.clone()).transfer(self.near_claim_amount);
             }
         }
 
-        self.internal_set_oracle(&oracle_id, oracle);
+        self.internal_report_prices(
+            &oracle_id,
+            oracle,
+            prices,
+            min_change_bps,
+            PriceSource::Report,
+        );
+    }
+
+    /// Returns the balance of the contract that isn't reserved for storage, stakes, rewards, fees
+    /// or balances of users, `0` if the reserved balances exceed it. Storage deposits are not
+    /// subtracted, the storage they pay for is part of the storage usage.
+    fn internal_liquid_balance(&self) -> Balance {
+        [
+            env::storage_byte_cost() * u128::from(env::storage_usage()),
+            self.total_stake,
+            self.rewards_pool,
+            self.total_unclaimed_rewards,
+            self.collected_fees,
+            self.total_subscription_balances,
+            self.total_price_request_fees,
+        ]
+        .iter()
+        .fold(
+            env::account_balance() + env::account_locked_balance(),
+            |balance, reserved| balance.saturating_sub(*reserved),
+        )
+    }
+
+    /// Records prices reported by the given oracle and updates the oracle stats.
+    /// If `min_change_bps` is given, prices within it of the previous report of the oracle only
+    /// refresh the timestamp of that report.
+    /// Assets with the commit-reveal reporting only accept `revealed` prices.
+    pub fn internal_report_prices(
+        &mut self,
+        oracle_id: &AccountId,
+        mut oracle: Oracle,
+        prices: Vec<AssetPrice>,
+        min_change_bps: Option<u32>,
+        source: PriceSource,
+    ) {
+        assert!(
+            !self.internal_is_suspended(oracle_id),
+            "{}",
+            ContractError::OracleSuspended
+        );
+        let initial_storage_usage = env::storage_usage();
+        let timestamp = now();
+
+        // Oracle stats
+        oracle.last_report = timestamp;
+        oracle.price_reports += prices.len() as u64;
+        Event::ReportPrices {
+            oracle_id,
+            prices: &prices,
+            source,
+        }
+        .emit();
 
         // Updating prices
-        for AssetPrice { asset_id, price } in prices {
+        let mut num_accepted_prices = 0;
+        let mut price_changes = vec![];
+        for AssetPrice {
+            asset_id,
+            price,
+            quote_asset_id,
+            nonce,
+        } in prices
+        {
             price.assert_valid();
+            let asset_id = match quote_asset_id {
+                Some(quote_asset_id) => quoted_asset_id(&asset_id, &quote_asset_id),
+                None => asset_id,
+            };
             if let Some(mut asset) = self.internal_get_asset(&asset_id) {
-                asset.remove_report(&oracle_id);
-                asset.add_report(Report {
-                    oracle_id: oracle_id.clone(),
-                    timestamp,
-                    price,
-                });
-                if !asset.emas.is_empty() {
-                    let timestamp_cut =
-                        timestamp.saturating_sub(to_nano(self.recency_duration_sec));
-                    let min_num_recent_reports =
-                        std::cmp::max(1, (self.oracles.len() + 1) / 2) as usize;
-                    if let Some(median_price) =
-                        asset.median_price(timestamp_cut, min_num_recent_reports)
-                    {
-                        for ema in asset.emas.iter_mut() {
-                            ema.recompute(median_price, timestamp);
+                let price = price.assert_valid_decimals(&asset_id, asset.price_decimals);
+                if let Some(U64(nonce)) = nonce {
+                    let last_nonce = oracle
+                        .asset_nonces
+                        .get(&asset_id)
+                        .copied()
+                        .unwrap_or_default();
+                    if nonce <= last_nonce {
+                        log!("Warning! Stale nonce {} for asset {}", nonce, asset_id);
+                        continue;
+                    }
+                }
+                if asset.paused {
+                    log!("Warning! Asset {} is paused", asset_id);
+                    continue;
+                }
+                if asset
+                    .deprecation
+                    .as_ref()
+                    .filter(|deprecation| timestamp >= deprecation.sunset_timestamp)
+                    .is_some()
+                {
+                    log!("Warning! Asset {} is past its sunset", asset_id);
+                    continue;
+                }
+                if asset.commit_reveal.is_some() && source != PriceSource::CommitReveal {
+                    log!("Warning! Asset {} only accepts revealed prices", asset_id);
+                    continue;
+                }
+                if let Some(price_band) = asset.price_band.as_ref() {
+                    if !price_band.contains(&price) {
+                        Event::RejectPrice {
+                            oracle_id,
+                            asset_id: &asset_id,
+                            price,
                         }
+                        .emit();
+                        continue;
                     }
                 }
+                let min_report_interval_sec = asset
+                    .min_report_interval_sec
+                    .unwrap_or(self.min_report_interval_sec);
+                if asset.reports.iter().any(|report| {
+                    &report.oracle_id == oracle_id
+                        && report.timestamp + to_nano(min_report_interval_sec) > timestamp
+                }) {
+                    Event::IgnoreReport {
+                        oracle_id,
+                        asset_id: &asset_id,
+                    }
+                    .emit();
+                    continue;
+                }
+                // Only accepted reports consume their nonce, so a rejected one can be resent.
+                if let Some(U64(nonce)) = nonce {
+                    oracle.asset_nonces.insert(asset_id.clone(), nonce);
+                }
+                *oracle.asset_reports.entry(asset_id.clone()).or_default() += 1;
+                num_accepted_prices += 1;
+                asset.record_report_interval(oracle_id, timestamp);
+                self.internal_record_round_report(
+                    &asset_id,
+                    &mut asset,
+                    Report {
+                        oracle_id: oracle_id.clone(),
+                        timestamp,
+                        price,
+                        source,
+                        block_height: env::block_height(),
+                    },
+                );
+                if let Some(min_change_bps) = min_change_bps {
+                    if let Some(report) = asset
+                        .reports
+                        .iter_mut()
+                        .find(|report| &report.oracle_id == oracle_id)
+                        .filter(|report| !price.deviates_from(&report.price, min_change_bps))
+                    {
+                        report.timestamp = timestamp;
+                        report.source = source;
+                        report.block_height = env::block_height();
+                        self.internal_set_asset(&asset_id, asset);
+                        continue;
+                    }
+                }
+                let timestamp_cut = self.asset_timestamp_cut(&asset, timestamp, None);
+                asset.add_report(
+                    Report {
+                        oracle_id: oracle_id.clone(),
+                        timestamp,
+                        price,
+                        source,
+                        block_height: env::block_height(),
+                    },
+                    timestamp_cut,
+                );
+                let deferred = match self.aggregation_window_sec {
+                    Some(window_sec) => timestamp < asset.aggregated_at + to_nano(window_sec),
+                    None => false,
+                };
+                if deferred {
+                    asset.pending_aggregation = true;
+                } else {
+                    self.internal_publish_price(
+                        &asset_id,
+                        &mut asset,
+                        timestamp,
+                        &mut price_changes,
+                    );
+                }
                 self.internal_set_asset(&asset_id, asset);
             } else {
                 log!("Warning! Unknown asset ID: {}", asset_id);
             }
         }
+        self.internal_accrue_rewards(&mut oracle, num_accepted_prices, timestamp);
+        self.internal_set_oracle(oracle_id, oracle);
+        self.internal_emit_price_changes(price_changes);
+        self.internal_update_storage(oracle_id, initial_storage_usage);
     }
 
-    #[payable]
-    pub fn oracle_call(
+    /// Aggregates the reports of the asset and publishes the aggregated price, updating its
+    /// EMAs, TWAPs and history.
+    pub fn internal_publish_price(
         &mut self,
-        receiver_id: AccountId,
-        asset_ids: Option<Vec<AssetId>>,
-        msg: String,
-    ) -> Promise {
-        self.assert_well_paid();
+        asset_id: &AssetId,
+        asset: &mut Asset,
+        timestamp: Timestamp,
+        price_changes: &mut Vec<PriceChange>,
+    ) {
+        asset.aggregated_at = timestamp;
+        asset.pending_aggregation = false;
+        // While the oracle set stabilizes, its reports don't affect published prices.
+        if self.is_stabilizing(timestamp) || asset.tripped {
+            return;
+        }
+        let median_price = match self.internal_aggregate_price(asset, timestamp, None) {
+            Some(median_price) => median_price,
+            None => return,
+        };
+        if asset.trips_circuit_breaker(median_price, timestamp) {
+            asset.tripped = true;
+            Event::TripCircuitBreaker {
+                asset_id,
+                price: median_price,
+            }
+            .emit();
+            return;
+        }
+        self.internal_update_peg(asset_id, asset, median_price, timestamp);
+        let point = PricePoint {
+            price: median_price,
+            timestamp,
+            block_height: env::block_height(),
+        };
+        let old_price = asset.last_good.map(|point| point.price);
+        asset.last_good = Some(point);
+        self.price_sequence += 1;
+        self.last_price_update = timestamp;
+        asset.sequence = self.price_sequence;
+        asset.record_block_median(env::block_height(), median_price);
+        self.internal_record_price(asset_id, point);
+        if self.batch_price_events {
+            let timestamp_cut = self.asset_timestamp_cut(asset, timestamp, None);
+            price_changes.push(PriceChange {
+                asset_id: asset_id.clone(),
+                old_price,
+                new_price: median_price,
+                num_reports: asset
+                    .reports
+                    .iter()
+                    .filter(|report| report.timestamp >= timestamp_cut)
+                    .count() as u32,
+            });
+        } else {
+            Event::UpdatePrice {
+                asset_id,
+                price: median_price,
+            }
+            .emit();
+        }
+        for ema in asset.emas.iter_mut() {
+            ema.recompute(median_price, timestamp);
+        }
+        for twap in asset.twaps.iter_mut() {
+            twap.record(median_price, timestamp);
+        }
+        for volatility in asset.volatilities.iter_mut() {
+            volatility.recompute(median_price, timestamp);
+        }
+    }
 
-        let sender_id = env::predecessor_account_id();
-        let price_data = self.get_price_data(asset_ids);
-        let remaining_gas = env::prepaid_gas() - env::used_gas();
-        assert!(remaining_gas >= GAS_FOR_PROMISE);
+    /// Logs the batched price changes.
+    pub fn internal_emit_price_changes(&self, price_changes: Vec<PriceChange>) {
+        if !price_changes.is_empty() {
+            Event::PriceChanges {
+                block_height: env::block_height(),
+                changes: &price_changes,
+            }
+            .emit();
+        }
+    }
 
-        ext_price_receiver::oracle_on_call(
-            sender_id,
-            price_data,
-            msg,
-            receiver_id,
-            NO_DEPOSIT,
-            remaining_gas - GAS_FOR_PROMISE,
-        )
+    /// Returns the current price for the given asset ID. The ID may reference an EMA of an asset,
+    /// e.g. `wrap.near#3600` is 1 hour EMA for `wrap.near`, a TWAP of an asset, e.g.
+    /// `wrap.near#twap3600`, a realized volatility of an asset, e.g. `wrap.near#vol3600`, a
+    /// composite index or a derived pair.
+    /// If `max_age_sec` is given, data older than it is ignored even if it's still recent.
+    pub fn internal_get_price(
+        &self,
+        asset_id: &str,
+        timestamp: Timestamp,
+        max_age_sec: Option<DurationSec>,
+    ) -> Option<Price> {
+        if self.paused {
+            return None;
+        }
+        if let Some((inverted_asset_id, round_up)) = inverse_asset_id(asset_id) {
+            let price = self.internal_get_price(inverted_asset_id, timestamp, max_age_sec)?;
+            return invert_price(price, round_up);
+        }
+        if let Some(components) = self.indexes.get(&asset_id.to_string()) {
+            self.internal_get_index_price(&components, timestamp, max_age_sec)
+        } else if let Some(pair) = self.pairs.get(&asset_id.to_string()) {
+            self.internal_get_pair_price(&pair, timestamp, max_age_sec)
+        } else if let Some((base_asset_id, suffix)) = asset_id.split_once('#') {
+            let asset = self
+                .internal_get_asset(&base_asset_id.to_string())
+                .filter(|asset| asset.is_available())?;
+            let timestamp_cut = self.asset_timestamp_cut(&asset, timestamp, max_age_sec);
+            if let Some(period_sec) = suffix.strip_prefix("twap") {
+                let period_sec: DurationSec = period_sec
+                    .parse()
+                    .unwrap_or_else(|_| panic!("{}", ContractError::InvalidTwapPeriod));
+                let twap = asset
+                    .twaps
+                    .into_iter()
+                    .find(|twap| twap.period_sec == period_sec)
+                    .unwrap_or_else(|| panic!("{}", ContractError::UnknownTwapPeriod(asset_id)));
+                if twap.timestamp < timestamp_cut {
+                    return None;
+                }
+                return twap.price(timestamp);
+            }
+            if let Some(period_sec) = suffix.strip_prefix("vol") {
+                let period_sec: DurationSec = period_sec
+                    .parse()
+                    .unwrap_or_else(|_| panic!("{}", ContractError::InvalidVolatilityPeriod));
+                let volatility = asset
+                    .volatilities
+                    .into_iter()
+                    .find(|volatility| volatility.period_sec == period_sec)
+                    .unwrap_or_else(|| {
+                        panic!("{}", ContractError::UnknownVolatilityPeriod(asset_id))
+                    });
+                if volatility.timestamp < timestamp_cut {
+                    return None;
+                }
+                return volatility.volatility();
+            }
+            let period_sec: DurationSec = suffix
+                .parse()
+                .unwrap_or_else(|_| panic!("{}", ContractError::InvalidEmaPeriod));
+            let ema = asset
+                .emas
+                .into_iter()
+                .find(|ema| ema.period_sec == period_sec)
+                .unwrap_or_else(|| panic!("{}", ContractError::UnknownEmaPeriod(asset_id)));
+            if ema.timestamp < timestamp_cut || !ema.is_warmed_up(timestamp) {
+                return None;
+            }
+            ema.price
+        } else {
+            let asset = self
+                .internal_get_asset(&asset_id.to_string())
+                .filter(|asset| asset.is_available())?;
+            if self.is_stabilizing(timestamp) || self.aggregation_window_sec.is_some() {
+                // Only aggregated prices are published, and they are frozen while the oracle set
+                // stabilizes.
+                let timestamp_cut = self.asset_timestamp_cut(&asset, timestamp, max_age_sec);
+                return asset
+                    .last_good
+                    .filter(|last_good| last_good.timestamp >= timestamp_cut)
+                    .map(|last_good| last_good.price);
+            }
+            let price = self.internal_aggregate_price(&asset, timestamp, max_age_sec)?;
+            if asset.multi_block_median_len.is_some() {
+                return asset.multi_block_median(env::block_height());
+            }
+            Some(price)
+        }
     }
-}
 
-impl Contract {
-    pub fn assert_well_paid(&self) {
-        assert_one_yocto();
+    /// Returns the current price like `internal_get_price`, but if the price of an asset is stale,
+    /// its expiry policy decides the result. The flag is set if the last aggregated price is
+    /// returned instead of the current one.
+    pub fn internal_get_price_or_expired(
+        &self,
+        asset_id: &str,
+        timestamp: Timestamp,
+    ) -> (Option<Price>, bool) {
+        if let Some(price) = self.internal_get_price(asset_id, timestamp, None) {
+            return (Some(price), false);
+        }
+        let asset = match self.internal_get_asset(&asset_id.to_string()) {
+            Some(asset) if self.internal_missing_price_status(asset_id) == PriceStatus::Stale => {
+                asset
+            }
+            _ => return (None, false),
+        };
+        match asset.expiry_policy.unwrap_or(self.expiry_policy) {
+            ExpiryPolicy::ReturnNone => (None, false),
+            ExpiryPolicy::ReturnLastGoodWithFlag => match asset.last_good {
+                Some(last_good) => (Some(last_good.price), true),
+                None => (None, false),
+            },
+            ExpiryPolicy::Panic => panic!("{}", ContractError::StalePrice(asset_id)),
+        }
+    }
+
+    /// Explains why the price of the given asset ID is unavailable.
+    pub fn internal_missing_price_status(&self, asset_id: &str) -> PriceStatus {
+        if self.paused {
+            return PriceStatus::Paused;
+        }
+        if self.indexes.get(&asset_id.to_string()).is_some()
+            || self.pairs.get(&asset_id.to_string()).is_some()
+        {
+            return PriceStatus::Stale;
+        }
+        match self.internal_get_asset(&base_asset_id(asset_id).to_string()) {
+            None => PriceStatus::Unknown,
+            Some(asset) if asset.paused => PriceStatus::Paused,
+            Some(asset) if asset.tripped => PriceStatus::Tripped,
+            Some(_) => PriceStatus::Stale,
+        }
+    }
+
+    /// Returns the recency duration of the asset, which defaults to the one adapted to its report
+    /// cadence and then to the contract-wide one.
+    pub fn asset_recency_duration_sec(&self, asset: &Asset) -> DurationSec {
+        asset
+            .recency_duration_sec
+            .or_else(|| {
+                let adaptive_recency = self.adaptive_recency?;
+                Some(adaptive_recency.recency_sec(asset.median_report_interval_sec()?))
+            })
+            .unwrap_or(self.recency_duration_sec)
+    }
+
+    /// Returns the effective recency duration of the asset, EMAs and TWAPs share it with their
+    /// asset.
+    pub fn internal_asset_recency_duration_sec(&self, asset_id: &str) -> Option<DurationSec> {
+        let base_asset_id = asset_id
+            .split_once('#')
+            .map_or(asset_id, |(base_asset_id, _)| base_asset_id);
+        self.internal_get_asset(&base_asset_id.to_string())
+            .map(|asset| self.asset_recency_duration_sec(&asset))
+    }
+
+    pub fn asset_price_ttl_sec(&self, asset: &Asset) -> DurationSec {
+        asset
+            .price_ttl_sec
+            .unwrap_or_else(|| self.asset_recency_duration_sec(asset))
+    }
+
+    /// Returns the earliest timestamp of data that is still recent for the asset.
+    pub fn asset_timestamp_cut(
+        &self,
+        asset: &Asset,
+        timestamp: Timestamp,
+        max_age_sec: Option<DurationSec>,
+    ) -> Timestamp {
+        let recency_duration_sec = self.asset_recency_duration_sec(asset);
+        let max_age_sec = max_age_sec.map_or(recency_duration_sec, |max_age_sec| {
+            std::cmp::min(max_age_sec, recency_duration_sec)
+        });
+        timestamp.saturating_sub(to_nano(max_age_sec))
+    }
+
+    /// Whether the oracle set was recently replaced and prices are frozen.
+    pub fn is_stabilizing(&self, timestamp: Timestamp) -> bool {
+        timestamp < self.stabilization_until
+    }
+
+    /// The median price requires recent reports from at least half of the oracles, unless the
+    /// owner set a different quorum.
+    pub fn min_num_recent_reports(&self) -> usize {
+        match self.min_num_recent_reports {
+            Some(min_num_recent_reports) => min_num_recent_reports as usize,
+            None => std::cmp::max(1, (self.oracles.len() + 1) / 2) as usize,
+        }
+    }
+
+    pub fn asset_min_num_recent_reports(&self, asset: &Asset) -> usize {
+        asset
+            .min_num_recent_reports
+            .map_or_else(|| self.min_num_recent_reports(), |n| n as usize)
     }
 }
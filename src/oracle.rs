@@ -1,4 +1,5 @@
 use crate::*;
+use std::collections::HashMap;
 
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
 #[serde(crate = "near_sdk::serde")]
@@ -9,6 +10,63 @@ pub struct Oracle {
 
     #[serde(with = "u64_dec_format")]
     pub last_near_claim: Timestamp,
+
+    /// The key used to verify reports submitted on behalf of this oracle by a relayer.
+    pub public_key: Option<PublicKey>,
+    /// The signed timestamp of the last relayed report. Relayed reports must have strictly
+    /// increasing timestamps, so a signed payload can't be applied twice.
+    #[serde(with = "u64_dec_format")]
+    pub last_signed_timestamp: Timestamp,
+
+    #[serde(with = "u128_dec_format")]
+    pub stake: Balance,
+    /// Unstaked NEAR that can be withdrawn after `unbonding_until`.
+    #[serde(with = "u128_dec_format")]
+    pub unbonding_stake: Balance,
+    #[serde(with = "u64_dec_format")]
+    pub unbonding_until: Timestamp,
+
+    /// The number of reported prices per asset.
+    pub asset_reports: HashMap<AssetId, u64>,
+    /// The nonce of the latest report per asset, for reports with nonces.
+    pub asset_nonces: HashMap<AssetId, u64>,
+
+    /// Accrued rewards that can be claimed.
+    #[serde(with = "u128_dec_format")]
+    pub rewards: Balance,
+    /// Rewards accrued within `reward_epoch`.
+    #[serde(with = "u128_dec_format")]
+    pub epoch_rewards: Balance,
+    pub reward_epoch: u64,
+
+    /// The weight assigned by the owner, used with `OracleWeighting::Assigned`.
+    pub weight: u32,
+}
+
+/// The liveness of an oracle.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OracleStatus {
+    pub account_id: AccountId,
+    #[serde(with = "u64_dec_format")]
+    pub last_report: Timestamp,
+    /// Seconds since the last report, `None` if the oracle has never reported.
+    pub last_report_age_sec: Option<u64>,
+    pub price_reports: u64,
+    pub asset_reports: HashMap<AssetId, u64>,
+}
+
+impl OracleStatus {
+    pub fn new(account_id: AccountId, oracle: Oracle, timestamp: Timestamp) -> Self {
+        Self {
+            account_id,
+            last_report: oracle.last_report,
+            last_report_age_sec: (oracle.last_report > 0)
+                .then(|| timestamp.saturating_sub(oracle.last_report) / to_nano(1)),
+            price_reports: oracle.price_reports,
+            asset_reports: oracle.asset_reports,
+        }
+    }
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
@@ -20,8 +78,17 @@ pub struct OracleV0 {
 }
 
 #[derive(BorshSerialize, BorshDeserialize)]
+pub struct OracleV1 {
+    pub last_report: Timestamp,
+    pub price_reports: u64,
+    pub last_near_claim: Timestamp,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+#[allow(clippy::large_enum_variant)]
 pub enum VOracle {
     V0(OracleV0),
+    V1(OracleV1),
     Current(Oracle),
 }
 
@@ -32,6 +99,33 @@ impl From<VOracle> for Oracle {
                 last_report: o.last_report,
                 price_reports: o.price_reports,
                 last_near_claim: 0,
+                public_key: None,
+                last_signed_timestamp: 0,
+                stake: 0,
+                unbonding_stake: 0,
+                unbonding_until: 0,
+                asset_reports: HashMap::new(),
+                asset_nonces: HashMap::new(),
+                rewards: 0,
+                epoch_rewards: 0,
+                reward_epoch: 0,
+                weight: 1,
+            },
+            VOracle::V1(o) => Oracle {
+                last_report: o.last_report,
+                price_reports: o.price_reports,
+                last_near_claim: o.last_near_claim,
+                public_key: None,
+                last_signed_timestamp: 0,
+                stake: 0,
+                unbonding_stake: 0,
+                unbonding_until: 0,
+                asset_reports: HashMap::new(),
+                asset_nonces: HashMap::new(),
+                rewards: 0,
+                epoch_rewards: 0,
+                reward_epoch: 0,
+                weight: 1,
             },
             VOracle::Current(c) => c,
         }
@@ -50,6 +144,17 @@ impl Oracle {
             last_report: 0,
             price_reports: 0,
             last_near_claim: 0,
+            public_key: None,
+            last_signed_timestamp: 0,
+            stake: 0,
+            unbonding_stake: 0,
+            unbonding_until: 0,
+            asset_reports: HashMap::new(),
+            asset_nonces: HashMap::new(),
+            rewards: 0,
+            epoch_rewards: 0,
+            reward_epoch: 0,
+            weight: 1,
         }
     }
 }
@@ -1,75 +1,1749 @@
 use crate::*;
+use near_sdk::is_promise_success;
 use near_sdk::json_types::U128;
 
+/// The `confirmation` that has to be passed to `renounce_ownership`.
+pub const RENOUNCE_OWNERSHIP_CONFIRMATION: &str = "I understand the contract will have no owner";
+
+/// The owner after the ownership is renounced. Nobody can sign transactions as this account.
+const NO_OWNER_ID: &str = "system";
+
+/// A parameter change of `execute_owner_actions`. Every action requires the same role as the
+/// method it calls.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub enum OwnerAction {
+    SetRecencyDurationSec {
+        recency_duration_sec: DurationSec,
+    },
+    SetMinNumRecentReports {
+        min_num_recent_reports: Option<u32>,
+    },
+    SetMinReportIntervalSec {
+        min_report_interval_sec: DurationSec,
+    },
+    SetFees {
+        flat_fee: U128,
+        per_asset_fee: U128,
+    },
+    SetPushFee {
+        push_fee: U128,
+    },
+    AddOracle {
+        account_id: AccountId,
+    },
+    RemoveOracle {
+        account_id: AccountId,
+    },
+    SetOracleWeight {
+        account_id: AccountId,
+        weight: u32,
+    },
+    AddAsset {
+        asset_id: AssetId,
+    },
+    RemoveAsset {
+        asset_id: AssetId,
+    },
+    SetAssetMetadata {
+        asset_id: AssetId,
+        metadata: Option<AssetMetadata>,
+    },
+    SetAssetRecencyDurationSec {
+        asset_id: AssetId,
+        recency_duration_sec: Option<DurationSec>,
+    },
+    SetAssetMaxDeviationBps {
+        asset_id: AssetId,
+        max_deviation_bps: Option<u32>,
+    },
+    SetAssetCircuitBreaker {
+        asset_id: AssetId,
+        circuit_breaker: Option<CircuitBreaker>,
+    },
+    AddAssetEma {
+        asset_id: AssetId,
+        period_sec: DurationSec,
+    },
+    GrantRole {
+        account_id: AccountId,
+        role: Role,
+    },
+    RevokeRole {
+        account_id: AccountId,
+        role: Role,
+    },
+    Pause,
+    Unpause,
+    SetMultisig {
+        multisig: Option<Multisig>,
+    },
+}
+
+/// The multi-owner mode. Actions of the owner have to be proposed and confirmed by `threshold`
+/// of the admins within `proposal_duration_sec`, the owner account itself loses its privileges.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Multisig {
+    pub admin_ids: Vec<AccountId>,
+    pub threshold: u32,
+    pub proposal_duration_sec: DurationSec,
+}
+
+impl Multisig {
+    pub fn assert_valid(&self) {
+        assert!(
+            self.threshold > 0 && self.threshold as usize <= self.admin_ids.len(),
+            "{}",
+            ContractError::InvalidThreshold
+        );
+        assert!(
+            self.proposal_duration_sec > 0,
+            "{}",
+            ContractError::PeriodNotPositive
+        );
+    }
+}
+
+/// Owner actions waiting for confirmations of the multisig admins.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OwnerProposal {
+    pub actions: Vec<OwnerAction>,
+    pub proposer_id: AccountId,
+    pub confirmations: Vec<AccountId>,
+    #[serde(with = "u64_dec_format")]
+    pub expires_at: Timestamp,
+}
+
+/// The gas of the call that applies the actions of a confirmed proposal.
+const GAS_FOR_EXECUTE_OWNER_ACTIONS: Gas = Gas(Gas::ONE_TERA.0 * 50);
+
+/// The gas of the callback that restores the proposal if its actions failed.
+const GAS_FOR_ON_EXECUTE_OWNER_ACTIONS: Gas = Gas(Gas::ONE_TERA.0 * 10);
+
+/// The prepaid gas required by the confirmation that executes a proposal, which covers the
+/// confirmation itself, the actions and the callback.
+const GAS_FOR_EXECUTE_OWNER_PROPOSAL: Gas = Gas(Gas::ONE_TERA.0 * 80);
+
+#[ext_contract(ext_owner_actions)]
+pub trait ExtOwnerActions {
+    fn execute_owner_actions(&mut self, actions: Vec<OwnerAction>);
+    fn on_execute_owner_actions(&mut self, proposal_id: u64, proposal: OwnerProposal);
+}
+
+#[near_bindgen]
+impl Contract {
+    #[payable]
+    pub fn set_recency_duration_sec(&mut self, recency_duration_sec: DurationSec) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        self.assert_no_timelock();
+        self.internal_set_recency_duration_sec(recency_duration_sec);
+    }
+
+    /// Sets the delay of timelocked configuration changes. While the delay is set, critical
+    /// parameters can only be changed with `propose_config_change`, including the delay itself.
+    #[payable]
+    pub fn set_timelock_sec(&mut self, timelock_sec: DurationSec) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        self.assert_no_timelock();
+        self.internal_set_timelock_sec(timelock_sec);
+    }
+
+    /// Sets how reports lose their weight in the aggregated price as they age. `None` switches
+    /// back to the regular median.
+    #[payable]
+    pub fn set_weight_decay(&mut self, weight_decay: Option<WeightDecay>) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        self.assert_no_timelock();
+        if let Some(weight_decay) = weight_decay.as_ref() {
+            weight_decay.assert_valid();
+        }
+        self.internal_set_weight_decay(weight_decay);
+    }
+
+    /// Sets where the weights of oracles in the aggregated price come from.
+    #[payable]
+    pub fn set_oracle_weighting(&mut self, oracle_weighting: OracleWeighting) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        self.assert_no_timelock();
+        self.internal_set_oracle_weighting(oracle_weighting);
+    }
+
+    /// Overrides the weighting of oracles for the asset. Passing `None` resets it to the
+    /// contract-wide weighting.
+    #[payable]
+    pub fn set_asset_oracle_weighting(
+        &mut self,
+        asset_id: AssetId,
+        oracle_weighting: Option<OracleWeighting>,
+    ) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        self.assert_no_timelock();
+        self.internal_set_asset_oracle_weighting(&asset_id, oracle_weighting);
+    }
+
+    /// Sets the weight of the oracle used with `OracleWeighting::Assigned`.
+    #[payable]
+    pub fn set_oracle_weight(&mut self, account_id: AccountId, weight: u32) {
+        assert_one_yocto();
+        self.assert_role(Role::OracleManager);
+        self.assert_no_timelock();
+        self.internal_set_oracle_weight(&account_id, weight);
+    }
+
+    #[payable]
+    pub fn add_oracle(&mut self, account_id: AccountId) {
+        assert_one_yocto();
+        self.assert_role(Role::OracleManager);
+        self.assert_no_timelock();
+        self.internal_add_oracle(&account_id);
+    }
+
+    #[payable]
+    pub fn remove_oracle(&mut self, account_id: AccountId) {
+        assert_one_yocto();
+        self.assert_role(Role::OracleManager);
+        self.assert_no_timelock();
+        assert!(
+            self.internal_remove_oracle(&account_id),
+            "{}",
+            ContractError::NotAnOracle
+        );
+    }
+
+    /// Lets the suspended oracle report again, its low score rounds start over.
+    #[payable]
+    pub fn reinstate_oracle(&mut self, account_id: AccountId) {
+        assert_one_yocto();
+        self.assert_role(Role::OracleManager);
+        self.assert_no_timelock();
+        self.internal_reinstate_oracle(&account_id);
+    }
+
+    /// Rewrites assets stored by older versions of the contract in the current version. Migrates
+    /// up to `limit` assets, continuing from where the previous call stopped, and returns the
+    /// number of migrated ones.
+    #[payable]
+    pub fn migrate_legacy_assets(&mut self, limit: u64) -> u64 {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        self.internal_migrate_legacy_assets(limit)
+    }
+
+    /// Sets how oracles are scored on finalized rounds. `None` stops scoring, suspended oracles
+    /// stay suspended until they're reinstated.
+    #[payable]
+    pub fn set_oracle_scoring(&mut self, oracle_scoring: Option<OracleScoring>) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        if let Some(oracle_scoring) = oracle_scoring.as_ref() {
+            oracle_scoring.assert_valid();
+        }
+        self.oracle_scoring = oracle_scoring;
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
+    }
+
+    /// Replaces all oracles with the given ones. Oracles remaining in the set keep their stats.
+    /// If the stabilization duration is set, prices are frozen to their last values until the new
+    /// oracles warm up.
+    #[payable]
+    pub fn replace_oracle_set(&mut self, account_ids: Vec<AccountId>) {
+        assert_one_yocto();
+        self.assert_role(Role::OracleManager);
+        self.assert_no_timelock();
+        self.internal_replace_oracle_set(account_ids);
+    }
+
+    /// Configures the backup oracles that take over when too few oracles are live, or removes
+    /// the failover with `None`.
+    #[payable]
+    pub fn set_failover(&mut self, failover: Option<FailoverConfig>) {
+        assert_one_yocto();
+        self.assert_role(Role::OracleManager);
+        self.assert_no_timelock();
+        self.internal_set_failover(failover);
+    }
+
+    #[payable]
+    pub fn set_oracle_set_stabilization_sec(&mut self, oracle_set_stabilization_sec: DurationSec) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        self.oracle_set_stabilization_sec = oracle_set_stabilization_sec;
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
+    }
+
+    /// Sets the number of recent reports required for a price. `None` requires reports from at
+    /// least half of the oracles.
+    #[payable]
+    pub fn set_min_num_recent_reports(&mut self, min_num_recent_reports: Option<u32>) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        self.assert_no_timelock();
+        assert_ne!(
+            min_num_recent_reports,
+            Some(0),
+            "{}",
+            ContractError::QuorumNotPositive
+        );
+        self.internal_set_min_num_recent_reports(min_num_recent_reports);
+    }
+
+    /// Sets the minimum interval between two reports of an oracle for the same asset. More
+    /// frequent reports are ignored. `0` disables the limit.
+    #[payable]
+    pub fn set_min_report_interval_sec(&mut self, min_report_interval_sec: DurationSec) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        self.min_report_interval_sec = min_report_interval_sec;
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
+    }
+
+    /// Sets what `get_price_data` returns for stale prices of assets.
+    #[payable]
+    pub fn set_expiry_policy(&mut self, expiry_policy: ExpiryPolicy) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        self.expiry_policy = expiry_policy;
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
+    }
+
+    /// Sets how many price snapshots are kept per asset and how often they are taken. Setting the
+    /// length to `0` stops taking snapshots.
+    #[payable]
+    pub fn set_price_history(
+        &mut self,
+        price_history_len: u32,
+        price_history_interval_sec: DurationSec,
+    ) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        self.price_history_len = price_history_len;
+        self.price_history_interval_sec = price_history_interval_sec;
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
+    }
+
+    /// Sets the duration of aggregation rounds and how many finalized rounds are kept per asset.
+    /// Setting the duration to `0` stops opening new rounds.
+    #[payable]
+    pub fn set_round_config(&mut self, round_duration_sec: DurationSec, round_history_len: u32) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        assert!(
+            round_duration_sec == 0 || round_history_len > 0,
+            "{}",
+            ContractError::RequiresRoundHistory
+        );
+        self.round_duration_sec = round_duration_sec;
+        self.round_history_len = round_history_len;
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
+    }
+
+    /// Sets the reward for every accepted price and the maximum reward an oracle can accrue
+    /// within an epoch of `reward_epoch_sec`.
+    #[payable]
+    pub fn set_reward_config(
+        &mut self,
+        reward_per_report: U128,
+        max_rewards_per_epoch: Option<U128>,
+        reward_epoch_sec: DurationSec,
+    ) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        self.reward_per_report = reward_per_report.into();
+        self.max_rewards_per_epoch = max_rewards_per_epoch.map(|amount| amount.into());
+        self.reward_epoch_sec = reward_epoch_sec;
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
+    }
+
+    /// Sets the fee of `oracle_call` as a flat fee plus a fee for every requested asset.
+    #[payable]
+    pub fn set_fees(&mut self, flat_fee: U128, per_asset_fee: U128) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        self.flat_fee = flat_fee.into();
+        self.per_asset_fee = per_asset_fee.into();
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
+    }
+
+    /// Sets the fee of every price push to subscribers.
+    #[payable]
+    pub fn set_push_fee(&mut self, push_fee: U128) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        self.push_fee = push_fee.into();
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
+    }
+
+    /// Makes recency durations of assets without their own one follow their report cadence.
+    /// `None` uses the contract-wide recency duration again.
+    #[payable]
+    pub fn set_adaptive_recency(&mut self, adaptive_recency: Option<AdaptiveRecency>) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        self.assert_no_timelock();
+        if let Some(adaptive_recency) = adaptive_recency.as_ref() {
+            adaptive_recency.assert_valid();
+        }
+        self.internal_set_adaptive_recency(adaptive_recency);
+    }
+
+    /// Defers the aggregation of reports to `finalize_prices` and to the first report after the
+    /// window since the last aggregation of the asset. `None` aggregates on every report.
+    #[payable]
+    pub fn set_aggregation_window_sec(&mut self, aggregation_window_sec: Option<DurationSec>) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        assert_ne!(
+            aggregation_window_sec,
+            Some(0),
+            "{}",
+            ContractError::PeriodNotPositive
+        );
+        self.aggregation_window_sec = aggregation_window_sec;
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
+    }
+
+    /// Sets whether the aggregated price changes of a report call are logged as one event.
+    #[payable]
+    pub fn set_batch_price_events(&mut self, batch_price_events: bool) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        self.batch_price_events = batch_price_events;
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
+    }
+
+    /// Sets whether NEAR fees of price requests are refunded when the receiver fails.
+    #[payable]
+    pub fn set_refund_failed_calls(&mut self, refund_failed_calls: bool) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        self.refund_failed_calls = refund_failed_calls;
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
+    }
+
+    /// Transfers collected fees to the given account, by default to the owner. Withdraws all
+    /// collected fees if the amount is not given.
+    #[payable]
+    pub fn withdraw_fees(
+        &mut self,
+        amount: Option<U128>,
+        receiver_id: Option<AccountId>,
+    ) -> Promise {
+        assert_one_yocto();
+        self.assert_owner();
+        let amount = self.internal_take_fees(amount);
+        Promise::new(receiver_id.unwrap_or_else(|| self.owner_id.clone())).transfer(amount)
+    }
+
+    /// Sets the attestation network accepted by `update_price_feeds`, or disables price feeds if
+    /// `config` is `None`.
+    #[payable]
+    pub fn set_price_feed_config(&mut self, config: Option<PriceFeedConfig>) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        if let Some(config) = config.as_ref() {
+            config.assert_valid();
+        }
+        self.price_feed = config;
+    }
+
+    /// Accepts the token as a payment for price requests with the given fee, or stops accepting
+    /// it if `token_fee` is `None`.
+    #[payable]
+    pub fn set_token_fee(&mut self, token_id: AccountId, token_fee: Option<TokenFee>) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        if let Some(token_fee) = token_fee {
+            self.token_fees.insert(&token_id, &token_fee);
+        } else {
+            self.token_fees.remove(&token_id);
+        }
+    }
+
+    /// Moves collected fees to the rewards pool. Moves all collected fees if the amount is not
+    /// given.
+    #[payable]
+    pub fn route_fees_to_rewards(&mut self, amount: Option<U128>) {
+        assert_one_yocto();
+        self.assert_owner();
+        let amount = self.internal_take_fees(amount);
+        self.rewards_pool += amount;
+    }
+
+    #[payable]
+    pub fn set_min_stake(&mut self, min_stake: U128) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        self.assert_no_timelock();
+        self.internal_set_min_stake(min_stake.into());
+    }
+
+    #[payable]
+    pub fn set_unbonding_period_sec(&mut self, unbonding_period_sec: DurationSec) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        self.unbonding_period_sec = unbonding_period_sec;
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
+    }
+
+    #[payable]
+    pub fn add_relayer(&mut self, account_id: AccountId) {
+        assert_one_yocto();
+        self.assert_role(Role::OracleManager);
+        assert!(
+            self.relayers.insert(&account_id),
+            "{}",
+            ContractError::RelayerAlreadyExists
+        );
+    }
+
+    #[payable]
+    pub fn remove_relayer(&mut self, account_id: AccountId) {
+        assert_one_yocto();
+        self.assert_role(Role::OracleManager);
+        assert!(
+            self.relayers.remove(&account_id),
+            "{}",
+            ContractError::NotARelayer
+        );
+    }
+
+    /// Adds a mirror oracle that prices can be relayed to with `relay_prices`.
+    #[payable]
+    pub fn add_mirror(&mut self, account_id: AccountId) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        assert!(
+            self.mirrors.get(&account_id).is_none(),
+            "{}",
+            ContractError::MirrorAlreadyExists
+        );
+        self.mirrors.insert(&account_id, &0);
+    }
+
+    #[payable]
+    pub fn remove_mirror(&mut self, account_id: AccountId) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        assert!(
+            self.mirrors.remove(&account_id).is_some(),
+            "{}",
+            ContractError::NotAMirror
+        );
+    }
+
+    #[payable]
+    pub fn add_dex_contract(&mut self, account_id: AccountId) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        assert!(
+            self.dex_contracts.insert(&account_id),
+            "{}",
+            ContractError::DexContractAlreadyExists
+        );
+    }
+
+    #[payable]
+    pub fn remove_dex_contract(&mut self, account_id: AccountId) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        assert!(
+            self.dex_contracts.remove(&account_id),
+            "{}",
+            ContractError::DexContractNotWhitelisted
+        );
+    }
+
+    /// Resets price report counters of all oracles, e.g. at the start of a new accounting epoch.
+    #[payable]
+    pub fn reset_oracle_report_counters(&mut self) {
+        assert_one_yocto();
+        self.assert_role(Role::OracleManager);
+        for account_id in self.oracles.keys_as_vector().to_vec() {
+            let mut oracle = self.internal_get_oracle(&account_id).unwrap();
+            oracle.price_reports = 0;
+            oracle.asset_reports.clear();
+            self.internal_set_oracle(&account_id, oracle);
+        }
+    }
+
+    #[payable]
+    pub fn add_asset(&mut self, asset_id: AssetId) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        assert!(
+            is_valid_asset_id(&asset_id),
+            "{}",
+            ContractError::InvalidAssetId
+        );
+        self.internal_add_asset(&asset_id);
+    }
+
+    /// Adds an asset for a symbol that isn't an account ID, e.g. `BTC` or `XAU`. The asset gets
+    /// the ID `symbol:{symbol}`, which can't collide with token assets. Returns the asset ID.
+    #[payable]
+    pub fn register_symbol(&mut self, symbol: String) -> AssetId {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        assert!(is_valid_symbol(&symbol), "{}", ContractError::InvalidSymbol);
+        assert!(
+            self.symbols.get(&symbol).is_none(),
+            "{}",
+            ContractError::SymbolAlreadyRegistered
+        );
+        let asset_id = symbol_asset_id(&symbol);
+        self.internal_add_asset(&asset_id);
+        self.symbols.insert(&symbol, &asset_id);
+        Event::RegisterSymbol {
+            symbol: &symbol,
+            asset_id: &asset_id,
+        }
+        .emit();
+        asset_id
+    }
+
+    pub fn get_symbol_asset_id(&self, symbol: String) -> Option<AssetId> {
+        self.symbols.get(&symbol)
+    }
+
+    pub fn get_symbols(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<(String, AssetId)> {
+        unordered_map_pagination(&self.symbols, from_index, limit)
+    }
+
+    /// Adds a feed of signed values of the kind, e.g. `BTC-PERP.FUNDING`. Feed IDs are symbols,
+    /// so the oracle serves them apart from assets.
+    #[payable]
+    pub fn add_rate_feed(&mut self, feed_id: String, kind: RateKind) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        assert!(
+            is_valid_symbol(&feed_id),
+            "{}",
+            ContractError::InvalidSymbol
+        );
+        assert!(
+            self.rate_feeds.get(&feed_id).is_none(),
+            "{}",
+            ContractError::RateFeedAlreadyExists
+        );
+        let initial_storage_usage = env::storage_usage();
+        self.rate_feeds.insert(
+            &feed_id,
+            &RateFeed {
+                kind,
+                reports: vec![],
+            },
+        );
+        self.internal_update_storage(&env::predecessor_account_id(), initial_storage_usage);
+        Event::AddRateFeed {
+            feed_id: &feed_id,
+            kind,
+        }
+        .emit();
+    }
+
+    #[payable]
+    pub fn remove_rate_feed(&mut self, feed_id: String) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        let initial_storage_usage = env::storage_usage();
+        self.rate_feeds
+            .remove(&feed_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingRateFeed));
+        self.internal_update_storage(&env::predecessor_account_id(), initial_storage_usage);
+        Event::RemoveRateFeed { feed_id: &feed_id }.emit();
+    }
+}
+
+impl Contract {
+    pub fn internal_add_asset(&mut self, asset_id: &AssetId) {
+        assert!(
+            self.internal_get_asset(asset_id).is_none(),
+            "{}",
+            ContractError::AssetAlreadyExists
+        );
+        assert!(
+            self.indexes.get(asset_id).is_none(),
+            "{}",
+            ContractError::IndexAlreadyExists
+        );
+        assert!(
+            self.pairs.get(asset_id).is_none(),
+            "{}",
+            ContractError::PairAlreadyExists
+        );
+        assert!(
+            self.internal_num_assets() < self.max_assets,
+            "{}",
+            ContractError::MaxAssetsReached(self.max_assets)
+        );
+        let initial_storage_usage = env::storage_usage();
+        let payer_id = env::predecessor_account_id();
+        self.internal_set_asset(asset_id, Asset::new());
+        if self.storage_deposit_required {
+            self.asset_storage_payers.insert(asset_id, &payer_id);
+        }
+        self.internal_update_storage(&payer_id, initial_storage_usage);
+        Event::AddAsset { asset_id }.emit();
+    }
+}
+
 #[near_bindgen]
 impl Contract {
+    /// Makes asset managers pay for the storage of assets they add and oracles pay for the
+    /// storage of prices they report, with deposits made through `storage_deposit`.
+    #[payable]
+    pub fn set_storage_deposit_required(&mut self, storage_deposit_required: bool) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        self.storage_deposit_required = storage_deposit_required;
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
+    }
+
+    #[payable]
+    pub fn set_max_assets(&mut self, max_assets: u64) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        assert!(
+            max_assets >= self.internal_num_assets(),
+            "{}",
+            ContractError::MaxAssetsBelowNumAssets
+        );
+        self.max_assets = max_assets;
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
+    }
+
+    /// Sets the maximum number of assets of a price request, which also limits requests of all
+    /// assets.
+    #[payable]
+    pub fn set_max_assets_per_request(&mut self, max_assets_per_request: u64) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        assert!(
+            max_assets_per_request > 0 && max_assets_per_request <= MAX_UNFILTERED_ASSETS,
+            "{}",
+            ContractError::InvalidMaxAssetsPerRequest(MAX_UNFILTERED_ASSETS)
+        );
+        self.max_assets_per_request = max_assets_per_request;
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
+    }
+
+    /// Removes the asset with its prices, metadata and subscriptions, whose balances are refunded
+    /// to the subscribers. The asset can't be removed while an index or a pair uses it. The
+    /// storage is released to the account that paid for the asset.
+    #[payable]
+    pub fn remove_asset(&mut self, asset_id: AssetId) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        if let Some((index_id, _)) = self.indexes.iter().find(|(_, components)| {
+            components
+                .iter()
+                .any(|component| index_component_base_asset_id(&component.asset_id) == asset_id)
+        }) {
+            panic!("{}", ContractError::AssetUsedByIndex(&index_id));
+        }
+        if let Some((pair_id, _)) = self.pairs.iter().find(|(_, pair)| {
+            base_asset_id(&pair.base_asset_id) == asset_id
+                || base_asset_id(&pair.quote_asset_id) == asset_id
+        }) {
+            panic!("{}", ContractError::AssetUsedByPair(&pair_id));
+        }
+        // Subscribers, bundles and oracles paid for their own storage.
+        self.internal_remove_asset_subscriptions(&asset_id);
+        self.internal_remove_from_bundles(&asset_id);
+        self.internal_remove_asset_nonces(&asset_id);
+        let initial_storage_usage = env::storage_usage();
+        let payer_id = self.asset_storage_payers.remove(&asset_id);
+        let asset = self
+            .internal_remove_asset(&asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        self.internal_remove_rounds(&asset_id, &asset);
+        for quote_asset_id in &asset.quote_asset_ids {
+            self.internal_remove_quoted_asset(&asset_id, quote_asset_id);
+        }
+        if let Some(group) = asset.group {
+            self.internal_remove_from_group(&group, &asset_id);
+        }
+        self.price_history.remove(&asset_id);
+        self.asset_metadata.remove(&asset_id);
+        self.price_feed_publish_times.remove(&asset_id);
+        if let Some(symbol) = asset_id.strip_prefix(SYMBOL_ASSET_ID_PREFIX) {
+            self.symbols.remove(&symbol.to_string());
+        }
+        if let Some(payer_id) = payer_id {
+            self.internal_update_storage(&payer_id, initial_storage_usage);
+        }
+        Event::RemoveAsset {
+            asset_id: &asset_id,
+        }
+        .emit();
+    }
+
+    /// Removes reports of the asset that are older than `max_age_sec`. Returns the number of
+    /// removed reports.
+    #[payable]
+    pub fn prune_reports(&mut self, asset_id: AssetId, max_age_sec: DurationSec) -> u64 {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        let initial_storage_usage = env::storage_usage();
+        let mut asset = self
+            .internal_get_asset(&asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        let timestamp_cut = now().saturating_sub(to_nano(max_age_sec));
+        let initial_num_reports = asset.reports.len();
+        asset
+            .reports
+            .retain(|report| report.timestamp >= timestamp_cut);
+        let num_reports = (initial_num_reports - asset.reports.len()) as u64;
+        self.internal_set_asset(&asset_id, asset);
+        self.internal_update_storage(&env::predecessor_account_id(), initial_storage_usage);
+        Event::PruneReports {
+            asset_id: &asset_id,
+            num_reports,
+        }
+        .emit();
+        num_reports
+    }
+
+    /// Sets the metadata of the asset or removes it if `metadata` is `None`.
+    #[payable]
+    pub fn set_asset_metadata(&mut self, asset_id: AssetId, metadata: Option<AssetMetadata>) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        assert!(
+            self.internal_get_asset(&asset_id).is_some(),
+            "{}",
+            ContractError::MissingAsset
+        );
+        if let Some(metadata) = metadata {
+            self.asset_metadata.insert(&asset_id, &metadata);
+        } else {
+            self.asset_metadata.remove(&asset_id);
+        }
+    }
+
+    /// Sets the price oracle contract that `oracle_call` gets prices of the asset from while the
+    /// asset doesn't have a fresh price. `None` removes the fallback.
+    #[payable]
+    pub fn set_asset_fallback_oracle(
+        &mut self,
+        asset_id: AssetId,
+        fallback_oracle_id: Option<AccountId>,
+    ) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        assert_ne!(
+            fallback_oracle_id.as_ref(),
+            Some(&env::current_account_id()),
+            "{}",
+            ContractError::InvalidFallbackOracle
+        );
+        let mut asset = self
+            .internal_get_asset(&asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        asset.fallback_oracle_id = fallback_oracle_id;
+        self.internal_set_asset(&asset_id, asset);
+    }
+
+    /// Sets the decimals reported prices of the asset must have. `None` accepts any decimals.
+    #[payable]
+    pub fn set_asset_price_decimals(&mut self, asset_id: AssetId, price_decimals: Option<u8>) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        assert!(
+            price_decimals.unwrap_or(0) <= MAX_VALID_DECIMALS,
+            "{}",
+            ContractError::InvalidDecimals
+        );
+        let mut asset = self
+            .internal_get_asset(&asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        asset.price_decimals = price_decimals;
+        self.internal_set_asset(&asset_id, asset);
+    }
+
+    /// Announces that the price feed of the asset stops at the sunset, optionally pointing
+    /// consumers to the replacement asset. Can be called again to move the sunset.
+    #[payable]
+    pub fn deprecate_asset(
+        &mut self,
+        asset_id: AssetId,
+        sunset_timestamp: U64,
+        replacement_asset_id: Option<AssetId>,
+    ) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        assert!(
+            sunset_timestamp.0 > now(),
+            "{}",
+            ContractError::SunsetNotInFuture
+        );
+        if let Some(replacement_asset_id) = replacement_asset_id.as_ref() {
+            assert!(
+                replacement_asset_id != &asset_id
+                    && self.internal_get_asset(replacement_asset_id).is_some(),
+                "{}",
+                ContractError::MissingAssetId(replacement_asset_id)
+            );
+        }
+        let mut asset = self
+            .internal_get_asset(&asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        Event::DeprecateAsset {
+            asset_id: &asset_id,
+            sunset_timestamp,
+            replacement_asset_id: replacement_asset_id.as_ref(),
+        }
+        .emit();
+        asset.deprecation = Some(AssetDeprecation {
+            sunset_timestamp: sunset_timestamp.0,
+            replacement_asset_id,
+        });
+        self.internal_set_asset(&asset_id, asset);
+    }
+
+    /// Moves the asset into the given group, or removes it from its group if `group` is `None`.
+    #[payable]
+    pub fn set_asset_group(&mut self, asset_id: AssetId, group: Option<GroupId>) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        let mut asset = self
+            .internal_get_asset(&asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        if let Some(old_group) = asset.group.as_ref() {
+            self.internal_remove_from_group(old_group, &asset_id);
+        }
+        if let Some(group) = group.as_ref() {
+            let mut asset_ids = self.asset_groups.get(group).unwrap_or_default();
+            asset_ids.push(asset_id.clone());
+            self.asset_groups.insert(group, &asset_ids);
+        }
+        asset.group = group;
+        self.internal_set_asset(&asset_id, asset);
+    }
+
+    #[payable]
+    pub fn pause_group(&mut self, group: GroupId) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        self.internal_update_group(&group, |asset| asset.paused = true);
+    }
+
+    #[payable]
+    pub fn unpause_group(&mut self, group: GroupId) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        self.internal_update_group(&group, |asset| asset.paused = false);
+    }
+
+    #[payable]
+    pub fn set_asset_circuit_breaker(
+        &mut self,
+        asset_id: AssetId,
+        circuit_breaker: Option<CircuitBreaker>,
+    ) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        self.assert_no_timelock();
+        if let Some(circuit_breaker) = circuit_breaker.as_ref() {
+            circuit_breaker.assert_valid();
+        }
+        self.internal_set_asset_circuit_breaker(&asset_id, circuit_breaker);
+    }
+
+    /// Resumes the asset after its circuit breaker tripped. The next aggregated price is accepted
+    /// as the new reference price.
+    #[payable]
+    pub fn resume_asset(&mut self, asset_id: AssetId) {
+        assert_one_yocto();
+        self.assert_role(Role::Guardian);
+        let mut asset = self
+            .internal_get_asset(&asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        assert!(asset.tripped, "{}", ContractError::AssetNotTripped);
+        asset.tripped = false;
+        asset.last_good = None;
+        self.internal_set_asset(&asset_id, asset);
+        Event::ResumeAsset {
+            asset_id: &asset_id,
+        }
+        .emit();
+    }
+
+    /// Stops accepting reports and returning prices.
+    #[payable]
+    pub fn pause(&mut self) {
+        assert_one_yocto();
+        self.assert_role(Role::Guardian);
+        self.paused = true;
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
+    }
+
+    #[payable]
+    pub fn unpause(&mut self) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        self.paused = false;
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
+    }
+
+    /// Overrides the recency duration of the asset. Passing `None` resets it to the
+    /// contract-wide recency duration.
+    #[payable]
+    pub fn set_asset_recency_duration_sec(
+        &mut self,
+        asset_id: AssetId,
+        recency_duration_sec: Option<DurationSec>,
+    ) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        self.assert_no_timelock();
+        self.internal_set_asset_recency_duration_sec(&asset_id, recency_duration_sec);
+    }
+
+    /// Overrides the recency duration for all assets of the group. Passing `None` resets them to
+    /// the contract-wide recency duration.
+    #[payable]
+    pub fn set_group_recency_duration_sec(
+        &mut self,
+        group: GroupId,
+        recency_duration_sec: Option<DurationSec>,
+    ) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        self.assert_no_timelock();
+        self.internal_set_group_recency_duration_sec(&group, recency_duration_sec);
+    }
+
+    /// Sets for how long the aggregated price of the asset stays valid after it's computed.
+    /// Passing `None` resets it to the recency duration of the asset.
+    #[payable]
+    pub fn set_asset_price_ttl_sec(
+        &mut self,
+        asset_id: AssetId,
+        price_ttl_sec: Option<DurationSec>,
+    ) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        assert_ne!(price_ttl_sec, Some(0), "{}", ContractError::TtlNotPositive);
+        let mut asset = self
+            .internal_get_asset(&asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        asset.price_ttl_sec = price_ttl_sec;
+        self.internal_set_asset(&asset_id, asset);
+    }
+
+    /// Overrides the number of recent reports required for a price of the asset. Passing `None`
+    /// resets it to the contract-wide quorum.
+    #[payable]
+    pub fn set_asset_min_num_recent_reports(
+        &mut self,
+        asset_id: AssetId,
+        min_num_recent_reports: Option<u32>,
+    ) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        self.assert_no_timelock();
+        assert_ne!(
+            min_num_recent_reports,
+            Some(0),
+            "{}",
+            ContractError::QuorumNotPositive
+        );
+        self.internal_set_asset_min_num_recent_reports(&asset_id, min_num_recent_reports);
+    }
+
+    /// Sets the maximum deviation in basis points of a report from the last aggregated price of
+    /// the asset. Reports deviating more are left out of the aggregation. `None` disables the
+    /// filter.
+    #[payable]
+    pub fn set_asset_max_deviation_bps(
+        &mut self,
+        asset_id: AssetId,
+        max_deviation_bps: Option<u32>,
+    ) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        self.assert_no_timelock();
+        assert_ne!(
+            max_deviation_bps,
+            Some(0),
+            "{}",
+            ContractError::DeviationNotPositive
+        );
+        self.internal_set_asset_max_deviation_bps(&asset_id, max_deviation_bps);
+    }
+
+    /// Overrides the minimum interval between two reports of an oracle for the asset. Passing
+    /// `None` resets it to the contract-wide interval.
+    #[payable]
+    pub fn set_asset_min_report_interval_sec(
+        &mut self,
+        asset_id: AssetId,
+        min_report_interval_sec: Option<DurationSec>,
+    ) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        let mut asset = self
+            .internal_get_asset(&asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        asset.min_report_interval_sec = min_report_interval_sec;
+        self.internal_set_asset(&asset_id, asset);
+    }
+
+    /// Overrides the expiry policy for the asset. Passing `None` resets it to the contract-wide
+    /// policy.
+    #[payable]
+    pub fn set_asset_expiry_policy(
+        &mut self,
+        asset_id: AssetId,
+        expiry_policy: Option<ExpiryPolicy>,
+    ) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        let mut asset = self
+            .internal_get_asset(&asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        asset.expiry_policy = expiry_policy;
+        self.internal_set_asset(&asset_id, asset);
+    }
+
+    /// Enables the commit-reveal reporting for the asset. `None` accepts regular reports again.
+    /// Pending commitments are discarded.
+    #[payable]
+    pub fn set_asset_commit_reveal(
+        &mut self,
+        asset_id: AssetId,
+        commit_reveal: Option<CommitRevealConfig>,
+    ) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        if let Some(commit_reveal) = commit_reveal.as_ref() {
+            commit_reveal.assert_valid();
+        }
+        let mut asset = self
+            .internal_get_asset(&asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        asset.commit_reveal = commit_reveal;
+        asset.commitments.clear();
+        self.internal_set_asset(&asset_id, asset);
+    }
+
+    /// Makes the asset a pegged asset, or a regular one with `None`. Resets the depeg status.
+    #[payable]
+    pub fn set_asset_peg(&mut self, asset_id: AssetId, peg: Option<Peg>) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        if let Some(peg) = peg.as_ref() {
+            peg.assert_valid();
+        }
+        let mut asset = self
+            .internal_get_asset(&asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        asset.peg = peg;
+        asset.depegged_since = None;
+        self.internal_set_asset(&asset_id, asset);
+    }
+
+    /// Makes the asset accept and aggregate prices in an additional quote asset, e.g. `near`.
+    #[payable]
+    pub fn add_asset_quote(&mut self, asset_id: AssetId, quote_asset_id: AssetId) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        assert!(
+            is_valid_asset_id(&quote_asset_id),
+            "{}",
+            ContractError::InvalidAssetId
+        );
+        let initial_storage_usage = env::storage_usage();
+        let mut asset = self
+            .internal_get_asset(&asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        assert!(
+            !asset.quote_asset_ids.contains(&quote_asset_id),
+            "{}",
+            ContractError::QuoteAssetAlreadyExists
+        );
+        // Quoted prices aren't listed among assets, so they're inserted directly.
+        self.assets.insert(
+            &quoted_asset_id(&asset_id, &quote_asset_id),
+            &Asset::new().into(),
+        );
+        asset.quote_asset_ids.push(quote_asset_id);
+        self.internal_set_asset(&asset_id, asset);
+        self.internal_update_storage(&env::predecessor_account_id(), initial_storage_usage);
+    }
+
+    /// Removes an additional quote asset of the asset along with its prices.
+    #[payable]
+    pub fn remove_asset_quote(&mut self, asset_id: AssetId, quote_asset_id: AssetId) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        let initial_storage_usage = env::storage_usage();
+        let mut asset = self
+            .internal_get_asset(&asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        let index = asset
+            .quote_asset_ids
+            .iter()
+            .position(|id| id == &quote_asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingQuoteAsset));
+        asset.quote_asset_ids.remove(index);
+        self.internal_remove_quoted_asset(&asset_id, &quote_asset_id);
+        self.internal_set_asset(&asset_id, asset);
+        self.internal_update_storage(&env::predecessor_account_id(), initial_storage_usage);
+    }
+
+    /// Restricts prices of the asset in price views and `oracle_call` to the consumers and
+    /// subscribers with a balance for a paid push. Prices derived from the asset are restricted the
+    /// same way. `None` makes prices public.
+    /// Only the contract's views and calls are restricted, the raw contract state and the reports
+    /// in transactions and events remain readable by anyone.
     #[payable]
-    pub fn set_recency_duration_sec(&mut self, recency_duration_sec: DurationSec) {
+    pub fn set_asset_consumers(&mut self, asset_id: AssetId, consumers: Option<Vec<AccountId>>) {
         assert_one_yocto();
-        self.assert_owner();
-        self.recency_duration_sec = recency_duration_sec;
+        self.assert_role(Role::AssetManager);
+        let initial_storage_usage = env::storage_usage();
+        let mut asset = self
+            .internal_get_asset(&asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        asset.consumers = consumers;
+        self.internal_set_asset(&asset_id, asset);
+        self.internal_update_storage(&env::predecessor_account_id(), initial_storage_usage);
     }
 
+    /// Sets the range of prices accepted from oracles for the asset. `None` accepts any price.
     #[payable]
-    pub fn add_oracle(&mut self, account_id: AccountId) {
+    pub fn set_asset_price_band(&mut self, asset_id: AssetId, price_band: Option<PriceBand>) {
         assert_one_yocto();
-        self.assert_owner();
-        assert!(self.internal_get_oracle(&account_id).is_none());
-        self.internal_set_oracle(&account_id, Oracle::new());
+        self.assert_role(Role::AssetManager);
+        self.assert_no_timelock();
+        if let Some(price_band) = price_band.as_ref() {
+            price_band.assert_valid();
+        }
+        self.internal_set_asset_price_band(&asset_id, price_band);
     }
 
+    /// Makes the asset return the median of the last `len` finalized block medians, which can't be
+    /// skewed by a burst of reports within one block. `None` returns the current median.
     #[payable]
-    pub fn remove_oracle(&mut self, account_id: AccountId) {
+    pub fn set_asset_multi_block_median_len(&mut self, asset_id: AssetId, len: Option<u32>) {
         assert_one_yocto();
-        self.assert_owner();
-        assert!(self.oracles.remove(&account_id).is_some());
+        self.assert_role(Role::AssetManager);
+        assert_ne!(len, Some(0), "{}", ContractError::LengthNotPositive);
+        let mut asset = self
+            .internal_get_asset(&asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        asset.multi_block_median_len = len;
+        asset.block_medians.clear();
+        self.internal_set_asset(&asset_id, asset);
     }
 
+    /// Sets the AMM pool the DEX price of the asset is fetched from and how it's combined with
+    /// reports. `None` only uses reports.
     #[payable]
-    pub fn add_asset(&mut self, asset_id: AssetId) {
+    pub fn set_asset_dex_source(&mut self, asset_id: AssetId, dex_source: Option<DexSource>) {
         assert_one_yocto();
-        self.assert_owner();
-        assert!(self.internal_get_asset(&asset_id).is_none());
-        self.internal_set_asset(&asset_id, Asset::new());
+        self.assert_role(Role::AssetManager);
+        if let Some(dex_source) = dex_source.as_ref() {
+            dex_source.assert_valid();
+            assert!(
+                self.dex_contracts.contains(&dex_source.contract_id),
+                "{}",
+                ContractError::DexContractNotWhitelisted
+            );
+        }
+        let mut asset = self
+            .internal_get_asset(&asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        asset.dex_source = dex_source;
+        asset.dex_price = None;
+        self.internal_set_asset(&asset_id, asset);
     }
 
+    /// Defines a composite index priced as a weighted basket of existing assets or their EMAs.
+    /// Redefining an existing index replaces its components.
     #[payable]
-    pub fn remove_asset(&mut self, asset_id: AssetId) {
+    pub fn define_index(&mut self, index_id: AssetId, components: Vec<IndexComponent>) {
         assert_one_yocto();
-        self.assert_owner();
-        assert!(self.assets.remove(&asset_id).is_some());
+        self.assert_role(Role::AssetManager);
+        assert!(
+            is_valid_derived_asset_id(&index_id),
+            "{}",
+            ContractError::InvalidIndexId
+        );
+        assert!(
+            self.internal_get_asset(&index_id).is_none(),
+            "{}",
+            ContractError::AssetAlreadyExists
+        );
+        assert!(
+            self.pairs.get(&index_id).is_none(),
+            "{}",
+            ContractError::PairAlreadyExists
+        );
+        assert!(
+            !components.is_empty(),
+            "{}",
+            ContractError::IndexRequiresComponents
+        );
+        for component in &components {
+            assert!(component.weight > 0, "{}", ContractError::WeightNotPositive);
+            let base_asset_id = index_component_base_asset_id(&component.asset_id).to_string();
+            assert!(
+                self.internal_get_asset(&base_asset_id).is_some(),
+                "{}",
+                ContractError::MissingAssetId(&component.asset_id)
+            );
+            assert!(
+                self.asset_metadata.get(&base_asset_id).is_some(),
+                "{}",
+                ContractError::MissingAssetMetadata(&base_asset_id)
+            );
+        }
+        self.indexes.insert(&index_id, &components);
+    }
+
+    /// Defines a named list of asset IDs that price requests can use instead of the IDs.
+    /// Redefining an existing bundle replaces its asset IDs.
+    #[payable]
+    pub fn set_bundle(&mut self, bundle_id: BundleId, asset_ids: Vec<AssetId>) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        assert!(!bundle_id.is_empty(), "{}", ContractError::InvalidBundleId);
+        assert!(
+            !asset_ids.is_empty(),
+            "{}",
+            ContractError::BundleRequiresAssets
+        );
+        assert!(
+            asset_ids.len() as u64 <= MAX_UNFILTERED_ASSETS,
+            "{}",
+            ContractError::BundleTooLarge(MAX_UNFILTERED_ASSETS)
+        );
+        let initial_storage_usage = env::storage_usage();
+        self.bundles.insert(&bundle_id, &asset_ids);
+        self.internal_update_storage(&env::predecessor_account_id(), initial_storage_usage);
+    }
+
+    #[payable]
+    pub fn remove_bundle(&mut self, bundle_id: BundleId) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        assert!(
+            self.bundles.remove(&bundle_id).is_some(),
+            "{}",
+            ContractError::MissingBundle
+        );
+    }
+
+    #[payable]
+    pub fn remove_index(&mut self, index_id: AssetId) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        assert!(
+            self.indexes.remove(&index_id).is_some(),
+            "{}",
+            ContractError::MissingIndex
+        );
+    }
+
+    /// Defines a synthetic asset priced from two existing assets or their EMAs and TWAPs.
+    /// Redefining an existing pair replaces it.
+    #[payable]
+    pub fn define_pair(&mut self, pair_id: AssetId, pair: DerivedPair) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        assert!(
+            is_valid_derived_asset_id(&pair_id),
+            "{}",
+            ContractError::InvalidPairId
+        );
+        assert!(
+            self.internal_get_asset(&pair_id).is_none(),
+            "{}",
+            ContractError::AssetAlreadyExists
+        );
+        assert!(
+            self.indexes.get(&pair_id).is_none(),
+            "{}",
+            ContractError::IndexAlreadyExists
+        );
+        for asset_id in [&pair.base_asset_id, &pair.quote_asset_id] {
+            let base_asset_id = asset_id
+                .split_once('#')
+                .map_or(asset_id.as_str(), |(base_asset_id, _)| base_asset_id);
+            assert!(
+                self.internal_get_asset(&base_asset_id.to_string())
+                    .is_some(),
+                "{}",
+                ContractError::MissingAssetId(asset_id)
+            );
+        }
+        self.pairs.insert(&pair_id, &pair);
+    }
+
+    #[payable]
+    pub fn remove_pair(&mut self, pair_id: AssetId) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        assert!(
+            self.pairs.remove(&pair_id).is_some(),
+            "{}",
+            ContractError::MissingPair
+        );
+    }
+
+    /// Sets how the EMA handles long gaps between aggregated prices. `None` counts the whole gap.
+    #[payable]
+    pub fn set_asset_ema_gap_policy(
+        &mut self,
+        asset_id: AssetId,
+        period_sec: DurationSec,
+        gap_policy: Option<EmaGapPolicy>,
+    ) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        if let Some(gap_policy) = gap_policy.as_ref() {
+            assert!(
+                gap_policy.max_gap_sec() > 0,
+                "{}",
+                ContractError::MaxGapNotPositive
+            );
+        }
+        let mut asset = self
+            .internal_get_asset(&asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        asset
+            .emas
+            .iter_mut()
+            .find(|ema| ema.period_sec == period_sec)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingEma))
+            .gap_policy = gap_policy;
+        self.internal_set_asset(&asset_id, asset);
     }
 
     #[payable]
     pub fn add_asset_ema(&mut self, asset_id: AssetId, period_sec: DurationSec) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_role(Role::AssetManager);
         let mut asset = self
             .internal_get_asset(&asset_id)
-            .expect("Missing an asset");
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
         if asset.emas.iter().any(|ema| ema.period_sec == period_sec) {
-            panic!("EMA for this period already exists");
+            panic!("{}", ContractError::EmaAlreadyExists);
         }
         asset.emas.push(AssetEma::new(period_sec));
         self.internal_set_asset(&asset_id, asset);
     }
 
+    /// Discards the state of the EMA, so the next aggregated price starts it over.
+    #[payable]
+    pub fn reset_ema(&mut self, asset_id: AssetId, period_sec: DurationSec) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        let mut asset = self
+            .internal_get_asset(&asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        asset
+            .emas
+            .iter_mut()
+            .find(|ema| ema.period_sec == period_sec)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingEma))
+            .reset();
+        self.internal_set_asset(&asset_id, asset);
+    }
+
+    /// Sets for how long after the start the EMA isn't returned.
+    #[payable]
+    pub fn set_asset_ema_warm_up_sec(
+        &mut self,
+        asset_id: AssetId,
+        period_sec: DurationSec,
+        warm_up_sec: DurationSec,
+    ) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        let mut asset = self
+            .internal_get_asset(&asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        asset
+            .emas
+            .iter_mut()
+            .find(|ema| ema.period_sec == period_sec)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingEma))
+            .warm_up_sec = warm_up_sec;
+        self.internal_set_asset(&asset_id, asset);
+    }
+
     #[payable]
     pub fn remove_asset_ema(&mut self, asset_id: AssetId, period_sec: DurationSec) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_role(Role::AssetManager);
         let mut asset = self
             .internal_get_asset(&asset_id)
-            .expect("Missing an asset");
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
         let last_num_emas = asset.emas.len();
         asset.emas.retain(|ema| ema.period_sec != period_sec);
         assert!(
             asset.emas.len() < last_num_emas,
-            "EMA for this period doesn't exists"
+            "{}",
+            ContractError::MissingEma
+        );
+        self.internal_set_asset(&asset_id, asset);
+    }
+
+    #[payable]
+    pub fn add_asset_twap(&mut self, asset_id: AssetId, period_sec: DurationSec) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        assert!(period_sec > 0, "{}", ContractError::PeriodNotPositive);
+        let mut asset = self
+            .internal_get_asset(&asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        if asset.twaps.iter().any(|twap| twap.period_sec == period_sec) {
+            panic!("{}", ContractError::TwapAlreadyExists);
+        }
+        asset.twaps.push(AssetTwap::new(period_sec));
+        self.internal_set_asset(&asset_id, asset);
+    }
+
+    #[payable]
+    pub fn remove_asset_twap(&mut self, asset_id: AssetId, period_sec: DurationSec) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        let mut asset = self
+            .internal_get_asset(&asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        let last_num_twaps = asset.twaps.len();
+        asset.twaps.retain(|twap| twap.period_sec != period_sec);
+        assert!(
+            asset.twaps.len() < last_num_twaps,
+            "{}",
+            ContractError::MissingTwap
+        );
+        self.internal_set_asset(&asset_id, asset);
+    }
+
+    /// Tracks the realized volatility of the asset over the period, returned as
+    /// `asset_id#vol{period_sec}`.
+    #[payable]
+    pub fn add_asset_volatility(&mut self, asset_id: AssetId, period_sec: DurationSec) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        assert!(period_sec > 0, "{}", ContractError::PeriodNotPositive);
+        let mut asset = self
+            .internal_get_asset(&asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        if asset
+            .volatilities
+            .iter()
+            .any(|volatility| volatility.period_sec == period_sec)
+        {
+            panic!("{}", ContractError::VolatilityAlreadyExists);
+        }
+        asset.volatilities.push(AssetVolatility::new(period_sec));
+        self.internal_set_asset(&asset_id, asset);
+    }
+
+    #[payable]
+    pub fn remove_asset_volatility(&mut self, asset_id: AssetId, period_sec: DurationSec) {
+        assert_one_yocto();
+        self.assert_role(Role::AssetManager);
+        let mut asset = self
+            .internal_get_asset(&asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        let last_num_volatilities = asset.volatilities.len();
+        asset
+            .volatilities
+            .retain(|volatility| volatility.period_sec != period_sec);
+        assert!(
+            asset.volatilities.len() < last_num_volatilities,
+            "{}",
+            ContractError::MissingVolatility
         );
         self.internal_set_asset(&asset_id, asset);
     }
 
+    /// Sets the DAO account that has every role, so its proposals can change parameters. Only
+    /// the owner can transfer the ownership or grant the `Admin` role.
+    #[payable]
+    pub fn set_dao_id(&mut self, dao_id: Option<AccountId>) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.dao_id = dao_id;
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
+    }
+
+    /// Enables the multi-owner mode, or disables it with `None`. Once it's enabled, this can only
+    /// be changed by a confirmed proposal.
+    #[payable]
+    pub fn set_multisig(&mut self, multisig: Option<Multisig>) {
+        assert_one_yocto();
+        self.assert_owner();
+        if let Some(multisig) = multisig.as_ref() {
+            multisig.assert_valid();
+        }
+        self.multisig = multisig;
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
+    }
+
+    /// Proposes owner actions in the multi-owner mode, the proposer confirms them right away.
+    /// Returns the ID of the proposal.
+    #[payable]
+    pub fn propose_owner_actions(&mut self, actions: Vec<OwnerAction>) -> u64 {
+        assert_one_yocto();
+        assert!(!actions.is_empty(), "{}", ContractError::RequiresActions);
+        let multisig = self.internal_assert_multisig_admin();
+        let proposer_id = env::predecessor_account_id();
+        let proposal_id = self.next_owner_proposal_id;
+        self.next_owner_proposal_id += 1;
+        Event::ProposeOwnerActions {
+            proposal_id,
+            proposer_id: &proposer_id,
+        }
+        .emit();
+        let proposal = OwnerProposal {
+            actions,
+            proposer_id: proposer_id.clone(),
+            confirmations: vec![proposer_id],
+            expires_at: now() + to_nano(multisig.proposal_duration_sec),
+        };
+        self.internal_execute_or_store_owner_proposal(proposal_id, proposal, &multisig);
+        proposal_id
+    }
+
+    /// Confirms the proposal. The confirmation that reaches the threshold schedules the actions,
+    /// which are applied by the contract itself as the owner, and requires 80 TGas of prepaid
+    /// gas.
+    #[payable]
+    pub fn confirm_owner_proposal(&mut self, proposal_id: u64) {
+        assert_one_yocto();
+        let multisig = self.internal_assert_multisig_admin();
+        let account_id = env::predecessor_account_id();
+        let mut proposal = self
+            .owner_proposals
+            .get(&proposal_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingProposal));
+        assert!(
+            now() < proposal.expires_at,
+            "{}",
+            ContractError::ProposalExpired
+        );
+        assert!(
+            !proposal.confirmations.contains(&account_id),
+            "{}",
+            ContractError::AlreadyConfirmed
+        );
+        Event::ConfirmOwnerProposal {
+            proposal_id,
+            account_id: &account_id,
+        }
+        .emit();
+        proposal.confirmations.push(account_id);
+        self.internal_execute_or_store_owner_proposal(proposal_id, proposal, &multisig);
+    }
+
+    /// Emits the execution of the proposal if its actions were applied, otherwise restores the
+    /// proposal with its confirmations, so it can still be confirmed by the other admins or
+    /// removed once it expires.
+    #[private]
+    pub fn on_execute_owner_actions(&mut self, proposal_id: u64, proposal: OwnerProposal) {
+        if is_promise_success() {
+            Event::ExecuteOwnerProposal { proposal_id }.emit();
+        } else {
+            self.owner_proposals.insert(&proposal_id, &proposal);
+        }
+    }
+
+    /// Removes the proposal after it expired. Can be called by anyone.
+    pub fn remove_expired_owner_proposal(&mut self, proposal_id: u64) {
+        let proposal = self
+            .owner_proposals
+            .get(&proposal_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingProposal));
+        assert!(
+            now() >= proposal.expires_at,
+            "{}",
+            ContractError::ProposalNotExpired
+        );
+        self.owner_proposals.remove(&proposal_id);
+    }
+
+    pub fn get_owner_proposals(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<(u64, OwnerProposal)> {
+        unordered_map_pagination(&self.owner_proposals, from_index, limit)
+    }
+
+    /// Applies the actions in order within one transaction, e.g. from a single DAO proposal. If
+    /// any action fails, none of them is applied.
+    #[payable]
+    pub fn execute_owner_actions(&mut self, actions: Vec<OwnerAction>) {
+        assert!(!actions.is_empty(), "{}", ContractError::RequiresActions);
+        for action in actions {
+            match action {
+                OwnerAction::SetRecencyDurationSec {
+                    recency_duration_sec,
+                } => self.set_recency_duration_sec(recency_duration_sec),
+                OwnerAction::SetMinNumRecentReports {
+                    min_num_recent_reports,
+                } => self.set_min_num_recent_reports(min_num_recent_reports),
+                OwnerAction::SetMinReportIntervalSec {
+                    min_report_interval_sec,
+                } => self.set_min_report_interval_sec(min_report_interval_sec),
+                OwnerAction::SetFees {
+                    flat_fee,
+                    per_asset_fee,
+                } => self.set_fees(flat_fee, per_asset_fee),
+                OwnerAction::SetPushFee { push_fee } => self.set_push_fee(push_fee),
+                OwnerAction::AddOracle { account_id } => self.add_oracle(account_id),
+                OwnerAction::RemoveOracle { account_id } => self.remove_oracle(account_id),
+                OwnerAction::SetOracleWeight { account_id, weight } => {
+                    self.set_oracle_weight(account_id, weight)
+                }
+                OwnerAction::AddAsset { asset_id } => self.add_asset(asset_id),
+                OwnerAction::RemoveAsset { asset_id } => self.remove_asset(asset_id),
+                OwnerAction::SetAssetMetadata { asset_id, metadata } => {
+                    self.set_asset_metadata(asset_id, metadata)
+                }
+                OwnerAction::SetAssetRecencyDurationSec {
+                    asset_id,
+                    recency_duration_sec,
+                } => self.set_asset_recency_duration_sec(asset_id, recency_duration_sec),
+                OwnerAction::SetAssetMaxDeviationBps {
+                    asset_id,
+                    max_deviation_bps,
+                } => self.set_asset_max_deviation_bps(asset_id, max_deviation_bps),
+                OwnerAction::SetAssetCircuitBreaker {
+                    asset_id,
+                    circuit_breaker,
+                } => self.set_asset_circuit_breaker(asset_id, circuit_breaker),
+                OwnerAction::AddAssetEma {
+                    asset_id,
+                    period_sec,
+                } => self.add_asset_ema(asset_id, period_sec),
+                OwnerAction::GrantRole { account_id, role } => self.grant_role(account_id, role),
+                OwnerAction::RevokeRole { account_id, role } => self.revoke_role(account_id, role),
+                OwnerAction::Pause => self.pause(),
+                OwnerAction::Unpause => self.unpause(),
+                OwnerAction::SetMultisig { multisig } => self.set_multisig(multisig),
+            }
+        }
+    }
+
     pub fn get_owner_id(&self) -> AccountId {
         self.owner_id.clone()
     }
@@ -81,24 +1755,347 @@ impl Contract {
     #[payable]
     pub fn update_near_claim_amount(&mut self, near_claim_amount: U128) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_role(Role::Admin);
         self.near_claim_amount = near_claim_amount.into();
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
+    }
+
+    /// Proposes the new owner, who has to accept the ownership with `accept_ownership`.
+    /// Passing `None` cancels the proposal.
+    #[payable]
+    pub fn propose_owner(&mut self, owner_id: Option<AccountId>) {
+        assert_one_yocto();
+        self.assert_owner();
+        if let Some(owner_id) = owner_id.as_ref() {
+            Event::ProposeOwner {
+                owner_id: &self.owner_id,
+                proposed_owner_id: owner_id,
+            }
+            .emit();
+        }
+        self.proposed_owner_id = owner_id;
+    }
+
+    /// Makes the proposed owner the owner. Can only be called by the proposed owner.
+    #[payable]
+    pub fn accept_ownership(&mut self) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        assert_eq!(
+            self.proposed_owner_id.as_ref(),
+            Some(&account_id),
+            "{}",
+            ContractError::NotProposedOwner
+        );
+        Event::TransferOwnership {
+            old_owner_id: &self.owner_id,
+            new_owner_id: &account_id,
+        }
+        .emit();
+        self.owner_id = account_id;
+        self.proposed_owner_id = None;
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
     }
 
+    /// Permanently gives up the ownership, so owner methods can no longer be called.
+    /// Requires `RENOUNCE_OWNERSHIP_CONFIRMATION` as the confirmation.
     #[payable]
-    pub fn update_owner_id(&mut self, owner_id: AccountId) {
+    pub fn renounce_ownership(&mut self, confirmation: String) {
         assert_one_yocto();
         self.assert_owner();
-        self.owner_id = owner_id;
+        assert_eq!(
+            confirmation,
+            RENOUNCE_OWNERSHIP_CONFIRMATION,
+            "{}",
+            ContractError::InvalidConfirmation
+        );
+        Event::RenounceOwnership {
+            owner_id: &self.owner_id,
+        }
+        .emit();
+        self.owner_id = AccountId::new_unchecked(NO_OWNER_ID.to_string());
+        self.proposed_owner_id = None;
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
     }
 }
 
 impl Contract {
+    pub fn internal_set_recency_duration_sec(&mut self, recency_duration_sec: DurationSec) {
+        self.recency_duration_sec = recency_duration_sec;
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
+    }
+
+    pub fn internal_set_asset_recency_duration_sec(
+        &mut self,
+        asset_id: &AssetId,
+        recency_duration_sec: Option<DurationSec>,
+    ) {
+        let mut asset = self
+            .internal_get_asset(asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        asset.recency_duration_sec = recency_duration_sec;
+        self.internal_set_asset(asset_id, asset);
+    }
+
+    pub fn internal_set_group_recency_duration_sec(
+        &mut self,
+        group: &GroupId,
+        recency_duration_sec: Option<DurationSec>,
+    ) {
+        self.internal_update_group(group, |asset| {
+            asset.recency_duration_sec = recency_duration_sec
+        });
+    }
+
+    pub fn internal_set_adaptive_recency(&mut self, adaptive_recency: Option<AdaptiveRecency>) {
+        self.adaptive_recency = adaptive_recency;
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
+    }
+
+    pub fn internal_add_oracle(&mut self, account_id: &AccountId) {
+        assert!(
+            self.internal_get_oracle(account_id).is_none(),
+            "{}",
+            ContractError::OracleAlreadyExists
+        );
+        self.internal_set_oracle(account_id, Oracle::new());
+        Event::AddOracle { account_id }.emit();
+    }
+
+    pub fn internal_replace_oracle_set(&mut self, account_ids: Vec<AccountId>) {
+        for account_id in self.oracles.keys_as_vector().to_vec() {
+            if !account_ids.contains(&account_id) {
+                self.internal_remove_oracle(&account_id);
+            }
+        }
+        for account_id in account_ids {
+            if self.internal_get_oracle(&account_id).is_none() {
+                self.internal_set_oracle(&account_id, Oracle::new());
+                Event::AddOracle {
+                    account_id: &account_id,
+                }
+                .emit();
+            }
+        }
+        self.stabilization_until = now() + to_nano(self.oracle_set_stabilization_sec);
+    }
+
+    pub fn internal_set_asset_circuit_breaker(
+        &mut self,
+        asset_id: &AssetId,
+        circuit_breaker: Option<CircuitBreaker>,
+    ) {
+        let mut asset = self
+            .internal_get_asset(asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        asset.circuit_breaker = circuit_breaker;
+        self.internal_set_asset(asset_id, asset);
+    }
+
+    pub fn internal_set_min_stake(&mut self, min_stake: Balance) {
+        self.min_stake = min_stake;
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
+    }
+
+    pub fn internal_set_oracle_weight(&mut self, account_id: &AccountId, weight: u32) {
+        let mut oracle = self
+            .internal_get_oracle(account_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::NotAnOracle));
+        oracle.weight = weight;
+        self.internal_set_oracle(account_id, oracle);
+    }
+
+    pub fn internal_set_oracle_weighting(&mut self, oracle_weighting: OracleWeighting) {
+        self.oracle_weighting = oracle_weighting;
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
+    }
+
+    pub fn internal_set_asset_oracle_weighting(
+        &mut self,
+        asset_id: &AssetId,
+        oracle_weighting: Option<OracleWeighting>,
+    ) {
+        let mut asset = self
+            .internal_get_asset(asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        asset.oracle_weighting = oracle_weighting;
+        self.internal_set_asset(asset_id, asset);
+    }
+
+    pub fn internal_set_min_num_recent_reports(&mut self, min_num_recent_reports: Option<u32>) {
+        self.min_num_recent_reports = min_num_recent_reports;
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
+    }
+
+    pub fn internal_set_asset_min_num_recent_reports(
+        &mut self,
+        asset_id: &AssetId,
+        min_num_recent_reports: Option<u32>,
+    ) {
+        let mut asset = self
+            .internal_get_asset(asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        asset.min_num_recent_reports = min_num_recent_reports;
+        self.internal_set_asset(asset_id, asset);
+    }
+
+    pub fn internal_set_weight_decay(&mut self, weight_decay: Option<WeightDecay>) {
+        self.weight_decay = weight_decay;
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
+    }
+
+    pub fn internal_set_asset_max_deviation_bps(
+        &mut self,
+        asset_id: &AssetId,
+        max_deviation_bps: Option<u32>,
+    ) {
+        let mut asset = self
+            .internal_get_asset(asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        asset.max_deviation_bps = max_deviation_bps;
+        self.internal_set_asset(asset_id, asset);
+    }
+
+    pub fn internal_set_asset_price_band(
+        &mut self,
+        asset_id: &AssetId,
+        price_band: Option<PriceBand>,
+    ) {
+        let mut asset = self
+            .internal_get_asset(asset_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingAsset));
+        asset.price_band = price_band;
+        self.internal_set_asset(asset_id, asset);
+    }
+
+    fn internal_remove_from_bundles(&mut self, asset_id: &AssetId) {
+        for (bundle_id, mut asset_ids) in self.bundles.to_vec() {
+            if !asset_ids.contains(asset_id) {
+                continue;
+            }
+            asset_ids.retain(|id| id != asset_id);
+            if asset_ids.is_empty() {
+                self.bundles.remove(&bundle_id);
+            } else {
+                self.bundles.insert(&bundle_id, &asset_ids);
+            }
+        }
+    }
+
+    fn internal_remove_asset_nonces(&mut self, asset_id: &AssetId) {
+        for account_id in self.oracles.keys_as_vector().to_vec() {
+            let mut oracle = self.internal_get_oracle(&account_id).unwrap();
+            if oracle.asset_nonces.remove(asset_id).is_some() {
+                self.internal_set_oracle(&account_id, oracle);
+            }
+        }
+    }
+
+    pub fn internal_remove_from_group(&mut self, group: &GroupId, asset_id: &AssetId) {
+        let mut asset_ids = self.asset_groups.get(group).unwrap_or_default();
+        asset_ids.retain(|id| id != asset_id);
+        if asset_ids.is_empty() {
+            self.asset_groups.remove(group);
+        } else {
+            self.asset_groups.insert(group, &asset_ids);
+        }
+    }
+
+    fn internal_update_group<F>(&mut self, group: &GroupId, f: F)
+    where
+        F: Fn(&mut Asset),
+    {
+        let asset_ids = self
+            .asset_groups
+            .get(group)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingGroup));
+        for asset_id in asset_ids {
+            let mut asset = self.internal_get_asset(&asset_id).unwrap();
+            f(&mut asset);
+            self.internal_set_asset(&asset_id, asset);
+        }
+    }
+
+    fn internal_take_fees(&mut self, amount: Option<U128>) -> Balance {
+        let amount = amount.map_or(self.collected_fees, |amount| amount.into());
+        assert!(amount > 0, "{}", ContractError::NothingToWithdraw);
+        assert!(
+            amount <= self.collected_fees,
+            "{}",
+            ContractError::NotEnoughCollectedFees
+        );
+        self.collected_fees -= amount;
+        amount
+    }
+
+    /// The account with the privileges of the owner, which is the contract itself in the
+    /// multi-owner mode.
+    pub fn internal_acting_owner_id(&self) -> AccountId {
+        if self.multisig.is_some() {
+            env::current_account_id()
+        } else {
+            self.owner_id.clone()
+        }
+    }
+
+    fn internal_assert_multisig_admin(&self) -> Multisig {
+        let multisig = self
+            .multisig
+            .clone()
+            .unwrap_or_else(|| panic!("{}", ContractError::MultisigDisabled));
+        assert!(
+            multisig.admin_ids.contains(&env::predecessor_account_id()),
+            "{}",
+            ContractError::NotAMultisigAdmin
+        );
+        multisig
+    }
+
+    /// Schedules the actions of the proposal once it has enough confirmations, otherwise stores
+    /// it. The proposal is restored if its actions fail.
+    fn internal_execute_or_store_owner_proposal(
+        &mut self,
+        proposal_id: u64,
+        proposal: OwnerProposal,
+        multisig: &Multisig,
+    ) {
+        // Confirmations of accounts that are no longer admins don't count.
+        let num_confirmations = proposal
+            .confirmations
+            .iter()
+            .filter(|account_id| multisig.admin_ids.contains(account_id))
+            .count();
+        if num_confirmations < multisig.threshold as usize {
+            self.owner_proposals.insert(&proposal_id, &proposal);
+            return;
+        }
+        assert!(
+            env::prepaid_gas() >= GAS_FOR_EXECUTE_OWNER_PROPOSAL,
+            "{}",
+            ContractError::NotEnoughPrepaidGas(GAS_FOR_EXECUTE_OWNER_PROPOSAL.0 / Gas::ONE_TERA.0)
+        );
+        self.owner_proposals.remove(&proposal_id);
+        ext_owner_actions::execute_owner_actions(
+            proposal.actions.clone(),
+            env::current_account_id(),
+            1,
+            GAS_FOR_EXECUTE_OWNER_ACTIONS,
+        )
+        .then(ext_owner_actions::on_execute_owner_actions(
+            proposal_id,
+            proposal,
+            env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_ON_EXECUTE_OWNER_ACTIONS,
+        ));
+    }
+
     pub fn assert_owner(&self) {
         assert_eq!(
-            self.owner_id,
+            self.internal_acting_owner_id(),
             env::predecessor_account_id(),
-            "Can only be called by the owner"
+            "{}",
+            ContractError::NotOwner
         );
     }
 }
@@ -0,0 +1,117 @@
+use crate::*;
+
+/// The decimals of a quotient are capped, so the numerator isn't scaled up indefinitely.
+const MAX_QUOTIENT_DECIMALS: u8 = 64;
+
+/// The prefix of the inverse of a price rounded down, e.g. `inverse:usdt.near` is USDT per USD.
+pub const INVERSE_PREFIX: &str = "inverse:";
+/// The prefix of the inverse of a price rounded up.
+pub const INVERSE_UP_PREFIX: &str = "inverse_up:";
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy)]
+#[serde(crate = "near_sdk::serde")]
+pub enum PairOperation {
+    Multiply,
+    Divide,
+}
+
+/// A synthetic asset priced as the product or the quotient of the prices of two assets, e.g.
+/// `token/USD` as `token/NEAR` multiplied by `NEAR/USD`.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DerivedPair {
+    pub base_asset_id: AssetId,
+    pub quote_asset_id: AssetId,
+    pub operation: PairOperation,
+}
+
+/// Multiplies the prices, dropping the least significant digits if the product doesn't fit.
+pub fn multiply_prices(a: Price, b: Price) -> Option<Price> {
+    let (mut a_multiplier, mut b_multiplier) = (a.multiplier, b.multiplier);
+    let mut decimals = a.decimals.checked_add(b.decimals)?;
+    loop {
+        if let Some(multiplier) = a_multiplier.checked_mul(b_multiplier) {
+            return Some(Price {
+                multiplier,
+                decimals,
+            });
+        }
+        decimals = decimals.checked_sub(1)?;
+        if a_multiplier > b_multiplier {
+            a_multiplier /= 10;
+        } else {
+            b_multiplier /= 10;
+        }
+    }
+}
+
+/// Divides the prices. The numerator is scaled up as much as possible to keep the precision.
+pub fn divide_prices(a: Price, b: Price) -> Option<Price> {
+    divide_prices_rounded(a, b, false)
+}
+
+/// Returns `1 / price`, rounded up if `round_up` is set and down otherwise.
+pub fn invert_price(price: Price, round_up: bool) -> Option<Price> {
+    let one = Price {
+        multiplier: 1,
+        decimals: 0,
+    };
+    divide_prices_rounded(one, price, round_up)
+}
+
+/// Returns the ID of the inverted asset and whether the inverse is rounded up, if the ID is the
+/// inverse of another one.
+pub fn inverse_asset_id(asset_id: &str) -> Option<(&str, bool)> {
+    if let Some(asset_id) = asset_id.strip_prefix(INVERSE_PREFIX) {
+        Some((asset_id, false))
+    } else {
+        asset_id
+            .strip_prefix(INVERSE_UP_PREFIX)
+            .map(|asset_id| (asset_id, true))
+    }
+}
+
+fn divide_prices_rounded(a: Price, b: Price, round_up: bool) -> Option<Price> {
+    if b.multiplier == 0 {
+        return None;
+    }
+    let mut multiplier = a.multiplier;
+    let mut decimals = i32::from(a.decimals) - i32::from(b.decimals);
+    while decimals < i32::from(MAX_QUOTIENT_DECIMALS) {
+        match multiplier.checked_mul(10) {
+            Some(scaled) => multiplier = scaled,
+            None => break,
+        }
+        decimals += 1;
+    }
+    let remainder = multiplier % b.multiplier;
+    let mut multiplier = multiplier / b.multiplier;
+    if round_up && remainder > 0 {
+        multiplier = multiplier.checked_add(1)?;
+    }
+    if decimals < 0 {
+        multiplier = multiplier.checked_mul(10u128.checked_pow(decimals.unsigned_abs())?)?;
+        decimals = 0;
+    }
+    Some(Price {
+        multiplier,
+        decimals: decimals as u8,
+    })
+}
+
+impl Contract {
+    /// Returns the price of the derived pair if both its assets have fresh prices.
+    pub fn internal_get_pair_price(
+        &self,
+        pair: &DerivedPair,
+        timestamp: Timestamp,
+        max_age_sec: Option<DurationSec>,
+    ) -> Option<Price> {
+        let base_price = self.internal_get_price(&pair.base_asset_id, timestamp, max_age_sec)?;
+        let quote_price = self.internal_get_price(&pair.quote_asset_id, timestamp, max_age_sec)?;
+        match pair.operation {
+            PairOperation::Multiply => multiply_prices(base_price, quote_price),
+            PairOperation::Divide => divide_prices(base_price, quote_price),
+        }
+    }
+}
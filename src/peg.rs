@@ -0,0 +1,82 @@
+use crate::*;
+
+/// The peg of a pegged asset, e.g. `1.0` for a USD stablecoin. The asset is depegged while its
+/// aggregated price deviates from the peg by more than `max_deviation_bps`, and the depeg is
+/// breached once it lasts at least `min_duration_sec`.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Peg {
+    pub price: Price,
+    pub max_deviation_bps: u32,
+    pub min_duration_sec: DurationSec,
+}
+
+impl Peg {
+    pub fn assert_valid(&self) {
+        self.price.assert_valid();
+        assert!(self.price.multiplier > 0, "The peg must be positive");
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DepegStatus {
+    pub asset_id: AssetId,
+    pub peg: Price,
+    pub price: Option<Price>,
+    /// The deviation of the current price from the peg.
+    pub deviation_bps: Option<u32>,
+    /// When the aggregated price started to deviate from the peg.
+    pub depegged_since: Option<U64>,
+    pub depeg_duration_sec: u64,
+    pub breached: bool,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the depeg status of a pegged asset.
+    pub fn get_depeg_status(&self, asset_id: AssetId) -> Option<DepegStatus> {
+        self.assert_asset_price_access(&asset_id);
+        let asset = self.internal_get_asset(&asset_id)?;
+        let peg = asset.peg?;
+        let timestamp = now();
+        let price = self.internal_get_price(&asset_id, timestamp, None);
+        let depeg_duration_sec = asset.depegged_since.map_or(0, |depegged_since| {
+            to_sec(timestamp.saturating_sub(depegged_since))
+        });
+        Some(DepegStatus {
+            asset_id,
+            peg: peg.price,
+            price,
+            deviation_bps: price.map(|price| price.deviation_bps(&peg.price)),
+            depegged_since: asset.depegged_since.map(U64),
+            depeg_duration_sec,
+            breached: asset.depegged_since.is_some()
+                && depeg_duration_sec >= u64::from(peg.min_duration_sec),
+        })
+    }
+}
+
+impl Contract {
+    /// Tracks whether the new aggregated price of a pegged asset deviates from the peg.
+    pub fn internal_update_peg(
+        &self,
+        asset_id: &AssetId,
+        asset: &mut Asset,
+        price: Price,
+        timestamp: Timestamp,
+    ) {
+        let peg = match asset.peg {
+            Some(peg) => peg,
+            None => return,
+        };
+        let depegged = price.deviation_bps(&peg.price) > peg.max_deviation_bps;
+        if depegged && asset.depegged_since.is_none() {
+            asset.depegged_since = Some(timestamp);
+            Event::Depeg { asset_id, price }.emit();
+        } else if !depegged && asset.depegged_since.is_some() {
+            asset.depegged_since = None;
+            Event::Repeg { asset_id, price }.emit();
+        }
+    }
+}
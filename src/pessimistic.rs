@@ -0,0 +1,71 @@
+use crate::*;
+
+/// Conservative prices of an asset for lending. The collateral price is the minimum and the
+/// borrow price is the maximum of the current price and the EMA and TWAP of the asset with the
+/// longest periods. Averages without a price are skipped.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetPessimisticPrice {
+    pub asset_id: AssetId,
+    pub collateral_price: Option<Price>,
+    pub borrow_price: Option<Price>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PessimisticPriceData {
+    #[serde(with = "u64_dec_format")]
+    pub timestamp: Timestamp,
+    pub prices: Vec<AssetPessimisticPrice>,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns conservative prices of the given assets, by default of the public assets.
+    pub fn get_pessimistic_price_data(
+        &self,
+        asset_ids: Option<Vec<AssetId>>,
+    ) -> PessimisticPriceData {
+        let asset_ids = self.internal_requested_asset_ids(asset_ids, None);
+        let timestamp = now();
+        PessimisticPriceData {
+            timestamp,
+            prices: asset_ids
+                .into_iter()
+                .map(|asset_id| self.internal_get_pessimistic_price(asset_id, timestamp))
+                .collect(),
+        }
+    }
+}
+
+impl Contract {
+    fn internal_get_pessimistic_price(
+        &self,
+        asset_id: AssetId,
+        timestamp: Timestamp,
+    ) -> AssetPessimisticPrice {
+        let price = self.internal_get_price(&asset_id, timestamp, None);
+        let mut prices: Vec<Price> = price.into_iter().collect();
+        if let Some(asset) = price.and_then(|_| self.internal_get_asset(&asset_id)) {
+            if let Some(ema) = asset.emas.iter().max_by_key(|ema| ema.period_sec) {
+                prices.extend(self.internal_get_price(
+                    &format!("{}#{}", asset_id, ema.period_sec),
+                    timestamp,
+                    None,
+                ));
+            }
+            if let Some(twap) = asset.twaps.iter().max_by_key(|twap| twap.period_sec) {
+                prices.extend(self.internal_get_price(
+                    &format!("{}#twap{}", asset_id, twap.period_sec),
+                    timestamp,
+                    None,
+                ));
+            }
+        }
+        AssetPessimisticPrice {
+            collateral_price: prices.iter().min().copied(),
+            borrow_price: prices.iter().max().copied(),
+            asset_id,
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use crate::*;
+use ed25519_dalek::Verifier;
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::CurveType;
+
+/// An external attestation network whose signed price updates anyone can submit.
+/// Attested prices are recorded as reports of `oracle_id`.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceFeedConfig {
+    pub oracle_id: AccountId,
+    /// ED25519 keys of the attesters.
+    pub public_keys: Vec<PublicKey>,
+    /// The number of distinct attesters that have to sign an update.
+    pub threshold: u32,
+    /// Updates with the confidence interval wider than this share of the price are rejected.
+    pub max_confidence_bps: u32,
+}
+
+impl PriceFeedConfig {
+    pub fn assert_valid(&self) {
+        assert!(
+            self.threshold > 0 && self.threshold as usize <= self.public_keys.len(),
+            "{}",
+            ContractError::InvalidThreshold
+        );
+        assert!(
+            self.public_keys
+                .iter()
+                .all(|public_key| matches!(public_key.curve_type(), CurveType::ED25519)),
+            "Only ED25519 keys are supported"
+        );
+    }
+}
+
+/// An attested price of an asset. The confidence is in the same decimals as the price.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct PriceFeedUpdate {
+    pub asset_id: AssetId,
+    pub price: Price,
+    pub confidence: Balance,
+    pub publish_time: Timestamp,
+}
+
+/// The Borsh-serialized blob submitted to `update_price_feeds`. Each signature is an ED25519
+/// signature of the Borsh-serialized update, made by the attester with the given key index.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct SignedPriceFeedUpdate {
+    pub update: PriceFeedUpdate,
+    pub signatures: Vec<(u8, Vec<u8>)>,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Verifies attested price updates and records them as reports of the price feed oracle.
+    /// Updates that aren't newer than the last accepted update of the asset are skipped.
+    pub fn update_price_feeds(&mut self, data: Vec<Base64VecU8>) {
+        self.assert_not_paused();
+        let config = self
+            .price_feed
+            .clone()
+            .expect("Price feeds are not configured");
+        let oracle = self
+            .internal_get_oracle(&config.oracle_id)
+            .expect("Price feed oracle is not registered");
+        let timestamp = now();
+        let mut prices = vec![];
+        for blob in data {
+            let SignedPriceFeedUpdate { update, signatures } =
+                SignedPriceFeedUpdate::try_from_slice(&blob.0)
+                    .expect("Failed to parse price update");
+            config.assert_signed(&update, &signatures);
+            update.price.assert_valid();
+            assert!(
+                update.confidence.saturating_mul(10000)
+                    <= update
+                        .price
+                        .multiplier
+                        .saturating_mul(u128::from(config.max_confidence_bps)),
+                "Confidence interval is too wide for {}",
+                update.asset_id
+            );
+            assert!(
+                update.publish_time <= timestamp
+                    && update.publish_time + to_nano(self.recency_duration_sec) >= timestamp,
+                "Publish time is outside of the recency window for {}",
+                update.asset_id
+            );
+            let last_publish_time = self
+                .price_feed_publish_times
+                .get(&update.asset_id)
+                .unwrap_or(0);
+            if update.publish_time <= last_publish_time {
+                log!("Warning! Skipping outdated update of {}", update.asset_id);
+                continue;
+            }
+            self.price_feed_publish_times
+                .insert(&update.asset_id, &update.publish_time);
+            prices.push(AssetPrice {
+                asset_id: update.asset_id,
+                price: update.price,
+                quote_asset_id: None,
+                nonce: None,
+            });
+        }
+        if !prices.is_empty() {
+            self.internal_report_prices(
+                &config.oracle_id,
+                oracle,
+                prices,
+                None,
+                PriceSource::PriceFeed,
+            );
+        }
+    }
+
+    pub fn get_price_feed_config(&self) -> Option<PriceFeedConfig> {
+        self.price_feed.clone()
+    }
+}
+
+impl PriceFeedConfig {
+    fn assert_signed(&self, update: &PriceFeedUpdate, signatures: &[(u8, Vec<u8>)]) {
+        let message = update.try_to_vec().unwrap();
+        let mut signers = vec![];
+        for (key_index, signature) in signatures {
+            let public_key = self
+                .public_keys
+                .get(*key_index as usize)
+                .expect("Unknown attester");
+            assert!(!signers.contains(key_index), "Duplicate signature");
+            // The first byte of the key is the curve type.
+            let public_key = ed25519_dalek::PublicKey::from_bytes(&public_key.as_bytes()[1..])
+                .expect("Invalid public key");
+            let signature =
+                ed25519_dalek::Signature::from_bytes(signature).expect("Invalid signature");
+            assert!(
+                public_key.verify(&message, &signature).is_ok(),
+                "Invalid signature"
+            );
+            signers.push(*key_index);
+        }
+        assert!(
+            signers.len() >= self.threshold as usize,
+            "Not enough signatures"
+        );
+    }
+}
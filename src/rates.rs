@@ -0,0 +1,269 @@
+use crate::*;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+
+const ONE: SignedPrice = SignedPrice {
+    multiplier: 1,
+    decimals: 0,
+};
+const MINUS_ONE: SignedPrice = SignedPrice {
+    multiplier: -1,
+    decimals: 0,
+};
+
+/// A signed value with a signed number of decimals, `multiplier * 10**-decimals`. Negative
+/// decimals scale the multiplier up, so it covers values far beyond `Price`. It serializes like
+/// `Price`, so a non-negative value with non-negative decimals reads the same as a price.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct SignedPrice {
+    #[serde(with = "i128_dec_format")]
+    pub multiplier: i128,
+    pub decimals: i8,
+}
+
+impl SignedPrice {
+    pub fn assert_valid(&self) {
+        assert!(
+            self.decimals.unsigned_abs() <= MAX_VALID_DECIMALS,
+            "{}",
+            ContractError::InvalidRate
+        );
+    }
+
+    /// Returns the value as a price, or `None` if it's negative or doesn't fit.
+    pub fn to_price(&self) -> Option<Price> {
+        let multiplier = u128::try_from(self.multiplier).ok()?;
+        if self.decimals >= 0 {
+            Some(Price {
+                multiplier,
+                decimals: self.decimals as u8,
+            })
+        } else {
+            Some(Price {
+                multiplier: multiplier
+                    .checked_mul(10u128.checked_pow(u32::from(self.decimals.unsigned_abs()))?)?,
+                decimals: 0,
+            })
+        }
+    }
+}
+
+impl From<Price> for SignedPrice {
+    fn from(price: Price) -> Self {
+        let (mut multiplier, mut decimals) = (price.multiplier, price.decimals as i8);
+        // Drops trailing digits that don't fit into `i128`.
+        while multiplier > i128::MAX as u128 {
+            multiplier /= 10;
+            decimals -= 1;
+        }
+        SignedPrice {
+            multiplier: multiplier as i128,
+            decimals,
+        }
+    }
+}
+
+impl PartialEq<Self> for SignedPrice {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for SignedPrice {}
+
+impl PartialOrd for SignedPrice {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SignedPrice {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if self.decimals < other.decimals {
+            return other.cmp(self).reverse();
+        }
+        let decimals_diff = (i16::from(self.decimals) - i16::from(other.decimals)) as u32;
+        if other.multiplier == 0 {
+            return self.multiplier.cmp(&0);
+        }
+        match 10i128
+            .checked_pow(decimals_diff)
+            .and_then(|scale| other.multiplier.checked_mul(scale))
+        {
+            Some(multiplier) => self.multiplier.cmp(&multiplier),
+            // The scaled value of `other` exceeds any multiplier of `self`.
+            None if other.multiplier > 0 => Ordering::Less,
+            None => Ordering::Greater,
+        }
+    }
+}
+
+/// What a rate feed represents, which decides the values oracles can report.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub enum RateKind {
+    /// A funding rate per funding period, between `-1.0` and `1.0`.
+    FundingRate,
+    /// An interest-rate benchmark, above `-1.0`.
+    InterestRate,
+    /// The basis between two prices, any value.
+    Basis,
+}
+
+impl RateKind {
+    pub fn assert_valid_rate(&self, rate: &SignedPrice) {
+        rate.assert_valid();
+        let valid = match self {
+            RateKind::FundingRate => *rate >= MINUS_ONE && *rate <= ONE,
+            RateKind::InterestRate => *rate > MINUS_ONE,
+            RateKind::Basis => true,
+        };
+        assert!(valid, "{}", ContractError::InvalidRate);
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RateReport {
+    pub oracle_id: AccountId,
+    #[serde(with = "u64_dec_format")]
+    pub timestamp: Timestamp,
+    #[serde(with = "u64_dec_format")]
+    pub block_height: BlockHeight,
+    pub rate: SignedPrice,
+}
+
+/// A feed of signed values like funding rates, interest-rate benchmarks or basis. Rate feeds are
+/// kept apart from assets, so their values never show up as prices.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RateFeed {
+    pub kind: RateKind,
+    /// The latest report of every oracle.
+    pub reports: Vec<RateReport>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetRate {
+    pub feed_id: String,
+    pub rate: SignedPrice,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetOptionalRate {
+    pub feed_id: String,
+    pub kind: Option<RateKind>,
+    pub rate: Option<SignedPrice>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RateData {
+    #[serde(with = "u64_dec_format")]
+    pub timestamp: Timestamp,
+    pub recency_duration_sec: DurationSec,
+    pub rates: Vec<AssetOptionalRate>,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Reports values of rate feeds. A new report of the oracle replaces its previous one.
+    pub fn report_rates(&mut self, rates: Vec<AssetRate>) {
+        self.assert_not_paused();
+        assert!(!rates.is_empty(), "{}", ContractError::RequiresPrices);
+        let oracle_id = env::predecessor_account_id();
+        let oracle = self
+            .internal_get_oracle(&oracle_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::NotAnOracle));
+        assert!(
+            self.has_min_stake(&oracle),
+            "{}",
+            ContractError::InsufficientStake
+        );
+        let initial_storage_usage = env::storage_usage();
+        let timestamp = now();
+        for AssetRate { feed_id, rate } in &rates {
+            let mut feed = self
+                .rate_feeds
+                .get(feed_id)
+                .unwrap_or_else(|| panic!("{}", ContractError::MissingRateFeed));
+            feed.kind.assert_valid_rate(rate);
+            feed.reports.retain(|report| report.oracle_id != oracle_id);
+            feed.reports.push(RateReport {
+                oracle_id: oracle_id.clone(),
+                timestamp,
+                block_height: env::block_height(),
+                rate: *rate,
+            });
+            self.rate_feeds.insert(feed_id, &feed);
+        }
+        self.internal_update_storage(&oracle_id, initial_storage_usage);
+        Event::ReportRates {
+            oracle_id: &oracle_id,
+            rates: &rates,
+        }
+        .emit();
+    }
+
+    pub fn get_rate_feed(&self, feed_id: String) -> Option<RateFeed> {
+        self.rate_feeds.get(&feed_id)
+    }
+
+    pub fn get_rate_feeds(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<(String, RateFeed)> {
+        unordered_map_pagination(&self.rate_feeds, from_index, limit)
+    }
+
+    /// Returns the median of the recent reports of every feed, `None` if they don't reach the
+    /// minimum number of recent reports.
+    pub fn get_rate_data(&self, feed_ids: Vec<String>) -> RateData {
+        self.assert_num_requested_assets(&feed_ids);
+        let timestamp = now();
+        let timestamp_cut = timestamp.saturating_sub(to_nano(self.recency_duration_sec));
+        let min_num_recent_reports = self.min_num_recent_reports();
+        RateData {
+            timestamp,
+            recency_duration_sec: self.recency_duration_sec,
+            rates: feed_ids
+                .into_iter()
+                .map(|feed_id| {
+                    let feed = self.rate_feeds.get(&feed_id);
+                    AssetOptionalRate {
+                        kind: feed.as_ref().map(|feed| feed.kind),
+                        rate: feed.and_then(|feed| {
+                            median_rate(&feed, timestamp_cut, min_num_recent_reports)
+                        }),
+                        feed_id,
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Returns the median of the reports since the cut, the upper one of an even number of them.
+fn median_rate(
+    feed: &RateFeed,
+    timestamp_cut: Timestamp,
+    min_num_recent_reports: usize,
+) -> Option<SignedPrice> {
+    let mut rates: Vec<SignedPrice> = feed
+        .reports
+        .iter()
+        .filter(|report| report.timestamp >= timestamp_cut)
+        .map(|report| report.rate)
+        .collect();
+    if rates.is_empty() || rates.len() < min_num_recent_reports {
+        return None;
+    }
+    rates.sort();
+    Some(rates[rates.len() / 2])
+}
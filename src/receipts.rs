@@ -0,0 +1,85 @@
+use crate::*;
+
+/// The record of price data delivered by `oracle_call` or `oracle_call_many`. Receipts are never
+/// removed, so protocols can attach the receipt ID to user positions as an audit trail.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceReceipt {
+    pub sender_id: AccountId,
+    pub receiver_ids: Vec<AccountId>,
+    #[serde(with = "u64_dec_format")]
+    pub block_height: BlockHeight,
+    /// The delivered price data, including the receipt ID and the commitment.
+    pub price_data: PriceData,
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn get_price_receipt(&self, receipt_id: U64) -> Option<PriceReceipt> {
+        let receipt = self.price_receipts.get(&receipt_id.0)?;
+        let asset_ids: Vec<AssetId> = receipt
+            .price_data
+            .prices
+            .iter()
+            .map(|price| price.asset_id.clone())
+            .collect();
+        self.assert_price_access(&asset_ids, None);
+        Some(receipt)
+    }
+
+    pub fn get_num_price_receipts(&self) -> U64 {
+        self.next_price_receipt_id.into()
+    }
+
+    /// Sets whether deliveries of price data are recorded as receipts. The sender pays for the
+    /// storage of receipts if storage deposits are required.
+    #[payable]
+    pub fn set_price_receipts_enabled(&mut self, price_receipts_enabled: bool) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        self.price_receipts_enabled = price_receipts_enabled;
+        Event::UpdateConfig(Box::new(self.get_config())).emit();
+    }
+}
+
+impl Contract {
+    /// Assigns the next receipt ID to the price data if receipts are enabled. Must be called
+    /// before the price data is committed, so the commitment covers the receipt ID.
+    pub fn internal_assign_price_receipt_id(&mut self, price_data: &mut PriceData) {
+        if !self.price_receipts_enabled {
+            return;
+        }
+        price_data.receipt_id = Some(self.next_price_receipt_id.into());
+        self.next_price_receipt_id += 1;
+    }
+
+    /// Records the receipt of the delivered price data, if it has a receipt ID.
+    pub fn internal_record_price_receipt(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_ids: Vec<AccountId>,
+        price_data: &PriceData,
+    ) {
+        let receipt_id = match price_data.receipt_id {
+            Some(receipt_id) => receipt_id.0,
+            None => return,
+        };
+        let initial_storage_usage = env::storage_usage();
+        self.price_receipts.insert(
+            &receipt_id,
+            &PriceReceipt {
+                sender_id: sender_id.clone(),
+                receiver_ids: receiver_ids.clone(),
+                block_height: env::block_height(),
+                price_data: price_data.clone(),
+            },
+        );
+        self.internal_update_storage(sender_id, initial_storage_usage);
+        Event::IssuePriceReceipt {
+            receipt_id,
+            sender_id,
+            receiver_ids: &receiver_ids,
+        }
+        .emit();
+    }
+}
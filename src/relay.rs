@@ -0,0 +1,48 @@
+use crate::*;
+
+/// The gas attached to every relay of prices to a mirror.
+const GAS_FOR_RELAY: Gas = Gas(Gas::ONE_TERA.0 * 20);
+
+/// A mirror oracle that keeps a read replica of prices. Every relay has the next sequence number
+/// of the mirror, so the mirror must ignore relays with a sequence number it has already seen.
+#[ext_contract(ext_price_mirror)]
+pub trait ExtPriceMirror {
+    fn receive_relayed_prices(&mut self, sequence: U64, price_data: PriceData);
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Pushes the latest prices of the assets to the mirror, by default of all public assets.
+    /// Can be called by anyone. Returns the sequence number of the relay.
+    pub fn relay_prices(&mut self, asset_ids: Option<Vec<AssetId>>, mirror_id: AccountId) -> U64 {
+        let sequence = self
+            .mirrors
+            .get(&mirror_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::NotAMirror))
+            + 1;
+        let asset_ids = self.internal_requested_asset_ids(asset_ids, Some(&mirror_id));
+        let price_data = self.internal_get_price_data(asset_ids, None, None);
+        self.mirrors.insert(&mirror_id, &sequence);
+        Event::RelayPrices {
+            mirror_id: &mirror_id,
+            sequence,
+        }
+        .emit();
+        ext_price_mirror::receive_relayed_prices(
+            sequence.into(),
+            price_data,
+            mirror_id,
+            NO_DEPOSIT,
+            GAS_FOR_RELAY,
+        );
+        sequence.into()
+    }
+
+    /// Returns the mirrors with the sequence numbers of their latest relays.
+    pub fn get_mirrors(&self) -> Vec<(AccountId, U64)> {
+        self.mirrors
+            .iter()
+            .map(|(mirror_id, sequence)| (mirror_id, sequence.into()))
+            .collect()
+    }
+}
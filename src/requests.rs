@@ -0,0 +1,247 @@
+use crate::*;
+use near_sdk::serde_json::json;
+
+/// The gas attached to the callback of a fulfilled price request.
+const GAS_FOR_PRICE_REQUEST_CALLBACK: Gas = Gas(Gas::ONE_TERA.0 * 20);
+
+/// The longest asset ID of a price request, which is the longest account ID.
+pub const MAX_PRICE_REQUEST_ASSET_ID_LEN: usize = 64;
+
+/// The longest method name of a price request callback.
+pub const MAX_PRICE_REQUEST_CALLBACK_LEN: usize = 64;
+
+/// An on-demand price of an asset, which doesn't have to be a reported asset. Oracles fulfill
+/// the request with their prices, and once the quorum reported, the median is the answer. The
+/// consumer gets `callback(request_id, asset_id, price)` with the answer, and the fee is split
+/// between the fulfilling oracles. The consumer pays the storage of the request, which is
+/// refunded once the request is fulfilled or cancelled and removed.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceRequest {
+    pub request_id: u64,
+    pub consumer_id: AccountId,
+    pub asset_id: AssetId,
+    /// The method of the consumer called with the answer.
+    pub callback: String,
+    #[serde(with = "u128_dec_format")]
+    pub fee: Balance,
+    /// The storage cost paid by the consumer on top of the fee.
+    #[serde(with = "u128_dec_format")]
+    pub storage_deposit: Balance,
+    #[serde(with = "u64_dec_format")]
+    pub created_at: Timestamp,
+    pub reports: Vec<Report>,
+    pub answer: Option<Price>,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Requests the price of the asset. The attached deposit covers the storage of the request,
+    /// and the rest is the fee for the fulfilling oracles, which must be at least the fee of a
+    /// price request of one asset. Returns the ID of the request.
+    #[payable]
+    pub fn request_price(&mut self, asset_id: AssetId, callback: String) -> U64 {
+        self.assert_not_paused();
+        let initial_storage_usage = env::storage_usage();
+        let consumer_id = env::predecessor_account_id();
+        let deposit = env::attached_deposit();
+        assert!(
+            !asset_id.is_empty() && asset_id.len() <= MAX_PRICE_REQUEST_ASSET_ID_LEN,
+            "{}",
+            ContractError::InvalidAssetId
+        );
+        assert!(
+            is_valid_callback(&callback),
+            "{}",
+            ContractError::InvalidCallback
+        );
+        let request_id = self.next_price_request_id;
+        self.next_price_request_id += 1;
+        let mut request = PriceRequest {
+            request_id,
+            consumer_id: consumer_id.clone(),
+            asset_id: asset_id.clone(),
+            callback,
+            fee: 0,
+            storage_deposit: 0,
+            created_at: now(),
+            reports: vec![],
+            answer: None,
+        };
+        self.price_requests.insert(&request_id, &request);
+        self.open_price_request_ids.insert(&request_id);
+        let storage_deposit = env::storage_byte_cost()
+            * Balance::from(env::storage_usage().saturating_sub(initial_storage_usage));
+        let min_deposit = storage_deposit + std::cmp::max(1, self.internal_fee(1));
+        assert!(
+            deposit >= min_deposit,
+            "{}",
+            ContractError::RequiresDeposit(min_deposit)
+        );
+        request.fee = deposit - storage_deposit;
+        request.storage_deposit = storage_deposit;
+        self.price_requests.insert(&request_id, &request);
+        self.total_price_request_fees += request.fee;
+        Event::RequestPrice {
+            request_id,
+            consumer_id: &consumer_id,
+            asset_id: &asset_id,
+        }
+        .emit();
+        request_id.into()
+    }
+
+    /// Reports the price for the open request. The report that reaches the quorum fulfills the
+    /// request, calls back the consumer and removes the request.
+    pub fn fulfill_price_request(&mut self, request_id: U64, price: Price) {
+        self.assert_not_paused();
+        price.assert_valid();
+        let oracle_id = env::predecessor_account_id();
+        let oracle = self
+            .internal_get_oracle(&oracle_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::NotAnOracle));
+        assert!(
+            self.has_min_stake(&oracle),
+            "{}",
+            ContractError::InsufficientStake
+        );
+        assert!(
+            !self.internal_is_suspended(&oracle_id),
+            "{}",
+            ContractError::OracleSuspended
+        );
+        let mut request = self
+            .price_requests
+            .get(&request_id.0)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingPriceRequest));
+        assert!(
+            request.answer.is_none(),
+            "{}",
+            ContractError::PriceRequestFulfilled
+        );
+        assert!(
+            request
+                .reports
+                .iter()
+                .all(|report| report.oracle_id != oracle_id),
+            "{}",
+            ContractError::OracleFulfilledRequest
+        );
+        request.reports.push(Report {
+            oracle_id,
+            timestamp: now(),
+            price,
+            source: PriceSource::Request,
+            block_height: env::block_height(),
+        });
+        request.answer = weighted_median(
+            request
+                .reports
+                .iter()
+                .map(|report| (report.price, 1))
+                .collect(),
+            self.min_num_recent_reports(),
+        );
+        if let Some(answer) = request.answer {
+            self.internal_complete_price_request(&request, answer);
+        } else {
+            self.price_requests.insert(&request_id.0, &request);
+        }
+    }
+
+    /// Cancels the open request of the caller and refunds its fee and storage deposit.
+    #[payable]
+    pub fn cancel_price_request(&mut self, request_id: U64) -> Promise {
+        assert_one_yocto();
+        let request = self
+            .price_requests
+            .get(&request_id.0)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingPriceRequest));
+        assert_eq!(
+            request.consumer_id,
+            env::predecessor_account_id(),
+            "{}",
+            ContractError::NotConsumer
+        );
+        // Requests fulfilled before they were removed on fulfillment stay in the state.
+        assert!(
+            request.answer.is_none(),
+            "{}",
+            ContractError::PriceRequestFulfilled
+        );
+        self.price_requests.remove(&request_id.0);
+        self.open_price_request_ids.remove(&request_id.0);
+        self.total_price_request_fees -= request.fee;
+        Promise::new(request.consumer_id).transfer(request.fee + request.storage_deposit)
+    }
+
+    pub fn get_price_request(&self, request_id: U64) -> Option<PriceRequest> {
+        self.price_requests.get(&request_id.0)
+    }
+
+    /// Returns the requests that are waiting for oracles.
+    pub fn get_open_price_requests(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<PriceRequest> {
+        let request_ids = self.open_price_request_ids.as_vector();
+        let from_index = from_index.unwrap_or(0);
+        let limit = limit.unwrap_or(request_ids.len());
+        (from_index..std::cmp::min(request_ids.len(), from_index.saturating_add(limit)))
+            .filter_map(|index| self.price_requests.get(&request_ids.get(index).unwrap()))
+            .collect()
+    }
+}
+
+impl Contract {
+    /// Splits the fee of the fulfilled request between its oracles, calls back the consumer and
+    /// refunds the storage deposit of the removed request.
+    fn internal_complete_price_request(&mut self, request: &PriceRequest, answer: Price) {
+        self.price_requests.remove(&request.request_id);
+        self.open_price_request_ids.remove(&request.request_id);
+        self.total_price_request_fees -= request.fee;
+        let num_oracles = request.reports.len() as Balance;
+        for (i, report) in request.reports.iter().enumerate() {
+            let mut amount = request.fee / num_oracles;
+            if i == 0 {
+                amount += request.fee % num_oracles;
+            }
+            // The fee of a removed oracle stays with the contract.
+            if let Some(mut oracle) = self.internal_get_oracle(&report.oracle_id) {
+                oracle.rewards += amount;
+                self.total_unclaimed_rewards += amount;
+                self.internal_set_oracle(&report.oracle_id, oracle);
+            }
+        }
+        Event::FulfillPriceRequest {
+            request_id: request.request_id,
+            answer,
+        }
+        .emit();
+        Promise::new(request.consumer_id.clone()).function_call(
+            request.callback.clone(),
+            json!({
+                "request_id": U64(request.request_id),
+                "asset_id": request.asset_id,
+                "price": answer,
+            })
+            .to_string()
+            .into_bytes(),
+            NO_DEPOSIT,
+            GAS_FOR_PRICE_REQUEST_CALLBACK,
+        );
+        if request.storage_deposit > 0 {
+            Promise::new(request.consumer_id.clone()).transfer(request.storage_deposit);
+        }
+    }
+}
+
+/// Callbacks are method names, so the consumer can't be called with arbitrary strings.
+fn is_valid_callback(callback: &str) -> bool {
+    !callback.is_empty()
+        && callback.len() <= MAX_PRICE_REQUEST_CALLBACK_LEN
+        && callback
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
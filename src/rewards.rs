@@ -0,0 +1,62 @@
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Adds the attached deposit to the pool rewards are paid from.
+    #[payable]
+    pub fn fund_rewards(&mut self) {
+        self.assert_owner();
+        let amount = env::attached_deposit();
+        assert!(amount > 0, "Requires a deposit");
+        self.rewards_pool += amount;
+    }
+
+    /// Transfers the accrued rewards to the calling oracle.
+    #[payable]
+    pub fn claim_rewards(&mut self) -> Promise {
+        assert_one_yocto();
+        let oracle_id = env::predecessor_account_id();
+        let mut oracle = self.internal_get_oracle(&oracle_id).expect("Not an oracle");
+        let amount = oracle.rewards;
+        assert!(amount > 0, "Nothing to claim");
+        oracle.rewards = 0;
+        self.total_unclaimed_rewards -= amount;
+        self.internal_set_oracle(&oracle_id, oracle);
+        Promise::new(oracle_id).transfer(amount)
+    }
+}
+
+impl Contract {
+    /// Accrues rewards for the given number of accepted prices, limited by the epoch cap of the
+    /// oracle and by the rewards pool.
+    pub fn internal_accrue_rewards(
+        &mut self,
+        oracle: &mut Oracle,
+        num_prices: u64,
+        timestamp: Timestamp,
+    ) {
+        if self.reward_per_report == 0 || num_prices == 0 {
+            return;
+        }
+        let epoch = if self.reward_epoch_sec > 0 {
+            timestamp / to_nano(self.reward_epoch_sec)
+        } else {
+            0
+        };
+        if oracle.reward_epoch != epoch {
+            oracle.reward_epoch = epoch;
+            oracle.epoch_rewards = 0;
+        }
+        let mut amount = self
+            .reward_per_report
+            .saturating_mul(Balance::from(num_prices));
+        if let Some(max_rewards_per_epoch) = self.max_rewards_per_epoch {
+            amount = amount.min(max_rewards_per_epoch.saturating_sub(oracle.epoch_rewards));
+        }
+        amount = amount.min(self.rewards_pool);
+        oracle.rewards += amount;
+        oracle.epoch_rewards += amount;
+        self.rewards_pool -= amount;
+        self.total_unclaimed_rewards += amount;
+    }
+}
@@ -0,0 +1,137 @@
+use crate::*;
+
+pub type RoundId = u64;
+
+/// An aggregation round of an asset. A round opens with the first report after the previous
+/// round ended, collects reports until `ended_at` and is finalized by the first report or
+/// `finalize_round` call after that. The answer is the weighted median of the round reports,
+/// `None` if they didn't reach the quorum.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Round {
+    pub round_id: RoundId,
+    #[serde(with = "u64_dec_format")]
+    pub started_at: Timestamp,
+    #[serde(with = "u64_dec_format")]
+    pub ended_at: Timestamp,
+    pub finalized: bool,
+    pub answer: Option<Price>,
+    /// The latest report of every oracle within the round.
+    pub reports: Vec<Report>,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the given round of the asset, which may be the open one.
+    pub fn get_round_data(&self, asset_id: AssetId, round_id: RoundId) -> Option<Round> {
+        self.assert_asset_price_access(&asset_id);
+        let asset = self.internal_get_asset(&asset_id)?;
+        match asset.open_round {
+            Some(round) if round.round_id == round_id => Some(round),
+            _ => self.rounds.get(&(asset_id, round_id)),
+        }
+    }
+
+    /// Returns the latest finalized round of the asset.
+    pub fn latest_round(&self, asset_id: AssetId) -> Option<Round> {
+        self.assert_asset_price_access(&asset_id);
+        let asset = self.internal_get_asset(&asset_id)?;
+        self.rounds.get(&(asset_id, asset.latest_round_id))
+    }
+
+    /// Finalizes the open round of the asset after it ended. Can be called by anyone.
+    pub fn finalize_round(&mut self, asset_id: AssetId) -> Round {
+        let mut asset = self
+            .internal_get_asset(&asset_id)
+            .expect("Missing an asset");
+        let round = self
+            .internal_finalize_round(&asset_id, &mut asset, now())
+            .expect("The round is still open");
+        self.internal_set_asset(&asset_id, asset);
+        round
+    }
+}
+
+impl Contract {
+    /// Adds the report to the open round of the asset, finalizing the previous round if it ended.
+    pub fn internal_record_round_report(
+        &mut self,
+        asset_id: &AssetId,
+        asset: &mut Asset,
+        report: Report,
+    ) {
+        if self.round_duration_sec == 0 {
+            return;
+        }
+        self.internal_finalize_round(asset_id, asset, report.timestamp);
+        let round_id = asset.latest_round_id + 1;
+        let ended_at = report.timestamp + to_nano(self.round_duration_sec);
+        let round = asset.open_round.get_or_insert_with(|| Round {
+            round_id,
+            started_at: report.timestamp,
+            ended_at,
+            finalized: false,
+            answer: None,
+            reports: vec![],
+        });
+        round
+            .reports
+            .retain(|round_report| round_report.oracle_id != report.oracle_id);
+        round.reports.push(report);
+    }
+
+    /// Finalizes the open round of the asset if it ended by the given time. Only the last
+    /// `round_history_len` rounds are kept.
+    pub fn internal_finalize_round(
+        &mut self,
+        asset_id: &AssetId,
+        asset: &mut Asset,
+        timestamp: Timestamp,
+    ) -> Option<Round> {
+        if asset.open_round.as_ref()?.ended_at > timestamp {
+            return None;
+        }
+        let mut round = asset.open_round.take().unwrap();
+        round.finalized = true;
+        round.answer = weighted_median(
+            round
+                .reports
+                .iter()
+                .map(|report| {
+                    (
+                        report.price,
+                        self.internal_oracle_weight(asset, &report.oracle_id),
+                    )
+                })
+                .collect(),
+            self.asset_min_num_recent_reports(asset),
+        );
+        asset.latest_round_id = round.round_id;
+        self.rounds
+            .insert(&(asset_id.clone(), round.round_id), &round);
+        self.internal_score_round(&round);
+        if let Some(expired_round_id) = round
+            .round_id
+            .checked_sub(u64::from(self.round_history_len))
+        {
+            self.rounds.remove(&(asset_id.clone(), expired_round_id));
+        }
+        Event::FinalizeRound {
+            asset_id,
+            round_id: round.round_id,
+            answer: round.answer,
+        }
+        .emit();
+        Some(round)
+    }
+
+    /// Removes the kept rounds of the asset.
+    pub fn internal_remove_rounds(&mut self, asset_id: &AssetId, asset: &Asset) {
+        let first_round_id = asset
+            .latest_round_id
+            .saturating_sub(u64::from(self.round_history_len));
+        for round_id in first_round_id..=asset.latest_round_id {
+            self.rounds.remove(&(asset_id.clone(), round_id));
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use crate::*;
+
+const FULL_SCORE_BPS: u32 = 10000;
+
+/// Scores oracles on every finalized round with an answer. An oracle that missed the round
+/// scores `0`, otherwise the score falls linearly from the full score at the answer to `0` at
+/// `max_deviation_bps` from it. Oracles scoring below `min_score_bps` for `max_low_score_rounds`
+/// rounds in a row are suspended until they're reinstated. Requires aggregation rounds.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OracleScoring {
+    pub max_deviation_bps: u32,
+    pub min_score_bps: u32,
+    pub max_low_score_rounds: u32,
+}
+
+impl OracleScoring {
+    pub fn assert_valid(&self) {
+        assert!(
+            self.max_deviation_bps > 0,
+            "The max deviation must be positive"
+        );
+        assert!(
+            self.min_score_bps <= FULL_SCORE_BPS,
+            "The min score can't exceed {}",
+            FULL_SCORE_BPS
+        );
+        assert!(
+            self.max_low_score_rounds > 0,
+            "The number of rounds must be positive"
+        );
+    }
+
+    /// Returns the score of a round report deviating from the answer by the given basis points.
+    pub fn round_score_bps(&self, deviation_bps: u32) -> u32 {
+        let penalty = u64::from(deviation_bps) * u64::from(FULL_SCORE_BPS)
+            / u64::from(self.max_deviation_bps);
+        FULL_SCORE_BPS.saturating_sub(std::cmp::min(penalty, u64::from(u32::MAX)) as u32)
+    }
+}
+
+/// The accuracy and uptime of an oracle in scored rounds.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OraclePerformance {
+    pub num_rounds: u64,
+    pub num_missed_rounds: u64,
+    /// The sum of deviations of the reports from the round answers.
+    pub total_deviation_bps: u64,
+    /// The score of the latest scored round.
+    pub score_bps: u32,
+    /// The number of the latest rounds in a row the oracle scored below the min score.
+    pub num_low_score_rounds: u32,
+    /// Suspended oracles can't report until they're reinstated.
+    pub suspended: bool,
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn get_oracle_performance(&self, account_id: AccountId) -> Option<OraclePerformance> {
+        self.oracle_performance.get(&account_id)
+    }
+}
+
+impl Contract {
+    pub fn internal_is_suspended(&self, account_id: &AccountId) -> bool {
+        self.oracle_performance
+            .get(account_id)
+            .filter(|performance| performance.suspended)
+            .is_some()
+    }
+
+    /// Scores all active oracles on the finalized round.
+    pub fn internal_score_round(&mut self, round: &Round) {
+        let (scoring, answer) = match (self.oracle_scoring, round.answer) {
+            (Some(scoring), Some(answer)) => (scoring, answer),
+            _ => return,
+        };
+        for account_id in self.oracles.keys_as_vector().to_vec() {
+            let mut performance = self.oracle_performance.get(&account_id).unwrap_or_default();
+            if performance.suspended {
+                continue;
+            }
+            match round
+                .reports
+                .iter()
+                .find(|report| report.oracle_id == account_id)
+            {
+                Some(report) => {
+                    let deviation_bps = report.price.deviation_bps(&answer);
+                    performance.num_rounds += 1;
+                    performance.total_deviation_bps = performance
+                        .total_deviation_bps
+                        .saturating_add(u64::from(deviation_bps));
+                    performance.score_bps = scoring.round_score_bps(deviation_bps);
+                }
+                None => {
+                    performance.num_missed_rounds += 1;
+                    performance.score_bps = 0;
+                }
+            }
+            if performance.score_bps < scoring.min_score_bps {
+                performance.num_low_score_rounds += 1;
+            } else {
+                performance.num_low_score_rounds = 0;
+            }
+            if performance.num_low_score_rounds >= scoring.max_low_score_rounds {
+                performance.suspended = true;
+                Event::SuspendOracle {
+                    account_id: &account_id,
+                    score_bps: performance.score_bps,
+                }
+                .emit();
+            }
+            self.oracle_performance.insert(&account_id, &performance);
+        }
+    }
+
+    pub fn internal_reinstate_oracle(&mut self, account_id: &AccountId) {
+        let mut performance = self
+            .oracle_performance
+            .get(account_id)
+            .filter(|performance| performance.suspended)
+            .expect("The oracle is not suspended");
+        performance.suspended = false;
+        performance.num_low_score_rounds = 0;
+        self.oracle_performance.insert(account_id, &performance);
+        Event::ReinstateOracle { account_id }.emit();
+    }
+}
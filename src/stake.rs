@@ -0,0 +1,173 @@
+use crate::*;
+
+/// The stake of a removed oracle. It can still be slashed until it's released.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RemovedOracleStake {
+    #[serde(with = "u128_dec_format")]
+    pub amount: Balance,
+    #[serde(with = "u64_dec_format")]
+    pub unbonding_until: Timestamp,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Adds the attached deposit to the stake of the calling oracle.
+    #[payable]
+    pub fn stake(&mut self) {
+        let oracle_id = env::predecessor_account_id();
+        let mut oracle = self
+            .internal_get_oracle(&oracle_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::NotAnOracle));
+        let amount = env::attached_deposit();
+        assert!(amount > 0, "{}", ContractError::RequiresDeposit(1));
+        oracle.stake += amount;
+        self.total_stake += amount;
+        self.internal_set_oracle(&oracle_id, oracle);
+    }
+
+    /// Starts unbonding the given amount of the stake. The unbonding stake doesn't count towards
+    /// the minimum stake, but can still be slashed. Unstaking more restarts the unbonding period.
+    #[payable]
+    pub fn unstake(&mut self, amount: U128) {
+        assert_one_yocto();
+        let oracle_id = env::predecessor_account_id();
+        let mut oracle = self
+            .internal_get_oracle(&oracle_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::NotAnOracle));
+        let amount: Balance = amount.into();
+        assert!(
+            amount > 0 && amount <= oracle.stake,
+            "{}",
+            ContractError::InvalidAmount
+        );
+        oracle.stake -= amount;
+        oracle.unbonding_stake += amount;
+        oracle.unbonding_until = now() + to_nano(self.unbonding_period_sec);
+        self.internal_set_oracle(&oracle_id, oracle);
+    }
+
+    /// Withdraws the unbonding stake of the calling oracle once the unbonding period ends.
+    #[payable]
+    pub fn withdraw_stake(&mut self) -> Promise {
+        assert_one_yocto();
+        let oracle_id = env::predecessor_account_id();
+        let mut oracle = self
+            .internal_get_oracle(&oracle_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::NotAnOracle));
+        let amount = oracle.unbonding_stake;
+        assert!(amount > 0, "{}", ContractError::NothingToWithdraw);
+        assert!(
+            now() >= oracle.unbonding_until,
+            "{}",
+            ContractError::StakeUnbonding
+        );
+        oracle.unbonding_stake = 0;
+        self.total_stake -= amount;
+        self.internal_set_oracle(&oracle_id, oracle);
+        Promise::new(oracle_id).transfer(amount)
+    }
+
+    /// Withdraws the stake the caller had when it was removed from the oracles, once its
+    /// unbonding period ends.
+    #[payable]
+    pub fn withdraw_removed_stake(&mut self) -> Promise {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let removed_stake = self
+            .removed_oracle_stakes
+            .get(&account_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::NothingToWithdraw));
+        assert!(
+            now() >= removed_stake.unbonding_until,
+            "{}",
+            ContractError::StakeUnbonding
+        );
+        self.removed_oracle_stakes.remove(&account_id);
+        self.total_stake -= removed_stake.amount;
+        Promise::new(account_id).transfer(removed_stake.amount)
+    }
+
+    /// Slashes up to the given amount from the stake of the oracle, starting with its unbonding
+    /// stake and then the stake it had when it was removed. Slashed NEAR stays on the contract
+    /// and funds NEAR claims of the oracles.
+    #[payable]
+    pub fn slash_oracle(&mut self, oracle_id: AccountId, amount: U128, reason: String) {
+        assert_one_yocto();
+        self.assert_role(Role::OracleManager);
+        let oracle = self.internal_get_oracle(&oracle_id);
+        let removed_stake = self.removed_oracle_stakes.get(&oracle_id);
+        assert!(
+            oracle.is_some() || removed_stake.is_some(),
+            "{}",
+            ContractError::NotAnOracle
+        );
+        let mut remaining = Balance::from(amount);
+        if let Some(mut oracle) = oracle {
+            let from_unbonding = remaining.min(oracle.unbonding_stake);
+            oracle.unbonding_stake -= from_unbonding;
+            let from_stake = (remaining - from_unbonding).min(oracle.stake);
+            oracle.stake -= from_stake;
+            remaining -= from_unbonding + from_stake;
+            self.internal_set_oracle(&oracle_id, oracle);
+        }
+        if let Some(mut removed_stake) = removed_stake {
+            let from_removed = remaining.min(removed_stake.amount);
+            removed_stake.amount -= from_removed;
+            remaining -= from_removed;
+            if removed_stake.amount > 0 {
+                self.removed_oracle_stakes
+                    .insert(&oracle_id, &removed_stake);
+            } else {
+                self.removed_oracle_stakes.remove(&oracle_id);
+            }
+        }
+        let amount = Balance::from(amount) - remaining;
+        self.total_stake -= amount;
+        log!("Slashed {} of {}: {}", amount, oracle_id, reason);
+    }
+
+    pub fn get_removed_oracle_stake(&self, account_id: AccountId) -> Option<RemovedOracleStake> {
+        self.removed_oracle_stakes.get(&account_id)
+    }
+}
+
+impl Contract {
+    pub fn has_min_stake(&self, oracle: &Oracle) -> bool {
+        oracle.stake >= self.min_stake
+    }
+
+    /// Removes the oracle and returns its unclaimed rewards. Its stake, including the unbonding
+    /// stake, unbonds for the unbonding period and can be withdrawn with
+    /// `withdraw_removed_stake`.
+    pub fn internal_remove_oracle(&mut self, account_id: &AccountId) -> bool {
+        let oracle: Oracle = match self.oracles.remove(account_id) {
+            Some(oracle) => oracle.into(),
+            None => return false,
+        };
+        Event::RemoveOracle { account_id }.emit();
+        self.oracle_performance.remove(account_id);
+        let stake = oracle.stake + oracle.unbonding_stake;
+        if stake > 0 {
+            let mut removed_stake =
+                self.removed_oracle_stakes
+                    .get(account_id)
+                    .unwrap_or(RemovedOracleStake {
+                        amount: 0,
+                        unbonding_until: 0,
+                    });
+            removed_stake.amount += stake;
+            removed_stake.unbonding_until = std::cmp::max(
+                oracle.unbonding_until,
+                now() + to_nano(self.unbonding_period_sec),
+            );
+            self.removed_oracle_stakes
+                .insert(account_id, &removed_stake);
+        }
+        self.total_unclaimed_rewards -= oracle.rewards;
+        if oracle.rewards > 0 {
+            Promise::new(account_id.clone()).transfer(oracle.rewards);
+        }
+        true
+    }
+}
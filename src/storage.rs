@@ -0,0 +1,190 @@
+use crate::*;
+use near_sdk::StorageUsage;
+
+/// The storage reserved for an account entry, the minimum storage balance covers it.
+const STORAGE_ACCOUNT_BYTES: StorageUsage = 200;
+
+/// Storage paid by an account for the assets it registered and the prices it reported.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct StorageAccount {
+    pub deposit: Balance,
+    pub used_bytes: StorageUsage,
+}
+
+impl StorageAccount {
+    pub fn available(&self) -> Balance {
+        self.deposit
+            .saturating_sub(env::storage_byte_cost() * Balance::from(self.used_bytes))
+    }
+}
+
+/// NEP-145 storage balance.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageBalance {
+    pub total: U128,
+    pub available: U128,
+}
+
+impl From<&StorageAccount> for StorageBalance {
+    fn from(account: &StorageAccount) -> Self {
+        Self {
+            total: account.deposit.into(),
+            available: account.available().into(),
+        }
+    }
+}
+
+/// NEP-145 storage balance bounds.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageBalanceBounds {
+    pub min: U128,
+    pub max: Option<U128>,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Deposits NEAR for storage of the given account, by default of the caller.
+    /// With `registration_only`, the deposit above the minimum balance is refunded.
+    #[payable]
+    pub fn storage_deposit(
+        &mut self,
+        account_id: Option<AccountId>,
+        registration_only: Option<bool>,
+    ) -> StorageBalance {
+        let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
+        let mut deposit = env::attached_deposit();
+        let mut account = match self.storage_accounts.get(&account_id) {
+            Some(account) => {
+                if registration_only.unwrap_or(false) && deposit > 0 {
+                    Promise::new(env::predecessor_account_id()).transfer(deposit);
+                    deposit = 0;
+                }
+                account
+            }
+            None => {
+                let min_balance = self.storage_balance_bounds().min.0;
+                assert!(
+                    deposit >= min_balance,
+                    "{}",
+                    ContractError::RequiresDeposit(min_balance)
+                );
+                if registration_only.unwrap_or(false) && deposit > min_balance {
+                    Promise::new(env::predecessor_account_id()).transfer(deposit - min_balance);
+                    deposit = min_balance;
+                }
+                StorageAccount {
+                    deposit: 0,
+                    used_bytes: STORAGE_ACCOUNT_BYTES,
+                }
+            }
+        };
+        account.deposit += deposit;
+        self.total_storage_deposits += deposit;
+        self.storage_accounts.insert(&account_id, &account);
+        (&account).into()
+    }
+
+    /// Withdraws the storage deposit that isn't used, all of it if the amount is not given.
+    #[payable]
+    pub fn storage_withdraw(&mut self, amount: Option<U128>) -> StorageBalance {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let mut account = self
+            .storage_accounts
+            .get(&account_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::NotRegistered(account_id.as_str())));
+        let available = account.available();
+        let amount = amount.map_or(available, |amount| amount.into());
+        assert!(
+            amount <= available,
+            "{}",
+            ContractError::NotEnoughStorageBalance
+        );
+        if amount > 0 {
+            account.deposit -= amount;
+            self.total_storage_deposits -= amount;
+            self.storage_accounts.insert(&account_id, &account);
+            Promise::new(account_id).transfer(amount);
+        }
+        (&account).into()
+    }
+
+    /// Removes the account and refunds its deposit. The account must not use any storage.
+    #[payable]
+    pub fn storage_unregister(&mut self, force: Option<bool>) -> bool {
+        assert_one_yocto();
+        assert!(
+            !force.unwrap_or(false),
+            "{}",
+            ContractError::ForceUnregisterNotSupported
+        );
+        let account_id = env::predecessor_account_id();
+        let account = match self.storage_accounts.get(&account_id) {
+            Some(account) => account,
+            None => return false,
+        };
+        assert_eq!(
+            account.used_bytes,
+            STORAGE_ACCOUNT_BYTES,
+            "{}",
+            ContractError::StorageInUse
+        );
+        self.storage_accounts.remove(&account_id);
+        self.total_storage_deposits -= account.deposit;
+        if account.deposit > 0 {
+            Promise::new(account_id).transfer(account.deposit);
+        }
+        true
+    }
+
+    pub fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
+        self.storage_accounts
+            .get(&account_id)
+            .map(|account| (&account).into())
+    }
+
+    pub fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+        StorageBalanceBounds {
+            min: (env::storage_byte_cost() * Balance::from(STORAGE_ACCOUNT_BYTES)).into(),
+            max: None,
+        }
+    }
+}
+
+impl Contract {
+    /// Charges the account for the storage used since `initial_storage_usage`, or credits it
+    /// if the storage was released. Does nothing unless storage deposits are required.
+    pub fn internal_update_storage(
+        &mut self,
+        account_id: &AccountId,
+        initial_storage_usage: StorageUsage,
+    ) {
+        if !self.storage_deposit_required {
+            return;
+        }
+        let storage_usage = env::storage_usage();
+        if storage_usage > initial_storage_usage {
+            let mut account = self
+                .storage_accounts
+                .get(account_id)
+                .unwrap_or_else(|| panic!("{}", ContractError::NotRegistered(account_id.as_str())));
+            account.used_bytes += storage_usage - initial_storage_usage;
+            assert!(
+                env::storage_byte_cost() * Balance::from(account.used_bytes) <= account.deposit,
+                "{}",
+                ContractError::NotEnoughStorageDeposit(account_id.as_str())
+            );
+            self.storage_accounts.insert(account_id, &account);
+        } else if let Some(mut account) = self.storage_accounts.get(account_id) {
+            account.used_bytes = std::cmp::max(
+                account
+                    .used_bytes
+                    .saturating_sub(initial_storage_usage - storage_usage),
+                STORAGE_ACCOUNT_BYTES,
+            );
+            self.storage_accounts.insert(account_id, &account);
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use crate::*;
+
+/// The gas attached to every push of prices to a subscriber.
+const GAS_FOR_PUSH: Gas = Gas(Gas::ONE_TERA.0 * 20);
+
+/// The subscriptions of an asset are walked on every push, so their number is bounded.
+pub const MAX_SUBSCRIPTIONS_PER_ASSET: usize = 50;
+
+/// A consumer contract that receives `oracle_on_call` whenever the aggregated price of the asset
+/// moved more than `min_change_bps` since the last push, or `max_interval_sec` after the last
+/// push. Pushes are sent by `push_prices`, and every push is paid from the balance.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Subscription {
+    pub receiver_id: AccountId,
+    pub asset_id: AssetId,
+    pub min_change_bps: u32,
+    pub max_interval_sec: DurationSec,
+    pub msg: String,
+    #[serde(with = "u128_dec_format")]
+    pub balance: Balance,
+    /// The part of the deposit covering the storage of the subscription, refunded with the
+    /// balance.
+    #[serde(with = "u128_dec_format")]
+    pub storage_deposit: Balance,
+    pub last_price: Option<Price>,
+    #[serde(with = "u64_dec_format")]
+    pub last_push: Timestamp,
+}
+
+impl Subscription {
+    pub fn is_due(&self, price: &Price, timestamp: Timestamp) -> bool {
+        match self.last_price.as_ref() {
+            None => true,
+            Some(last_price) => {
+                price.deviates_from(last_price, self.min_change_bps)
+                    || timestamp >= self.last_push + to_nano(self.max_interval_sec)
+            }
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Subscribes the caller to price pushes of the asset. Requires a push fee to be set. The
+    /// attached deposit pays for the storage of the subscription and funds the pushes, it must
+    /// cover at least one of them.
+    #[payable]
+    pub fn subscribe(
+        &mut self,
+        asset_id: AssetId,
+        min_change_bps: u32,
+        max_interval_sec: DurationSec,
+        msg: String,
+    ) -> u64 {
+        let initial_storage_usage = env::storage_usage();
+        let receiver_id = env::predecessor_account_id();
+        let deposit = env::attached_deposit();
+        assert!(self.push_fee > 0, "{}", ContractError::PushFeeRequired);
+        assert!(
+            self.internal_get_asset(&asset_id).is_some(),
+            "{}",
+            ContractError::MissingAsset
+        );
+        let mut subscription_ids = self.asset_subscriptions.get(&asset_id).unwrap_or_default();
+        assert!(
+            subscription_ids.len() < MAX_SUBSCRIPTIONS_PER_ASSET,
+            "{}",
+            ContractError::TooManySubscriptions(MAX_SUBSCRIPTIONS_PER_ASSET)
+        );
+        let subscription_id = self.next_subscription_id;
+        self.next_subscription_id += 1;
+        let mut subscription = Subscription {
+            receiver_id: receiver_id.clone(),
+            asset_id: asset_id.clone(),
+            min_change_bps,
+            max_interval_sec,
+            msg,
+            balance: 0,
+            storage_deposit: 0,
+            last_price: None,
+            last_push: 0,
+        };
+        self.subscriptions.insert(&subscription_id, &subscription);
+        subscription_ids.push(subscription_id);
+        self.asset_subscriptions
+            .insert(&asset_id, &subscription_ids);
+        let storage_deposit = env::storage_byte_cost()
+            * Balance::from(env::storage_usage().saturating_sub(initial_storage_usage));
+        let min_deposit = storage_deposit + self.push_fee;
+        assert!(
+            deposit >= min_deposit,
+            "{}",
+            ContractError::RequiresDeposit(min_deposit)
+        );
+        subscription.balance = deposit - storage_deposit;
+        subscription.storage_deposit = storage_deposit;
+        self.subscriptions.insert(&subscription_id, &subscription);
+        self.total_subscription_balances += subscription.balance;
+        Event::Subscribe {
+            subscription_id,
+            receiver_id: &receiver_id,
+            asset_id: &asset_id,
+        }
+        .emit();
+        subscription_id
+    }
+
+    /// Adds the attached deposit to the balance of the subscription. Can be called by anyone.
+    #[payable]
+    pub fn top_up_subscription(&mut self, subscription_id: u64) {
+        let mut subscription = self
+            .subscriptions
+            .get(&subscription_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingSubscription));
+        let deposit = env::attached_deposit();
+        assert!(deposit > 0, "{}", ContractError::RequiresDeposit(1));
+        subscription.balance += deposit;
+        self.total_subscription_balances += deposit;
+        self.subscriptions.insert(&subscription_id, &subscription);
+    }
+
+    /// Removes the subscription of the caller and refunds its remaining balance and storage
+    /// deposit.
+    pub fn unsubscribe(&mut self, subscription_id: u64) {
+        let subscription = self
+            .subscriptions
+            .get(&subscription_id)
+            .unwrap_or_else(|| panic!("{}", ContractError::MissingSubscription));
+        assert_eq!(
+            subscription.receiver_id,
+            env::predecessor_account_id(),
+            "{}",
+            ContractError::NotSubscriber
+        );
+        let mut subscription_ids = self
+            .asset_subscriptions
+            .get(&subscription.asset_id)
+            .unwrap_or_default();
+        subscription_ids.retain(|id| *id != subscription_id);
+        if subscription_ids.is_empty() {
+            self.asset_subscriptions.remove(&subscription.asset_id);
+        } else {
+            self.asset_subscriptions
+                .insert(&subscription.asset_id, &subscription_ids);
+        }
+        self.internal_remove_subscription(subscription_id, subscription);
+    }
+
+    /// Pushes the current price of the asset to subscribers that are due and can pay for the
+    /// push. Can be called by anyone, the caller pays for the gas. Subscribers that don't fit into
+    /// the prepaid gas are skipped until the next call. Returns the number of pushes.
+    pub fn push_prices(&mut self, asset_id: AssetId) -> u32 {
+        self.assert_not_paused();
+        let timestamp = now();
+        let price = match self.internal_get_price(&asset_id, timestamp, None) {
+            Some(price) => price,
+            None => return 0,
+        };
+        self.internal_push_price(&asset_id, price, timestamp)
+    }
+
+    pub fn get_subscription(&self, subscription_id: u64) -> Option<Subscription> {
+        self.subscriptions.get(&subscription_id)
+    }
+
+    pub fn get_subscriptions(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<(u64, Subscription)> {
+        unordered_map_pagination(&self.subscriptions, from_index, limit)
+    }
+}
+
+impl Contract {
+    /// Removes the subscriptions of the removed asset and refunds their balances.
+    pub fn internal_remove_asset_subscriptions(&mut self, asset_id: &AssetId) {
+        for subscription_id in self
+            .asset_subscriptions
+            .remove(asset_id)
+            .unwrap_or_default()
+        {
+            if let Some(subscription) = self.subscriptions.get(&subscription_id) {
+                self.internal_remove_subscription(subscription_id, subscription);
+            }
+        }
+    }
+
+    fn internal_remove_subscription(&mut self, subscription_id: u64, subscription: Subscription) {
+        self.subscriptions.remove(&subscription_id);
+        self.total_subscription_balances -= subscription.balance;
+        Event::Unsubscribe { subscription_id }.emit();
+        let refund = subscription.balance + subscription.storage_deposit;
+        if refund > 0 {
+            Promise::new(subscription.receiver_id).transfer(refund);
+        }
+    }
+
+    fn internal_push_price(
+        &mut self,
+        asset_id: &AssetId,
+        price: Price,
+        timestamp: Timestamp,
+    ) -> u32 {
+        let subscription_ids = match self.asset_subscriptions.get(asset_id) {
+            Some(subscription_ids) => subscription_ids,
+            None => return 0,
+        };
+        let mut num_pushes = 0;
+        let price_data = self.internal_get_price_data(vec![asset_id.clone()], None, None);
+        for subscription_id in subscription_ids {
+            let mut subscription = self.subscriptions.get(&subscription_id).unwrap();
+            if !subscription.is_due(&price, timestamp)
+                || subscription.balance < self.push_fee
+                || !self.internal_has_price_access(asset_id, &subscription.receiver_id)
+            {
+                continue;
+            }
+            if env::prepaid_gas().0 < env::used_gas().0 + GAS_FOR_PUSH.0 + GAS_FOR_PROMISE.0 {
+                log!("Warning! Not enough gas to push prices of {}", asset_id);
+                break;
+            }
+            subscription.balance -= self.push_fee;
+            self.total_subscription_balances -= self.push_fee;
+            self.collected_fees += self.push_fee;
+            subscription.last_price = Some(price);
+            subscription.last_push = timestamp;
+            ext_price_receiver::oracle_on_call(
+                env::current_account_id(),
+                price_data.clone(),
+                subscription.msg.clone(),
+                subscription.receiver_id.clone(),
+                NO_DEPOSIT,
+                GAS_FOR_PUSH,
+            );
+            self.subscriptions.insert(&subscription_id, &subscription);
+            num_pushes += 1;
+        }
+        num_pushes
+    }
+}
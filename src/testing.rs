@@ -0,0 +1,65 @@
+use crate::*;
+
+/// The storage key of the pinned time. It's outside of the contract state, so builds with and
+/// without the `testing` feature share the state layout.
+const TESTING_NOW_KEY: &[u8] = b"testing_now";
+
+/// Returns the time pinned with `set_testing_now`, if any.
+pub(crate) fn testing_now() -> Option<Timestamp> {
+    env::storage_read(TESTING_NOW_KEY)
+        .map(|bytes| Timestamp::try_from_slice(&bytes).expect("Failed to read the pinned time"))
+}
+
+impl AssetPrice {
+    pub fn new(asset_id: AssetId, price: Price) -> Self {
+        Self {
+            asset_id,
+            price,
+            quote_asset_id: None,
+            nonce: None,
+        }
+    }
+}
+
+impl Report {
+    pub fn new(oracle_id: AccountId, timestamp: Timestamp, price: Price) -> Self {
+        Self {
+            oracle_id,
+            timestamp,
+            price,
+            source: PriceSource::Report,
+            block_height: env::block_height(),
+        }
+    }
+}
+
+/// Deterministic time control for integration tests. Only built with the `testing` feature, which
+/// must never be enabled for deployed contracts.
+#[near_bindgen]
+impl Contract {
+    /// Pins the time the contract sees, e.g. for recency, EMAs and circuit breakers. `None` goes
+    /// back to block timestamps.
+    pub fn set_testing_now(&mut self, timestamp: Option<U64>) {
+        self.assert_owner();
+        match timestamp {
+            Some(U64(timestamp)) => {
+                env::storage_write(TESTING_NOW_KEY, &timestamp.try_to_vec().unwrap());
+            }
+            None => {
+                env::storage_remove(TESTING_NOW_KEY);
+            }
+        }
+    }
+
+    /// Moves the time the contract sees forward and pins it.
+    pub fn advance_testing_now(&mut self, duration_sec: DurationSec) -> U64 {
+        self.assert_owner();
+        let timestamp = now() + to_nano(duration_sec);
+        env::storage_write(TESTING_NOW_KEY, &timestamp.try_to_vec().unwrap());
+        timestamp.into()
+    }
+
+    pub fn get_testing_now(&self) -> U64 {
+        now().into()
+    }
+}
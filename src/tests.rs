@@ -0,0 +1,4687 @@
+use crate::*;
+use ed25519_dalek::Signer;
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::test_utils::{get_created_receipts, get_logs, VMContextBuilder};
+use near_sdk::{
+    testing_env, CryptoHash, CurveType, PromiseOrValue, PromiseResult, RuntimeFeesConfig, VMConfig,
+};
+use std::convert::TryInto;
+
+const ORACLE_ID: &str = "oracle.near";
+const OWNER_ID: &str = "owner.near";
+
+const WRAP_NEAR: &str = "wrap.near";
+const USDC: &str = "usdc.near";
+const DAI: &str = "dai.near";
+
+fn a(account_id: &str) -> AccountId {
+    AccountId::new_unchecked(account_id.to_string())
+}
+
+fn ts(sec: u32) -> Timestamp {
+    to_nano(1_600_000_000 + sec)
+}
+
+fn user(index: usize) -> AccountId {
+    a(&format!("user_{}.near", index))
+}
+
+fn price(multiplier: Balance) -> Price {
+    Price {
+        multiplier,
+        decimals: 28,
+    }
+}
+
+fn wrap_near_prices(multiplier: Balance) -> Vec<AssetPrice> {
+    vec![AssetPrice {
+        asset_id: WRAP_NEAR.to_string(),
+        price: price(multiplier),
+        quote_asset_id: None,
+        nonce: None,
+    }]
+}
+
+fn keypair(seed: u8) -> ed25519_dalek::Keypair {
+    let secret = ed25519_dalek::SecretKey::from_bytes(&[seed; 32]).unwrap();
+    let public = (&secret).into();
+    ed25519_dalek::Keypair { secret, public }
+}
+
+fn near_public_key(keypair: &ed25519_dalek::Keypair) -> PublicKey {
+    let mut data = vec![CurveType::ED25519 as u8];
+    data.extend(keypair.public.as_bytes());
+    data.try_into().unwrap()
+}
+
+fn delegated_report(
+    keypair: &ed25519_dalek::Keypair,
+    oracle_id: AccountId,
+    timestamp: Timestamp,
+    prices: Vec<AssetPrice>,
+) -> DelegatedReport {
+    let message = (a(ORACLE_ID), &oracle_id, timestamp, &prices)
+        .try_to_vec()
+        .unwrap();
+    DelegatedReport {
+        oracle_id,
+        timestamp,
+        prices,
+        signature: Base64VecU8(keypair.sign(&message).to_bytes().to_vec()),
+    }
+}
+
+struct Env {
+    contract: Contract,
+    timestamp: Timestamp,
+}
+
+impl Env {
+    fn setup() -> Self {
+        let timestamp = ts(0);
+        set_context(a(OWNER_ID), timestamp, 0);
+        let contract = Contract::new(90, a(OWNER_ID), U128(5 * ONE_NEAR));
+        Self {
+            contract,
+            timestamp,
+        }
+    }
+
+    fn skip_time(&mut self, seconds: u32) {
+        self.timestamp += to_nano(seconds);
+        set_context(a(OWNER_ID), self.timestamp, 0);
+    }
+
+    fn as_owner(&mut self) -> &mut Contract {
+        set_context(a(OWNER_ID), self.timestamp, 1);
+        &mut self.contract
+    }
+
+    fn as_user(&mut self, account_id: AccountId) -> &mut Contract {
+        set_context(account_id, self.timestamp, 0);
+        &mut self.contract
+    }
+
+    fn add_oracle(&mut self, account_id: AccountId) {
+        self.as_owner().add_oracle(account_id);
+    }
+
+    fn add_asset(&mut self, asset_id: &str) {
+        self.as_owner().add_asset(asset_id.to_string());
+    }
+
+    fn make_reports(&mut self, multipliers: &[u128]) {
+        for (i, &multiplier) in multipliers.iter().enumerate() {
+            if multiplier > 0 {
+                self.as_user(user(i)).report_prices(
+                    vec![AssetPrice {
+                        asset_id: WRAP_NEAR.to_string(),
+                        price: price(multiplier),
+                        quote_asset_id: None,
+                        nonce: None,
+                    }],
+                    None,
+                );
+            }
+        }
+    }
+}
+
+fn set_context(predecessor_account_id: AccountId, timestamp: Timestamp, deposit: Balance) {
+    testing_env!(VMContextBuilder::new()
+        .current_account_id(a(ORACLE_ID))
+        .predecessor_account_id(predecessor_account_id)
+        .block_timestamp(timestamp)
+        .account_balance(1000 * ONE_NEAR)
+        .attached_deposit(deposit)
+        .build());
+}
+
+#[test]
+fn test_oracle_leaderboard() {
+    let mut e = Env::setup();
+    for i in 0..3 {
+        e.add_oracle(user(i));
+    }
+    e.add_asset(WRAP_NEAR);
+
+    e.make_reports(&[100000, 110000, 106000]);
+    e.make_reports(&[0, 110000, 106000]);
+    e.make_reports(&[0, 110000]);
+
+    let leaderboard = e.contract.get_oracle_leaderboard(None, None);
+    let counts: Vec<_> = leaderboard
+        .iter()
+        .map(|(account_id, oracle)| (account_id.clone(), oracle.price_reports))
+        .collect();
+    assert_eq!(counts, vec![(user(1), 3), (user(2), 2), (user(0), 1)]);
+
+    let page = e.contract.get_oracle_leaderboard(Some(1), Some(1));
+    assert_eq!(page.len(), 1);
+    assert_eq!(page[0].0, user(2));
+
+    e.as_owner().reset_oracle_report_counters();
+    assert!(e
+        .contract
+        .get_oracle_leaderboard(None, None)
+        .iter()
+        .all(|(_, oracle)| oracle.price_reports == 0));
+}
+
+#[test]
+fn test_recency_boundary() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.make_reports(&[100000]);
+
+    let wrap_near_price = |e: &Env| {
+        e.contract
+            .get_price_data(Some(vec![WRAP_NEAR.to_string()]), None, None, None)
+            .prices[0]
+            .price
+    };
+
+    // The report is still recent exactly at the end of the recency window.
+    set_now_override(Some(ts(90)));
+    assert_eq!(wrap_near_price(&e), Some(price(100000)));
+
+    set_now_override(Some(ts(90) + 1));
+    assert!(wrap_near_price(&e).is_none());
+
+    set_now_override(None);
+    assert_eq!(wrap_near_price(&e), Some(price(100000)));
+}
+
+#[test]
+fn test_staleness_deadline() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    assert!(e
+        .contract
+        .get_staleness_deadline(WRAP_NEAR.to_string())
+        .is_none());
+
+    e.skip_time(10);
+    e.make_reports(&[100000]);
+    assert_eq!(
+        e.contract.get_staleness_deadline(WRAP_NEAR.to_string()),
+        Some(U64(ts(10 + 90)))
+    );
+
+    // With 3 oracles, the median needs 2 recent reports, so the second newest one decides.
+    e.add_oracle(user(1));
+    e.add_oracle(user(2));
+    e.skip_time(30);
+    e.make_reports(&[0, 110000]);
+    e.skip_time(30);
+    e.make_reports(&[0, 0, 106000]);
+    assert_eq!(
+        e.contract.get_staleness_deadline(WRAP_NEAR.to_string()),
+        Some(U64(ts(40 + 90)))
+    );
+
+    e.skip_time(61);
+    assert!(e
+        .contract
+        .get_staleness_deadline(WRAP_NEAR.to_string())
+        .is_none());
+}
+
+#[test]
+fn test_report_prices_delegated() {
+    let mut e = Env::setup();
+    for i in 0..3 {
+        e.add_oracle(user(i));
+    }
+    e.add_asset(WRAP_NEAR);
+    let relayer = a("relayer.near");
+    e.as_owner().add_relayer(relayer.clone());
+
+    let keys: Vec<_> = (0..3).map(|i| keypair(i as u8 + 1)).collect();
+    for (i, key) in keys.iter().enumerate() {
+        e.as_user(user(i))
+            .set_oracle_public_key(Some(near_public_key(key)));
+    }
+
+    e.as_user(relayer.clone()).report_prices_delegated(
+        vec![
+            delegated_report(&keys[0], user(0), ts(0), wrap_near_prices(100000)),
+            // Signed with the key of another oracle.
+            delegated_report(&keys[2], user(1), ts(0), wrap_near_prices(110000)),
+            delegated_report(&keys[2], user(2), ts(0), wrap_near_prices(106000)),
+        ],
+        None,
+    );
+
+    let asset = e.contract.get_asset(WRAP_NEAR.to_string()).unwrap();
+    let mut reporters: Vec<_> = asset.reports.iter().map(|r| r.oracle_id.clone()).collect();
+    reporters.sort();
+    assert_eq!(reporters, vec![user(0), user(2)]);
+    assert_eq!(e.contract.get_oracle(user(1)).unwrap().price_reports, 0);
+}
+
+#[test]
+fn test_price_sources() {
+    let mut e = Env::setup();
+    for i in 0..2 {
+        e.add_oracle(user(i));
+    }
+    e.add_asset(WRAP_NEAR);
+    let relayer = a("relayer.near");
+    e.as_owner().add_relayer(relayer.clone());
+    let key = keypair(1);
+    e.as_user(user(0))
+        .set_oracle_public_key(Some(near_public_key(&key)));
+
+    e.as_user(relayer).report_prices_delegated(
+        vec![delegated_report(
+            &key,
+            user(0),
+            ts(0),
+            wrap_near_prices(100000),
+        )],
+        None,
+    );
+    e.make_reports(&[0, 110000]);
+
+    let details = e
+        .contract
+        .get_price_detailed(WRAP_NEAR.to_string())
+        .unwrap();
+    let source_of = |oracle_id: AccountId| {
+        details
+            .reports
+            .iter()
+            .find(|report| report.oracle_id == oracle_id)
+            .unwrap()
+            .source
+    };
+    assert_eq!(source_of(user(0)), PriceSource::Delegated);
+    assert_eq!(source_of(user(1)), PriceSource::Report);
+    assert_eq!(
+        details.sources,
+        vec![PriceSource::Delegated, PriceSource::Report]
+    );
+}
+
+#[test]
+#[should_panic(expected = "Invalid report of user_1.near: Invalid signature")]
+fn test_report_prices_delegated_fail_on_invalid() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_oracle(user(1));
+    e.add_asset(WRAP_NEAR);
+    let relayer = a("relayer.near");
+    e.as_owner().add_relayer(relayer.clone());
+
+    let keys: Vec<_> = (0..2).map(|i| keypair(i as u8 + 1)).collect();
+    for (i, key) in keys.iter().enumerate() {
+        e.as_user(user(i))
+            .set_oracle_public_key(Some(near_public_key(key)));
+    }
+
+    e.as_user(relayer).report_prices_delegated(
+        vec![
+            delegated_report(&keys[0], user(0), ts(0), wrap_near_prices(100000)),
+            delegated_report(&keys[0], user(1), ts(0), wrap_near_prices(110000)),
+        ],
+        Some(true),
+    );
+}
+
+#[test]
+fn test_asset_groups() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    for asset_id in [WRAP_NEAR, USDC, DAI] {
+        e.add_asset(asset_id);
+    }
+    e.as_owner()
+        .set_asset_group(USDC.to_string(), Some("stablecoins".to_string()));
+    e.as_owner()
+        .set_asset_group(DAI.to_string(), Some("stablecoins".to_string()));
+    assert_eq!(
+        e.contract.get_asset_group("stablecoins".to_string()),
+        vec![USDC.to_string(), DAI.to_string()]
+    );
+
+    let report_all = |e: &mut Env| {
+        e.as_user(user(0)).report_prices(
+            [WRAP_NEAR, USDC, DAI]
+                .iter()
+                .map(|asset_id| AssetPrice {
+                    asset_id: asset_id.to_string(),
+                    price: price(100000),
+                    quote_asset_id: None,
+                    nonce: None,
+                })
+                .collect(),
+            None,
+        );
+    };
+    let available_prices = |e: &Env| -> Vec<bool> {
+        e.contract
+            .get_price_data(None, None, None, None)
+            .prices
+            .iter()
+            .map(|p| p.price.is_some())
+            .collect()
+    };
+    report_all(&mut e);
+    assert_eq!(available_prices(&e), vec![true, true, true]);
+
+    e.as_owner().pause_group("stablecoins".to_string());
+    assert_eq!(available_prices(&e), vec![true, false, false]);
+
+    e.as_owner().unpause_group("stablecoins".to_string());
+    e.as_owner()
+        .set_group_recency_duration_sec("stablecoins".to_string(), Some(600));
+    e.skip_time(120);
+    assert_eq!(available_prices(&e), vec![false, true, true]);
+
+    e.as_owner().set_asset_group(DAI.to_string(), None);
+    assert_eq!(
+        e.contract.get_asset_group("stablecoins".to_string()),
+        vec![USDC.to_string()]
+    );
+}
+
+#[test]
+fn test_report_prices_delegated_replay() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    let relayer = a("relayer.near");
+    e.as_owner().add_relayer(relayer.clone());
+    let key = keypair(1);
+    e.as_user(user(0))
+        .set_oracle_public_key(Some(near_public_key(&key)));
+
+    let report = || delegated_report(&key, user(0), ts(0), wrap_near_prices(100000));
+    e.as_user(relayer.clone())
+        .report_prices_delegated(vec![report()], None);
+    assert_eq!(e.contract.get_oracle(user(0)).unwrap().price_reports, 1);
+
+    // Replaying the same signed payload later is ignored.
+    e.skip_time(10);
+    e.as_user(relayer.clone())
+        .report_prices_delegated(vec![report()], None);
+    let oracle = e.contract.get_oracle(user(0)).unwrap();
+    assert_eq!(oracle.price_reports, 1);
+    assert_eq!(oracle.last_report, ts(0));
+
+    // A payload signed a long time ago is rejected even if it wasn't submitted before.
+    e.skip_time(100);
+    e.as_user(relayer.clone()).report_prices_delegated(
+        vec![delegated_report(
+            &key,
+            user(0),
+            ts(5),
+            wrap_near_prices(110000),
+        )],
+        None,
+    );
+    assert_eq!(e.contract.get_oracle(user(0)).unwrap().price_reports, 1);
+
+    e.as_user(relayer).report_prices_delegated(
+        vec![delegated_report(
+            &key,
+            user(0),
+            ts(110),
+            wrap_near_prices(110000),
+        )],
+        None,
+    );
+    assert_eq!(e.contract.get_oracle(user(0)).unwrap().price_reports, 2);
+}
+
+#[test]
+fn test_index_price() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.add_asset(USDC);
+    for (asset_id, decimals) in [(WRAP_NEAR, 24), (USDC, 6)] {
+        e.as_owner().set_asset_metadata(
+            asset_id.to_string(),
+            Some(AssetMetadata {
+                decimals,
+                symbol: asset_id.to_uppercase(),
+                quote_asset_id: "usd".to_string(),
+                description: None,
+            }),
+        );
+    }
+    e.as_owner().define_index(
+        "basket".to_string(),
+        vec![
+            IndexComponent {
+                asset_id: WRAP_NEAR.to_string(),
+                weight: 5000,
+            },
+            IndexComponent {
+                asset_id: USDC.to_string(),
+                weight: 5000,
+            },
+        ],
+    );
+
+    let basket_price = |e: &Env| {
+        e.contract
+            .get_price_data(Some(vec!["basket".to_string()]), None, None, None)
+            .prices[0]
+            .price
+    };
+    e.make_reports(&[100000]);
+    // USDC hasn't been reported yet.
+    assert!(basket_price(&e).is_none());
+
+    e.as_user(user(0)).report_prices(
+        vec![AssetPrice {
+            asset_id: USDC.to_string(),
+            price: Price {
+                multiplier: 10000,
+                decimals: 10,
+            },
+            quote_asset_id: None,
+            nonce: None,
+        }],
+        None,
+    );
+    // 0.5 * 10 for 1 NEAR + 0.5 * 1 for 1 USDC.
+    assert_eq!(
+        basket_price(&e),
+        Some(Price {
+            multiplier: 550000000,
+            decimals: 8
+        })
+    );
+
+    e.skip_time(91);
+    assert!(basket_price(&e).is_none());
+}
+
+#[test]
+#[should_panic(expected = "E089_ASSET_USED_BY_INDEX")]
+fn test_remove_index_component() {
+    let mut e = Env::setup();
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_asset_metadata(
+        WRAP_NEAR.to_string(),
+        Some(AssetMetadata {
+            decimals: 24,
+            symbol: "wNEAR".to_string(),
+            quote_asset_id: "usd".to_string(),
+            description: None,
+        }),
+    );
+    e.as_owner().define_index(
+        "near-index".to_string(),
+        vec![IndexComponent {
+            asset_id: format!("{}#ema3600", WRAP_NEAR),
+            weight: 10000,
+        }],
+    );
+    e.as_owner().remove_asset(WRAP_NEAR.to_string());
+}
+
+#[test]
+fn test_price_detailed() {
+    let mut e = Env::setup();
+    for i in 0..3 {
+        e.add_oracle(user(i));
+    }
+    e.add_asset(WRAP_NEAR);
+    e.make_reports(&[100000, 110000]);
+    e.skip_time(60);
+    e.make_reports(&[0, 0, 106000]);
+
+    let details = e
+        .contract
+        .get_price_detailed(WRAP_NEAR.to_string())
+        .unwrap();
+    assert_eq!(details.mode, AggregationMode::Median);
+    assert_eq!(details.price, Some(price(106000)));
+    assert_eq!(details.reports.len(), 3);
+    assert!(details
+        .reports
+        .iter()
+        .all(|report| report.weight == FULL_WEIGHT));
+    let mut prices: Vec<_> = details.reports.iter().map(|report| report.price).collect();
+    prices.sort();
+    assert_eq!(Some(prices[prices.len() / 2]), details.price);
+
+    // The first two reports are no longer recent.
+    e.skip_time(60);
+    let details = e
+        .contract
+        .get_price_detailed(WRAP_NEAR.to_string())
+        .unwrap();
+    assert!(details.price.is_none());
+    assert_eq!(details.reports.len(), 1);
+    assert_eq!(details.reports[0].oracle_id, user(2));
+}
+
+#[test]
+fn test_weight_decay() {
+    let mut e = Env::setup();
+    for i in 0..3 {
+        e.add_oracle(user(i));
+    }
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_weight_decay(Some(WeightDecay::Linear {
+        grace_period_sec: 0,
+        duration_sec: 90,
+    }));
+
+    e.make_reports(&[100000, 101000]);
+    e.skip_time(40);
+    e.make_reports(&[0, 0, 120000]);
+
+    let weights = |e: &Env| -> Vec<u32> {
+        e.contract
+            .get_price_detailed(WRAP_NEAR.to_string())
+            .unwrap()
+            .reports
+            .iter()
+            .map(|report| report.weight)
+            .collect()
+    };
+    let wrap_near_price = |e: &Env| {
+        e.contract
+            .get_price_data(Some(vec![WRAP_NEAR.to_string()]), None, None, None)
+            .prices[0]
+            .price
+    };
+
+    e.skip_time(5);
+    assert_eq!(weights(&e), vec![5000, 5000, 9444]);
+    assert_eq!(wrap_near_price(&e), Some(price(101000)));
+
+    // Silent oracles lose their influence and the fresh report takes over the median.
+    e.skip_time(15);
+    assert_eq!(weights(&e), vec![3333, 3333, 7777]);
+    assert_eq!(wrap_near_price(&e), Some(price(120000)));
+
+    e.as_owner().set_weight_decay(None);
+    assert_eq!(wrap_near_price(&e), Some(price(101000)));
+}
+
+#[test]
+fn test_price_data_per_asset_age() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.add_asset(USDC);
+    e.add_asset(DAI);
+    e.as_user(user(0)).report_prices(
+        [WRAP_NEAR, USDC]
+            .iter()
+            .map(|asset_id| AssetPrice {
+                asset_id: asset_id.to_string(),
+                price: price(100000),
+                quote_asset_id: None,
+                nonce: None,
+            })
+            .collect(),
+        None,
+    );
+    e.skip_time(30);
+
+    let result = e.contract.get_price_data_per_asset_age(vec![
+        (WRAP_NEAR.to_string(), 10),
+        (USDC.to_string(), 60),
+        (DAI.to_string(), 60),
+        ("unknown.near".to_string(), 60),
+    ]);
+    let statuses: Vec<_> = result.iter().map(|p| p.status).collect();
+    assert_eq!(
+        statuses,
+        vec![
+            PriceStatus::Stale,
+            PriceStatus::Fresh,
+            PriceStatus::Stale,
+            PriceStatus::Unknown
+        ]
+    );
+    assert!(result[0].price.is_none());
+    assert_eq!(result[1].price, Some(price(100000)));
+
+    // The max age can't extend the recency duration of the contract.
+    e.skip_time(90);
+    let result = e
+        .contract
+        .get_price_data_per_asset_age(vec![(USDC.to_string(), 3600)]);
+    assert_eq!(result[0].status, PriceStatus::Stale);
+}
+
+#[test]
+#[should_panic(expected = "Reached the maximum number of assets: 2")]
+fn test_max_assets() {
+    let mut e = Env::setup();
+    e.as_owner().set_max_assets(2);
+    e.add_asset(WRAP_NEAR);
+    e.add_asset(USDC);
+    let config = e.contract.get_config();
+    assert_eq!(config.num_assets, 2);
+    assert_eq!(config.max_assets, 2);
+
+    e.add_asset(DAI);
+}
+
+#[test]
+fn test_oracle_set_stabilization() {
+    let mut e = Env::setup();
+    for i in 0..3 {
+        e.add_oracle(user(i));
+    }
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_oracle_set_stabilization_sec(60);
+    e.make_reports(&[100000, 110000, 106000]);
+
+    e.skip_time(10);
+    e.as_owner()
+        .replace_oracle_set(vec![user(3), user(4), user(5)]);
+    assert!(e.contract.get_oracle(user(0)).is_none());
+    e.make_reports(&[0, 0, 0, 200000, 210000, 205000]);
+
+    // The last price of the previous oracle set is served during the stabilization window.
+    e.skip_time(30);
+    let price_data = e.contract.get_price_data(None, None, None, None);
+    assert!(price_data.frozen);
+    assert_eq!(price_data.prices[0].price, Some(price(106000)));
+
+    // Reports of the previous oracle set are no longer recent.
+    e.skip_time(60);
+    let price_data = e.contract.get_price_data(None, None, None, None);
+    assert!(!price_data.frozen);
+    assert_eq!(price_data.prices[0].price, Some(price(205000)));
+}
+
+#[test]
+fn test_oracle_set_stabilization_recency() {
+    let mut e = Env::setup();
+    for i in 0..3 {
+        e.add_oracle(user(i));
+    }
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_oracle_set_stabilization_sec(600);
+    e.make_reports(&[100000, 110000, 106000]);
+
+    e.skip_time(10);
+    e.as_owner()
+        .replace_oracle_set(vec![user(3), user(4), user(5)]);
+
+    e.skip_time(30);
+    let price_data = e.contract.get_price_data(None, None, None, None);
+    assert!(price_data.frozen);
+    assert_eq!(price_data.prices[0].price, Some(price(106000)));
+    // The frozen price is still limited by the requested max age.
+    let price_data = e.contract.get_price_data(None, None, None, Some(20));
+    assert_eq!(price_data.prices[0].price, None);
+
+    // The frozen price is older than the recency duration.
+    e.skip_time(60);
+    let price_data = e.contract.get_price_data(None, None, None, None);
+    assert!(price_data.frozen);
+    assert_eq!(price_data.prices[0].price, None);
+}
+
+#[test]
+fn test_price_ttl() {
+    let mut e = Env::setup();
+    for i in 0..3 {
+        e.add_oracle(user(i));
+    }
+    e.add_asset(WRAP_NEAR);
+    e.as_owner()
+        .set_asset_price_ttl_sec(WRAP_NEAR.to_string(), Some(60));
+    e.make_reports(&[100000, 110000, 106000]);
+
+    e.skip_time(30);
+    let checked = e.contract.get_price_checked(WRAP_NEAR.to_string()).unwrap();
+    assert_eq!(checked.price, price(106000));
+    assert_eq!(checked.price_ttl_sec, 60);
+    assert_eq!(checked.expires_at, ts(0) + to_nano(60));
+
+    // The aggregate expires while its reports are still recent.
+    e.skip_time(30);
+    assert!(e
+        .contract
+        .get_price_checked(WRAP_NEAR.to_string())
+        .is_none());
+    assert_eq!(
+        e.contract.get_price_data(None, None, None, None).prices[0].price,
+        Some(price(106000))
+    );
+}
+
+#[test]
+fn test_asset_upgrade_on_read() {
+    let mut e = Env::setup();
+    let report = ReportV0 {
+        oracle_id: user(0),
+        timestamp: ts(0),
+        price: price(100000),
+    };
+    // Stored by an older version of the contract.
+    let stored = VAsset::V1(AssetV1 {
+        reports: vec![report],
+        emas: vec![AssetEmaV0 {
+            period_sec: 60,
+            timestamp: 0,
+            price: None,
+        }],
+    })
+    .try_to_vec()
+    .unwrap();
+    let v_asset = VAsset::try_from_slice(&stored).unwrap();
+    e.contract.assets.insert(&WRAP_NEAR.to_string(), &v_asset);
+    e.contract.asset_ids.push(&WRAP_NEAR.to_string());
+
+    let asset = e
+        .contract
+        .internal_get_asset(&WRAP_NEAR.to_string())
+        .unwrap();
+    assert_eq!(asset.reports.len(), 1);
+    assert_eq!(asset.emas.len(), 1);
+    assert!(asset.group.is_none());
+    assert!(!asset.paused);
+    assert!(asset.recency_duration_sec.is_none());
+    assert!(asset.last_good.is_none());
+    assert!(asset.price_ttl_sec.is_none());
+
+    e.contract.internal_set_asset(&WRAP_NEAR.to_string(), asset);
+    assert!(matches!(
+        e.contract.assets.get(&WRAP_NEAR.to_string()),
+        Some(VAsset::Current(_))
+    ));
+}
+
+#[test]
+fn test_migrate_legacy_assets() {
+    let mut e = Env::setup();
+    e.add_asset("ndai");
+    for asset_id in [WRAP_NEAR, "usdt"] {
+        e.contract.assets.insert(
+            &asset_id.to_string(),
+            &VAsset::V0(AssetV0 { reports: vec![] }),
+        );
+        e.contract.asset_ids.push(&asset_id.to_string());
+    }
+    assert_eq!(e.contract.get_unmigrated_count(), 2);
+
+    assert_eq!(e.as_owner().migrate_legacy_assets(2), 1);
+    assert_eq!(e.contract.get_unmigrated_count(), 1);
+    assert!(get_logs()
+        .iter()
+        .any(|log| log.contains(r#""event":"migrate_legacy_assets""#)));
+    assert_eq!(e.as_owner().migrate_legacy_assets(2), 1);
+    assert_eq!(e.contract.get_unmigrated_count(), 0);
+    assert_eq!(e.as_owner().migrate_legacy_assets(10), 0);
+    assert!(matches!(
+        e.contract.assets.get(&"usdt".to_string()),
+        Some(VAsset::Current(_))
+    ));
+}
+
+#[test]
+fn test_stake_and_slash() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_min_stake(U128(10 * ONE_NEAR));
+    e.as_owner().set_unbonding_period_sec(3600);
+
+    set_context(user(0), e.timestamp, 12 * ONE_NEAR);
+    e.contract.stake();
+    e.make_reports(&[100000]);
+
+    set_context(user(0), e.timestamp, 1);
+    e.contract.unstake(U128(2 * ONE_NEAR));
+    let oracle = e.contract.get_oracle(user(0)).unwrap();
+    assert_eq!(oracle.stake, 10 * ONE_NEAR);
+    assert_eq!(oracle.unbonding_stake, 2 * ONE_NEAR);
+    assert_eq!(oracle.unbonding_until, ts(3600));
+
+    // The unbonding stake is slashed first.
+    e.as_owner()
+        .slash_oracle(user(0), U128(3 * ONE_NEAR), "Bad price".to_string());
+    let oracle = e.contract.get_oracle(user(0)).unwrap();
+    assert_eq!(oracle.stake, 9 * ONE_NEAR);
+    assert_eq!(oracle.unbonding_stake, 0);
+    assert_eq!(e.contract.get_config().total_stake.0, 9 * ONE_NEAR);
+}
+
+#[test]
+fn test_remove_oracle_unbonds_stake() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.as_owner().set_unbonding_period_sec(3600);
+    set_context(user(0), e.timestamp, 12 * ONE_NEAR);
+    e.contract.stake();
+
+    e.as_owner().remove_oracle(user(0));
+    assert!(get_created_receipts().is_empty());
+    let removed_stake = e.contract.get_removed_oracle_stake(user(0)).unwrap();
+    assert_eq!(removed_stake.amount, 12 * ONE_NEAR);
+    assert_eq!(removed_stake.unbonding_until, ts(3600));
+
+    // The removed oracle can still be slashed while its stake unbonds.
+    e.as_owner()
+        .slash_oracle(user(0), U128(2 * ONE_NEAR), "Bad price".to_string());
+    assert_eq!(e.contract.get_config().total_stake.0, 10 * ONE_NEAR);
+
+    e.skip_time(3600);
+    set_context(user(0), e.timestamp, 1);
+    e.contract.withdraw_removed_stake();
+    assert_eq!(get_created_receipts()[0].receiver_id, user(0));
+    assert!(e.contract.get_removed_oracle_stake(user(0)).is_none());
+    assert_eq!(e.contract.get_config().total_stake.0, 0);
+}
+
+#[test]
+#[should_panic(expected = "E107_STAKE_UNBONDING")]
+fn test_withdraw_removed_stake_while_unbonding() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.as_owner().set_unbonding_period_sec(3600);
+    set_context(user(0), e.timestamp, 12 * ONE_NEAR);
+    e.contract.stake();
+    e.as_owner().remove_oracle(user(0));
+
+    e.skip_time(3599);
+    set_context(user(0), e.timestamp, 1);
+    e.contract.withdraw_removed_stake();
+}
+
+#[test]
+#[should_panic(expected = "Insufficient stake")]
+fn test_report_without_stake() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_min_stake(U128(10 * ONE_NEAR));
+    e.make_reports(&[100000]);
+}
+
+#[test]
+fn test_min_num_recent_reports() {
+    let mut e = Env::setup();
+    for i in 0..3 {
+        e.add_oracle(user(i));
+    }
+    e.add_asset(WRAP_NEAR);
+    e.make_reports(&[100000, 110000]);
+    assert_eq!(e.contract.get_config().min_num_recent_reports, 2);
+    assert_eq!(
+        e.contract.get_price_data(None, None, None, None).prices[0].price,
+        Some(price(110000))
+    );
+
+    e.as_owner().set_min_num_recent_reports(Some(3));
+    assert_eq!(e.contract.get_config().min_num_recent_reports, 3);
+    assert!(e.contract.get_price_data(None, None, None, None).prices[0]
+        .price
+        .is_none());
+
+    // The asset override takes precedence over the contract-wide quorum.
+    e.as_owner()
+        .set_asset_min_num_recent_reports(WRAP_NEAR.to_string(), Some(2));
+    assert_eq!(
+        e.contract.get_price_data(None, None, None, None).prices[0].price,
+        Some(price(110000))
+    );
+}
+
+#[test]
+fn test_events() {
+    let mut e = Env::setup();
+    e.add_asset(WRAP_NEAR);
+    assert_eq!(
+        get_logs(),
+        vec![
+            r#"EVENT_JSON:{"standard":"price_oracle","version":"1.0.0","event":"add_asset","data":[{"asset_id":"wrap.near"}]}"#
+        ]
+    );
+
+    e.add_oracle(user(0));
+    e.make_reports(&[100000]);
+    let logs = get_logs();
+    assert_eq!(logs.len(), 2);
+    assert!(logs[0].starts_with(
+        r#"EVENT_JSON:{"standard":"price_oracle","version":"1.0.0","event":"report_prices","data":[{"oracle_id":"user_0.near","prices":[{"asset_id":"wrap.near","#
+    ));
+    assert!(logs[1].contains(r#""event":"update_price""#));
+
+    e.as_owner().set_recency_duration_sec(60);
+    assert!(get_logs()[0].contains(r#""recency_duration_sec":60"#));
+}
+
+#[test]
+fn test_price_data_detailed() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.add_asset(DAI);
+    e.make_reports(&[100000]);
+    let price_data = e.contract.get_price_data_detailed(
+        Some(vec![
+            WRAP_NEAR.to_string(),
+            DAI.to_string(),
+            USDC.to_string(),
+            format!("inverse:{}", USDC),
+        ]),
+        None,
+        None,
+        None,
+    );
+    assert_eq!(price_data.prices.len(), 1);
+    assert_eq!(price_data.prices[0].asset_id, WRAP_NEAR);
+    assert_eq!(price_data.stale.len(), 1);
+    assert_eq!(price_data.stale[0].asset_id, DAI);
+    assert_eq!(
+        price_data.unknown_assets,
+        vec![USDC.to_string(), format!("inverse:{}", USDC)]
+    );
+}
+
+#[test]
+#[should_panic(expected = "E074_TOO_MANY_ASSETS_REQUESTED")]
+fn test_max_assets_per_request() {
+    let mut e = Env::setup();
+    e.add_asset(WRAP_NEAR);
+    e.add_asset(DAI);
+    e.as_owner().set_max_assets_per_request(1);
+    assert_eq!(e.contract.get_config().max_assets_per_request, 1);
+    assert_eq!(
+        e.contract
+            .get_price_data(None, None, None, None)
+            .prices
+            .len(),
+        1
+    );
+    e.contract.get_price_data(
+        Some(vec![WRAP_NEAR.to_string(), DAI.to_string()]),
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_import_config() {
+    let mut e = Env::setup();
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_asset_metadata(
+        WRAP_NEAR.to_string(),
+        Some(AssetMetadata {
+            decimals: 24,
+            symbol: "wNEAR".to_string(),
+            quote_asset_id: "usd".to_string(),
+            description: None,
+        }),
+    );
+    e.as_owner().add_asset_ema(WRAP_NEAR.to_string(), 3600);
+    e.as_owner()
+        .set_asset_recency_duration_sec(WRAP_NEAR.to_string(), Some(30));
+    e.as_owner().set_asset_price_band(
+        WRAP_NEAR.to_string(),
+        Some(PriceBand {
+            min: price(1000),
+            max: price(1000000),
+        }),
+    );
+    e.as_owner()
+        .set_asset_ema_warm_up_sec(WRAP_NEAR.to_string(), 3600, 7200);
+    e.as_owner().set_asset_ema_gap_policy(
+        WRAP_NEAR.to_string(),
+        3600,
+        Some(EmaGapPolicy::Cap { max_gap_sec: 600 }),
+    );
+    e.as_owner()
+        .add_asset_quote(WRAP_NEAR.to_string(), "near".to_string());
+    e.as_owner()
+        .set_asset_group(WRAP_NEAR.to_string(), Some("near".to_string()));
+    e.as_owner()
+        .set_asset_consumers(WRAP_NEAR.to_string(), Some(vec![user(1)]));
+    e.as_owner().add_dex_contract(a("ref.near"));
+    e.as_owner().set_asset_dex_source(
+        WRAP_NEAR.to_string(),
+        Some(DexSource {
+            contract_id: a("ref.near"),
+            pool_id: 3,
+            token_in: a(WRAP_NEAR),
+            amount_in: U128(10u128.pow(24)),
+            token_out: a(USDC),
+            decimals: 28,
+            policy: DexPolicy::MedianOnly,
+        }),
+    );
+    e.as_owner()
+        .set_asset_fallback_oracle(WRAP_NEAR.to_string(), Some(user(2)));
+    let sunset_timestamp = U64(e.timestamp + to_nano(86400));
+    e.as_owner()
+        .deprecate_asset(WRAP_NEAR.to_string(), sunset_timestamp, None);
+    e.as_owner().register_symbol("BTC".to_string());
+    let config = e.contract.export_config(None, None);
+    let json = near_sdk::serde_json::to_string(&config).unwrap();
+
+    // Unit tests share the storage, so the mirror is the same contract with the assets removed.
+    e.as_owner().remove_asset(WRAP_NEAR.to_string());
+    e.as_owner().remove_asset("symbol:BTC".to_string());
+    e.add_asset(DAI);
+    let num_imported = e
+        .as_owner()
+        .import_config(near_sdk::serde_json::from_str(&json).unwrap(), false);
+    assert_eq!(num_imported, 2);
+    assert_eq!(
+        e.contract.get_symbol_asset_id("BTC".to_string()),
+        Some("symbol:BTC".to_string())
+    );
+    let mirrored = e.contract.export_config(Some(1), None);
+    assert_eq!(near_sdk::serde_json::to_string(&mirrored).unwrap(), json);
+    assert_eq!(
+        e.contract.get_asset_group("near".to_string()),
+        vec![WRAP_NEAR.to_string()]
+    );
+    assert!(e
+        .contract
+        .internal_get_asset(&quoted_asset_id(WRAP_NEAR, "near"))
+        .is_some());
+    let ema = &e
+        .contract
+        .internal_get_asset(&WRAP_NEAR.to_string())
+        .unwrap()
+        .emas[0];
+    assert_eq!(ema.warm_up_sec, 7200);
+    assert_eq!(ema.gap_policy, Some(EmaGapPolicy::Cap { max_gap_sec: 600 }));
+
+    // Dropping quote assets and the group of an existing asset cleans them up.
+    let mut near_config = e.contract.export_config(Some(1), Some(1));
+    near_config[0].quote_asset_ids.clear();
+    near_config[0].group = None;
+    assert_eq!(e.as_owner().import_config(near_config, true), 1);
+    assert!(e
+        .contract
+        .internal_get_asset(&quoted_asset_id(WRAP_NEAR, "near"))
+        .is_none());
+    assert!(e.contract.get_asset_group("near".to_string()).is_empty());
+
+    // Existing assets are only reconfigured with `overwrite`.
+    let mut dai_config = e.contract.export_config(Some(0), Some(1));
+    dai_config[0].emas = vec![EmaConfig {
+        period_sec: 60,
+        warm_up_sec: 0,
+        gap_policy: None,
+    }];
+    let dai_json = near_sdk::serde_json::to_string(&dai_config).unwrap();
+    let dai_config = || near_sdk::serde_json::from_str(&dai_json).unwrap();
+    assert_eq!(e.as_owner().import_config(dai_config(), false), 0);
+    assert!(e.contract.export_config(Some(0), Some(1))[0]
+        .emas
+        .is_empty());
+    assert_eq!(e.as_owner().import_config(dai_config(), true), 1);
+    assert_eq!(
+        e.contract.export_config(Some(0), Some(1))[0].emas[0].period_sec,
+        60
+    );
+}
+
+#[test]
+fn test_adaptive_recency() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_adaptive_recency(Some(AdaptiveRecency {
+        min_recency_sec: 30,
+        max_recency_sec: 600,
+        multiplier_bps: 20000,
+    }));
+    for _ in 0..3 {
+        e.make_reports(&[100000]);
+        e.skip_time(100);
+    }
+    let cadence = e.contract.get_asset_cadence(WRAP_NEAR.to_string()).unwrap();
+    assert_eq!(cadence.median_report_interval_sec, None);
+    assert_eq!(cadence.effective_recency_sec, 90);
+
+    e.make_reports(&[100000]);
+    let cadence = e.contract.get_asset_cadence(WRAP_NEAR.to_string()).unwrap();
+    assert_eq!(cadence.median_report_interval_sec, Some(100));
+    assert_eq!(cadence.effective_recency_sec, 200);
+    // Fresh beyond the contract-wide recency duration.
+    e.skip_time(150);
+    let price_data = e.contract.get_price_data(None, None, None, None);
+    assert_eq!(price_data.prices[0].price, Some(price(100000)));
+    assert_eq!(price_data.prices[0].recency_duration_sec, Some(200));
+}
+
+#[test]
+#[should_panic(expected = "E076_INVALID_ADAPTIVE_RECENCY")]
+fn test_adaptive_recency_invalid() {
+    let mut e = Env::setup();
+    e.as_owner().set_adaptive_recency(Some(AdaptiveRecency {
+        min_recency_sec: 600,
+        max_recency_sec: 30,
+        multiplier_bps: 20000,
+    }));
+}
+
+#[test]
+fn test_deferred_aggregation() {
+    let mut e = Env::setup();
+    for i in 0..3 {
+        e.add_oracle(user(i));
+    }
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_aggregation_window_sec(Some(60));
+    let wrap_near_price =
+        |e: &Env| e.contract.get_price_data(None, None, None, None).prices[0].price;
+
+    // The first report is aggregated right away, but doesn't reach the quorum alone.
+    e.make_reports(&[100000, 100000]);
+    assert_eq!(wrap_near_price(&e), None);
+    assert!(
+        e.contract
+            .get_asset(WRAP_NEAR.to_string())
+            .unwrap()
+            .pending_aggregation
+    );
+    e.as_user(user(5))
+        .finalize_prices(vec![WRAP_NEAR.to_string()]);
+    assert_eq!(wrap_near_price(&e), Some(price(100000)));
+
+    e.skip_time(10);
+    e.make_reports(&[0, 110000, 110000]);
+    assert_eq!(wrap_near_price(&e), Some(price(100000)));
+    let finalized = e
+        .as_user(user(5))
+        .finalize_prices(vec![WRAP_NEAR.to_string()]);
+    assert_eq!(finalized, vec![WRAP_NEAR.to_string()]);
+    assert_eq!(wrap_near_price(&e), Some(price(110000)));
+    assert!(e
+        .as_user(user(5))
+        .finalize_prices(vec![WRAP_NEAR.to_string()])
+        .is_empty());
+
+    // The first report after the window triggers the aggregation.
+    e.skip_time(30);
+    e.make_reports(&[120000, 120000]);
+    assert_eq!(wrap_near_price(&e), Some(price(110000)));
+    e.skip_time(30);
+    e.make_reports(&[0, 0, 120000]);
+    assert_eq!(wrap_near_price(&e), Some(price(120000)));
+}
+
+#[test]
+fn test_register_symbol() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    let asset_id = e.as_owner().register_symbol("BTC".to_string());
+    assert_eq!(asset_id, "symbol:BTC");
+    assert_eq!(
+        e.contract.get_symbol_asset_id("BTC".to_string()),
+        Some(asset_id.clone())
+    );
+    e.as_user(user(0)).report_prices(
+        vec![AssetPrice {
+            asset_id: asset_id.clone(),
+            price: price(100000),
+            quote_asset_id: None,
+            nonce: None,
+        }],
+        None,
+    );
+    let price_data = e
+        .contract
+        .get_price_data(Some(vec![asset_id.clone()]), None, None, None);
+    assert_eq!(price_data.prices[0].price, Some(price(100000)));
+
+    e.as_owner().remove_asset(asset_id);
+    assert!(e.contract.get_symbols(None, None).is_empty());
+}
+
+#[test]
+#[should_panic(expected = "E073_SYMBOL_ALREADY_REGISTERED")]
+fn test_register_symbol_twice() {
+    let mut e = Env::setup();
+    e.as_owner().register_symbol("XAU".to_string());
+    e.as_owner().register_symbol("XAU".to_string());
+}
+
+#[test]
+fn test_batch_price_events() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.add_asset(USDC);
+    e.as_owner().set_batch_price_events(true);
+    let prices = |multiplier| {
+        [WRAP_NEAR, USDC]
+            .iter()
+            .map(|asset_id| AssetPrice {
+                asset_id: asset_id.to_string(),
+                price: price(multiplier),
+                quote_asset_id: None,
+                nonce: None,
+            })
+            .collect::<Vec<_>>()
+    };
+    e.as_user(user(0)).report_prices(prices(100000), None);
+    let logs = get_logs();
+    assert_eq!(logs.len(), 2);
+    assert!(logs[1].contains(r#""event":"price_changes""#));
+    assert!(logs[1].contains(r#""asset_id":"usdc.near","old_price":null"#));
+
+    e.skip_time(10);
+    e.as_user(user(0)).report_prices(prices(110000), None);
+    let logs = get_logs();
+    assert_eq!(logs.len(), 2);
+    assert!(logs[1].contains(
+        r#"{"asset_id":"wrap.near","old_price":{"multiplier":"100000","decimals":28},"new_price":{"multiplier":"110000","decimals":28},"num_reports":1}"#
+    ));
+}
+
+#[test]
+fn test_price_history() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_price_history(2, 60);
+
+    e.make_reports(&[100000]);
+    // Too early for the next snapshot.
+    e.skip_time(30);
+    e.make_reports(&[101000]);
+    e.skip_time(30);
+    e.make_reports(&[102000]);
+    e.skip_time(60);
+    e.make_reports(&[103000]);
+
+    // Only the last 2 snapshots are kept.
+    let history =
+        e.contract
+            .get_price_history(WRAP_NEAR.to_string(), U64(ts(0)), U64(ts(120)), None);
+    assert_eq!(
+        history
+            .iter()
+            .map(|point| (point.timestamp, point.price))
+            .collect::<Vec<_>>(),
+        vec![(ts(60), price(102000)), (ts(120), price(103000))]
+    );
+
+    let point = e
+        .contract
+        .get_price_at(WRAP_NEAR.to_string(), U64(ts(100)))
+        .unwrap();
+    assert_eq!(point.price, price(102000));
+    assert!(e
+        .contract
+        .get_price_at(WRAP_NEAR.to_string(), U64(ts(59)))
+        .is_none());
+}
+
+#[test]
+fn test_price_at_height() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_price_history(10, 60);
+    let set_block = |account_id: AccountId, timestamp: Timestamp, block_height: BlockHeight| {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(a(ORACLE_ID))
+            .predecessor_account_id(account_id)
+            .block_timestamp(timestamp)
+            .block_index(block_height)
+            .account_balance(1000 * ONE_NEAR)
+            .build());
+    };
+    set_block(user(0), ts(0), 100);
+    e.contract.report_prices(wrap_near_prices(100000), None);
+    set_block(user(0), ts(60), 160);
+    e.contract.report_prices(wrap_near_prices(101000), None);
+
+    let asset = e.contract.get_asset(WRAP_NEAR.to_string()).unwrap();
+    assert_eq!(asset.reports[0].block_height, 160);
+    assert_eq!(
+        e.contract
+            .get_price_data(None, None, None, None)
+            .block_height,
+        160
+    );
+    let point = e
+        .contract
+        .get_price_at_height(WRAP_NEAR.to_string(), U64(159))
+        .unwrap();
+    assert_eq!((point.price, point.block_height), (price(100000), 100));
+    assert_eq!(
+        e.contract
+            .get_price_at_height(WRAP_NEAR.to_string(), U64(160))
+            .unwrap()
+            .price,
+        price(101000)
+    );
+    assert!(e
+        .contract
+        .get_price_at_height(WRAP_NEAR.to_string(), U64(99))
+        .is_none());
+}
+
+#[test]
+fn test_twap() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().add_asset_twap(WRAP_NEAR.to_string(), 100);
+    let twap_id = format!("{}#twap100", WRAP_NEAR);
+
+    e.make_reports(&[100000]);
+    e.skip_time(50);
+    e.make_reports(&[200000]);
+
+    // The observations don't cover the whole period yet.
+    e.skip_time(10);
+    let price_data = e
+        .contract
+        .get_price_data(Some(vec![twap_id.clone()]), None, None, None);
+    assert!(price_data.prices[0].price.is_none());
+
+    e.skip_time(40);
+    let price_data = e
+        .contract
+        .get_price_data(Some(vec![twap_id]), None, None, None);
+    assert_eq!(
+        price_data.prices[0].price,
+        Some(Price {
+            multiplier: 1500000000,
+            decimals: 32,
+        })
+    );
+}
+
+#[test]
+fn test_price_decimals() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_owner()
+        .set_asset_price_decimals(WRAP_NEAR.to_string(), Some(30));
+    e.make_reports(&[100000]);
+
+    let asset = e.contract.get_asset(WRAP_NEAR.to_string()).unwrap();
+    assert_eq!(asset.reports[0].price.multiplier, 10000000);
+    assert_eq!(asset.reports[0].price.decimals, 30);
+}
+
+#[test]
+#[should_panic(
+    expected = "The price of wrap.near has 28 decimals and can't be normalized to the expected 26"
+)]
+fn test_price_decimals_mismatch() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_owner()
+        .set_asset_price_decimals(WRAP_NEAR.to_string(), Some(26));
+    e.make_reports(&[100001]);
+}
+
+#[test]
+fn test_report_prices_borsh() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_user(user(0))
+        .report_prices_borsh(vec![AssetPriceBorsh {
+            asset_id: WRAP_NEAR.to_string(),
+            price: price(100000),
+            quote_asset_id: None,
+            nonce: Some(1),
+        }]);
+
+    let asset = e.contract.get_asset(WRAP_NEAR.to_string()).unwrap();
+    assert_eq!(asset.reports[0].price, price(100000));
+    assert_eq!(
+        e.contract.get_oracle(user(0)).unwrap().asset_nonces[WRAP_NEAR],
+        1
+    );
+}
+
+#[test]
+fn test_deprecate_asset() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.add_asset("usdt");
+    e.as_owner()
+        .deprecate_asset(WRAP_NEAR.to_string(), U64(ts(60)), Some("usdt".to_string()));
+    e.make_reports(&[100000]);
+
+    let price_data = e.contract.get_price_data(None, None, None, None);
+    let deprecation = price_data.prices[0].deprecation.as_ref().unwrap();
+    assert_eq!(deprecation.sunset_timestamp, ts(60));
+    assert_eq!(deprecation.replacement_asset_id.as_deref(), Some("usdt"));
+    assert!(price_data.prices[1].deprecation.is_none());
+
+    e.skip_time(60);
+    e.make_reports(&[110000]);
+    let asset = e.contract.get_asset(WRAP_NEAR.to_string()).unwrap();
+    assert_eq!(asset.reports[0].price, price(100000));
+}
+
+#[test]
+#[should_panic(expected = "E070_SUNSET_NOT_IN_FUTURE")]
+fn test_deprecate_asset_past_sunset() {
+    let mut e = Env::setup();
+    e.add_asset(WRAP_NEAR);
+    e.as_owner()
+        .deprecate_asset(WRAP_NEAR.to_string(), U64(ts(0)), None);
+}
+
+#[test]
+fn test_volatility() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_owner()
+        .add_asset_volatility(WRAP_NEAR.to_string(), 3600);
+    let volatility_id = format!("{}#vol3600", WRAP_NEAR);
+
+    e.make_reports(&[100000]);
+    assert!(e
+        .contract
+        .get_volatility(WRAP_NEAR.to_string(), 3600)
+        .is_none());
+
+    e.skip_time(60);
+    e.make_reports(&[110000]);
+    let volatility = e
+        .contract
+        .get_volatility(WRAP_NEAR.to_string(), 3600)
+        .unwrap();
+    let expected = 1.1f64.ln() * 60f64.sqrt();
+    assert!(
+        (volatility.multiplier as f64 / 1e12 - expected).abs() < 1e-9,
+        "{}",
+        volatility.multiplier
+    );
+    let price_data = e
+        .contract
+        .get_price_data(Some(vec![volatility_id.clone()]), None, None, None);
+    assert_eq!(price_data.prices[0].price, Some(volatility));
+
+    // The volatility is stale after the recency duration of the asset.
+    e.skip_time(91);
+    let price_data = e
+        .contract
+        .get_price_data(Some(vec![volatility_id]), None, None, None);
+    assert!(price_data.prices[0].price.is_none());
+}
+
+#[test]
+#[should_panic(expected = "Unknown EMA period: wrap.near#600")]
+fn test_unknown_ema_period() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().add_asset_ema(WRAP_NEAR.to_string(), 3600);
+    e.make_reports(&[100000]);
+    e.contract
+        .get_price_data(Some(vec![format!("{}#600", WRAP_NEAR)]), None, None, None);
+}
+
+#[test]
+fn test_outlier_rejection() {
+    let mut e = Env::setup();
+    for i in 0..3 {
+        e.add_oracle(user(i));
+    }
+    e.add_asset(WRAP_NEAR);
+    e.as_owner()
+        .set_asset_max_deviation_bps(WRAP_NEAR.to_string(), Some(1000));
+    e.make_reports(&[100000, 101000, 102000]);
+
+    // Two colluding oracles can't move the price.
+    e.skip_time(10);
+    e.make_reports(&[100000000, 100000000]);
+    let price_data = e.contract.get_price_data(None, None, None, None);
+    assert!(price_data.prices[0].price.is_none());
+
+    e.make_reports(&[101500, 100000000, 102500]);
+    let price_data = e.contract.get_price_data(None, None, None, None);
+    assert_eq!(price_data.prices[0].price, Some(price(102500)));
+}
+
+#[test]
+fn test_price_deviation() {
+    let reference = price(100000);
+    assert!(!price(110000).deviates_from(&reference, 1000));
+    assert!(!Price {
+        multiplier: 9000,
+        decimals: 27,
+    }
+    .deviates_from(&reference, 1000));
+    assert!(price(110001).deviates_from(&reference, 1000));
+    assert!(price(89999).deviates_from(&reference, 1000));
+}
+
+#[test]
+fn test_circuit_breaker() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().grant_role(user(9), Role::Guardian);
+    e.as_owner().set_asset_circuit_breaker(
+        WRAP_NEAR.to_string(),
+        Some(CircuitBreaker {
+            max_deviation_bps: 2000,
+            window_sec: 60,
+        }),
+    );
+    e.make_reports(&[100000]);
+    e.skip_time(10);
+    e.make_reports(&[110000]);
+
+    e.skip_time(10);
+    e.make_reports(&[150000]);
+    assert!(get_logs()[1].contains(r#""event":"trip_circuit_breaker""#));
+    let statuses = e
+        .contract
+        .get_price_data_per_asset_age(vec![(WRAP_NEAR.to_string(), 90)]);
+    assert!(statuses[0].price.is_none());
+    assert_eq!(statuses[0].status, PriceStatus::Tripped);
+
+    set_context(user(9), e.timestamp, 1);
+    e.contract.resume_asset(WRAP_NEAR.to_string());
+    e.skip_time(10);
+    e.make_reports(&[150000]);
+    assert_eq!(
+        e.contract.get_price_data(None, None, None, None).prices[0].price,
+        Some(price(150000))
+    );
+}
+
+#[test]
+fn test_pause() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().grant_role(user(9), Role::Guardian);
+    e.make_reports(&[100000]);
+
+    set_context(user(9), e.timestamp, 1);
+    e.contract.pause();
+    assert!(e.contract.get_price_data(None, None, None, None).prices[0]
+        .price
+        .is_none());
+
+    e.as_owner().unpause();
+    assert_eq!(
+        e.contract.get_price_data(None, None, None, None).prices[0].price,
+        Some(price(100000))
+    );
+}
+
+#[test]
+#[should_panic(expected = "The contract is paused")]
+fn test_report_when_paused() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().pause();
+    e.make_reports(&[100000]);
+}
+
+#[test]
+fn test_asset_metadata() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.add_asset(USDC);
+    e.as_owner().add_asset_ema(WRAP_NEAR.to_string(), 3600);
+    e.as_owner().set_asset_metadata(
+        WRAP_NEAR.to_string(),
+        Some(AssetMetadata {
+            decimals: 24,
+            symbol: "wNEAR".to_string(),
+            quote_asset_id: "usd".to_string(),
+            description: None,
+        }),
+    );
+    assert_eq!(
+        e.contract
+            .get_asset_metadata(WRAP_NEAR.to_string())
+            .unwrap()
+            .symbol,
+        "wNEAR"
+    );
+
+    let price_data = e.contract.get_price_data(
+        Some(vec![
+            WRAP_NEAR.to_string(),
+            format!("{}#3600", WRAP_NEAR),
+            USDC.to_string(),
+        ]),
+        None,
+        None,
+        None,
+    );
+    assert_eq!(price_data.prices[0].decimals, Some(24));
+    assert_eq!(price_data.prices[1].decimals, Some(24));
+    assert_eq!(price_data.prices[2].decimals, None);
+}
+
+#[test]
+fn test_asset_recency_duration() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.add_asset(USDC);
+    e.as_owner()
+        .set_asset_recency_duration_sec(USDC.to_string(), Some(600));
+    e.as_user(user(0)).report_prices(
+        vec![
+            AssetPrice {
+                asset_id: WRAP_NEAR.to_string(),
+                price: price(100000),
+                quote_asset_id: None,
+                nonce: None,
+            },
+            AssetPrice {
+                asset_id: USDC.to_string(),
+                price: price(1000),
+                quote_asset_id: None,
+                nonce: None,
+            },
+        ],
+        None,
+    );
+
+    e.skip_time(100);
+    let price_data = e.contract.get_price_data(None, None, None, None);
+    assert!(price_data.prices[0].price.is_none());
+    assert_eq!(price_data.prices[0].recency_duration_sec, Some(90));
+    assert_eq!(price_data.prices[1].price, Some(price(1000)));
+    assert_eq!(price_data.prices[1].recency_duration_sec, Some(600));
+}
+
+#[test]
+fn test_derived_pairs() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.add_asset(DAI);
+    e.as_owner().define_pair(
+        "dai/wrap.near".to_string(),
+        DerivedPair {
+            base_asset_id: DAI.to_string(),
+            quote_asset_id: WRAP_NEAR.to_string(),
+            operation: PairOperation::Divide,
+        },
+    );
+    assert!(e
+        .contract
+        .get_price_data(Some(vec!["dai/wrap.near".to_string()]), None, None, None)
+        .prices[0]
+        .price
+        .is_none());
+
+    // 1 NEAR is $4, 1 DAI is $1.
+    e.as_user(user(0)).report_prices(
+        vec![
+            AssetPrice {
+                asset_id: WRAP_NEAR.to_string(),
+                price: Price {
+                    multiplier: 40000,
+                    decimals: 28,
+                },
+                quote_asset_id: None,
+                nonce: None,
+            },
+            AssetPrice {
+                asset_id: DAI.to_string(),
+                price: Price {
+                    multiplier: 10000,
+                    decimals: 22,
+                },
+                quote_asset_id: None,
+                nonce: None,
+            },
+        ],
+        None,
+    );
+    let price = e
+        .contract
+        .get_price_data(Some(vec!["dai/wrap.near".to_string()]), None, None, None)
+        .prices[0]
+        .price
+        .unwrap();
+    // 10**18 DAI units cost 0.25 NEAR, i.e. 0.25 * 10**24 yoctoNEAR.
+    assert_eq!(
+        price,
+        Price {
+            multiplier: 25 * 10u128.pow(32),
+            decimals: 28,
+        }
+    );
+}
+
+#[test]
+fn test_price_math() {
+    let a = Price {
+        multiplier: 40000,
+        decimals: 28,
+    };
+    let b = Price {
+        multiplier: 12,
+        decimals: 1,
+    };
+    assert_eq!(
+        multiply_prices(a, b),
+        Some(Price {
+            multiplier: 480000,
+            decimals: 29,
+        })
+    );
+    assert_eq!(
+        multiply_prices(
+            Price {
+                multiplier: u128::MAX,
+                decimals: 10,
+            },
+            Price {
+                multiplier: 100,
+                decimals: 2,
+            },
+        ),
+        Some(Price {
+            multiplier: u128::MAX / 100 * 100,
+            decimals: 10,
+        })
+    );
+    assert_eq!(
+        divide_prices(b, a),
+        Some(Price {
+            multiplier: 3 * 10u128.pow(33),
+            decimals: 10,
+        })
+    );
+    assert!(divide_prices(
+        a,
+        Price {
+            multiplier: 0,
+            decimals: 0,
+        }
+    )
+    .is_none());
+}
+
+#[test]
+fn test_inverse_price() {
+    let third = Price {
+        multiplier: 3,
+        decimals: 0,
+    };
+    assert_eq!(
+        invert_price(third, false),
+        Some(Price {
+            multiplier: 10u128.pow(38) / 3,
+            decimals: 38,
+        })
+    );
+    assert_eq!(
+        invert_price(third, true),
+        Some(Price {
+            multiplier: 10u128.pow(38) / 3 + 1,
+            decimals: 38,
+        })
+    );
+
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.make_reports(&[100000]);
+    let prices: Vec<_> = e
+        .contract
+        .get_price_data(
+            Some(vec![
+                format!("inverse:{}", WRAP_NEAR),
+                format!("inverse_up:{}", WRAP_NEAR),
+            ]),
+            None,
+            None,
+            None,
+        )
+        .prices
+        .into_iter()
+        .map(|p| p.price)
+        .collect();
+    let expected = Some(Price {
+        multiplier: 10u128.pow(33),
+        decimals: 10,
+    });
+    assert_eq!(prices, vec![expected, expected]);
+}
+
+#[test]
+fn test_price_confidence() {
+    let mut e = Env::setup();
+    for i in 0..3 {
+        e.add_oracle(user(i));
+    }
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().add_asset_ema(WRAP_NEAR.to_string(), 3600);
+    e.make_reports(&[100000, 110000, 120000]);
+
+    let prices = e.contract.get_price_data_with_confidence(Some(vec![
+        WRAP_NEAR.to_string(),
+        format!("{}#3600", WRAP_NEAR),
+    ]));
+    assert_eq!(prices[0].price, Some(price(110000)));
+    let confidence = prices[0].confidence.as_ref().unwrap();
+    assert_eq!(confidence.min, price(100000));
+    assert_eq!(confidence.max, price(120000));
+    assert_eq!(confidence.num_reports, 3);
+    // sqrt(200000000 / 3) ~= 8164.9658
+    assert_eq!(
+        confidence.std_dev,
+        Price {
+            multiplier: 81649658,
+            decimals: 32,
+        }
+    );
+    assert!(prices[1].price.is_some());
+    assert!(prices[1].confidence.is_none());
+}
+
+#[test]
+fn test_oracle_liveness() {
+    let mut e = Env::setup();
+    for i in 0..3 {
+        e.add_oracle(user(i));
+    }
+    e.add_asset(WRAP_NEAR);
+    e.make_reports(&[100000, 110000]);
+    e.skip_time(100);
+    e.make_reports(&[100000]);
+
+    let status = e.contract.get_oracle_status(user(1)).unwrap();
+    assert_eq!(status.last_report, ts(0));
+    assert_eq!(status.last_report_age_sec, Some(100));
+    assert_eq!(status.asset_reports.get(WRAP_NEAR), Some(&1));
+
+    let mut stale_oracles: Vec<_> = e
+        .contract
+        .get_stale_oracles(60)
+        .into_iter()
+        .map(|status| (status.account_id, status.last_report_age_sec))
+        .collect();
+    stale_oracles.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(stale_oracles, vec![(user(1), Some(100)), (user(2), None)]);
+}
+
+#[test]
+fn test_rewards() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    set_context(a(OWNER_ID), e.timestamp, ONE_NEAR);
+    e.contract.fund_rewards();
+    e.as_owner()
+        .set_reward_config(U128(ONE_NEAR / 10), Some(U128(ONE_NEAR / 4)), 3600);
+
+    for _ in 0..3 {
+        e.make_reports(&[100000]);
+    }
+    // The epoch cap is reached after the third report.
+    let oracle = e.contract.get_oracle(user(0)).unwrap();
+    assert_eq!(oracle.rewards, ONE_NEAR / 4);
+
+    e.skip_time(3600);
+    e.make_reports(&[100000]);
+    let oracle = e.contract.get_oracle(user(0)).unwrap();
+    assert_eq!(oracle.rewards, ONE_NEAR / 4 + ONE_NEAR / 10);
+
+    set_context(user(0), e.timestamp, 1);
+    e.contract.claim_rewards();
+    assert_eq!(e.contract.get_oracle(user(0)).unwrap().rewards, 0);
+    assert_eq!(
+        e.contract.get_config().rewards_pool.0,
+        ONE_NEAR - ONE_NEAR / 4 - ONE_NEAR / 10
+    );
+}
+
+#[test]
+fn test_oracle_call_fees() {
+    let mut e = Env::setup();
+    e.add_asset(WRAP_NEAR);
+    e.add_asset(USDC);
+    e.as_owner().set_fees(U128(1000), U128(100));
+    assert_eq!(e.contract.internal_fee(2), 1200);
+
+    set_context(user(0), e.timestamp, 1200);
+    e.contract
+        .oracle_call(user(1), None, "".to_string(), None, None, None, None);
+    assert_eq!(e.contract.get_config().collected_fees.0, 1200);
+
+    e.as_owner().route_fees_to_rewards(Some(U128(200)));
+    e.as_owner().withdraw_fees(None, None);
+    let config = e.contract.get_config();
+    assert_eq!(config.collected_fees.0, 0);
+    assert_eq!(config.rewards_pool.0, 200);
+}
+
+#[test]
+#[should_panic(expected = "Requires attached deposit of at least 1100 yoctoNEAR")]
+fn test_oracle_call_underpaid() {
+    let mut e = Env::setup();
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_fees(U128(1000), U128(100));
+    set_context(user(0), e.timestamp, 1099);
+    e.contract.oracle_call(
+        user(1),
+        Some(vec![WRAP_NEAR.to_string()]),
+        "".to_string(),
+        None,
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_oracle_call_many() {
+    let mut e = Env::setup();
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_fees(U128(1000), U128(0));
+
+    set_context(user(0), e.timestamp, 2000);
+    e.contract.oracle_call_many(
+        vec![
+            ReceiverCall {
+                receiver_id: user(1),
+                msg: "".to_string(),
+            },
+            ReceiverCall {
+                receiver_id: user(2),
+                msg: "".to_string(),
+            },
+        ],
+        None,
+        None,
+        None,
+        None,
+    );
+    assert_eq!(e.contract.get_config().collected_fees.0, 2000);
+    let receivers: Vec<_> = get_created_receipts()
+        .into_iter()
+        .map(|receipt| receipt.receiver_id)
+        .collect();
+    // Every receiver call has a callback.
+    assert_eq!(
+        receivers,
+        vec![user(1), a(ORACLE_ID), user(2), a(ORACLE_ID)]
+    );
+    assert_eq!(
+        e.contract.get_consumer_stats(user(0)).unwrap().fees_paid,
+        2000
+    );
+}
+
+#[test]
+fn test_ft_on_transfer() {
+    let mut e = Env::setup();
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_token_fee(
+        a(USDC),
+        Some(TokenFee {
+            flat_fee: 100,
+            per_asset_fee: 10,
+        }),
+    );
+
+    set_context(a(USDC), e.timestamp, 0);
+    let msg = format!(
+        r#"{{"receiver_id":"{}","asset_ids":["{}"],"msg":""}}"#,
+        user(1),
+        WRAP_NEAR
+    );
+    let result = e.contract.ft_on_transfer(user(0), U128(110), msg);
+    assert!(matches!(result, PromiseOrValue::Value(U128(0))));
+    assert_eq!(get_created_receipts()[0].receiver_id, user(1));
+    assert_eq!(
+        e.contract.get_collected_token_fees(),
+        vec![(a(USDC), U128(110))]
+    );
+}
+
+fn price_feed_update(
+    keypairs: &[(u8, &ed25519_dalek::Keypair)],
+    update: PriceFeedUpdate,
+) -> Base64VecU8 {
+    let message = update.try_to_vec().unwrap();
+    let signatures = keypairs
+        .iter()
+        .map(|(key_index, keypair)| (*key_index, keypair.sign(&message).to_bytes().to_vec()))
+        .collect();
+    Base64VecU8(
+        SignedPriceFeedUpdate { update, signatures }
+            .try_to_vec()
+            .unwrap(),
+    )
+}
+
+#[test]
+fn test_update_price_feeds() {
+    let mut e = Env::setup();
+    let feed_id = a("feed.near");
+    e.add_oracle(feed_id.clone());
+    e.add_asset(WRAP_NEAR);
+    let keypairs: Vec<_> = (1..=3).map(keypair).collect();
+    e.as_owner().set_price_feed_config(Some(PriceFeedConfig {
+        oracle_id: feed_id.clone(),
+        public_keys: keypairs.iter().map(near_public_key).collect(),
+        threshold: 2,
+        max_confidence_bps: 100,
+    }));
+
+    e.skip_time(10);
+    let blob = price_feed_update(
+        &[(0, &keypairs[0]), (2, &keypairs[2])],
+        PriceFeedUpdate {
+            asset_id: WRAP_NEAR.to_string(),
+            price: price(100000),
+            confidence: 500,
+            publish_time: e.timestamp - to_nano(5),
+        },
+    );
+    e.as_user(user(0)).update_price_feeds(vec![blob.clone()]);
+    let price_data = e
+        .contract
+        .get_price_data(Some(vec![WRAP_NEAR.to_string()]), None, None, None);
+    assert_eq!(price_data.prices[0].price, Some(price(100000)));
+    assert_eq!(e.contract.get_oracle(feed_id).unwrap().price_reports, 1);
+
+    // Replayed updates are skipped.
+    e.skip_time(10);
+    e.as_user(user(0)).update_price_feeds(vec![blob]);
+    assert_eq!(
+        e.contract.get_oracle(a("feed.near")).unwrap().price_reports,
+        1
+    );
+}
+
+#[test]
+#[should_panic(expected = "Not enough signatures")]
+fn test_update_price_feeds_below_threshold() {
+    let mut e = Env::setup();
+    e.add_oracle(a("feed.near"));
+    e.add_asset(WRAP_NEAR);
+    let keypairs: Vec<_> = (1..=3).map(keypair).collect();
+    e.as_owner().set_price_feed_config(Some(PriceFeedConfig {
+        oracle_id: a("feed.near"),
+        public_keys: keypairs.iter().map(near_public_key).collect(),
+        threshold: 2,
+        max_confidence_bps: 100,
+    }));
+    let blob = price_feed_update(
+        &[(1, &keypairs[1])],
+        PriceFeedUpdate {
+            asset_id: WRAP_NEAR.to_string(),
+            price: price(100000),
+            confidence: 0,
+            publish_time: e.timestamp,
+        },
+    );
+    e.as_user(user(0)).update_price_feeds(vec![blob]);
+}
+
+#[test]
+fn test_migrate_assets() {
+    let mut e = Env::setup();
+    for asset_id in [WRAP_NEAR, USDC, DAI] {
+        e.contract.assets.insert(
+            &asset_id.to_string(),
+            &VAsset::V0(AssetV0 { reports: vec![] }),
+        );
+        e.contract.asset_ids.push(&asset_id.to_string());
+    }
+    assert_eq!(e.contract.get_num_legacy_assets(), 3);
+
+    assert_eq!(e.as_owner().migrate_assets(Some(1), Some(1)), 1);
+    assert_eq!(e.contract.get_num_legacy_assets(), 2);
+    assert_eq!(e.as_owner().migrate_assets(None, None), 2);
+    assert_eq!(e.contract.get_num_legacy_assets(), 0);
+    assert_eq!(e.as_owner().migrate_assets(None, None), 0);
+}
+
+#[test]
+fn test_ownership_transfer() {
+    let mut e = Env::setup();
+    e.as_owner().propose_owner(Some(user(0)));
+    assert_eq!(e.contract.get_config().proposed_owner_id, Some(user(0)));
+    assert_eq!(e.contract.get_owner_id(), a(OWNER_ID));
+
+    set_context(user(0), e.timestamp, 1);
+    e.contract.accept_ownership();
+    assert_eq!(e.contract.get_owner_id(), user(0));
+    assert_eq!(e.contract.get_config().proposed_owner_id, None);
+}
+
+#[test]
+#[should_panic(expected = "Can only be called by the proposed owner")]
+fn test_accept_ownership_not_proposed() {
+    let mut e = Env::setup();
+    e.as_owner().propose_owner(Some(user(0)));
+    set_context(user(1), e.timestamp, 1);
+    e.contract.accept_ownership();
+}
+
+#[test]
+fn test_renounce_ownership() {
+    let mut e = Env::setup();
+    e.as_owner()
+        .renounce_ownership(crate::owner::RENOUNCE_OWNERSHIP_CONFIRMATION.to_string());
+    assert_eq!(e.contract.get_owner_id(), a("system"));
+}
+
+#[test]
+#[should_panic(expected = "Invalid confirmation")]
+fn test_renounce_ownership_invalid_confirmation() {
+    let mut e = Env::setup();
+    e.as_owner().renounce_ownership("yes".to_string());
+}
+
+#[test]
+fn test_timelocked_config_change() {
+    let mut e = Env::setup();
+    e.as_owner().set_timelock_sec(3600);
+    let proposal_id = e
+        .as_owner()
+        .propose_config_change(ConfigChange::SetRecencyDurationSec {
+            recency_duration_sec: 300,
+        });
+    assert_eq!(e.contract.get_proposals().len(), 1);
+
+    e.skip_time(3600);
+    e.as_user(user(0)).execute_proposal(proposal_id);
+    assert_eq!(e.contract.get_config().recency_duration_sec, 300);
+    assert!(e.contract.get_proposals().is_empty());
+}
+
+#[test]
+#[should_panic(expected = "E098_PROPOSAL_TIMELOCKED")]
+fn test_execute_proposal_before_timelock() {
+    let mut e = Env::setup();
+    e.as_owner().set_timelock_sec(3600);
+    let proposal_id = e
+        .as_owner()
+        .propose_config_change(ConfigChange::SetTimelockSec { timelock_sec: 0 });
+    e.skip_time(3599);
+    e.as_user(user(0)).execute_proposal(proposal_id);
+}
+
+#[test]
+#[should_panic(expected = "E099_REQUIRES_TIMELOCKED_PROPOSAL")]
+fn test_timelocked_direct_change() {
+    let mut e = Env::setup();
+    e.as_owner().set_timelock_sec(3600);
+    e.as_owner().set_recency_duration_sec(300);
+}
+
+#[test]
+#[should_panic(expected = "E099_REQUIRES_TIMELOCKED_PROPOSAL")]
+fn test_timelocked_add_oracle() {
+    let mut e = Env::setup();
+    e.as_owner().set_timelock_sec(3600);
+    e.add_oracle(user(0));
+}
+
+#[test]
+#[should_panic(expected = "E099_REQUIRES_TIMELOCKED_PROPOSAL")]
+fn test_timelocked_remove_oracle() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.as_owner().set_timelock_sec(3600);
+    e.as_owner().remove_oracle(user(0));
+}
+
+#[test]
+#[should_panic(expected = "E099_REQUIRES_TIMELOCKED_PROPOSAL")]
+fn test_timelocked_asset_recency_duration() {
+    let mut e = Env::setup();
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_timelock_sec(3600);
+    e.as_owner()
+        .set_asset_recency_duration_sec(WRAP_NEAR.to_string(), Some(3600));
+}
+
+#[test]
+#[should_panic(expected = "E099_REQUIRES_TIMELOCKED_PROPOSAL")]
+fn test_timelocked_group_recency_duration() {
+    let mut e = Env::setup();
+    e.add_asset(USDC);
+    e.as_owner()
+        .set_asset_group(USDC.to_string(), Some("stablecoins".to_string()));
+    e.as_owner().set_timelock_sec(3600);
+    e.as_owner()
+        .set_group_recency_duration_sec("stablecoins".to_string(), Some(3600));
+}
+
+#[test]
+#[should_panic(expected = "E099_REQUIRES_TIMELOCKED_PROPOSAL")]
+fn test_timelocked_adaptive_recency() {
+    let mut e = Env::setup();
+    e.as_owner().set_timelock_sec(3600);
+    e.as_owner().set_adaptive_recency(None);
+}
+
+#[test]
+#[should_panic(expected = "E099_REQUIRES_TIMELOCKED_PROPOSAL")]
+fn test_timelocked_min_stake() {
+    let mut e = Env::setup();
+    e.as_owner().set_timelock_sec(3600);
+    e.as_owner().set_min_stake(U128(ONE_NEAR));
+}
+
+#[test]
+#[should_panic(expected = "E099_REQUIRES_TIMELOCKED_PROPOSAL")]
+fn test_timelocked_asset_price_band() {
+    let mut e = Env::setup();
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_timelock_sec(3600);
+    e.as_owner()
+        .set_asset_price_band(WRAP_NEAR.to_string(), None);
+}
+
+#[test]
+#[should_panic(expected = "E028_QUORUM_NOT_POSITIVE")]
+fn test_propose_invalid_quorum() {
+    let mut e = Env::setup();
+    e.as_owner().set_timelock_sec(3600);
+    e.as_owner()
+        .propose_config_change(ConfigChange::SetMinNumRecentReports {
+            min_num_recent_reports: Some(0),
+        });
+}
+
+#[test]
+fn test_timelocked_aggregation_changes() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_timelock_sec(3600);
+    let proposal_ids = vec![
+        e.as_owner()
+            .propose_config_change(ConfigChange::SetMinStake {
+                min_stake: U128(ONE_NEAR),
+            }),
+        e.as_owner()
+            .propose_config_change(ConfigChange::SetOracleWeight {
+                account_id: user(0),
+                weight: 3,
+            }),
+        e.as_owner()
+            .propose_config_change(ConfigChange::SetAssetMinNumRecentReports {
+                asset_id: WRAP_NEAR.to_string(),
+                min_num_recent_reports: Some(2),
+            }),
+        e.as_owner()
+            .propose_config_change(ConfigChange::SetAssetMaxDeviationBps {
+                asset_id: WRAP_NEAR.to_string(),
+                max_deviation_bps: Some(500),
+            }),
+    ];
+
+    e.skip_time(3600);
+    for proposal_id in proposal_ids {
+        e.as_user(user(2)).execute_proposal(proposal_id);
+    }
+    assert_eq!(e.contract.get_config().min_stake.0, ONE_NEAR);
+    assert_eq!(e.contract.get_oracle(user(0)).unwrap().weight, 3);
+    let asset = e.contract.get_asset(WRAP_NEAR.to_string()).unwrap();
+    assert_eq!(asset.min_num_recent_reports, Some(2));
+    assert_eq!(asset.max_deviation_bps, Some(500));
+}
+
+#[test]
+fn test_timelocked_oracle_changes() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_timelock_sec(3600);
+    let add_proposal_id = e.as_owner().propose_config_change(ConfigChange::AddOracle {
+        account_id: user(1),
+    });
+    let remove_proposal_id = e
+        .as_owner()
+        .propose_config_change(ConfigChange::RemoveOracle {
+            account_id: user(0),
+        });
+    let recency_proposal_id =
+        e.as_owner()
+            .propose_config_change(ConfigChange::SetAssetRecencyDurationSec {
+                asset_id: WRAP_NEAR.to_string(),
+                recency_duration_sec: Some(3600),
+            });
+
+    e.skip_time(3600);
+    e.as_user(user(2)).execute_proposal(add_proposal_id);
+    e.as_user(user(2)).execute_proposal(remove_proposal_id);
+    e.as_user(user(2)).execute_proposal(recency_proposal_id);
+    assert!(e.contract.get_oracle(user(0)).is_none());
+    assert!(e.contract.get_oracle(user(1)).is_some());
+    assert_eq!(
+        e.contract
+            .get_asset(WRAP_NEAR.to_string())
+            .unwrap()
+            .recency_duration_sec,
+        Some(3600)
+    );
+}
+
+#[test]
+#[should_panic(expected = "Missing a proposal")]
+fn test_cancel_proposal() {
+    let mut e = Env::setup();
+    e.as_owner().set_timelock_sec(3600);
+    let proposal_id = e
+        .as_owner()
+        .propose_config_change(ConfigChange::ReplaceOracleSet {
+            account_ids: vec![user(0)],
+        });
+    e.as_owner().cancel_proposal(proposal_id);
+    e.skip_time(3600);
+    e.as_user(user(0)).execute_proposal(proposal_id);
+}
+
+#[test]
+fn test_roles() {
+    let mut e = Env::setup();
+    e.as_owner().grant_role(user(0), Role::Admin);
+    set_context(user(0), e.timestamp, 1);
+    e.contract.grant_role(user(1), Role::AssetManager);
+    assert_eq!(
+        e.contract.get_role_members(Role::AssetManager),
+        vec![user(1)]
+    );
+
+    set_context(user(1), e.timestamp, 1);
+    e.contract.add_asset(WRAP_NEAR.to_string());
+    assert_eq!(e.contract.get_assets(None, None).len(), 1);
+
+    e.as_owner().revoke_role(user(0), Role::Admin);
+    assert!(e.contract.get_roles(user(0)).is_empty());
+}
+
+#[test]
+#[should_panic(
+    expected = "Can only be called by the owner or an account with the OracleManager role"
+)]
+fn test_missing_role() {
+    let mut e = Env::setup();
+    e.as_owner().grant_role(user(1), Role::AssetManager);
+    set_context(user(1), e.timestamp, 1);
+    e.contract.add_oracle(user(2));
+}
+
+#[test]
+#[should_panic(expected = "Can only be called by the owner")]
+fn test_admin_cannot_grant_admin() {
+    let mut e = Env::setup();
+    e.as_owner().grant_role(user(0), Role::Admin);
+    set_context(user(0), e.timestamp, 1);
+    e.contract.grant_role(user(1), Role::Admin);
+}
+
+#[test]
+fn test_staged_code() {
+    let mut e = Env::setup();
+    let code = vec![1, 2, 3];
+    set_context(a(OWNER_ID), e.timestamp, ONE_NEAR);
+    e.contract
+        .stage_code(Base64VecU8(code.clone()), U64(e.timestamp + to_nano(3600)));
+    let staged_code = e.contract.get_staged_code().unwrap();
+    let code_hash: CryptoHash = staged_code.code_hash.into();
+    assert_eq!(code_hash.to_vec(), env::sha256(&code));
+    assert_eq!(staged_code.deploy_at, e.timestamp + to_nano(3600));
+    // The deposit above the storage cost is refunded right away.
+    assert!(staged_code.deposit.0 < ONE_NEAR);
+    assert_eq!(get_created_receipts()[0].receiver_id, a(OWNER_ID));
+
+    e.skip_time(3600);
+    e.contract.deploy_staged();
+    assert!(e.contract.get_staged_code().is_none());
+    let receivers: Vec<_> = get_created_receipts()
+        .into_iter()
+        .map(|receipt| receipt.receiver_id)
+        .collect();
+    // The staging deposit is refunded with the deployment.
+    assert_eq!(receivers, vec![a(OWNER_ID), a(ORACLE_ID)]);
+}
+
+#[test]
+fn test_remove_staged_code_refunds_deposit() {
+    let mut e = Env::setup();
+    set_context(a(OWNER_ID), e.timestamp, ONE_NEAR);
+    e.contract
+        .stage_code(Base64VecU8(vec![1, 2, 3]), U64(e.timestamp + to_nano(3600)));
+    let deposit = e.contract.get_staged_code().unwrap().deposit;
+
+    e.as_owner().remove_staged_code();
+    assert!(e.contract.get_staged_code().is_none());
+    let receipts = get_created_receipts();
+    assert_eq!(receipts.len(), 1);
+    assert_eq!(receipts[0].receiver_id, a(OWNER_ID));
+    assert!(matches!(
+        receipts[0].actions[0],
+        near_sdk::mock::VmAction::Transfer { deposit: amount } if amount == deposit.0
+    ));
+}
+
+#[test]
+#[should_panic(expected = "E102_STAGED_CODE_NOT_DEPLOYABLE")]
+fn test_deploy_staged_code_early() {
+    let mut e = Env::setup();
+    set_context(a(OWNER_ID), e.timestamp, ONE_NEAR);
+    e.contract
+        .stage_code(Base64VecU8(vec![1, 2, 3]), U64(e.timestamp + to_nano(3600)));
+    e.skip_time(3599);
+    e.contract.deploy_staged();
+}
+
+#[test]
+fn test_storage_deposit() {
+    let mut e = Env::setup();
+    e.as_owner().set_storage_deposit_required(true);
+    set_context(a(OWNER_ID), e.timestamp, ONE_NEAR);
+    e.contract.storage_deposit(None, None);
+    let initial_available = e
+        .contract
+        .storage_balance_of(a(OWNER_ID))
+        .unwrap()
+        .available
+        .0;
+
+    e.add_asset(WRAP_NEAR);
+    let available = e
+        .contract
+        .storage_balance_of(a(OWNER_ID))
+        .unwrap()
+        .available
+        .0;
+    assert!(available < initial_available);
+
+    e.as_owner().remove_asset(WRAP_NEAR.to_string());
+    let balance = e.contract.storage_balance_of(a(OWNER_ID)).unwrap();
+    assert_eq!(balance.available.0, initial_available);
+    assert_eq!(balance.total.0, ONE_NEAR);
+
+    e.as_owner().storage_withdraw(None);
+    assert_eq!(
+        e.contract
+            .storage_balance_of(a(OWNER_ID))
+            .unwrap()
+            .available
+            .0,
+        0
+    );
+}
+
+#[test]
+fn test_remove_asset_releases_storage_of_payer() {
+    let mut e = Env::setup();
+    e.as_owner().grant_role(user(5), Role::AssetManager);
+    e.as_owner().set_storage_deposit_required(true);
+    for account_id in [a(OWNER_ID), user(5)] {
+        set_context(account_id, e.timestamp, ONE_NEAR);
+        e.contract.storage_deposit(None, None);
+    }
+    let available = |e: &Env, account_id: AccountId| {
+        e.contract
+            .storage_balance_of(account_id)
+            .unwrap()
+            .available
+            .0
+    };
+    let initial_available = available(&e, user(5));
+
+    set_context(user(5), e.timestamp, 1);
+    e.contract.add_asset(WRAP_NEAR.to_string());
+    assert!(available(&e, user(5)) < initial_available);
+
+    e.as_owner().remove_asset(WRAP_NEAR.to_string());
+    assert_eq!(available(&e, user(5)), initial_available);
+    assert_eq!(available(&e, a(OWNER_ID)), initial_available);
+}
+
+#[test]
+fn test_remove_asset_cleanup() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.add_asset(DAI);
+    e.as_owner().set_bundle(
+        "tokens".to_string(),
+        vec![WRAP_NEAR.to_string(), DAI.to_string()],
+    );
+    e.as_owner()
+        .set_bundle("near".to_string(), vec![WRAP_NEAR.to_string()]);
+    e.as_user(user(0)).report_prices(
+        vec![AssetPrice {
+            asset_id: WRAP_NEAR.to_string(),
+            price: price(100000),
+            quote_asset_id: None,
+            nonce: Some(U64(1)),
+        }],
+        None,
+    );
+    e.as_owner().set_push_fee(U128(10));
+    set_context(a("lending.near"), e.timestamp, ONE_NEAR / 10);
+    let subscription_id = e
+        .contract
+        .subscribe(WRAP_NEAR.to_string(), 100, 60, "".to_string());
+
+    e.as_owner().remove_asset(WRAP_NEAR.to_string());
+    assert!(e.contract.get_subscription(subscription_id).is_none());
+    assert_eq!(e.contract.get_config().total_subscription_balances.0, 0);
+    assert_eq!(
+        get_created_receipts()[0].actions[0],
+        near_sdk::mock::VmAction::Transfer {
+            deposit: ONE_NEAR / 10
+        }
+    );
+    assert_eq!(
+        e.contract.get_bundle("tokens".to_string()),
+        Some(vec![DAI.to_string()])
+    );
+    assert!(e.contract.get_bundle("near".to_string()).is_none());
+    assert!(e
+        .contract
+        .get_oracle(user(0))
+        .unwrap()
+        .asset_nonces
+        .is_empty());
+}
+
+#[test]
+#[should_panic(expected = "E094_ASSET_USED_BY_PAIR")]
+fn test_remove_pair_component() {
+    let mut e = Env::setup();
+    e.add_asset(WRAP_NEAR);
+    e.add_asset(DAI);
+    e.as_owner().define_pair(
+        "dai/wrap.near".to_string(),
+        DerivedPair {
+            base_asset_id: DAI.to_string(),
+            quote_asset_id: format!("{}#ema3600", WRAP_NEAR),
+            operation: PairOperation::Divide,
+        },
+    );
+    e.as_owner().remove_asset(WRAP_NEAR.to_string());
+}
+
+#[test]
+#[should_panic(expected = "E108_NOT_REGISTERED: The account user_0.near is not registered")]
+fn test_storage_deposit_required_for_reports() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_storage_deposit_required(true);
+    e.make_reports(&[100000]);
+}
+
+#[test]
+fn test_prune_reports() {
+    let mut e = Env::setup();
+    for i in 0..2 {
+        e.add_oracle(user(i));
+    }
+    e.add_asset(WRAP_NEAR);
+    e.make_reports(&[100000]);
+    e.skip_time(70);
+    e.make_reports(&[0, 100000]);
+
+    assert_eq!(e.as_owner().prune_reports(WRAP_NEAR.to_string(), 60), 1);
+    let asset = e.contract.get_asset(WRAP_NEAR.to_string()).unwrap();
+    assert_eq!(asset.reports.len(), 1);
+    assert_eq!(asset.reports[0].oracle_id, user(1));
+}
+
+#[test]
+fn test_reports_evicted_on_report() {
+    let mut e = Env::setup();
+    for i in 0..2 {
+        e.add_oracle(user(i));
+    }
+    e.add_asset(WRAP_NEAR);
+    e.make_reports(&[100000]);
+    e.skip_time(120);
+    e.make_reports(&[0, 100000]);
+
+    let asset = e.contract.get_asset(WRAP_NEAR.to_string()).unwrap();
+    assert_eq!(asset.reports.len(), 1);
+    assert_eq!(asset.reports[0].oracle_id, user(1));
+
+    let mut asset = Asset::new();
+    for i in 0..MAX_REPORTS_PER_ASSET + 1 {
+        asset.add_report(
+            Report {
+                oracle_id: user(i),
+                timestamp: ts(i as u32),
+                price: price(100000),
+                source: PriceSource::Report,
+                block_height: 0,
+            },
+            0,
+        );
+    }
+    assert_eq!(asset.reports.len(), MAX_REPORTS_PER_ASSET);
+    assert!(asset
+        .reports
+        .iter()
+        .all(|report| report.oracle_id != user(0)));
+}
+
+#[test]
+fn test_removed_asset_has_no_price() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.make_reports(&[100000]);
+    e.as_owner().remove_asset(WRAP_NEAR.to_string());
+    let price_data = e
+        .contract
+        .get_price_data(Some(vec![WRAP_NEAR.to_string()]), None, None, None);
+    assert_eq!(price_data.prices[0].price, None);
+}
+
+#[test]
+fn test_remove_asset_keeps_asset_ids() {
+    let mut e = Env::setup();
+    for asset_id in [WRAP_NEAR, USDC, DAI] {
+        e.add_asset(asset_id);
+    }
+    e.as_owner().remove_asset(WRAP_NEAR.to_string());
+    let asset_ids: Vec<AssetId> = e
+        .contract
+        .get_assets(None, None)
+        .into_iter()
+        .map(|(asset_id, _)| asset_id)
+        .collect();
+    assert_eq!(asset_ids, vec![DAI.to_string(), USDC.to_string()]);
+    assert_eq!(e.contract.get_config().num_assets, 2);
+}
+
+#[test]
+fn test_migrate_state_keeps_legacy_assets() {
+    Env::setup();
+    let mut legacy_assets = UnorderedMap::new(StorageKey::Assets);
+    for asset_id in [WRAP_NEAR, USDC] {
+        legacy_assets.insert(&asset_id.to_string(), &VAsset::from(Asset::new()));
+    }
+    let mut contract: Contract = ContractV0 {
+        oracles: UnorderedMap::new(StorageKey::Oracles),
+        assets: legacy_assets,
+        recency_duration_sec: 90,
+        owner_id: a(OWNER_ID),
+        near_claim_amount: 5 * ONE_NEAR,
+    }
+    .into();
+    assert!(contract.asset_ids.is_empty());
+    assert_eq!(contract.get_config().num_assets, 2);
+    assert_eq!(contract.get_unmigrated_count(), 2);
+    assert!(contract.internal_get_asset(&USDC.to_string()).is_some());
+    let asset_ids: Vec<AssetId> = contract
+        .get_assets(None, None)
+        .into_iter()
+        .map(|(asset_id, _)| asset_id)
+        .collect();
+    assert_eq!(asset_ids, vec![WRAP_NEAR.to_string(), USDC.to_string()]);
+
+    // Writing an asset moves it out of the legacy map.
+    let asset = contract.internal_get_asset(&WRAP_NEAR.to_string()).unwrap();
+    contract.internal_set_asset(&WRAP_NEAR.to_string(), asset);
+    assert_eq!(contract.asset_ids.to_vec(), vec![WRAP_NEAR.to_string()]);
+    assert_eq!(contract.legacy_assets.len(), 1);
+
+    set_context(a(OWNER_ID), 0, 1);
+    assert_eq!(contract.migrate_legacy_assets(1), 1);
+    assert!(contract.legacy_assets.is_empty());
+    assert_eq!(contract.get_unmigrated_count(), 0);
+    assert_eq!(contract.get_config().num_assets, 2);
+}
+
+#[test]
+fn test_report_prices_if_changed() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.make_reports(&[100000]);
+
+    e.skip_time(60);
+    e.as_user(user(0))
+        .report_prices_if_changed(wrap_near_prices(100050), 10, None);
+    let asset = e.contract.get_asset(WRAP_NEAR.to_string()).unwrap();
+    assert_eq!(asset.reports[0].timestamp, e.timestamp);
+    assert_eq!(asset.reports[0].price.multiplier, 100000);
+
+    e.skip_time(60);
+    e.as_user(user(0))
+        .report_prices_if_changed(wrap_near_prices(100200), 10, None);
+    let asset = e.contract.get_asset(WRAP_NEAR.to_string()).unwrap();
+    assert_eq!(asset.reports[0].price.multiplier, 100200);
+    assert_eq!(e.contract.get_oracle(user(0)).unwrap().price_reports, 3);
+}
+
+#[test]
+fn test_oracle_weighting() {
+    let mut e = Env::setup();
+    for i in 0..3 {
+        e.add_oracle(user(i));
+    }
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_oracle_weight(user(2), 3);
+    e.make_reports(&[100000, 101000, 120000]);
+
+    let wrap_near_price = |e: &Env| {
+        e.contract
+            .get_price_data(Some(vec![WRAP_NEAR.to_string()]), None, None, None)
+            .prices[0]
+            .price
+    };
+    assert_eq!(wrap_near_price(&e), Some(price(101000)));
+
+    e.as_owner()
+        .set_asset_oracle_weighting(WRAP_NEAR.to_string(), Some(OracleWeighting::Assigned));
+    assert_eq!(wrap_near_price(&e), Some(price(120000)));
+    let details = e
+        .contract
+        .get_price_detailed(WRAP_NEAR.to_string())
+        .unwrap();
+    assert_eq!(details.mode, AggregationMode::WeightedMedian);
+    assert_eq!(details.reports[2].oracle_weight, U128(3));
+
+    // Oracles without stake have no weight.
+    e.as_owner()
+        .set_asset_oracle_weighting(WRAP_NEAR.to_string(), None);
+    e.as_owner().set_oracle_weighting(OracleWeighting::Stake);
+    set_context(user(0), e.timestamp, 20 * ONE_NEAR);
+    e.contract.stake();
+    set_context(user(1), e.timestamp, 10 * ONE_NEAR);
+    e.contract.stake();
+    assert_eq!(wrap_near_price(&e), Some(price(100000)));
+}
+
+#[test]
+fn test_price_data_v2() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.add_asset(USDC);
+    e.make_reports(&[100000]);
+    let report_timestamp = e.timestamp;
+
+    let price_data = e.contract.get_price_data_v2(None);
+    assert_eq!(price_data.prices[0].price, Some(price(100000)));
+    assert_eq!(
+        price_data.prices[0].last_report_timestamp,
+        Some(U64(report_timestamp))
+    );
+    assert!(!price_data.prices[0].stale);
+    assert_eq!(price_data.prices[1].last_report_timestamp, None);
+    assert_eq!(price_data.prices[1].status, PriceStatus::Stale);
+
+    e.skip_time(91);
+    let price_data = e
+        .contract
+        .get_price_data_v2(Some(vec![WRAP_NEAR.to_string()]));
+    assert!(price_data.prices[0].price.is_none());
+    assert!(price_data.prices[0].stale);
+    assert_eq!(
+        price_data.prices[0].last_report_timestamp,
+        Some(U64(report_timestamp))
+    );
+}
+
+#[test]
+fn test_asset_id_validation() {
+    assert!(is_valid_asset_id(WRAP_NEAR));
+    assert!(is_valid_asset_id("usd"));
+    assert!(!is_valid_asset_id("wrap.near#3600"));
+    assert!(!is_valid_asset_id("Wrap.near"));
+    assert!(!is_valid_asset_id(""));
+    assert!(is_valid_derived_asset_id("dai/wrap.near"));
+    assert!(!is_valid_derived_asset_id("dai/"));
+    assert!(is_valid_derived_asset_id("symbol:XAU/usd"));
+    assert!(is_valid_symbol("BRK.B"));
+    assert!(!is_valid_symbol("btc"));
+    assert!(!is_valid_symbol(""));
+}
+
+#[test]
+#[should_panic(expected = "Invalid asset ID")]
+fn test_add_invalid_asset() {
+    let mut e = Env::setup();
+    e.add_asset("wrap.near ");
+}
+
+#[test]
+fn test_price_band() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(USDC);
+    e.as_owner().set_asset_price_band(
+        USDC.to_string(),
+        Some(PriceBand {
+            min: Price {
+                multiplier: 5000,
+                decimals: 22,
+            },
+            max: Price {
+                multiplier: 20000,
+                decimals: 22,
+            },
+        }),
+    );
+    let report = |e: &mut Env, multiplier: Balance| {
+        e.as_user(user(0)).report_prices(
+            vec![AssetPrice {
+                asset_id: USDC.to_string(),
+                price: Price {
+                    multiplier,
+                    decimals: 22,
+                },
+                quote_asset_id: None,
+                nonce: None,
+            }],
+            None,
+        );
+    };
+    let usdc_price = |e: &Env| {
+        e.contract
+            .get_price_data(Some(vec![USDC.to_string()]), None, None, None)
+            .prices[0]
+            .price
+            .map(|price| price.multiplier)
+    };
+
+    report(&mut e, 30000);
+    assert!(get_logs()
+        .iter()
+        .any(|log| log.contains(r#""event":"reject_price""#)));
+    assert_eq!(usdc_price(&e), None);
+
+    report(&mut e, 10001);
+    assert_eq!(usdc_price(&e), Some(10001));
+}
+
+#[test]
+fn test_dex_policy() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().add_dex_contract(a("ref.near"));
+    let dex_source = |policy: DexPolicy| DexSource {
+        contract_id: a("ref.near"),
+        pool_id: 3,
+        token_in: a(WRAP_NEAR),
+        amount_in: U128(10u128.pow(24)),
+        token_out: a(USDC),
+        decimals: 28,
+        policy,
+    };
+    e.as_owner().set_asset_dex_source(
+        WRAP_NEAR.to_string(),
+        Some(dex_source(DexPolicy::RequireAgreement {
+            max_deviation_bps: 100,
+        })),
+    );
+    let on_dex_price = |e: &mut Env, amount_out: Balance| {
+        e.as_user(a(ORACLE_ID))
+            .on_dex_price(WRAP_NEAR.to_string(), Ok(U128(amount_out)));
+    };
+    let wrap_near_price = |e: &Env| {
+        e.contract
+            .get_price_data(Some(vec![WRAP_NEAR.to_string()]), None, None, None)
+            .prices[0]
+            .price
+            .map(|price| price.multiplier)
+    };
+
+    e.make_reports(&[100000]);
+    assert_eq!(wrap_near_price(&e), None);
+
+    e.as_user(user(1)).refresh_dex_price(WRAP_NEAR.to_string());
+    assert_eq!(get_created_receipts().len(), 2);
+    on_dex_price(&mut e, 100500);
+    assert_eq!(wrap_near_price(&e), Some(100000));
+
+    on_dex_price(&mut e, 120000);
+    assert_eq!(wrap_near_price(&e), None);
+
+    e.as_owner()
+        .set_asset_dex_source(WRAP_NEAR.to_string(), Some(dex_source(DexPolicy::DexOnly)));
+    assert_eq!(wrap_near_price(&e), None);
+    on_dex_price(&mut e, 120000);
+    assert_eq!(wrap_near_price(&e), Some(120000));
+
+    e.skip_time(91);
+    assert_eq!(wrap_near_price(&e), None);
+}
+
+#[test]
+fn test_subscriptions() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_push_fee(U128(10));
+    set_context(a("lending.near"), e.timestamp, ONE_NEAR / 10);
+    let subscription_id = e
+        .contract
+        .subscribe(WRAP_NEAR.to_string(), 100, 60, "".to_string());
+    let subscription = e.contract.get_subscription(subscription_id).unwrap();
+    assert!(subscription.storage_deposit > 0);
+    let balance = ONE_NEAR / 10 - subscription.storage_deposit;
+    assert_eq!(subscription.balance, balance);
+
+    let num_pushes = |multiplier: Balance, e: &mut Env| {
+        e.make_reports(&[multiplier]);
+        // Reports don't push prices.
+        assert!(get_created_receipts().is_empty());
+        let num_pushes = e.as_user(user(5)).push_prices(WRAP_NEAR.to_string());
+        assert_eq!(get_created_receipts().len(), num_pushes as usize);
+        num_pushes
+    };
+    assert_eq!(num_pushes(100000, &mut e), 1);
+    // Within the threshold of the last push.
+    assert_eq!(num_pushes(100500, &mut e), 0);
+    assert_eq!(num_pushes(110000, &mut e), 1);
+
+    let subscription = e.contract.get_subscription(subscription_id).unwrap();
+    assert_eq!(subscription.balance, balance - 20);
+    assert_eq!(subscription.last_price, Some(price(110000)));
+    assert_eq!(e.contract.get_config().collected_fees.0, 20);
+
+    // The balance doesn't cover the push.
+    e.as_owner().set_push_fee(U128(ONE_NEAR));
+    assert_eq!(num_pushes(120000, &mut e), 0);
+
+    e.as_user(a("lending.near")).unsubscribe(subscription_id);
+    assert!(e.contract.get_subscription(subscription_id).is_none());
+    assert_eq!(e.contract.get_config().total_subscription_balances.0, 0);
+    assert_eq!(
+        get_created_receipts()[0].actions[0],
+        near_sdk::mock::VmAction::Transfer {
+            deposit: subscription.balance + subscription.storage_deposit
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "E020_REQUIRES_DEPOSIT")]
+fn test_subscribe_requires_storage_deposit() {
+    let mut e = Env::setup();
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_push_fee(U128(10));
+    set_context(a("lending.near"), e.timestamp, 10);
+    e.contract
+        .subscribe(WRAP_NEAR.to_string(), 100, 60, "".to_string());
+}
+
+#[test]
+#[should_panic(expected = "E091_TOO_MANY_SUBSCRIPTIONS")]
+fn test_max_subscriptions_per_asset() {
+    let mut e = Env::setup();
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_push_fee(U128(10));
+    for i in 0..=MAX_SUBSCRIPTIONS_PER_ASSET {
+        set_context(user(i), e.timestamp, ONE_NEAR / 10);
+        e.contract
+            .subscribe(WRAP_NEAR.to_string(), 100, 60, "".to_string());
+    }
+}
+
+#[test]
+fn test_rounds() {
+    let mut e = Env::setup();
+    for i in 0..3 {
+        e.add_oracle(user(i));
+    }
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_round_config(30, 2);
+
+    e.make_reports(&[100000, 110000]);
+    assert!(e.contract.latest_round(WRAP_NEAR.to_string()).is_none());
+    let round = e.contract.get_round_data(WRAP_NEAR.to_string(), 1).unwrap();
+    assert!(!round.finalized);
+    assert_eq!(round.reports.len(), 2);
+
+    e.skip_time(30);
+    e.make_reports(&[0, 0, 120000]);
+    let round = e.contract.latest_round(WRAP_NEAR.to_string()).unwrap();
+    assert_eq!(round.round_id, 1);
+    assert!(round.finalized);
+    assert_eq!(round.answer, Some(price(110000)));
+
+    // The second round only has one report, below the quorum.
+    e.skip_time(30);
+    let round = e.as_user(user(1)).finalize_round(WRAP_NEAR.to_string());
+    assert_eq!(round.round_id, 2);
+    assert!(round.answer.is_none());
+
+    e.skip_time(1);
+    e.make_reports(&[100000]);
+    e.skip_time(30);
+    e.as_user(user(1)).finalize_round(WRAP_NEAR.to_string());
+    assert!(e
+        .contract
+        .get_round_data(WRAP_NEAR.to_string(), 1)
+        .is_none());
+    assert_eq!(
+        e.contract
+            .latest_round(WRAP_NEAR.to_string())
+            .unwrap()
+            .round_id,
+        3
+    );
+}
+
+#[test]
+fn test_aggregator_views() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_asset_metadata(
+        WRAP_NEAR.to_string(),
+        Some(AssetMetadata {
+            decimals: 24,
+            symbol: "NEAR".to_string(),
+            quote_asset_id: "usd".to_string(),
+            description: None,
+        }),
+    );
+    assert_eq!(e.contract.decimals(WRAP_NEAR.to_string()), 8);
+    assert_eq!(e.contract.description(WRAP_NEAR.to_string()), "NEAR / USD");
+    assert!(e
+        .contract
+        .latest_round_data(WRAP_NEAR.to_string())
+        .is_none());
+
+    e.make_reports(&[100000]);
+    let data = e.contract.latest_round_data(WRAP_NEAR.to_string()).unwrap();
+    assert_eq!(data.round_id.0, 0);
+    assert_eq!(data.answer.0, 10 * 10i128.pow(8));
+    assert_eq!(data.updated_at.0, 1_600_000_000);
+
+    e.as_owner().set_round_config(30, 10);
+    e.make_reports(&[110000]);
+    e.skip_time(30);
+    e.as_user(user(1)).finalize_round(WRAP_NEAR.to_string());
+    let data = e.contract.latest_round_data(WRAP_NEAR.to_string()).unwrap();
+    assert_eq!(data.round_id.0, 1);
+    assert_eq!(data.answer.0, 11 * 10i128.pow(8));
+}
+
+#[test]
+fn test_aggregator_rounds_gated() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_asset_metadata(
+        WRAP_NEAR.to_string(),
+        Some(AssetMetadata {
+            decimals: 24,
+            symbol: "NEAR".to_string(),
+            quote_asset_id: "usd".to_string(),
+            description: None,
+        }),
+    );
+    e.as_owner().set_round_config(30, 10);
+    e.make_reports(&[110000]);
+    e.skip_time(30);
+    e.as_user(user(1)).finalize_round(WRAP_NEAR.to_string());
+    assert!(e
+        .contract
+        .latest_round_data(WRAP_NEAR.to_string())
+        .is_some());
+
+    e.as_owner().pause();
+    assert!(e
+        .contract
+        .latest_round_data(WRAP_NEAR.to_string())
+        .is_none());
+    e.as_owner().unpause();
+
+    let sunset_timestamp = U64(e.timestamp + to_nano(10));
+    e.as_owner()
+        .deprecate_asset(WRAP_NEAR.to_string(), sunset_timestamp, None);
+    assert!(e
+        .contract
+        .latest_round_data(WRAP_NEAR.to_string())
+        .is_some());
+    e.skip_time(10);
+    assert!(e
+        .contract
+        .latest_round_data(WRAP_NEAR.to_string())
+        .is_none());
+}
+
+#[test]
+fn test_min_report_interval() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_min_report_interval_sec(10);
+    let wrap_near_price = |e: &Env| {
+        e.contract
+            .get_price_data(Some(vec![WRAP_NEAR.to_string()]), None, None, None)
+            .prices[0]
+            .price
+    };
+
+    e.make_reports(&[100000]);
+    e.skip_time(5);
+    e.make_reports(&[110000]);
+    assert!(get_logs()
+        .iter()
+        .any(|log| log.contains(r#""event":"ignore_report""#)));
+    assert_eq!(wrap_near_price(&e), Some(price(100000)));
+
+    e.skip_time(5);
+    e.make_reports(&[110000]);
+    assert_eq!(wrap_near_price(&e), Some(price(110000)));
+
+    e.as_owner()
+        .set_asset_min_report_interval_sec(WRAP_NEAR.to_string(), Some(0));
+    e.make_reports(&[120000]);
+    assert_eq!(wrap_near_price(&e), Some(price(120000)));
+}
+
+#[test]
+#[should_panic(expected = "Missing a price for usdc.near")]
+fn test_oracle_call_require_all() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.add_asset(USDC);
+    e.make_reports(&[100000]);
+    set_context(user(1), e.timestamp, 1);
+    e.contract.oracle_call(
+        user(2),
+        Some(vec![WRAP_NEAR.to_string()]),
+        "".to_string(),
+        Some(true),
+        None,
+        None,
+        None,
+    );
+    set_context(user(1), e.timestamp, 1);
+    e.contract
+        .oracle_call(user(2), None, "".to_string(), Some(true), None, None, None);
+}
+
+#[test]
+fn test_price_data_commitment() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.make_reports(&[100000]);
+    assert!(e.contract.get_last_price_data_commitment().is_none());
+    set_context(user(1), e.timestamp, 1);
+    e.contract.oracle_call(
+        user(2),
+        Some(vec![WRAP_NEAR.to_string()]),
+        "".to_string(),
+        None,
+        None,
+        None,
+        None,
+    );
+
+    let delivered = e
+        .contract
+        .get_price_data(Some(vec![WRAP_NEAR.to_string()]), None, None, None);
+    let commitment = e.contract.get_last_price_data_commitment().unwrap();
+    assert_eq!(
+        CryptoHash::from(commitment.commitment),
+        delivered.commitment_hash()
+    );
+    let mut with_commitment = delivered.clone();
+    with_commitment.commitment = Some(commitment.commitment);
+    assert_eq!(
+        with_commitment.commitment_hash(),
+        delivered.commitment_hash()
+    );
+}
+
+#[test]
+fn test_max_staleness() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.make_reports(&[100000]);
+    e.skip_time(30);
+
+    let wrap_near_price = |e: &Env, max_staleness_sec| {
+        e.contract
+            .get_price_data(
+                Some(vec![WRAP_NEAR.to_string()]),
+                None,
+                None,
+                max_staleness_sec,
+            )
+            .prices[0]
+            .price
+    };
+    assert_eq!(wrap_near_price(&e, None), Some(price(100000)));
+    assert_eq!(wrap_near_price(&e, Some(60)), Some(price(100000)));
+    assert_eq!(wrap_near_price(&e, Some(20)), None);
+}
+
+#[test]
+#[should_panic(expected = "Missing a price for wrap.near")]
+fn test_oracle_call_max_staleness() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.make_reports(&[100000]);
+    e.skip_time(30);
+    set_context(user(1), e.timestamp, 1);
+    e.contract.oracle_call(
+        user(2),
+        Some(vec![WRAP_NEAR.to_string()]),
+        "".to_string(),
+        Some(true),
+        None,
+        None,
+        Some(20),
+    );
+}
+
+#[test]
+fn test_price_receipts() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.make_reports(&[100000]);
+    e.as_owner().set_price_receipts_enabled(true);
+    for _ in 0..2 {
+        set_context(user(1), e.timestamp, 1);
+        e.contract.oracle_call(
+            user(2),
+            Some(vec![WRAP_NEAR.to_string()]),
+            "".to_string(),
+            None,
+            None,
+            None,
+            None,
+        );
+    }
+    assert_eq!(e.contract.get_num_price_receipts().0, 2);
+    let receipt = e.contract.get_price_receipt(U64(1)).unwrap();
+    assert_eq!(receipt.sender_id, user(1));
+    assert_eq!(receipt.receiver_ids, vec![user(2)]);
+    assert_eq!(receipt.price_data.receipt_id, Some(U64(1)));
+    assert_eq!(receipt.price_data.prices[0].price, Some(price(100000)));
+    assert_eq!(
+        receipt.price_data.commitment,
+        e.contract
+            .get_last_price_data_commitment()
+            .map(|commitment| commitment.commitment)
+    );
+    assert!(e.contract.get_price_receipt(U64(2)).is_none());
+}
+
+#[test]
+fn test_fallback_oracle() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.add_asset(DAI);
+    e.as_owner()
+        .set_asset_fallback_oracle(WRAP_NEAR.to_string(), Some(a("fallback.near")));
+    e.make_reports(&[100000]);
+    e.skip_time(100);
+    let asset_ids = vec![WRAP_NEAR.to_string(), DAI.to_string()];
+    let mut price_data = e
+        .contract
+        .get_price_data(Some(asset_ids.clone()), None, None, None);
+    let fallback_asset_ids = vec![vec![WRAP_NEAR.to_string()]];
+    assert_eq!(
+        e.contract.internal_fallback_requests(&price_data),
+        vec![(a("fallback.near"), fallback_asset_ids[0].clone())]
+    );
+
+    // A stale asset with a fallback oracle doesn't fail requests of all prices.
+    set_context(user(1), e.timestamp, 1);
+    e.contract.oracle_call(
+        user(2),
+        Some(vec![WRAP_NEAR.to_string()]),
+        "".to_string(),
+        Some(true),
+        None,
+        None,
+        None,
+    );
+
+    set_context(user(0), e.timestamp, 0);
+    e.contract.report_prices(wrap_near_prices(101000), None);
+    let fallback_price_data = e.contract.get_price_data(Some(asset_ids), None, None, None);
+    apply_fallback_prices(
+        &mut price_data,
+        &fallback_asset_ids,
+        vec![Some(fallback_price_data)],
+    );
+    assert_eq!(price_data.prices[0].price, Some(price(101000)));
+    assert!(price_data.prices[0].fallback);
+    assert!(price_data.prices[1].price.is_none());
+    assert!(!price_data.prices[1].fallback);
+}
+
+#[test]
+fn test_price_data_paged() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    for asset_id in [WRAP_NEAR, USDC, DAI] {
+        e.add_asset(asset_id);
+    }
+    e.make_reports(&[100000]);
+    let page: Vec<_> = e
+        .contract
+        .get_price_data_paged(Some(0), Some(2))
+        .prices
+        .into_iter()
+        .map(|price| price.asset_id)
+        .collect();
+    assert_eq!(page, vec![WRAP_NEAR.to_string(), USDC.to_string()]);
+    let page = e.contract.get_price_data_paged(Some(2), None).prices;
+    assert_eq!(page.len(), 1);
+    assert_eq!(page[0].asset_id, DAI);
+    assert!(e
+        .contract
+        .get_price_data_paged(Some(3), None)
+        .prices
+        .is_empty());
+}
+
+#[test]
+fn test_asset_reports() {
+    let mut e = Env::setup();
+    for i in 0..3 {
+        e.add_oracle(user(i));
+    }
+    e.add_asset(WRAP_NEAR);
+    e.make_reports(&[100000]);
+    e.skip_time(60);
+    e.as_owner()
+        .set_asset_max_deviation_bps(WRAP_NEAR.to_string(), Some(1000));
+    e.make_reports(&[0, 100000, 101000]);
+    e.skip_time(1);
+    e.make_reports(&[0, 0, 200000]);
+    e.skip_time(30);
+
+    let reports = e.contract.get_asset_reports(WRAP_NEAR.to_string()).unwrap();
+    assert_eq!(reports.price, None);
+    let inclusions: Vec<_> = reports
+        .reports
+        .into_iter()
+        .map(|report| (report.oracle_id, report.inclusion))
+        .collect();
+    assert_eq!(
+        inclusions,
+        vec![
+            (user(0), ReportInclusion::Stale),
+            (user(1), ReportInclusion::Included),
+            (user(2), ReportInclusion::Outlier),
+        ]
+    );
+}
+
+#[test]
+fn test_expiry_policy() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.make_reports(&[100000]);
+    e.skip_time(91);
+    let wrap_near_price = |e: &Env| {
+        let price = e
+            .contract
+            .get_price_data(Some(vec![WRAP_NEAR.to_string()]), None, None, None)
+            .prices
+            .remove(0);
+        (price.price, price.expired)
+    };
+    assert_eq!(wrap_near_price(&e), (None, false));
+
+    e.as_owner()
+        .set_expiry_policy(ExpiryPolicy::ReturnLastGoodWithFlag);
+    assert_eq!(wrap_near_price(&e), (Some(price(100000)), true));
+
+    e.make_reports(&[110000]);
+    assert_eq!(wrap_near_price(&e), (Some(price(110000)), false));
+}
+
+#[test]
+#[should_panic(expected = "The price of wrap.near is stale")]
+fn test_expiry_policy_panic() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.add_asset(USDC);
+    e.as_owner()
+        .set_asset_expiry_policy(WRAP_NEAR.to_string(), Some(ExpiryPolicy::Panic));
+    e.contract
+        .get_price_data(Some(vec![USDC.to_string()]), None, None, None);
+    e.contract.get_price_data(None, None, None, None);
+}
+
+#[test]
+fn test_commit_reveal() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_oracle(user(1));
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_asset_commit_reveal(
+        WRAP_NEAR.to_string(),
+        Some(CommitRevealConfig {
+            commit_duration_sec: 10,
+            reveal_duration_sec: 10,
+        }),
+    );
+    let wrap_near_price = |e: &Env| {
+        e.contract
+            .get_price_data(Some(vec![WRAP_NEAR.to_string()]), None, None, None)
+            .prices[0]
+            .price
+    };
+    let asset_id = WRAP_NEAR.to_string();
+
+    e.make_reports(&[100000, 100000]);
+    assert_eq!(wrap_near_price(&e), None);
+
+    for (i, multiplier) in [(0, 100000), (1, 110000)] {
+        e.as_user(user(i)).commit_prices(vec![PriceCommitment {
+            asset_id: asset_id.clone(),
+            hash: commitment_hash(&asset_id, &price(multiplier), "salt").into(),
+        }]);
+    }
+    e.skip_time(10);
+    for (i, multiplier) in [(0, 100000), (1, 110000)] {
+        e.as_user(user(i)).reveal_prices(vec![PriceReveal {
+            asset_id: asset_id.clone(),
+            price: price(multiplier),
+            salt: "salt".to_string(),
+        }]);
+    }
+    assert_eq!(wrap_near_price(&e), Some(price(110000)));
+    assert!(e
+        .contract
+        .get_asset(asset_id)
+        .unwrap()
+        .commitments
+        .is_empty());
+}
+
+#[test]
+#[should_panic(expected = "The price doesn't match the commitment")]
+fn test_commit_reveal_mismatch() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_asset_commit_reveal(
+        WRAP_NEAR.to_string(),
+        Some(CommitRevealConfig {
+            commit_duration_sec: 10,
+            reveal_duration_sec: 10,
+        }),
+    );
+    let asset_id = WRAP_NEAR.to_string();
+    e.as_user(user(0)).commit_prices(vec![PriceCommitment {
+        asset_id: asset_id.clone(),
+        hash: commitment_hash(&asset_id, &price(100000), "salt").into(),
+    }]);
+    e.skip_time(10);
+    e.as_user(user(0)).reveal_prices(vec![PriceReveal {
+        asset_id,
+        price: price(120000),
+        salt: "salt".to_string(),
+    }]);
+}
+
+#[test]
+fn test_depeg_status() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_asset_peg(
+        WRAP_NEAR.to_string(),
+        Some(Peg {
+            price: Price {
+                multiplier: 10,
+                decimals: 24,
+            },
+            max_deviation_bps: 100,
+            min_duration_sec: 60,
+        }),
+    );
+    let status = |e: &Env| e.contract.get_depeg_status(WRAP_NEAR.to_string()).unwrap();
+
+    e.make_reports(&[100050]);
+    let depeg_status = status(&e);
+    assert_eq!(depeg_status.deviation_bps, Some(5));
+    assert!(depeg_status.depegged_since.is_none());
+
+    e.make_reports(&[97000]);
+    e.skip_time(30);
+    e.make_reports(&[97000]);
+    let depeg_status = status(&e);
+    assert_eq!(depeg_status.deviation_bps, Some(300));
+    assert_eq!(depeg_status.depeg_duration_sec, 30);
+    assert!(!depeg_status.breached);
+
+    e.skip_time(30);
+    e.make_reports(&[97000]);
+    assert!(status(&e).breached);
+
+    e.make_reports(&[100000]);
+    let depeg_status = status(&e);
+    assert_eq!(depeg_status.deviation_bps, Some(0));
+    assert!(!depeg_status.breached);
+}
+
+#[test]
+fn test_ema_warm_up_and_reset() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().add_asset_ema(WRAP_NEAR.to_string(), 60);
+    e.as_owner()
+        .set_asset_ema_warm_up_sec(WRAP_NEAR.to_string(), 60, 30);
+    let ema_price = |e: &Env| {
+        e.contract
+            .get_price_data(Some(vec![format!("{}#60", WRAP_NEAR)]), None, None, None)
+            .prices[0]
+            .price
+    };
+
+    e.make_reports(&[100000]);
+    assert_eq!(ema_price(&e), None);
+    let state = e.contract.get_ema_state(WRAP_NEAR.to_string(), 60).unwrap();
+    assert_eq!(state.ema.started_at, ts(0));
+    assert!(!state.warmed_up);
+
+    e.skip_time(30);
+    e.make_reports(&[100000]);
+    assert!(ema_price(&e).is_some());
+
+    e.as_owner().reset_ema(WRAP_NEAR.to_string(), 60);
+    assert_eq!(ema_price(&e), None);
+    e.make_reports(&[200000]);
+    let state = e.contract.get_ema_state(WRAP_NEAR.to_string(), 60).unwrap();
+    assert_eq!(state.ema.price, Some(price(200000)));
+    assert_eq!(state.ema.started_at, ts(30));
+}
+
+#[test]
+fn test_price_conversion() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.add_asset(DAI);
+    e.as_user(user(0)).report_prices(
+        vec![
+            AssetPrice {
+                asset_id: WRAP_NEAR.to_string(),
+                price: price(100000),
+                quote_asset_id: None,
+                nonce: None,
+            },
+            AssetPrice {
+                asset_id: DAI.to_string(),
+                price: price(10u128.pow(10)),
+                quote_asset_id: None,
+                nonce: None,
+            },
+        ],
+        None,
+    );
+    let five_near = U128(5 * 10u128.pow(24));
+    assert_eq!(
+        e.contract.value_in_usd(WRAP_NEAR.to_string(), five_near),
+        Some(U128(50 * 10u128.pow(18)))
+    );
+    assert_eq!(
+        e.contract
+            .convert_amount(five_near, WRAP_NEAR.to_string(), DAI.to_string()),
+        Some(U128(50 * 10u128.pow(18)))
+    );
+    assert_eq!(
+        e.contract
+            .convert_amount(five_near, WRAP_NEAR.to_string(), USDC.to_string()),
+        None
+    );
+}
+
+#[test]
+fn test_mul_div_pow10() {
+    assert_eq!(
+        mul_div_pow10(u128::MAX, u128::MAX, 0, u128::MAX),
+        Some(u128::MAX)
+    );
+    assert_eq!(mul_div_pow10(u128::MAX, 10, -1, 1), Some(u128::MAX));
+    assert_eq!(mul_div_pow10(u128::MAX, 10, 0, 1), None);
+    assert_eq!(mul_div_pow10(7, 3, 40, 10u128.pow(38)), Some(2100));
+    assert_eq!(mul_div_pow10(12345, 1, -50, 1), Some(0));
+    assert_eq!(mul_div_pow10(1, 1, 0, 0), None);
+}
+
+#[test]
+fn test_asset_quotes() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_owner()
+        .add_asset_quote(WRAP_NEAR.to_string(), USDC.to_string());
+    e.as_user(user(0)).report_prices(
+        vec![
+            AssetPrice {
+                asset_id: WRAP_NEAR.to_string(),
+                price: price(100000),
+                quote_asset_id: None,
+                nonce: None,
+            },
+            AssetPrice {
+                asset_id: WRAP_NEAR.to_string(),
+                price: price(99000),
+                quote_asset_id: Some(USDC.to_string()),
+                nonce: None,
+            },
+            AssetPrice {
+                asset_id: WRAP_NEAR.to_string(),
+                price: price(5),
+                quote_asset_id: Some(DAI.to_string()),
+                nonce: None,
+            },
+        ],
+        None,
+    );
+    assert_eq!(e.contract.get_assets(None, None).len(), 1);
+    let prices = |e: &Env, quote_asset_id: Option<&str>| {
+        e.contract
+            .get_price_data(None, quote_asset_id.map(|id| id.to_string()), None, None)
+            .prices[0]
+            .price
+    };
+    assert_eq!(prices(&e, None), Some(price(100000)));
+    assert_eq!(prices(&e, Some(USDC)), Some(price(99000)));
+    assert!(prices(&e, Some(DAI)).is_none());
+
+    e.as_owner()
+        .remove_asset_quote(WRAP_NEAR.to_string(), USDC.to_string());
+    assert!(prices(&e, Some(USDC)).is_none());
+    assert_eq!(prices(&e, None), Some(price(100000)));
+}
+
+#[test]
+fn test_failover() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_failover(Some(FailoverConfig {
+        backup_oracle_ids: vec![user(1), user(2)],
+        min_live_oracles: 1,
+        failover_window_sec: 60,
+    }));
+    e.skip_time(30);
+    e.make_reports(&[100000]);
+    e.skip_time(60);
+    assert_eq!(e.contract.get_num_live_oracles(), 1);
+
+    e.skip_time(1);
+    assert_eq!(e.contract.get_num_live_oracles(), 0);
+    e.as_user(user(3)).activate_failover();
+    assert!(e.contract.get_oracle(user(1)).is_some());
+    assert!(e.contract.get_oracle(user(2)).is_some());
+    assert!(e.contract.get_config().failover.is_none());
+    e.make_reports(&[0, 101000]);
+    assert_eq!(
+        e.contract.get_price_data(None, None, None, None).prices[0].price,
+        Some(price(101000))
+    );
+}
+
+#[test]
+#[should_panic(expected = "Enough oracles are live")]
+fn test_failover_with_live_oracles() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_failover(Some(FailoverConfig {
+        backup_oracle_ids: vec![user(1)],
+        min_live_oracles: 1,
+        failover_window_sec: 60,
+    }));
+    e.skip_time(60);
+    e.make_reports(&[100000]);
+    e.as_user(user(3)).activate_failover();
+}
+
+#[test]
+fn test_asset_consumers() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.add_asset(USDC);
+    e.as_owner()
+        .set_asset_consumers(WRAP_NEAR.to_string(), Some(vec![user(1)]));
+    e.make_reports(&[100000]);
+
+    let price_data = e.contract.get_price_data(None, None, None, None);
+    assert_eq!(price_data.prices.len(), 1);
+    assert_eq!(price_data.prices[0].asset_id, USDC);
+
+    let wrap_near_price = |e: &mut Env, account_id: AccountId| {
+        e.as_user(account_id)
+            .get_price_data(Some(vec![WRAP_NEAR.to_string()]), None, None, None)
+            .prices[0]
+            .price
+    };
+    assert_eq!(wrap_near_price(&mut e, user(1)), Some(price(100000)));
+    assert!(e
+        .contract
+        .get_assets(None, None)
+        .iter()
+        .all(|(asset_id, _)| asset_id == USDC));
+
+    e.as_owner().set_push_fee(U128(10));
+    set_context(user(2), e.timestamp, ONE_NEAR / 10);
+    e.contract
+        .subscribe(WRAP_NEAR.to_string(), 100, 60, "".to_string());
+    assert_eq!(wrap_near_price(&mut e, user(2)), Some(price(100000)));
+    assert!(e
+        .as_user(user(2))
+        .get_asset_reports(WRAP_NEAR.to_string())
+        .is_some());
+
+    set_context(user(3), e.timestamp, 1);
+    e.contract.oracle_call(
+        user(1),
+        Some(vec![WRAP_NEAR.to_string()]),
+        "".to_string(),
+        None,
+        None,
+        None,
+        None,
+    );
+}
+
+#[test]
+#[should_panic(expected = "No access to prices of wrap.near#60")]
+fn test_asset_consumers_no_access() {
+    let mut e = Env::setup();
+    e.add_asset(WRAP_NEAR);
+    e.as_owner()
+        .set_asset_consumers(WRAP_NEAR.to_string(), Some(vec![user(1)]));
+    e.as_user(user(2))
+        .get_price_data(Some(vec![format!("{}#60", WRAP_NEAR)]), None, None, None);
+}
+
+#[test]
+#[should_panic(expected = "E090_PUSH_FEE_REQUIRED")]
+fn test_subscribe_requires_push_fee() {
+    let mut e = Env::setup();
+    e.add_asset(WRAP_NEAR);
+    set_context(user(2), e.timestamp, ONE_NEAR / 10);
+    e.contract
+        .subscribe(WRAP_NEAR.to_string(), 100, 60, "".to_string());
+}
+
+#[test]
+#[should_panic(expected = "No access to prices of dai/wrap.near")]
+fn test_asset_consumers_pair() {
+    let mut e = Env::setup();
+    e.add_asset(WRAP_NEAR);
+    e.add_asset(DAI);
+    e.as_owner().define_pair(
+        "dai/wrap.near".to_string(),
+        DerivedPair {
+            base_asset_id: DAI.to_string(),
+            quote_asset_id: WRAP_NEAR.to_string(),
+            operation: PairOperation::Divide,
+        },
+    );
+    e.as_owner()
+        .set_asset_consumers(WRAP_NEAR.to_string(), Some(vec![user(1)]));
+    e.as_user(user(2))
+        .get_price_data(Some(vec!["dai/wrap.near".to_string()]), None, None, None);
+}
+
+#[test]
+#[should_panic(expected = "No access to prices of wrap.near@usdc")]
+fn test_asset_consumers_quoted_asset() {
+    let mut e = Env::setup();
+    e.add_asset(WRAP_NEAR);
+    e.as_owner()
+        .set_asset_consumers(WRAP_NEAR.to_string(), Some(vec![user(1)]));
+    e.as_user(user(2))
+        .get_price_checked(format!("{}@usdc", WRAP_NEAR));
+}
+
+#[test]
+#[should_panic(expected = "No access to prices of wrap.near")]
+fn test_asset_consumers_reports() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_owner()
+        .set_asset_consumers(WRAP_NEAR.to_string(), Some(vec![user(1)]));
+    e.make_reports(&[100000]);
+    e.as_user(user(2)).get_asset_reports(WRAP_NEAR.to_string());
+}
+
+#[test]
+fn test_report_nonces() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    let report = |e: &mut Env, multiplier: Balance, nonce: u64| {
+        e.as_user(user(0)).report_prices(
+            vec![AssetPrice {
+                asset_id: WRAP_NEAR.to_string(),
+                price: price(multiplier),
+                quote_asset_id: None,
+                nonce: Some(U64(nonce)),
+            }],
+            None,
+        );
+        e.contract
+            .get_price_data(None, None, None, None)
+            .prices
+            .remove(0)
+            .price
+    };
+    assert_eq!(report(&mut e, 100000, 2), Some(price(100000)));
+    assert_eq!(report(&mut e, 90000, 2), Some(price(100000)));
+    assert_eq!(report(&mut e, 90000, 1), Some(price(100000)));
+    assert_eq!(report(&mut e, 110000, 3), Some(price(110000)));
+    assert_eq!(
+        e.contract.get_oracle(user(0)).unwrap().asset_nonces[WRAP_NEAR],
+        3
+    );
+
+    // A report rejected by the price band doesn't consume its nonce.
+    e.as_owner().set_asset_price_band(
+        WRAP_NEAR.to_string(),
+        Some(PriceBand {
+            min: price(50000),
+            max: price(200000),
+        }),
+    );
+    assert_eq!(report(&mut e, 500000, 4), Some(price(110000)));
+    assert_eq!(
+        e.contract.get_oracle(user(0)).unwrap().asset_nonces[WRAP_NEAR],
+        3
+    );
+    assert_eq!(report(&mut e, 120000, 4), Some(price(120000)));
+}
+
+#[test]
+fn test_multi_block_median() {
+    let mut e = Env::setup();
+    for i in 0..3 {
+        e.add_oracle(user(i));
+    }
+    e.add_asset(WRAP_NEAR);
+    e.as_owner()
+        .set_asset_multi_block_median_len(WRAP_NEAR.to_string(), Some(3));
+    let set_block = |account_id: AccountId, block_height: BlockHeight| {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(a(ORACLE_ID))
+            .predecessor_account_id(account_id)
+            .block_timestamp(ts(0))
+            .block_index(block_height)
+            .account_balance(1000 * ONE_NEAR)
+            .build());
+    };
+    let report = |e: &mut Env, i: usize, multiplier: Balance, block_height: BlockHeight| {
+        set_block(user(i), block_height);
+        e.contract.report_prices(wrap_near_prices(multiplier), None);
+    };
+    let wrap_near_price = |e: &Env, block_height: BlockHeight| {
+        set_block(a(OWNER_ID), block_height);
+        e.contract.get_price_data(None, None, None, None).prices[0].price
+    };
+
+    report(&mut e, 0, 100000, 10);
+    report(&mut e, 1, 101000, 10);
+    assert!(wrap_near_price(&e, 10).is_none());
+    assert_eq!(wrap_near_price(&e, 11), Some(price(101000)));
+
+    report(&mut e, 2, 100000, 11);
+    // A burst of reports within one block only affects its own block median.
+    report(&mut e, 0, 200000, 12);
+    report(&mut e, 1, 200000, 12);
+    assert_eq!(wrap_near_price(&e, 12), Some(price(101000)));
+    assert_eq!(wrap_near_price(&e, 13), Some(price(101000)));
+    assert_eq!(
+        e.contract
+            .get_asset(WRAP_NEAR.to_string())
+            .unwrap()
+            .block_medians
+            .len(),
+        3
+    );
+}
+
+#[test]
+fn test_oracle_health() {
+    let mut e = Env::setup();
+    for i in 0..2 {
+        e.add_oracle(user(i));
+    }
+    e.add_asset(WRAP_NEAR);
+    e.add_asset(USDC);
+    e.add_asset(DAI);
+    e.as_owner()
+        .set_asset_group(DAI.to_string(), Some("stables".to_string()));
+    e.as_owner().pause_group("stables".to_string());
+    e.make_reports(&[100000, 100000]);
+    e.skip_time(10);
+    e.as_user(user(0)).report_prices(
+        vec![AssetPrice {
+            asset_id: USDC.to_string(),
+            price: price(10000),
+            quote_asset_id: None,
+            nonce: None,
+        }],
+        None,
+    );
+
+    let health = e.contract.get_oracle_health();
+    assert_eq!(health.num_assets, 3);
+    assert_eq!(health.num_fresh_assets, 2);
+    assert_eq!(health.num_paused_assets, 1);
+    assert_eq!(health.num_oracles, 2);
+    assert_eq!(health.num_active_oracles, 2);
+    assert_eq!(health.oldest_fresh_report, Some(U64(ts(0))));
+    assert!(!health.paused);
+    assert!(health.staged_code_hash.is_none());
+
+    e.skip_time(85);
+    let health = e.contract.get_oracle_health();
+    assert_eq!(health.num_fresh_assets, 1);
+    assert_eq!(health.num_active_oracles, 1);
+    assert_eq!(health.oldest_fresh_report, Some(U64(ts(10))));
+}
+
+#[test]
+fn test_gas_budget() {
+    let _e = Env::setup();
+    let default_gas = GasBudget::default().receiver_gas(1);
+    let floor_gas = GasBudget {
+        reserved_gas: Some(Gas(0)),
+        min_receiver_gas: None,
+    }
+    .receiver_gas(1);
+    assert!(floor_gas.0 > default_gas.0 + 4 * Gas::ONE_TERA.0);
+    let split_gas = GasBudget {
+        reserved_gas: Some(Gas(20 * Gas::ONE_TERA.0)),
+        min_receiver_gas: Some(Gas(50 * Gas::ONE_TERA.0)),
+    }
+    .receiver_gas(2);
+    assert!(split_gas.0 < default_gas.0 / 2 - 20 * Gas::ONE_TERA.0);
+}
+
+#[test]
+#[should_panic(expected = "The receivers would get less than")]
+fn test_oracle_call_min_receiver_gas() {
+    let mut e = Env::setup();
+    e.add_asset(WRAP_NEAR);
+    set_context(user(0), e.timestamp, 1);
+    e.contract.oracle_call(
+        user(1),
+        None,
+        "".to_string(),
+        None,
+        Some(GasBudget {
+            reserved_gas: None,
+            min_receiver_gas: Some(Gas(300 * Gas::ONE_TERA.0)),
+        }),
+        None,
+        None,
+    );
+}
+
+#[test]
+fn test_oracle_call_complete() {
+    let mut e = Env::setup();
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_fees(U128(1000), U128(0));
+    set_context(user(0), e.timestamp, 1000);
+    e.contract
+        .oracle_call(user(1), None, "".to_string(), None, None, None, None);
+    set_context(user(0), e.timestamp, 1000);
+    e.contract
+        .oracle_call(user(1), None, "".to_string(), None, None, None, None);
+
+    set_context(a(ORACLE_ID), e.timestamp, 0);
+    assert_eq!(
+        e.contract
+            .internal_on_oracle_call_complete(user(0), user(1), 1000, Some(b"true".to_vec())),
+        Some(b"true".to_vec())
+    );
+    e.contract
+        .internal_on_oracle_call_complete(user(0), user(1), 1000, None);
+    assert!(get_created_receipts().is_empty());
+
+    e.as_owner().set_refund_failed_calls(true);
+    set_context(a(ORACLE_ID), e.timestamp, 0);
+    e.contract
+        .internal_on_oracle_call_complete(user(0), user(1), 1000, None);
+    assert_eq!(get_created_receipts()[0].receiver_id, user(0));
+    let stats = e.contract.get_consumer_stats(user(0)).unwrap();
+    assert_eq!(stats.num_calls, 2);
+    assert_eq!(stats.num_failed_calls, 2);
+    assert_eq!(stats.fees_paid, 2000);
+    assert_eq!(stats.fees_refunded, 1000);
+    assert_eq!(e.contract.get_config().collected_fees.0, 1000);
+}
+
+#[test]
+fn test_bundles() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.add_asset(USDC);
+    e.add_asset(DAI);
+    e.as_owner().set_bundle(
+        "stablecoins".to_string(),
+        vec![DAI.to_string(), USDC.to_string()],
+    );
+    let price_data = e
+        .contract
+        .get_price_data(None, None, Some("stablecoins".to_string()), None);
+    let asset_ids: Vec<_> = price_data
+        .prices
+        .into_iter()
+        .map(|price| price.asset_id)
+        .collect();
+    assert_eq!(asset_ids, vec![DAI.to_string(), USDC.to_string()]);
+
+    e.as_owner().set_fees(U128(1000), U128(100));
+    set_context(user(1), e.timestamp, 1200);
+    e.contract.oracle_call(
+        user(2),
+        None,
+        "".to_string(),
+        None,
+        None,
+        Some("stablecoins".to_string()),
+        None,
+    );
+    assert_eq!(e.contract.get_config().collected_fees.0, 1200);
+
+    e.as_owner().remove_bundle("stablecoins".to_string());
+    assert!(e.contract.get_bundle("stablecoins".to_string()).is_none());
+}
+
+#[test]
+#[should_panic(expected = "Requires either asset IDs or a bundle")]
+fn test_bundle_with_asset_ids() {
+    let mut e = Env::setup();
+    e.add_asset(WRAP_NEAR);
+    e.as_owner()
+        .set_bundle("near".to_string(), vec![WRAP_NEAR.to_string()]);
+    e.contract.get_price_data(
+        Some(vec![WRAP_NEAR.to_string()]),
+        None,
+        Some("near".to_string()),
+        None,
+    );
+}
+
+#[test]
+fn test_execute_owner_actions() {
+    let mut e = Env::setup();
+    e.as_owner().set_dao_id(Some(a("dao.sputnik-dao.near")));
+    set_context(a("dao.sputnik-dao.near"), e.timestamp, 1);
+    e.contract.execute_owner_actions(vec![
+        OwnerAction::AddAsset {
+            asset_id: WRAP_NEAR.to_string(),
+        },
+        OwnerAction::SetAssetRecencyDurationSec {
+            asset_id: WRAP_NEAR.to_string(),
+            recency_duration_sec: Some(300),
+        },
+        OwnerAction::AddOracle {
+            account_id: user(0),
+        },
+    ]);
+
+    let asset = e.contract.get_asset(WRAP_NEAR.to_string()).unwrap();
+    assert_eq!(asset.recency_duration_sec, Some(300));
+    assert!(e.contract.get_oracle(user(0)).is_some());
+    assert_eq!(
+        e.contract.get_config().dao_id,
+        Some(a("dao.sputnik-dao.near"))
+    );
+}
+
+#[test]
+#[should_panic(
+    expected = "E097_REQUIRES_ROLE: Can only be called by the owner or an account with the Admin role"
+)]
+fn test_execute_owner_actions_without_role() {
+    let mut e = Env::setup();
+    set_context(user(0), e.timestamp, 1);
+    e.contract
+        .execute_owner_actions(vec![OwnerAction::SetRecencyDurationSec {
+            recency_duration_sec: 60,
+        }]);
+}
+
+fn setup_multisig(e: &mut Env) {
+    e.as_owner().set_multisig(Some(Multisig {
+        admin_ids: vec![user(0), user(1), user(2)],
+        threshold: 2,
+        proposal_duration_sec: 3600,
+    }));
+}
+
+#[test]
+fn test_multisig() {
+    let mut e = Env::setup();
+    setup_multisig(&mut e);
+    let actions = || {
+        vec![OwnerAction::AddAsset {
+            asset_id: WRAP_NEAR.to_string(),
+        }]
+    };
+    set_context(user(0), e.timestamp, 1);
+    let proposal_id = e.contract.propose_owner_actions(actions());
+    assert_eq!(e.contract.get_owner_proposals(None, None).len(), 1);
+
+    set_context(user(1), e.timestamp, 1);
+    e.contract.confirm_owner_proposal(proposal_id);
+    assert!(e.contract.get_owner_proposals(None, None).is_empty());
+    assert_eq!(get_created_receipts().len(), 2);
+
+    // The scheduled call of the contract to itself.
+    set_context(a(ORACLE_ID), e.timestamp, 1);
+    e.contract.execute_owner_actions(actions());
+    assert!(e.contract.get_asset(WRAP_NEAR.to_string()).is_some());
+}
+
+#[test]
+fn test_multisig_failed_actions() {
+    let mut e = Env::setup();
+    setup_multisig(&mut e);
+    set_context(user(0), e.timestamp, 1);
+    let proposal_id = e.contract.propose_owner_actions(vec![OwnerAction::Pause]);
+    let (_, mut proposal) = e.contract.get_owner_proposals(None, None).pop().unwrap();
+    set_context(user(1), e.timestamp, 1);
+    e.contract.confirm_owner_proposal(proposal_id);
+    // The callback gets the proposal with all confirmations.
+    proposal.confirmations.push(user(1));
+    assert!(!get_logs()
+        .iter()
+        .any(|log| log.contains(r#""event":"execute_owner_proposal""#)));
+
+    testing_env!(
+        VMContextBuilder::new()
+            .current_account_id(a(ORACLE_ID))
+            .predecessor_account_id(a(ORACLE_ID))
+            .block_timestamp(e.timestamp)
+            .build(),
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        Default::default(),
+        vec![PromiseResult::Failed],
+    );
+    e.contract.on_execute_owner_actions(proposal_id, proposal);
+    let proposals = e.contract.get_owner_proposals(None, None);
+    assert_eq!(proposals.len(), 1);
+    assert_eq!(proposals[0].1.confirmations, vec![user(0), user(1)]);
+    assert!(get_logs().is_empty());
+}
+
+#[test]
+#[should_panic(expected = "E093_NOT_ENOUGH_PREPAID_GAS")]
+fn test_multisig_requires_gas() {
+    let mut e = Env::setup();
+    setup_multisig(&mut e);
+    set_context(user(0), e.timestamp, 1);
+    let proposal_id = e.contract.propose_owner_actions(vec![OwnerAction::Pause]);
+    testing_env!(VMContextBuilder::new()
+        .current_account_id(a(ORACLE_ID))
+        .predecessor_account_id(user(1))
+        .block_timestamp(e.timestamp)
+        .attached_deposit(1)
+        .prepaid_gas(Gas(Gas::ONE_TERA.0 * 50))
+        .build());
+    e.contract.confirm_owner_proposal(proposal_id);
+}
+
+#[test]
+#[should_panic(
+    expected = "Can only be called by the owner or an account with the AssetManager role"
+)]
+fn test_multisig_owner_loses_privileges() {
+    let mut e = Env::setup();
+    setup_multisig(&mut e);
+    e.add_asset(WRAP_NEAR);
+}
+
+#[test]
+#[should_panic(
+    expected = "E097_REQUIRES_ROLE: Can only be called by the owner or an account with the Admin role"
+)]
+fn test_multisig_suspends_roles() {
+    let mut e = Env::setup();
+    e.as_owner().grant_role(user(5), Role::Admin);
+    e.as_owner().grant_role(user(6), Role::Guardian);
+    setup_multisig(&mut e);
+    set_context(user(6), e.timestamp, 1);
+    e.contract.pause();
+    set_context(user(5), e.timestamp, 1);
+    e.contract.unpause();
+}
+
+#[test]
+#[should_panic(
+    expected = "Can only be called by the owner or an account with the AssetManager role"
+)]
+fn test_multisig_suspends_dao() {
+    let mut e = Env::setup();
+    e.as_owner().set_dao_id(Some(a("dao.sputnik-dao.near")));
+    setup_multisig(&mut e);
+    set_context(a("dao.sputnik-dao.near"), e.timestamp, 1);
+    e.contract.add_asset(WRAP_NEAR.to_string());
+}
+
+#[test]
+#[should_panic(expected = "E084_ALREADY_CONFIRMED")]
+fn test_multisig_confirm_twice() {
+    let mut e = Env::setup();
+    setup_multisig(&mut e);
+    set_context(user(0), e.timestamp, 1);
+    let proposal_id = e.contract.propose_owner_actions(vec![OwnerAction::Pause]);
+    e.contract.confirm_owner_proposal(proposal_id);
+}
+
+#[test]
+#[should_panic(expected = "The proposal expired")]
+fn test_multisig_proposal_expired() {
+    let mut e = Env::setup();
+    setup_multisig(&mut e);
+    set_context(user(0), e.timestamp, 1);
+    let proposal_id = e.contract.propose_owner_actions(vec![OwnerAction::Pause]);
+    e.skip_time(3600);
+    set_context(user(1), e.timestamp, 1);
+    e.contract.confirm_owner_proposal(proposal_id);
+}
+
+#[test]
+fn test_relay_prices() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.make_reports(&[100000]);
+    e.as_owner().add_mirror(a("mirror.near"));
+
+    set_context(user(1), e.timestamp, 0);
+    assert_eq!(e.contract.relay_prices(None, a("mirror.near")), U64(1));
+    assert_eq!(
+        e.contract
+            .relay_prices(Some(vec![WRAP_NEAR.to_string()]), a("mirror.near")),
+        U64(2)
+    );
+    assert_eq!(e.contract.get_mirrors(), vec![(a("mirror.near"), U64(2))]);
+}
+
+#[test]
+#[should_panic(expected = "E069_NOT_A_MIRROR")]
+fn test_relay_prices_to_unknown_mirror() {
+    let mut e = Env::setup();
+    e.add_asset(WRAP_NEAR);
+    e.contract.relay_prices(None, a("mirror.near"));
+}
+
+#[test]
+fn test_oracle_scoring() {
+    let mut e = Env::setup();
+    for i in 0..3 {
+        e.add_oracle(user(i));
+    }
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_round_config(30, 10);
+    e.as_owner().set_oracle_scoring(Some(OracleScoring {
+        max_deviation_bps: 100,
+        min_score_bps: 5000,
+        max_low_score_rounds: 2,
+    }));
+
+    for _ in 0..2 {
+        e.make_reports(&[100000, 100000]);
+        e.skip_time(30);
+        e.as_user(user(1)).finalize_round(WRAP_NEAR.to_string());
+        e.skip_time(1);
+    }
+    let performance = e.contract.get_oracle_performance(user(0)).unwrap();
+    assert_eq!(performance.num_rounds, 2);
+    assert_eq!(performance.score_bps, 10000);
+    assert!(!performance.suspended);
+    let performance = e.contract.get_oracle_performance(user(2)).unwrap();
+    assert_eq!(performance.num_missed_rounds, 2);
+    assert!(performance.suspended);
+
+    e.as_owner().reinstate_oracle(user(2));
+    e.make_reports(&[0, 0, 100000]);
+    assert!(
+        !e.contract
+            .get_oracle_performance(user(2))
+            .unwrap()
+            .suspended
+    );
+}
+
+#[test]
+#[should_panic(expected = "The oracle is suspended")]
+fn test_suspended_oracle_report() {
+    let mut e = Env::setup();
+    for i in 0..3 {
+        e.add_oracle(user(i));
+    }
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().set_round_config(30, 10);
+    e.as_owner().set_oracle_scoring(Some(OracleScoring {
+        max_deviation_bps: 100,
+        min_score_bps: 5000,
+        max_low_score_rounds: 1,
+    }));
+    e.make_reports(&[100000, 100000]);
+    e.skip_time(30);
+    e.as_user(user(1)).finalize_round(WRAP_NEAR.to_string());
+    e.make_reports(&[0, 0, 100000]);
+}
+
+#[test]
+#[should_panic(expected = "E001_NOT_AN_ORACLE: Not an oracle")]
+fn test_error_code() {
+    let mut e = Env::setup();
+    e.add_asset(WRAP_NEAR);
+    e.make_reports(&[100000]);
+}
+
+#[test]
+fn test_price_requests() {
+    let mut e = Env::setup();
+    for i in 0..3 {
+        e.add_oracle(user(i));
+    }
+    set_context(user(5), e.timestamp, ONE_NEAR);
+    let request_id = e
+        .contract
+        .request_price("long-tail.near".to_string(), "on_price".to_string());
+    assert_eq!(e.contract.get_open_price_requests(None, None).len(), 1);
+    let request = e.contract.get_price_request(request_id).unwrap();
+    assert!(request.storage_deposit > 0);
+    assert_eq!(request.fee + request.storage_deposit, ONE_NEAR);
+
+    e.as_user(user(0))
+        .fulfill_price_request(request_id, price(100000));
+    assert!(e
+        .contract
+        .get_price_request(request_id)
+        .unwrap()
+        .answer
+        .is_none());
+    e.as_user(user(1))
+        .fulfill_price_request(request_id, price(110000));
+
+    assert!(e.contract.get_price_request(request_id).is_none());
+    assert!(e.contract.get_open_price_requests(None, None).is_empty());
+    assert_eq!(
+        e.contract.get_oracle(user(0)).unwrap().rewards,
+        request.fee - request.fee / 2
+    );
+    assert_eq!(
+        e.contract.get_oracle(user(1)).unwrap().rewards,
+        request.fee / 2
+    );
+    assert_eq!(e.contract.get_config().total_price_request_fees.0, 0);
+    let receipts = get_created_receipts();
+    assert_eq!(receipts.len(), 2);
+    assert_eq!(receipts[1].receiver_id, user(5));
+    assert_eq!(
+        receipts[1].actions[0],
+        near_sdk::mock::VmAction::Transfer {
+            deposit: request.storage_deposit
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "E020_REQUIRES_DEPOSIT")]
+fn test_price_request_requires_storage_deposit() {
+    let e = Env::setup();
+    set_context(user(5), e.timestamp, 1);
+    let mut contract = e.contract;
+    contract.request_price("long-tail.near".to_string(), "on_price".to_string());
+}
+
+#[test]
+#[should_panic(expected = "E092_INVALID_CALLBACK")]
+fn test_price_request_invalid_callback() {
+    let e = Env::setup();
+    set_context(user(5), e.timestamp, ONE_NEAR);
+    let mut contract = e.contract;
+    contract.request_price("long-tail.near".to_string(), "on_price".repeat(10));
+}
+
+#[test]
+fn test_cancel_price_request() {
+    let mut e = Env::setup();
+    set_context(user(5), e.timestamp, ONE_NEAR);
+    let request_id = e
+        .contract
+        .request_price("long-tail.near".to_string(), "on_price".to_string());
+    set_context(user(5), e.timestamp, 1);
+    e.contract.cancel_price_request(request_id);
+    assert!(e.contract.get_price_request(request_id).is_none());
+    assert_eq!(e.contract.get_config().total_price_request_fees.0, 0);
+    assert_eq!(
+        get_created_receipts()[0].actions[0],
+        near_sdk::mock::VmAction::Transfer { deposit: ONE_NEAR }
+    );
+}
+
+#[test]
+#[should_panic(expected = "E117_ORACLE_FULFILLED_REQUEST")]
+fn test_price_request_fulfilled_twice() {
+    let mut e = Env::setup();
+    for i in 0..3 {
+        e.add_oracle(user(i));
+    }
+    set_context(user(5), e.timestamp, ONE_NEAR);
+    let request_id = e
+        .contract
+        .request_price("long-tail.near".to_string(), "on_price".to_string());
+    e.as_user(user(0))
+        .fulfill_price_request(request_id, price(100000));
+    e.as_user(user(0))
+        .fulfill_price_request(request_id, price(100000));
+}
+
+#[test]
+fn test_price_data_sequence() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.add_asset(DAI);
+    let price_data = e.contract.get_price_data(None, None, None, None);
+    assert_eq!(price_data.sequence, 0);
+    assert!(price_data.prices[0].sequence.is_none());
+
+    e.make_reports(&[100000]);
+    e.skip_time(10);
+    e.as_user(user(0)).report_prices(
+        vec![AssetPrice {
+            asset_id: DAI.to_string(),
+            price: price(1000),
+            quote_asset_id: None,
+            nonce: None,
+        }],
+        None,
+    );
+
+    let price_data = e.contract.get_price_data(None, None, None, None);
+    assert_eq!(price_data.sequence, 2);
+    assert_eq!(price_data.last_update_timestamp, e.timestamp);
+    assert_eq!(price_data.prices[0].sequence, Some(U64(1)));
+    assert_eq!(
+        price_data.prices[0].last_update_timestamp,
+        Some(U64(e.timestamp - to_nano(10)))
+    );
+    assert_eq!(price_data.prices[1].sequence, Some(U64(2)));
+}
+
+#[test]
+fn test_pessimistic_price_data() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_owner().add_asset_twap(WRAP_NEAR.to_string(), 100);
+
+    e.make_reports(&[100000]);
+    e.skip_time(50);
+    e.make_reports(&[200000]);
+    e.skip_time(50);
+
+    let price_data = e
+        .contract
+        .get_pessimistic_price_data(Some(vec![WRAP_NEAR.to_string()]));
+    assert_eq!(price_data.prices[0].collateral_price, Some(price(150000)));
+    assert_eq!(price_data.prices[0].borrow_price, Some(price(200000)));
+}
+
+#[cfg(feature = "testing")]
+#[test]
+fn test_testing_now() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.make_reports(&[100000]);
+
+    e.as_owner().set_testing_now(Some(U64(ts(0))));
+    assert_eq!(e.as_owner().advance_testing_now(91), U64(ts(91)));
+    assert_eq!(e.contract.get_testing_now(), U64(ts(91)));
+    let price_data = e.contract.get_price_data(None, None, None, None);
+    assert!(price_data.prices[0].price.is_none());
+
+    e.as_owner().set_testing_now(None);
+    let price_data = e.contract.get_price_data(None, None, None, None);
+    assert_eq!(price_data.prices[0].price, Some(price(100000)));
+}
+
+#[test]
+fn test_signed_price_cmp() {
+    let signed = |multiplier, decimals| SignedPrice {
+        multiplier,
+        decimals,
+    };
+    assert!(signed(-15, 1) < signed(-1, 0));
+    assert!(signed(5, -40) > signed(i128::MAX, 0));
+    assert!(signed(-5, -40) < signed(i128::MIN, 0));
+    assert_eq!(signed(100, 2), signed(1, 0));
+    assert_eq!(SignedPrice::from(price(100000)), signed(100000, 28));
+    assert_eq!(signed(-1, 0).to_price(), None);
+    assert_eq!(
+        signed(3, -2).to_price(),
+        Some(Price {
+            multiplier: 300,
+            decimals: 0
+        })
+    );
+}
+
+#[test]
+fn test_rate_feeds() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_oracle(user(1));
+    e.as_owner()
+        .add_rate_feed("BTC-PERP.FUNDING".to_string(), RateKind::FundingRate);
+    let report = |e: &mut Env, oracle_id, multiplier| {
+        e.as_user(oracle_id).report_rates(vec![AssetRate {
+            feed_id: "BTC-PERP.FUNDING".to_string(),
+            rate: SignedPrice {
+                multiplier,
+                decimals: 6,
+            },
+        }]);
+    };
+    report(&mut e, user(0), -125);
+    assert!(get_logs()[0].contains(r#""rate":{"multiplier":"-125","decimals":6}"#));
+    report(&mut e, user(1), 50);
+
+    let rate_data = e
+        .contract
+        .get_rate_data(vec!["BTC-PERP.FUNDING".to_string(), "SOFR".to_string()]);
+    assert_eq!(rate_data.rates[0].kind, Some(RateKind::FundingRate));
+    assert_eq!(
+        rate_data.rates[0].rate,
+        Some(SignedPrice {
+            multiplier: 50,
+            decimals: 6
+        })
+    );
+    assert!(rate_data.rates[1].rate.is_none());
+
+    e.skip_time(100);
+    let rate_data = e
+        .contract
+        .get_rate_data(vec!["BTC-PERP.FUNDING".to_string()]);
+    assert!(rate_data.rates[0].rate.is_none());
+}
+
+#[test]
+#[should_panic(expected = "E078_INVALID_RATE")]
+fn test_rate_out_of_range() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.as_owner()
+        .add_rate_feed("SOFR".to_string(), RateKind::InterestRate);
+    e.as_user(user(0)).report_rates(vec![AssetRate {
+        feed_id: "SOFR".to_string(),
+        rate: SignedPrice {
+            multiplier: -100,
+            decimals: 2,
+        },
+    }]);
+}
+
+#[test]
+fn test_near_claim_with_reserved_balances_above_balance() {
+    let mut e = Env::setup();
+    e.add_oracle(user(0));
+    e.add_asset(WRAP_NEAR);
+    e.as_user(user(0))
+        .report_prices(wrap_near_prices(100000), Some(true));
+    let last_near_claim = e.contract.get_oracle(user(0)).unwrap().last_near_claim;
+    assert_eq!(last_near_claim, e.timestamp);
+
+    e.skip_time(24 * 60 * 60 + 1);
+    e.contract.rewards_pool = 2000 * ONE_NEAR;
+    e.as_user(user(0))
+        .report_prices(wrap_near_prices(110000), Some(true));
+    let oracle = e.contract.get_oracle(user(0)).unwrap();
+    assert_eq!(oracle.last_near_claim, last_near_claim);
+    let price_data = e.contract.get_price_data(None, None, None, None);
+    assert_eq!(price_data.prices[0].price, Some(price(110000)));
+}
@@ -0,0 +1,80 @@
+use crate::*;
+
+/// The number of observations kept per TWAP window.
+const TWAP_NUM_OBSERVATIONS: u64 = 20;
+
+/// A time-weighted average of the aggregated price over `period_sec`, e.g. `wrap.near#twap3600`.
+/// The aggregated price is sampled at most once per 1/20 of the period.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetTwap {
+    pub period_sec: DurationSec,
+    /// The time of the last aggregated price, even if it wasn't sampled.
+    #[serde(with = "u64_dec_format")]
+    pub timestamp: Timestamp,
+    /// Sampled prices, each is valid until the next one. The first one may be older than the
+    /// period to cover the start of the window.
+    pub observations: Vec<PricePoint>,
+}
+
+impl AssetTwap {
+    pub fn new(period_sec: DurationSec) -> Self {
+        Self {
+            period_sec,
+            timestamp: 0,
+            observations: vec![],
+        }
+    }
+
+    pub fn record(&mut self, median_price: Price, timestamp: Timestamp) {
+        self.timestamp = timestamp;
+        let period = to_nano(self.period_sec);
+        if let Some(last) = self.observations.last() {
+            if last.timestamp + period / TWAP_NUM_OBSERVATIONS > timestamp {
+                return;
+            }
+        }
+        self.observations.push(PricePoint {
+            price: median_price,
+            timestamp,
+            block_height: env::block_height(),
+        });
+        let window_start = timestamp.saturating_sub(period);
+        let num_expired = self
+            .observations
+            .iter()
+            .skip(1)
+            .take_while(|point| point.timestamp <= window_start)
+            .count();
+        self.observations.drain(..num_expired);
+    }
+
+    /// Returns the average price over the period ending at the given time, or `None` if the
+    /// observations don't cover the whole period.
+    pub fn price(&self, timestamp: Timestamp) -> Option<Price> {
+        let period = to_nano(self.period_sec);
+        let window_start = timestamp.checked_sub(period)?;
+        if self.observations.first()?.timestamp > window_start {
+            return None;
+        }
+        let decimals = self
+            .observations
+            .iter()
+            .map(|point| point.price.decimals)
+            .max()?;
+        let mut sum = 0f64;
+        for (i, point) in self.observations.iter().enumerate() {
+            let from = std::cmp::max(point.timestamp, window_start);
+            let to = self
+                .observations
+                .get(i + 1)
+                .map_or(timestamp, |next| next.timestamp);
+            if to > from {
+                let multiplier = point.price.multiplier as f64
+                    * 10f64.powi(decimals as i32 - point.price.decimals as i32);
+                sum += multiplier * (to - from) as f64;
+            }
+        }
+        Some(f64_to_price(sum / period as f64, decimals))
+    }
+}
@@ -1,11 +1,165 @@
 use crate::*;
+use near_sdk::json_types::{Base58CryptoHash, Base64VecU8};
+use near_sdk::CryptoHash;
+use std::convert::TryInto;
+
+const GAS_FOR_DEPLOY_STAGED_CALL: Gas = Gas(Gas::ONE_TERA.0 * 20);
+const GAS_FOR_STAGED_VERIFICATION_CALL: Gas = Gas(Gas::ONE_TERA.0 * 5);
+
+/// The code that can be deployed with `deploy_staged` or `upgrade` at or after `deploy_at`.
+/// The deposit for its storage is refunded to the account that staged it once the code is
+/// deployed or removed.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct StagedCode {
+    pub code_hash: CryptoHash,
+    pub deploy_at: Timestamp,
+    pub staged_by: AccountId,
+    pub deposit: Balance,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StagedCodeView {
+    pub code_hash: Base58CryptoHash,
+    #[serde(with = "u64_dec_format")]
+    pub deploy_at: Timestamp,
+    pub staged_by: AccountId,
+    pub deposit: U128,
+}
 
 #[near_bindgen]
 impl Contract {
     #[private]
     #[init(ignore_state)]
     pub fn migrate_state() -> Self {
-        env::state_read().unwrap()
+        let contract: ContractV0 = env::state_read().unwrap();
+        contract.into()
+    }
+
+    /// Stores up to `limit` assets starting from `from_index` in the current version.
+    /// Returns the number of upgraded assets.
+    #[payable]
+    pub fn migrate_assets(&mut self, from_index: Option<u64>, limit: Option<u64>) -> u64 {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        let from_index = from_index.unwrap_or(0);
+        let to_index = std::cmp::min(
+            from_index.saturating_add(limit.unwrap_or(self.asset_ids.len())),
+            self.asset_ids.len(),
+        );
+        let asset_ids: Vec<AssetId> = (from_index..to_index)
+            .map(|index| self.asset_ids.get(index).unwrap())
+            .collect();
+        let mut num_migrated = 0;
+        for asset_id in asset_ids {
+            let v_asset = self.assets.get(&asset_id).unwrap();
+            if !v_asset.is_current() {
+                self.internal_set_asset(&asset_id, v_asset.into());
+                num_migrated += 1;
+            }
+        }
+        num_migrated
+    }
+
+    /// Returns the number of assets in `asset_ids` that are stored in a legacy version.
+    pub fn get_num_legacy_assets(&self) -> u64 {
+        self.asset_ids
+            .iter()
+            .filter(|asset_id| !self.assets.get(asset_id).unwrap().is_current())
+            .count() as u64
+    }
+
+    /// Stores the new code that can be deployed with `deploy_staged` at or after `deploy_at`,
+    /// replacing the code staged before. The deploy time has to respect the timelock.
+    /// Requires a deposit covering the storage of the code, the rest of it is refunded.
+    #[payable]
+    pub fn stage_code(&mut self, code: Base64VecU8, deploy_at: U64) {
+        self.assert_owner();
+        let deploy_at = deploy_at.into();
+        assert!(
+            deploy_at >= now() + to_nano(self.timelock_sec),
+            "{}",
+            ContractError::DeployTimeBeforeTimelock
+        );
+        self.internal_release_staged_code();
+        let initial_storage_usage = env::storage_usage();
+        let staged_by = env::predecessor_account_id();
+        self.staged_code_info = Some(StagedCode {
+            code_hash: env::sha256(&code.0).try_into().unwrap(),
+            deploy_at,
+            staged_by: staged_by.clone(),
+            deposit: 0,
+        });
+        self.staged_code.set(&code.0);
+        let storage_cost = std::cmp::max(
+            env::storage_byte_cost()
+                * Balance::from(env::storage_usage().saturating_sub(initial_storage_usage)),
+            1,
+        );
+        let deposit = env::attached_deposit();
+        assert!(
+            deposit >= storage_cost,
+            "{}",
+            ContractError::RequiresDeposit(storage_cost)
+        );
+        self.staged_code_info.as_mut().unwrap().deposit = storage_cost;
+        if deposit > storage_cost {
+            Promise::new(staged_by).transfer(deposit - storage_cost);
+        }
+    }
+
+    #[payable]
+    pub fn remove_staged_code(&mut self) {
+        assert_one_yocto();
+        self.assert_owner();
+        assert!(
+            self.internal_release_staged_code().is_some(),
+            "{}",
+            ContractError::NoStagedCode
+        );
+    }
+
+    /// Deploys the staged code and migrates the state with the new code.
+    pub fn deploy_staged(&mut self) -> Promise {
+        self.assert_owner();
+        let code = self
+            .staged_code
+            .get()
+            .unwrap_or_else(|| panic!("{}", ContractError::NoStagedCode));
+        self.internal_assert_staged_code_deployable();
+        self.internal_release_staged_code();
+        let required_gas =
+            env::used_gas() + GAS_FOR_DEPLOY_STAGED_CALL + GAS_FOR_STAGED_VERIFICATION_CALL;
+        assert!(
+            env::prepaid_gas() >= required_gas,
+            "{}",
+            ContractError::NotEnoughGasForMigration
+        );
+        Promise::new(env::current_account_id())
+            .deploy_contract(code)
+            .function_call(
+                "migrate_state".to_string(),
+                b"{}".to_vec(),
+                NO_DEPOSIT,
+                env::prepaid_gas() - required_gas,
+            )
+            .function_call(
+                "get_owner_id".to_string(),
+                b"{}".to_vec(),
+                NO_DEPOSIT,
+                GAS_FOR_STAGED_VERIFICATION_CALL,
+            )
+    }
+
+    pub fn get_staged_code(&self) -> Option<StagedCodeView> {
+        self.staged_code_info
+            .as_ref()
+            .map(|staged_code_info| StagedCodeView {
+                code_hash: staged_code_info.code_hash.into(),
+                deploy_at: staged_code_info.deploy_at,
+                staged_by: staged_code_info.staged_by.clone(),
+                deposit: staged_code_info.deposit.into(),
+            })
     }
 
     /// Returns semver of this contract.
@@ -14,6 +168,30 @@ impl Contract {
     }
 }
 
+impl Contract {
+    fn internal_assert_staged_code_deployable(&self) {
+        let staged_code_info = self
+            .staged_code_info
+            .as_ref()
+            .unwrap_or_else(|| panic!("{}", ContractError::NoStagedCode));
+        assert!(
+            now() >= staged_code_info.deploy_at,
+            "{}",
+            ContractError::StagedCodeNotDeployable
+        );
+    }
+
+    /// Removes the staged code and refunds its deposit to the account that staged it.
+    fn internal_release_staged_code(&mut self) -> Option<StagedCode> {
+        let staged_code_info = self.staged_code_info.take()?;
+        self.staged_code.remove();
+        if staged_code_info.deposit > 0 {
+            Promise::new(staged_code_info.staged_by.clone()).transfer(staged_code_info.deposit);
+        }
+        Some(staged_code_info)
+    }
+}
+
 mod upgrade {
     use near_sdk::{require, Gas};
 
@@ -25,12 +203,26 @@ mod upgrade {
     const MIN_GAS_FOR_MIGRATE_STATE_CALL: Gas = Gas(Gas::ONE_TERA.0 * 10);
 
     /// Self upgrade and call migrate, optimizes gas by not loading into memory the code.
-    /// Takes as input non serialized set of bytes of the code.
+    /// Takes as input non serialized set of bytes of the code, which has to be the staged code
+    /// once it can be deployed.
     #[no_mangle]
     pub extern "C" fn upgrade() {
         env::setup_panic_hook();
-        let contract: Contract = env::state_read().expect("ERR_CONTRACT_IS_NOT_INITIALIZED");
+        let mut contract: Contract = env::state_read()
+            .unwrap_or_else(|| env::panic_str(&ContractError::NotInitialized.to_string()));
         contract.assert_owner();
+        contract.internal_assert_staged_code_deployable();
+        unsafe {
+            sys::input(0);
+            sys::sha256(u64::MAX, 0, 1);
+        }
+        require!(
+            env::read_register(1).as_deref()
+                == Some(&contract.staged_code_info.as_ref().unwrap().code_hash[..]),
+            ContractError::StagedCodeMismatch.to_string()
+        );
+        contract.internal_release_staged_code();
+        env::state_write(&contract);
         let current_account_id = env::current_account_id().as_bytes().to_vec();
         let migrate_method_name = b"migrate_state".to_vec();
         let verification_method_name = b"get_owner_id".to_vec();
@@ -47,7 +239,7 @@ mod upgrade {
                 env::used_gas() + GAS_TO_COMPLETE_UPGRADE_CALL + GAS_FOR_VERIFICATION_CALL;
             require!(
                 env::prepaid_gas() >= required_gas + MIN_GAS_FOR_MIGRATE_STATE_CALL,
-                "Not enough gas to complete state migration"
+                ContractError::NotEnoughGasForMigration.to_string()
             );
             let migrate_state_attached_gas = env::prepaid_gas() - required_gas;
             // Scheduling state migration.
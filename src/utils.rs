@@ -1,8 +1,12 @@
 use crate::*;
 use std::cmp::Ordering;
+use std::str::FromStr;
 
 const MAX_U128_DECIMALS: u8 = 38;
-const MAX_VALID_DECIMALS: u8 = 77;
+pub(crate) const MAX_VALID_DECIMALS: u8 = 77;
+/// Deviations are in basis points.
+const DEVIATION_DECIMALS: u8 = 4;
+const FULL_DEVIATION_BPS: u32 = 10000;
 
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
@@ -25,6 +29,78 @@ impl Price {
     pub fn assert_valid(&self) {
         assert!(self.decimals <= MAX_VALID_DECIMALS);
     }
+
+    /// Asserts the reported price of the asset has the decimals the asset expects. A price with
+    /// other decimals is rescaled to them if it can be done exactly.
+    pub fn assert_valid_decimals(&self, asset_id: &str, price_decimals: Option<u8>) -> Price {
+        match price_decimals {
+            None => *self,
+            Some(price_decimals) => self.with_decimals(price_decimals).unwrap_or_else(|| {
+                panic!(
+                    "The price of {} has {} decimals and can't be normalized to the expected {}",
+                    asset_id, self.decimals, price_decimals
+                )
+            }),
+        }
+    }
+
+    /// Returns the same price with the given decimals, or `None` if it doesn't fit or would lose
+    /// precision.
+    pub fn with_decimals(&self, decimals: u8) -> Option<Price> {
+        let multiplier = if decimals >= self.decimals {
+            self.multiplier
+                .checked_mul(10u128.checked_pow(u32::from(decimals - self.decimals))?)?
+        } else {
+            let divisor = 10u128.checked_pow(u32::from(self.decimals - decimals))?;
+            let multiplier = self.multiplier / divisor;
+            if multiplier * divisor != self.multiplier {
+                return None;
+            }
+            multiplier
+        };
+        Some(Price {
+            multiplier,
+            decimals,
+        })
+    }
+
+    /// Whether the price differs from the reference price by more than the given basis points.
+    pub fn deviates_from(&self, reference: &Price, max_deviation_bps: u32) -> bool {
+        let bound = |bps: u32| {
+            Some(Price {
+                multiplier: reference.multiplier.checked_mul(bps as u128)?,
+                decimals: reference.decimals + DEVIATION_DECIMALS,
+            })
+        };
+        match (
+            bound(FULL_DEVIATION_BPS.saturating_sub(max_deviation_bps)),
+            bound(FULL_DEVIATION_BPS.saturating_add(max_deviation_bps)),
+        ) {
+            (Some(lower), Some(upper)) => *self < lower || *self > upper,
+            _ => false,
+        }
+    }
+
+    /// Returns the deviation from the reference price in basis points, saturating at `u32::MAX`.
+    pub fn deviation_bps(&self, reference: &Price) -> u32 {
+        let decimals = std::cmp::max(self.decimals, reference.decimals);
+        let scale = |price: &Price| {
+            price
+                .multiplier
+                .checked_mul(10u128.checked_pow(u32::from(decimals - price.decimals))?)
+        };
+        match (scale(self), scale(reference)) {
+            (Some(multiplier), Some(reference_multiplier)) if reference_multiplier > 0 => {
+                (std::cmp::max(multiplier, reference_multiplier)
+                    - std::cmp::min(multiplier, reference_multiplier))
+                .checked_mul(u128::from(FULL_DEVIATION_BPS))
+                .map_or(u32::MAX, |diff| {
+                    std::cmp::min(diff / reference_multiplier, u128::from(u32::MAX)) as u32
+                })
+            }
+            _ => u32::MAX,
+        }
+    }
 }
 
 impl PartialEq<Self> for Price {
@@ -85,6 +161,27 @@ pub(crate) mod u128_dec_format {
     }
 }
 
+pub(crate) mod i128_dec_format {
+    use near_sdk::serde::de;
+    use near_sdk::serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(num: &i128, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&num.to_string())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<i128, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
 pub(crate) mod u64_dec_format {
     use near_sdk::serde::de;
     use near_sdk::serde::{Deserialize, Deserializer, Serializer};
@@ -106,10 +203,40 @@ pub(crate) mod u64_dec_format {
     }
 }
 
+/// Returns the current block timestamp. All time reads of the contract should go through it, so
+/// unit tests can pin the clock with `set_now_override`, and builds with the `testing` feature
+/// with `set_testing_now`.
+pub(crate) fn now() -> Timestamp {
+    #[cfg(test)]
+    if let Some(timestamp) = NOW_OVERRIDE.with(|now| now.get()) {
+        return timestamp;
+    }
+    #[cfg(feature = "testing")]
+    if let Some(timestamp) = crate::testing::testing_now() {
+        return timestamp;
+    }
+    env::block_timestamp()
+}
+
+#[cfg(test)]
+thread_local! {
+    static NOW_OVERRIDE: std::cell::Cell<Option<Timestamp>> = const { std::cell::Cell::new(None) };
+}
+
+/// Overrides the timestamp returned by `now()`. Only available in unit tests.
+#[cfg(test)]
+pub(crate) fn set_now_override(timestamp: Option<Timestamp>) {
+    NOW_OVERRIDE.with(|now| now.set(timestamp));
+}
+
 pub(crate) fn to_nano(ts: u32) -> Timestamp {
     Timestamp::from(ts) * 10u64.pow(9)
 }
 
+pub(crate) fn to_sec(timestamp: Timestamp) -> u64 {
+    timestamp / 10u64.pow(9)
+}
+
 pub(crate) fn unordered_map_pagination<K, VV, V>(
     m: &UnorderedMap<K, VV>,
     from_index: Option<u64>,
@@ -128,3 +255,145 @@ where
         .map(|index| (keys.get(index).unwrap(), values.get(index).unwrap().into()))
         .collect()
 }
+
+/// Asset IDs are account IDs of tokens or symbolic IDs like `usd` that follow the same rules,
+/// so `#` stays reserved for EMAs and TWAPs of assets.
+pub fn is_valid_asset_id(asset_id: &str) -> bool {
+    AccountId::from_str(asset_id).is_ok()
+}
+
+/// The prefix of IDs of assets registered by symbols, e.g. `symbol:BTC`. Such IDs never collide
+/// with account IDs, since `:` isn't valid in them.
+pub const SYMBOL_ASSET_ID_PREFIX: &str = "symbol:";
+
+const MAX_SYMBOL_LEN: usize = 32;
+
+/// Symbols consist of uppercase letters, digits, `.`, `-` and `_`, e.g. `BRK.B`.
+pub fn is_valid_symbol(symbol: &str) -> bool {
+    !symbol.is_empty()
+        && symbol.len() <= MAX_SYMBOL_LEN
+        && symbol
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || matches!(c, '.' | '-' | '_'))
+}
+
+pub fn symbol_asset_id(symbol: &str) -> AssetId {
+    format!("{}{}", SYMBOL_ASSET_ID_PREFIX, symbol)
+}
+
+pub fn is_symbol_asset_id(asset_id: &str) -> bool {
+    asset_id
+        .strip_prefix(SYMBOL_ASSET_ID_PREFIX)
+        .map(is_valid_symbol)
+        .unwrap_or(false)
+}
+
+/// IDs of indexes and pairs can also join valid asset IDs with `/`, e.g. `dai/wrap.near` or
+/// `symbol:XAU/usd`.
+pub fn is_valid_derived_asset_id(asset_id: &str) -> bool {
+    asset_id
+        .split('/')
+        .all(|asset_id| is_valid_asset_id(asset_id) || is_symbol_asset_id(asset_id))
+}
+
+/// The largest power of 10 that fits into `u128`.
+const MAX_U128_POW10: u32 = 38;
+
+/// An unsigned 256-bit integer for intermediate results of price math.
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub struct U256 {
+    pub hi: u128,
+    pub lo: u128,
+}
+
+impl U256 {
+    /// Returns the full product of two `u128` numbers.
+    pub fn full_mul(a: u128, b: u128) -> Self {
+        let mask = u128::from(u64::MAX);
+        let (a_hi, a_lo) = (a >> 64, a & mask);
+        let (b_hi, b_lo) = (b >> 64, b & mask);
+        let lo_lo = a_lo * b_lo;
+        let hi_lo = a_hi * b_lo;
+        let lo_hi = a_lo * b_hi;
+        let cross = (lo_lo >> 64) + (hi_lo & mask) + (lo_hi & mask);
+        Self {
+            hi: a_hi * b_hi + (hi_lo >> 64) + (lo_hi >> 64) + (cross >> 64),
+            lo: (cross << 64) | (lo_lo & mask),
+        }
+    }
+
+    pub fn checked_mul(self, m: u128) -> Option<Self> {
+        let low = Self::full_mul(self.lo, m);
+        Some(Self {
+            hi: low.hi.checked_add(self.hi.checked_mul(m)?)?,
+            lo: low.lo,
+        })
+    }
+
+    /// Divides by a positive divisor, rounding down.
+    pub fn div_u128(self, divisor: u128) -> Self {
+        let mut quotient = Self { hi: 0, lo: 0 };
+        let mut remainder = 0u128;
+        for i in (0..256).rev() {
+            let bit = if i >= 128 {
+                (self.hi >> (i - 128)) & 1
+            } else {
+                (self.lo >> i) & 1
+            };
+            let carry = remainder >> 127;
+            remainder = (remainder << 1) | bit;
+            if carry == 1 || remainder >= divisor {
+                remainder = remainder.wrapping_sub(divisor);
+                if i >= 128 {
+                    quotient.hi |= 1 << (i - 128);
+                } else {
+                    quotient.lo |= 1 << i;
+                }
+            }
+        }
+        quotient
+    }
+
+    pub fn checked_mul_pow10(mut self, mut exponent: u32) -> Option<Self> {
+        while exponent > 0 {
+            let step = std::cmp::min(exponent, MAX_U128_POW10);
+            self = self.checked_mul(10u128.pow(step))?;
+            exponent -= step;
+        }
+        Some(self)
+    }
+
+    pub fn div_pow10(mut self, mut exponent: u32) -> Self {
+        while exponent > 0 {
+            let step = std::cmp::min(exponent, MAX_U128_POW10);
+            self = self.div_u128(10u128.pow(step));
+            exponent -= step;
+        }
+        self
+    }
+
+    pub fn to_u128(self) -> Option<u128> {
+        if self.hi == 0 {
+            Some(self.lo)
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns `amount * multiplier * 10**exponent / divisor` rounded down. The intermediate results
+/// have 256 bits, so it only returns `None` if the result doesn't fit into `u128` or the divisor
+/// is zero.
+pub fn mul_div_pow10(amount: u128, multiplier: u128, exponent: i32, divisor: u128) -> Option<u128> {
+    if divisor == 0 {
+        return None;
+    }
+    let product = U256::full_mul(amount, multiplier);
+    let scaled = if exponent >= 0 {
+        product.checked_mul_pow10(exponent as u32)?
+    } else {
+        product.div_pow10(exponent.unsigned_abs())
+    };
+    scaled.div_u128(divisor).to_u128()
+}
@@ -0,0 +1,181 @@
+use crate::*;
+
+/// The weight of a report from an oracle that reported recently, i.e. weights are in basis points.
+pub const FULL_WEIGHT: u32 = 10000;
+
+/// How the weight of a report decays with its age, so an oracle that goes silent gradually loses
+/// its influence on the aggregated price before its report falls out of recency.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy)]
+#[serde(crate = "near_sdk::serde")]
+pub enum WeightDecay {
+    /// The weight drops linearly from full to zero within `duration_sec` after the grace period.
+    Linear {
+        grace_period_sec: DurationSec,
+        duration_sec: DurationSec,
+    },
+    /// The weight halves every `half_life_sec` after the grace period.
+    Exponential {
+        grace_period_sec: DurationSec,
+        half_life_sec: DurationSec,
+    },
+}
+
+impl WeightDecay {
+    pub fn assert_valid(&self) {
+        match self {
+            WeightDecay::Linear { duration_sec, .. } => assert!(*duration_sec > 0),
+            WeightDecay::Exponential { half_life_sec, .. } => assert!(*half_life_sec > 0),
+        }
+    }
+
+    /// Returns the weight of a report of the given age.
+    pub fn weight(&self, age: Duration) -> u32 {
+        match *self {
+            WeightDecay::Linear {
+                grace_period_sec,
+                duration_sec,
+            } => {
+                let decay_time = age.saturating_sub(to_nano(grace_period_sec));
+                let duration = to_nano(duration_sec);
+                if decay_time >= duration {
+                    0
+                } else {
+                    (u128::from(FULL_WEIGHT) * u128::from(duration - decay_time)
+                        / u128::from(duration)) as u32
+                }
+            }
+            WeightDecay::Exponential {
+                grace_period_sec,
+                half_life_sec,
+            } => {
+                let decay_time = age.saturating_sub(to_nano(grace_period_sec));
+                let half_lives = decay_time as f64 / to_nano(half_life_sec) as f64;
+                (f64::from(FULL_WEIGHT) * 0.5f64.powf(half_lives)).round() as u32
+            }
+        }
+    }
+}
+
+/// Where the weight of an oracle in the aggregated price comes from. It's combined with the
+/// weight decay of its report.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub enum OracleWeighting {
+    /// All oracles have the same weight.
+    Equal,
+    /// Oracles are weighted by their bonded stake.
+    Stake,
+    /// Oracles are weighted by the weight assigned by the owner.
+    Assigned,
+}
+
+/// Returns the weighted median of the given prices, ignoring reports with zero weight.
+/// With equal weights it picks the same price as the regular median.
+pub fn weighted_median(
+    mut prices: Vec<(Price, u128)>,
+    min_num_recent_reports: usize,
+) -> Option<Price> {
+    prices.retain(|(_, weight)| *weight > 0);
+    if prices.len() < min_num_recent_reports {
+        return None;
+    }
+    prices.sort_by_key(|(price, _)| *price);
+    let total_weight: u128 = prices.iter().map(|(_, weight)| *weight).sum();
+    let mut cumulative_weight = 0u128;
+    prices
+        .into_iter()
+        .find(|(_, weight)| {
+            cumulative_weight += *weight;
+            cumulative_weight * 2 > total_weight
+        })
+        .map(|(price, _)| price)
+}
+
+impl Contract {
+    /// Returns recent reports of the asset with their effective weights.
+    pub fn internal_weighted_reports<'a>(
+        &self,
+        asset: &'a Asset,
+        timestamp: Timestamp,
+        max_age_sec: Option<DurationSec>,
+    ) -> Vec<(&'a Report, u32)> {
+        let timestamp_cut = self.asset_timestamp_cut(asset, timestamp, max_age_sec);
+        asset
+            .reports
+            .iter()
+            .filter(|report| {
+                report.timestamp >= timestamp_cut && !asset.is_outlier(report, timestamp_cut)
+            })
+            .map(|report| (report, self.internal_report_weight(report, timestamp)))
+            .collect()
+    }
+
+    /// Returns the weight of the report after the weight decay.
+    pub fn internal_report_weight(&self, report: &Report, timestamp: Timestamp) -> u32 {
+        self.weight_decay.map_or(FULL_WEIGHT, |weight_decay| {
+            weight_decay.weight(timestamp.saturating_sub(report.timestamp))
+        })
+    }
+
+    pub fn asset_oracle_weighting(&self, asset: &Asset) -> OracleWeighting {
+        asset.oracle_weighting.unwrap_or(self.oracle_weighting)
+    }
+
+    /// Returns the weight of the oracle in the aggregated price of the asset.
+    pub fn internal_oracle_weight(&self, asset: &Asset, oracle_id: &AccountId) -> u128 {
+        match self.asset_oracle_weighting(asset) {
+            OracleWeighting::Equal => 1,
+            OracleWeighting::Stake => self
+                .internal_get_oracle(oracle_id)
+                .map_or(0, |oracle| oracle.stake),
+            OracleWeighting::Assigned => self
+                .internal_get_oracle(oracle_id)
+                .map_or(0, |oracle| u128::from(oracle.weight)),
+        }
+    }
+
+    pub fn aggregation_mode(&self, asset: &Asset) -> AggregationMode {
+        if self.weight_decay.is_some()
+            || self.asset_oracle_weighting(asset) != OracleWeighting::Equal
+        {
+            AggregationMode::WeightedMedian
+        } else {
+            AggregationMode::Median
+        }
+    }
+
+    /// Aggregates recent reports of the asset into a single price, combined with the DEX price
+    /// of the asset according to its DEX policy.
+    pub fn internal_aggregate_price(
+        &self,
+        asset: &Asset,
+        timestamp: Timestamp,
+        max_age_sec: Option<DurationSec>,
+    ) -> Option<Price> {
+        let price = match self.aggregation_mode(asset) {
+            AggregationMode::Median => asset.median_price(
+                self.asset_timestamp_cut(asset, timestamp, max_age_sec),
+                self.asset_min_num_recent_reports(asset),
+            ),
+            AggregationMode::WeightedMedian => weighted_median(
+                self.internal_weighted_reports(asset, timestamp, max_age_sec)
+                    .into_iter()
+                    .map(|(report, weight)| {
+                        (
+                            report.price,
+                            u128::from(weight)
+                                * self.internal_oracle_weight(asset, &report.oracle_id),
+                        )
+                    })
+                    .collect(),
+                self.asset_min_num_recent_reports(asset),
+            ),
+        };
+        self.internal_apply_dex_policy(
+            asset,
+            price,
+            self.asset_timestamp_cut(asset, timestamp, max_age_sec),
+        )
+    }
+}
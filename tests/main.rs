@@ -1,9 +1,10 @@
+use near_sdk::borsh::BorshSerialize;
 use near_sdk::json_types::U128;
 use near_sdk::serde_json::json;
 use near_sdk::{AccountId, Gas, Timestamp};
 use near_sdk_sim::runtime::GenesisConfig;
 use near_sdk_sim::{init_simulator, to_yocto, ExecutionResult, UserAccount};
-use price_oracle::{AssetId, AssetPrice, DurationSec, Price, PriceData};
+use price_oracle::{AssetId, AssetPrice, AssetPriceBorsh, DurationSec, Price, PriceData};
 
 near_sdk_sim::lazy_static_include::lazy_static_include_bytes! {
     CONTARCT_WASM_BYTES => "res/price_oracle.wasm",
@@ -146,6 +147,20 @@ impl Env {
         )
     }
 
+    pub fn report_prices_borsh(
+        &self,
+        user: &UserAccount,
+        payload: Vec<AssetPriceBorsh>,
+    ) -> ExecutionResult {
+        user.call(
+            self.contract.account_id(),
+            "report_prices_borsh",
+            &payload.try_to_vec().unwrap(),
+            MAX_GAS.0,
+            0,
+        )
+    }
+
     pub fn get_price_data(&self, asset_ids: Option<Vec<AssetId>>) -> PriceData {
         self.near
             .view(
@@ -171,6 +186,8 @@ impl Env {
                             multiplier,
                             decimals: 28,
                         },
+                        quote_asset_id: None,
+                        nonce: None,
                     }],
                 )
                 .assert_success();
@@ -542,3 +559,75 @@ fn test_version() {
 
     assert_eq!(version, LATEST_VERSION);
 }
+
+#[test]
+fn test_report_prices_gas() {
+    let e = Env::setup(&CONTARCT_WASM_BYTES);
+    e.add_oracle(&e.users[0]);
+    let prices: Vec<AssetPrice> = (0..20)
+        .map(|i| {
+            let asset_id = format!("token_{}.near", i);
+            e.add_asset(&asset_id);
+            AssetPrice {
+                asset_id,
+                price: Price {
+                    multiplier: 100000,
+                    decimals: 28,
+                },
+                quote_asset_id: None,
+                nonce: None,
+            }
+        })
+        .collect();
+
+    // The first report stores new reports, the second one replaces them.
+    e.report_prices(&e.users[0], prices.clone())
+        .assert_success();
+    e.skip_time(10);
+    let result = e.report_prices(&e.users[0], prices);
+    result.assert_success();
+    // About 30 TGas plus the function call fees.
+    assert!(result.gas_burnt().0 < 40 * Gas::ONE_TERA.0);
+}
+
+#[test]
+fn test_report_prices_borsh_gas() {
+    let e = Env::setup(&CONTARCT_WASM_BYTES);
+    e.add_oracle(&e.users[0]);
+    let prices: Vec<AssetPrice> = (0..50)
+        .map(|i| {
+            let asset_id = format!("token_{}.near", i);
+            e.add_asset(&asset_id);
+            AssetPrice {
+                asset_id,
+                price: Price {
+                    multiplier: 100000,
+                    decimals: 28,
+                },
+                quote_asset_id: None,
+                nonce: None,
+            }
+        })
+        .collect();
+    let payload: Vec<AssetPriceBorsh> = prices
+        .iter()
+        .map(|price| AssetPriceBorsh {
+            asset_id: price.asset_id.clone(),
+            price: price.price,
+            quote_asset_id: None,
+            nonce: None,
+        })
+        .collect();
+
+    e.report_prices(&e.users[0], prices.clone())
+        .assert_success();
+    e.skip_time(10);
+    let json_result = e.report_prices(&e.users[0], prices);
+    json_result.assert_success();
+    e.skip_time(10);
+    let borsh_result = e.report_prices_borsh(&e.users[0], payload);
+    borsh_result.assert_success();
+    // See the gas of price reports in README.md.
+    assert!(json_result.gas_burnt().0 < 90 * Gas::ONE_TERA.0);
+    assert!(borsh_result.gas_burnt().0 < json_result.gas_burnt().0);
+}